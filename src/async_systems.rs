@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use crate::commands::Commands;
+use crate::World;
+
+type Read = Box<dyn FnOnce(&World)>;
+
+#[derive(Default)]
+struct AsyncShared {
+    frame: u64,
+    commands: Commands,
+    reads: Vec<Read>,
+}
+
+/**
+Handle through which an async system accesses the world. The future of the system never holds
+the world itself: it reads it through closures run by the schedule between polls, and records
+its changes into commands applied after each poll. See `Schedule::add_async_system`.
+*/
+#[derive(Clone)]
+pub struct AsyncWorld {
+    shared: Rc<RefCell<AsyncShared>>,
+}
+
+impl AsyncWorld {
+    /**
+    Runs `read` against the world and resolves to its result. Reads are served within the
+    same schedule run, so several of them in a row don't wait for the next frame.
+    */
+    pub fn read<R: 'static>(
+        &self,
+        read: impl FnOnce(&World) -> R + 'static,
+    ) -> impl Future<Output = R> {
+        let shared = self.shared.clone();
+        let result = Rc::new(RefCell::new(None));
+        let mut read = Some(read);
+
+        poll_fn(move |_context| {
+            if let Some(result) = result.borrow_mut().take() {
+                return Poll::Ready(result);
+            }
+
+            if let Some(read) = read.take() {
+                let result = result.clone();
+
+                shared.borrow_mut().reads.push(Box::new(move |world| {
+                    *result.borrow_mut() = Some(read(world))
+                }));
+            }
+
+            Poll::Pending
+        })
+    }
+
+    /**
+    Records changes to the world, applied once the system yields.
+    */
+    pub fn commands(&self, record: impl FnOnce(&mut Commands)) {
+        record(&mut self.shared.borrow_mut().commands);
+    }
+
+    /**
+    Resolves on the next run of the schedule.
+    */
+    pub fn next_frame(&self) -> impl Future<Output = ()> {
+        let shared = self.shared.clone();
+        let frame = shared.borrow().frame;
+
+        poll_fn(move |_context| {
+            if shared.borrow().frame > frame {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for AsyncWorld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncWorld")
+            .field("frame", &self.shared.borrow().frame)
+            .finish()
+    }
+}
+
+pub(crate) struct AsyncSystem {
+    world: AsyncWorld,
+    future: Pin<Box<dyn Future<Output = ()>>>,
+}
+
+impl AsyncSystem {
+    pub fn new<F: Future<Output = ()> + 'static>(system: impl FnOnce(AsyncWorld) -> F) -> Self {
+        let world = AsyncWorld {
+            shared: Rc::default(),
+        };
+
+        Self {
+            future: Box::pin(system(world.clone())),
+            world,
+        }
+    }
+
+    /**
+    Polls the system until it waits for the next frame or finishes, serving its reads in
+    between, then applies the commands it recorded. Returns `true` once the system is done.
+    */
+    pub fn run(&mut self, world: &mut World) -> bool {
+        let mut context = Context::from_waker(Waker::noop());
+        let finished = loop {
+            if self.future.as_mut().poll(&mut context).is_ready() {
+                break true;
+            }
+
+            let reads = std::mem::take(&mut self.world.shared.borrow_mut().reads);
+
+            if reads.is_empty() {
+                break false;
+            }
+
+            for read in reads {
+                read(world);
+            }
+        };
+
+        let mut shared = self.world.shared.borrow_mut();
+        let commands = std::mem::take(&mut shared.commands);
+
+        shared.frame += 1;
+        drop(shared);
+
+        // Like the other systems, async ones have nowhere to report errors to, so commands
+        // that fail are dropped.
+        let _ = world.apply_commands(commands);
+
+        finished
+    }
+}
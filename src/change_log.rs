@@ -0,0 +1,183 @@
+use log::Level;
+
+use crate::journal::StructuralChange;
+
+/**
+Target of the records written by the `ChangeLogger`, to filter them in the logger setup.
+*/
+pub const LOG_TARGET: &str = "sara_ecs::changes";
+
+/**
+Logs every spawn, despawn, component insertion and removal through the `log` crate, with the
+entity ID, the component type name and the current caller tag, if any. The world only logs
+while a `ChangeLogger` resource exists and is enabled, so it can be switched on and off at
+runtime. Requires the `log` feature.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::change_log::ChangeLogger;
+
+struct Poisoned;
+
+let mut world = World::new();
+
+world.add_resource(ChangeLogger::new()).unwrap();
+world.register_component::<Poisoned>();
+
+// Logs "entity 0: removed ...::Poisoned (caller: combat)" at debug level.
+let player = world.create_entity().with_component(Poisoned).unwrap().id();
+world.with_caller("combat", |world| world.remove_entity_component::<Poisoned>(player).unwrap());
+
+world.get_resource_mut::<ChangeLogger>().unwrap().set_enabled(false);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct ChangeLogger {
+    enabled: bool,
+    level: Level,
+    pub(crate) caller: Option<String>,
+}
+
+impl Default for ChangeLogger {
+    fn default() -> Self {
+        ChangeLogger {
+            enabled: true,
+            level: Level::Debug,
+            caller: None,
+        }
+    }
+}
+
+impl ChangeLogger {
+    pub fn new() -> Self {
+        ChangeLogger::default()
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn caller(&self) -> Option<&str> {
+        self.caller.as_deref()
+    }
+
+    /**
+    Tags the changes logged from now on with `caller`, like the name of the running system.
+    See `World::with_caller` to tag a single block of code.
+    */
+    pub fn set_caller(&mut self, caller: impl Into<String>) {
+        self.caller = Some(caller.into());
+    }
+
+    pub fn clear_caller(&mut self) {
+        self.caller = None;
+    }
+
+    // Whether changes would make it to the logger, so they are only built when needed.
+    pub(crate) fn is_logging(&self) -> bool {
+        self.enabled && log::log_enabled!(target: LOG_TARGET, self.level)
+    }
+
+    pub(crate) fn log(&self, change: &StructuralChange) {
+        let caller = self.caller.as_deref().unwrap_or("unknown");
+
+        match change {
+            StructuralChange::Spawned { entity_id } => {
+                log::log!(target: LOG_TARGET, self.level,
+                    "entity {entity_id}: spawned (caller: {caller})");
+            }
+            StructuralChange::Despawned { entity_id } => {
+                log::log!(target: LOG_TARGET, self.level,
+                    "entity {entity_id}: despawned (caller: {caller})");
+            }
+            StructuralChange::ComponentInserted {
+                entity_id,
+                component,
+            } => {
+                log::log!(target: LOG_TARGET, self.level,
+                    "entity {entity_id}: inserted {component} (caller: {caller})");
+            }
+            StructuralChange::ComponentRemoved {
+                entity_id,
+                component,
+            } => {
+                log::log!(target: LOG_TARGET, self.level,
+                    "entity {entity_id}: removed {component} (caller: {caller})");
+            }
+            StructuralChange::ResourceChanged { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use log::{Log, Metadata, Record};
+
+    use super::*;
+    use crate::World;
+
+    static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Recorder;
+
+    impl Log for Recorder {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.target() == LOG_TARGET
+        }
+
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                RECORDS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    struct Poisoned;
+
+    #[test]
+    fn changes_are_logged_with_the_caller_while_enabled() {
+        log::set_logger(&Recorder).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let mut world = World::new();
+        world.register_component::<Poisoned>();
+        world.add_resource(ChangeLogger::new()).unwrap();
+
+        let player = world.create_entity().with_component(Poisoned).unwrap().id();
+        world.with_caller("combat", |world| {
+            world.remove_entity_component::<Poisoned>(player).unwrap();
+        });
+        world
+            .get_resource_mut::<ChangeLogger>()
+            .unwrap()
+            .set_enabled(false);
+        world.create_entity().with_component(Poisoned).unwrap();
+
+        let records = RECORDS.lock().unwrap();
+        let poisoned = std::any::type_name::<Poisoned>();
+
+        assert_eq!(
+            *records,
+            vec![
+                "entity 0: spawned (caller: unknown)".to_owned(),
+                format!("entity 0: inserted {poisoned} (caller: unknown)"),
+                format!("entity 0: removed {poisoned} (caller: combat)"),
+            ]
+        );
+        assert_eq!(world.get_resource::<ChangeLogger>().unwrap().caller(), None);
+    }
+}
@@ -0,0 +1,26 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use crate::dynamic_component::DynamicComponent;
+
+/**
+In-memory copy of the entities of a world and their components, created with
+`World::checkpoint` and restored with `World::restore`. Components are copied with the clone
+functions of the type registry, without going through serialization. Resources are not included.
+*/
+#[derive(Debug, Default)]
+pub struct WorldCheckpoint {
+    pub(crate) components: HashMap<TypeId, Vec<Option<Box<dyn Any>>>>,
+    pub(crate) dynamic_components: HashMap<String, Vec<Option<DynamicComponent>>>,
+    pub(crate) zero_sized_components: HashMap<TypeId, Box<dyn Any>>,
+    pub(crate) entity_component_bitmasks: Vec<u32>,
+    pub(crate) entity_group_bitmasks: Vec<u64>,
+    pub(crate) entity_tag_bitmasks: Vec<u64>,
+    pub(crate) disabled_entities: Vec<bool>,
+}
+
+impl WorldCheckpoint {
+    pub fn entity_count(&self) -> usize {
+        self.entity_component_bitmasks.len()
+    }
+}
@@ -0,0 +1,139 @@
+use std::any::Any;
+
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+pub type Command = Box<dyn FnOnce(&mut World) -> Result<(), ECSError>>;
+
+/**
+Buffer of structural changes recorded while the world is borrowed, for example while iterating
+a query, and applied afterwards with `World::apply_commands`. Commands are applied in the
+order they were recorded.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::commands::Commands;
+use sara_ecs::ecs_errors::ECSError;
+
+struct Health(pub u32);
+struct Dying;
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dying>();
+    world.create_entity().with_component(Health(0))?;
+    world.create_entity().with_component(Health(10))?;
+
+    let mut commands = Commands::new();
+
+    for entity in world.query().with_component_filter::<Health>()?.get_entities() {
+        if entity.get_component::<Health>()?.0 == 0 {
+            entity.commands(&mut commands).insert(Dying).remove::<Health>();
+        }
+    }
+
+    world.apply_commands(commands)?;
+
+    let dying = world.query().with_component_filter::<Dying>()?.run();
+    assert_eq!(dying.entity_ids, vec![0]);
+    Ok(())
+}
+```
+*/
+#[derive(Default)]
+pub struct Commands {
+    commands: Vec<Command>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Commands::default()
+    }
+
+    /**
+    Records an arbitrary change to the world.
+    */
+    pub fn add(&mut self, command: impl FnOnce(&mut World) -> Result<(), ECSError> + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    /**
+    Records changes to an existing entity.
+    */
+    pub fn entity(&mut self, entity_id: usize) -> EntityCommands<'_> {
+        EntityCommands {
+            commands: self,
+            entity_id,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub(crate) fn drain(self) -> impl Iterator<Item = Command> {
+        self.commands.into_iter()
+    }
+}
+
+impl std::fmt::Debug for Commands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Commands")
+            .field("commands", &self.commands.len())
+            .finish()
+    }
+}
+
+/**
+Records changes to one entity into a `Commands` buffer. Returned by `Commands::entity` and
+`QueryEntity::commands`.
+*/
+pub struct EntityCommands<'a> {
+    commands: &'a mut Commands,
+    entity_id: usize,
+}
+
+impl EntityCommands<'_> {
+    pub fn id(&self) -> usize {
+        self.entity_id
+    }
+
+    /**
+    Adds a component to the entity, see `World::add_component_to_entity`.
+    */
+    pub fn insert(&mut self, component: impl Any) -> &mut Self {
+        let entity_id = self.entity_id;
+
+        self.commands
+            .add(move |world| world.add_component_to_entity(entity_id, component));
+        self
+    }
+
+    /**
+    Removes a component from the entity, see `World::remove_entity_component`.
+    */
+    pub fn remove<T: Any>(&mut self) -> &mut Self {
+        let entity_id = self.entity_id;
+
+        self.commands
+            .add(move |world| world.remove_entity_component::<T>(entity_id));
+        self
+    }
+
+    /**
+    Removes the entity, see `World::remove_entity`.
+    */
+    pub fn despawn(&mut self) {
+        let entity_id = self.entity_id;
+
+        self.commands
+            .add(move |world| world.remove_entity(entity_id));
+    }
+}
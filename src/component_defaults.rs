@@ -0,0 +1,35 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub type ComponentConstructor = Rc<dyn Fn() -> Box<dyn Any>>;
+
+/**
+Functions creating the default value of component types, registered with
+`World::register_component_with_default`.
+*/
+#[derive(Default)]
+pub struct ComponentDefaults {
+    constructors: HashMap<TypeId, ComponentConstructor>,
+}
+
+impl ComponentDefaults {
+    pub fn insert<T: Any>(&mut self, default: impl Fn() -> T + 'static) {
+        self.constructors
+            .insert(TypeId::of::<T>(), Rc::new(move || Box::new(default())));
+    }
+
+    pub fn create(&self, type_id: &TypeId) -> Option<Box<dyn Any>> {
+        self.constructors
+            .get(type_id)
+            .map(|constructor| constructor())
+    }
+}
+
+impl std::fmt::Debug for ComponentDefaults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentDefaults")
+            .field("constructors", &self.constructors.len())
+            .finish()
+    }
+}
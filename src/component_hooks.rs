@@ -0,0 +1,63 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use crate::World;
+
+pub type ComponentHook = fn(&mut World, usize);
+
+#[derive(Default, Debug, Clone)]
+struct Hooks {
+    on_add: Vec<ComponentHook>,
+    on_remove: Vec<ComponentHook>,
+}
+
+#[derive(Default, Debug)]
+pub struct ComponentHooks {
+    hooks: HashMap<TypeId, Hooks>,
+}
+
+impl ComponentHooks {
+    pub fn add_on_add(&mut self, type_id: TypeId, hook: ComponentHook) {
+        self.hooks.entry(type_id).or_default().on_add.push(hook);
+    }
+
+    pub fn add_on_remove(&mut self, type_id: TypeId, hook: ComponentHook) {
+        self.hooks.entry(type_id).or_default().on_remove.push(hook);
+    }
+
+    pub fn on_add(&self, type_id: &TypeId) -> Vec<ComponentHook> {
+        self.hooks
+            .get(type_id)
+            .map(|hooks| hooks.on_add.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn on_remove(&self, type_id: &TypeId) -> Vec<ComponentHook> {
+        self.hooks
+            .get(type_id)
+            .map(|hooks| hooks.on_remove.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn first_hook(_world: &mut World, _entity_id: usize) {}
+    fn second_hook(_world: &mut World, _entity_id: usize) {}
+
+    #[test]
+    fn hooks_are_stored_per_type() {
+        let mut hooks = ComponentHooks::default();
+
+        hooks.add_on_add(TypeId::of::<u32>(), first_hook);
+        hooks.add_on_add(TypeId::of::<u32>(), second_hook);
+        hooks.add_on_remove(TypeId::of::<f32>(), first_hook);
+
+        assert_eq!(hooks.on_add(&TypeId::of::<u32>()).len(), 2);
+        assert!(hooks.on_remove(&TypeId::of::<u32>()).is_empty());
+        assert_eq!(hooks.on_remove(&TypeId::of::<f32>()).len(), 1);
+        assert!(hooks.on_add(&TypeId::of::<f32>()).is_empty());
+    }
+}
@@ -0,0 +1,30 @@
+use std::any::TypeId;
+
+/**
+How the values of a component type are stored.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    // One value per entity, in the column of the type.
+    Column,
+    // Zero-sized types, whose value is shared by every entity that has the component.
+    ZeroSized,
+    // Components described at runtime, see `World::register_dynamic_component`.
+    Dynamic,
+}
+
+/**
+Metadata about a registered component type, listed by `World::component_registrations`.
+Dynamic components all share the `TypeId` of `DynamicComponent`, and are named after their
+descriptor.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentRegistration {
+    pub type_id: TypeId,
+    pub name: String,
+    // Bit identifying the component in entity masks.
+    pub bitmask: u32,
+    pub storage: StorageKind,
+    // Number of living entities with the component.
+    pub count: usize,
+}
@@ -0,0 +1,136 @@
+use std::any::{type_name, Any, TypeId};
+
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+/**
+A set of component types that can be registered in one call with `World::register_components`,
+and whose values can be spawned together from an entity pool. Implemented for tuples of up to
+12 types.
+*/
+pub trait ComponentSet {
+    /**
+    Registers every type of the set that isn't registered yet, pushing the names of the ones
+    that didn't fit in the component limit into `rejected`.
+    */
+    fn register(world: &mut World, rejected: &mut Vec<&'static str>);
+
+    fn type_ids() -> Vec<TypeId>;
+
+    /**
+    Adds every component of the set to an entity. Components the entity already has are
+    overwritten in place.
+    */
+    fn insert(self, world: &mut World, entity_id: usize) -> Result<(), ECSError>
+    where
+        Self: Sized;
+}
+
+macro_rules! impl_component_set {
+    ($($component:ident),+) => {
+        impl<$($component: Any),+> ComponentSet for ($($component,)+) {
+            fn register(world: &mut World, rejected: &mut Vec<&'static str>) {
+                $(
+                    if !world.try_register_component::<$component>() {
+                        rejected.push(type_name::<$component>());
+                    }
+                )+
+            }
+
+            fn type_ids() -> Vec<TypeId> {
+                vec![$(TypeId::of::<$component>()),+]
+            }
+
+            #[allow(non_snake_case)]
+            fn insert(self, world: &mut World, entity_id: usize) -> Result<(), ECSError> {
+                let ($($component,)+) = self;
+
+                $(world.add_component_to_entity(entity_id, $component)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_component_set!(A);
+impl_component_set!(A, B);
+impl_component_set!(A, B, C);
+impl_component_set!(A, B, C, D);
+impl_component_set!(A, B, C, D, E);
+impl_component_set!(A, B, C, D, E, F);
+impl_component_set!(A, B, C, D, E, F, G);
+impl_component_set!(A, B, C, D, E, F, G, H);
+impl_component_set!(A, B, C, D, E, F, G, H, I);
+impl_component_set!(A, B, C, D, E, F, G, H, I, J);
+impl_component_set!(A, B, C, D, E, F, G, H, I, J, K);
+impl_component_set!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ecs_errors::ECSError;
+
+    struct Marker<const N: usize>;
+
+    #[test]
+    fn components_past_the_limit_are_rejected() {
+        let mut world = World::new();
+
+        world
+            .register_components::<(
+                Marker<0>,
+                Marker<1>,
+                Marker<2>,
+                Marker<3>,
+                Marker<4>,
+                Marker<5>,
+                Marker<6>,
+                Marker<7>,
+                Marker<8>,
+                Marker<9>,
+                Marker<10>,
+                Marker<11>,
+            )>()
+            .unwrap();
+        world
+            .register_components::<(
+                Marker<12>,
+                Marker<13>,
+                Marker<14>,
+                Marker<15>,
+                Marker<16>,
+                Marker<17>,
+                Marker<18>,
+                Marker<19>,
+                Marker<20>,
+                Marker<21>,
+                Marker<22>,
+                Marker<23>,
+            )>()
+            .unwrap();
+
+        let result = world.register_components::<(
+            Marker<24>,
+            Marker<25>,
+            Marker<26>,
+            Marker<27>,
+            Marker<28>,
+            Marker<29>,
+            Marker<30>,
+            Marker<31>,
+            Marker<32>,
+            Marker<0>,
+            Marker<33>,
+        )>();
+
+        match result {
+            Err(ECSError::TooManyComponents(rejected)) => {
+                assert_eq!(
+                    rejected,
+                    vec![type_name::<Marker<32>>(), type_name::<Marker<33>>()]
+                );
+            }
+            _ => panic!("expected TooManyComponents"),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+/**
+A component in a `WorldDump`. `value` is only known for types registered with
+`World::register_debuggable_component`.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentDump {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityDump {
+    pub id: usize,
+    pub name: Option<String>,
+    pub disabled: bool,
+    pub components: Vec<ComponentDump>,
+}
+
+impl EntityDump {
+    pub fn component(&self, name: &str) -> Option<&ComponentDump> {
+        self.components
+            .iter()
+            .find(|component| component.name == name)
+    }
+}
+
+/**
+A resource in a `WorldDump`. `value` is only known for types registered in the type registry
+with `TypeRegistry::register_debug`, and for resources that weren't mutably borrowed while
+the dump was made.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceDump {
+    pub name: String,
+    pub key: Option<String>,
+    pub value: Option<String>,
+}
+
+/**
+Listing of the living entities of a world with their components, and of its resources,
+created with `World::debug_dump`. Entities are sorted by ID, and components and resources by
+name. `Display` prints it as an indented tree:
+
+```text
+Entities (1):
+  0 "Player":
+    Health: Health(100)
+    Player
+Resources (1):
+  Score: Score(10)
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorldDump {
+    pub entities: Vec<EntityDump>,
+    pub resources: Vec<ResourceDump>,
+}
+
+impl WorldDump {
+    pub fn entity(&self, id: usize) -> Option<&EntityDump> {
+        self.entities.iter().find(|entity| entity.id == id)
+    }
+}
+
+impl fmt::Display for WorldDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Entities ({}):", self.entities.len())?;
+
+        for entity in &self.entities {
+            write!(f, "  {}", entity.id)?;
+            if let Some(name) = &entity.name {
+                write!(f, " {name:?}")?;
+            }
+            if entity.disabled {
+                write!(f, " (disabled)")?;
+            }
+            writeln!(f, ":")?;
+
+            for component in &entity.components {
+                write_entry(f, "    ", &component.name, component.value.as_deref())?;
+            }
+        }
+
+        writeln!(f, "Resources ({}):", self.resources.len())?;
+
+        for resource in &self.resources {
+            let name = match &resource.key {
+                Some(key) => format!("{}[{key:?}]", resource.name),
+                None => resource.name.clone(),
+            };
+            write_entry(f, "  ", &name, resource.value.as_deref())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_entry(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    name: &str,
+    value: Option<&str>,
+) -> fmt::Result {
+    match value {
+        Some(value) => writeln!(f, "{indent}{name}: {value}"),
+        None => writeln!(f, "{indent}{name}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dumps_are_printed_as_a_tree() {
+        let dump = WorldDump {
+            entities: vec![EntityDump {
+                id: 3,
+                name: Some("Goblin".to_owned()),
+                disabled: true,
+                components: vec![
+                    ComponentDump {
+                        name: "Enemy".to_owned(),
+                        value: None,
+                    },
+                    ComponentDump {
+                        name: "Health".to_owned(),
+                        value: Some("Health(10)".to_owned()),
+                    },
+                ],
+            }],
+            resources: vec![ResourceDump {
+                name: "Level".to_owned(),
+                key: Some("current".to_owned()),
+                value: Some("2".to_owned()),
+            }],
+        };
+
+        assert_eq!(
+            dump.to_string(),
+            "Entities (1):\n  3 \"Goblin\" (disabled):\n    Enemy\n    Health: Health(10)\n\
+             Resources (1):\n  Level[\"current\"]: 2\n"
+        );
+    }
+}
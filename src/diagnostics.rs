@@ -0,0 +1,141 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+/**
+Statistics about a world, refreshed by `World::update_diagnostics` for overlays and telemetry.
+The world only maintains them while a `WorldDiagnostics` resource exists. Per-tick counts
+cover what happened between the last two updates, so `update_diagnostics` is meant to run
+once per tick, usually as the last system of the schedule.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::diagnostics::WorldDiagnostics;
+
+struct Bullet;
+
+let mut world = World::new();
+
+world.add_resource(WorldDiagnostics::default()).unwrap();
+world.register_component::<Bullet>();
+
+for _ in 0..3 {
+    world.create_entity().with_component(Bullet).unwrap();
+}
+world.remove_entity(0).unwrap();
+world.update_diagnostics();
+
+let diagnostics = world.get_resource::<WorldDiagnostics>().unwrap();
+
+assert_eq!(diagnostics.entity_count, 2);
+assert_eq!((diagnostics.spawned, diagnostics.despawned), (3, 1));
+assert_eq!(diagnostics.component_count(std::any::type_name::<Bullet>()), 2);
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldDiagnostics {
+    // Number of updates so far.
+    pub tick: u64,
+    // Living entities.
+    pub entity_count: usize,
+    // Entities spawned during the last tick.
+    pub spawned: usize,
+    // Entities despawned during the last tick.
+    pub despawned: usize,
+    // Queries created during the last tick.
+    pub queries: usize,
+    pub total_spawned: u64,
+    pub total_despawned: u64,
+    // Living instances of each registered component, by type name.
+    pub component_counts: BTreeMap<String, usize>,
+}
+
+impl WorldDiagnostics {
+    pub fn component_count(&self, name: &str) -> usize {
+        self.component_counts.get(name).copied().unwrap_or(0)
+    }
+}
+
+/**
+A broken invariant of the world's storage, as found by `World::validate`. Components and
+relations are identified by their type names.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A component column doesn't have a slot for every entity.
+    ColumnLength {
+        component: String,
+        length: usize,
+        entity_count: usize,
+    },
+    /// The entity's bitmask says it has the component, but its slot is empty.
+    MissingComponent { entity_id: usize, component: String },
+    /// The child's `Parent` points at an entity whose `Children` don't list it.
+    ChildNotListed { child: usize, parent: usize },
+    /// The parent lists a child whose `Parent` doesn't point back at it.
+    ParentNotSet { parent: usize, child: usize },
+    /// A relation, or a hierarchy link, points at an entity that isn't alive.
+    DeadTarget {
+        source: usize,
+        target: usize,
+        relation: String,
+    },
+}
+
+// Counted in cells so that `World::query`, which only borrows the world, can count too.
+#[derive(Debug, Default)]
+pub(crate) struct DiagnosticsCounters {
+    spawned: Cell<usize>,
+    despawned: Cell<usize>,
+    queries: Cell<usize>,
+}
+
+impl DiagnosticsCounters {
+    pub fn count_spawn(&self) {
+        self.spawned.set(self.spawned.get() + 1);
+    }
+
+    pub fn count_despawn(&self) {
+        self.despawned.set(self.despawned.get() + 1);
+    }
+
+    pub fn count_query(&self) {
+        self.queries.set(self.queries.get() + 1);
+    }
+
+    /**
+    Moves the counts of the tick that just ended into `diagnostics`, and starts counting again.
+    */
+    pub fn flush(&self, diagnostics: &mut WorldDiagnostics) {
+        diagnostics.tick += 1;
+        diagnostics.spawned = self.spawned.take();
+        diagnostics.despawned = self.despawned.take();
+        diagnostics.queries = self.queries.take();
+        diagnostics.total_spawned += diagnostics.spawned as u64;
+        diagnostics.total_despawned += diagnostics.despawned as u64;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flushing_resets_the_tick_counts() {
+        let counters = DiagnosticsCounters::default();
+        let mut diagnostics = WorldDiagnostics::default();
+
+        counters.count_spawn();
+        counters.count_spawn();
+        counters.count_query();
+        counters.flush(&mut diagnostics);
+
+        assert_eq!((diagnostics.spawned, diagnostics.queries), (2, 1));
+
+        counters.count_despawn();
+        counters.flush(&mut diagnostics);
+
+        assert_eq!((diagnostics.spawned, diagnostics.despawned), (0, 1));
+        assert_eq!((diagnostics.total_spawned, diagnostics.tick), (2, 2));
+    }
+}
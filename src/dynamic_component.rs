@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+/**
+Type of a field in a dynamic component.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+/**
+Value of a field in a dynamic component.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl DynamicValue {
+    pub fn field_type(&self) -> FieldType {
+        match self {
+            DynamicValue::Bool(_) => FieldType::Bool,
+            DynamicValue::Int(_) => FieldType::Int,
+            DynamicValue::Float(_) => FieldType::Float,
+            DynamicValue::String(_) => FieldType::String,
+        }
+    }
+
+    fn default_of(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::Bool => DynamicValue::Bool(false),
+            FieldType::Int => DynamicValue::Int(0),
+            FieldType::Float => DynamicValue::Float(0.0),
+            FieldType::String => DynamicValue::String(String::new()),
+        }
+    }
+}
+
+/**
+Layout of a component described at runtime instead of by a Rust type, for example one loaded
+from a mod or defined by a script. Registered with `World::register_dynamic_component`.
+
+Example:
+```
+use sara_ecs::dynamic_component::{DynamicComponentDescriptor, FieldType};
+
+let descriptor = DynamicComponentDescriptor::new("Mana")
+    .with_field("current", FieldType::Int)
+    .with_field("regeneration", FieldType::Float);
+
+let mana = descriptor.instantiate();
+
+assert_eq!(mana.name, "Mana");
+assert_eq!(mana.fields.len(), 2);
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicComponentDescriptor {
+    pub name: String,
+    pub fields: Vec<(String, FieldType)>,
+}
+
+impl DynamicComponentDescriptor {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fields: vec![],
+        }
+    }
+
+    pub fn with_field(mut self, name: impl Into<String>, field_type: FieldType) -> Self {
+        self.fields.push((name.into(), field_type));
+        self
+    }
+
+    /**
+    Creates a component with every field set to the default value of its type.
+    */
+    pub fn instantiate(&self) -> DynamicComponent {
+        let mut component = DynamicComponent::new(self.name.clone());
+
+        for (name, field_type) in &self.fields {
+            component
+                .fields
+                .insert(name.clone(), DynamicValue::default_of(*field_type));
+        }
+
+        component
+    }
+
+    /**
+    Checks that `component` has this descriptor's name and exactly its fields, with matching types.
+    */
+    pub fn matches(&self, component: &DynamicComponent) -> bool {
+        component.name == self.name
+            && component.fields.len() == self.fields.len()
+            && self.fields.iter().all(|(name, field_type)| {
+                component
+                    .fields
+                    .get(name)
+                    .is_some_and(|value| value.field_type() == *field_type)
+            })
+    }
+}
+
+/**
+Instance of a dynamic component. Entities store it like any other component, under the name of
+its descriptor.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicComponent {
+    pub name: String,
+    pub fields: HashMap<String, DynamicValue>,
+}
+
+impl DynamicComponent {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fields: HashMap::new(),
+        }
+    }
+
+    pub fn with_field(mut self, name: impl Into<String>, value: DynamicValue) -> Self {
+        self.fields.insert(name.into(), value);
+        self
+    }
+
+    pub fn get(&self, field: &str) -> Option<&DynamicValue> {
+        self.fields.get(field)
+    }
+
+    /**
+    Writes `value` into an existing field. Returns `false` if the field does not exist or
+    holds a different type.
+    */
+    pub fn set(&mut self, field: &str, value: DynamicValue) -> bool {
+        match self.fields.get_mut(field) {
+            Some(current) if current.field_type() == value.field_type() => {
+                *current = value;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn descriptor() -> DynamicComponentDescriptor {
+        DynamicComponentDescriptor::new("Mana")
+            .with_field("current", FieldType::Int)
+            .with_field("label", FieldType::String)
+    }
+
+    #[test]
+    fn instances_start_with_default_values() {
+        let mana = descriptor().instantiate();
+
+        assert_eq!(mana.get("current"), Some(&DynamicValue::Int(0)));
+        assert_eq!(
+            mana.get("label"),
+            Some(&DynamicValue::String(String::new()))
+        );
+    }
+
+    #[test]
+    fn set_checks_field_types() {
+        let mut mana = descriptor().instantiate();
+
+        assert!(mana.set("current", DynamicValue::Int(10)));
+        assert!(!mana.set("current", DynamicValue::Float(1.0)));
+        assert!(!mana.set("maximum", DynamicValue::Int(1)));
+        assert_eq!(mana.get("current"), Some(&DynamicValue::Int(10)));
+    }
+
+    #[test]
+    fn descriptors_match_their_instances_only() {
+        let descriptor = descriptor();
+
+        assert!(descriptor.matches(&descriptor.instantiate()));
+        assert!(!descriptor.matches(&DynamicComponent::new("Mana")));
+        assert!(!descriptor.matches(
+            &DynamicComponent::new("Mana")
+                .with_field("current", DynamicValue::Bool(true))
+                .with_field("label", DynamicValue::String("blue".to_owned()))
+        ));
+    }
+}
@@ -1,22 +1,107 @@
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ECSError {
     #[error("Attempted to add to an entity without calling create entity first.")]
     CreateComponentNeverCalled,
 
-    #[error("Attempted to reference a component that was not registered.")]
-    ComponentNotRegistered,
+    #[error("Attempted to reference component {type_name}, which was not registered.")]
+    ComponentNotRegistered { type_name: String },
 
-    #[error("Attempted to reference an entity that does not exist.")]
-    EntityDoesNotExist,
+    #[error("Attempted to reference entity {id}, which does not exist.")]
+    EntityDoesNotExist { id: usize },
 
-    #[error("Attempted to reference component data that does not exist.")]
-    ComponentDoesNotExist,
+    #[error(
+        "Attempted to reference component {type_name} of entity {id}, which does not have one."
+    )]
+    ComponentDoesNotExist { id: usize, type_name: String },
 
     #[error("Attempted to downcast to the wrong type.")]
     DowncastToWrongType,
 
     #[error("Resource already registered.")]
     ResourceAlreadyRegistered,
+
+    #[error("Attempted to reference resource {type_name}, which does not exist.")]
+    ResourceDoesNotExist { type_name: String },
+
+    #[error("Attempted to send an event that was not registered.")]
+    EventNotRegistered,
+
+    #[error("Attempted to serialize a type that was not registered as serializable.")]
+    TypeNotSerializable,
+
+    #[error("Serialization failed: {0}")]
+    Serialization(String),
+
+    #[error("Attempted to reference a prefab that does not exist.")]
+    PrefabDoesNotExist,
+
+    #[error("Attempted to override a prefab value on an entity that is not a prefab instance.")]
+    NotAPrefabInstance,
+
+    #[error("Attempted to reflect a type that was not registered as reflectable.")]
+    TypeNotReflectable,
+
+    #[error(
+        "Attempted to add a dynamic component whose fields do not match its registered layout."
+    )]
+    DynamicComponentMismatch,
+
+    #[error("Attempted to clone a component type that was not registered as cloneable.")]
+    TypeNotCloneable,
+
+    #[error("Attempted to roll back to a tick that is not buffered.")]
+    TickNotBuffered,
+
+    #[error("Attempted to create more than 64 entity groups.")]
+    TooManyGroups,
+
+    #[error("Attempted to make an entity its own ancestor.")]
+    HierarchyCycle,
+
+    #[error("Attempted to use the spatial index before enabling it.")]
+    SpatialIndexNotEnabled,
+
+    #[error("Attempted to add a unique component to a second entity.")]
+    UniqueComponentTaken,
+
+    #[error("Attempted to create a component that has no default value.")]
+    NoDefaultValue,
+
+    #[error("Attempted to create more than 64 entity tags.")]
+    TooManyTags,
+
+    #[error("Attempted to run a named system that does not exist.")]
+    SystemNotFound,
+
+    #[error("Attempted to register more than 32 component types: {0:?}.")]
+    TooManyComponents(Vec<&'static str>),
+
+    #[error(
+        "Attempted to borrow component {type_name} of entity {id} while it's mutably borrowed."
+    )]
+    ComponentAlreadyBorrowed { id: usize, type_name: String },
+
+    #[error("Attempted to access component {component} outside of the script sandbox.")]
+    ScriptAccessDenied { component: String },
+
+    #[error("Attempted to use field {field} of {component}, which is missing or of another type.")]
+    FieldNotAccessible { component: String, field: String },
+
+    #[error("Attempted to reload systems built against another layout of component {component}.")]
+    ComponentLayoutMismatch { component: String },
+
+    #[error("Attempted to use the entity pool of a component set that has none.")]
+    EntityPoolNotEnabled,
+
+    #[error("Attempted to read the column of component {type_name}, which was not queried.")]
+    ComponentNotQueried { type_name: String },
+
+    #[error("Attempted to despawn entity {id} into a pool, but it is already parked in one.")]
+    EntityAlreadyPooled { id: usize },
+
+    #[error("Background tasks panicked: {0:?}.")]
+    TasksPanicked(Vec<String>),
 }
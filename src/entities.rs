@@ -0,0 +1,124 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::entity_storage::EntityStorage;
+
+/**
+Access to the entity table through a shared reference, returned by `World::entities`.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct Entities<'a> {
+    entity_storage: &'a EntityStorage,
+}
+
+impl<'a> Entities<'a> {
+    pub(crate) fn new(entity_storage: &'a EntityStorage) -> Self {
+        Self { entity_storage }
+    }
+
+    /**
+    Allocates the ID of a new entity right away, without mutable access to the world, so
+    systems holding `&World` can spawn entities and fill in their components through
+    `Commands`. The entity is added to the world, and its spawn events sent, the next time
+    commands are applied, an entity is created or a component is added.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::commands::Commands;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Bullet;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+        let mut commands = Commands::new();
+
+        world.register_component::<Bullet>();
+
+        let bullet = world.entities().reserve();
+        commands.entity(bullet).insert(Bullet);
+
+        world.apply_commands(commands)?;
+
+        let bullets = world.query().with_component_filter::<Bullet>()?.run();
+        assert_eq!(bullets.entity_ids, vec![bullet]);
+        Ok(())
+    }
+    ```
+    */
+    pub fn reserve(&self) -> usize {
+        self.entity_storage.reserve_entity()
+    }
+
+    /**
+    Handle reserving entities like `reserve` from any thread, so tasks running in the
+    background can allocate the entities their results are added to. Reserved entities are added
+    to the world like the ones reserved through `reserve`.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::commands::Commands;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Bullet;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+        let reserver = world.entities().reserver();
+
+        world.register_component::<Bullet>();
+
+        let bullet = std::thread::spawn(move || reserver.reserve()).join().unwrap();
+        let mut commands = Commands::new();
+
+        commands.entity(bullet).insert(Bullet);
+        world.apply_commands(commands)?;
+
+        let bullets = world.query().with_component_filter::<Bullet>()?.run();
+        assert_eq!(bullets.entity_ids, vec![bullet]);
+        Ok(())
+    }
+    ```
+    */
+    pub fn reserver(&self) -> EntityReserver {
+        EntityReserver {
+            next_entity_id: self.entity_storage.reserved_entity_counter(),
+        }
+    }
+
+    /**
+    Number of slots in the entity table, living entities or not. Reserved entities are only
+    counted once they are added to the world.
+    */
+    pub fn len(&self) -> usize {
+        self.entity_storage.entity_count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_alive(&self, entity_id: usize) -> bool {
+        self.entity_storage.is_alive(entity_id)
+    }
+}
+
+/**
+Reserves entity IDs from any thread, returned by `Entities::reserver`. Entities reserved
+before the world is reset, by loading a snapshot or restoring a checkpoint, are dropped.
+*/
+#[derive(Debug, Clone)]
+pub struct EntityReserver {
+    next_entity_id: Arc<AtomicUsize>,
+}
+
+impl EntityReserver {
+    /**
+    Allocates the ID of a new entity, like `Entities::reserve`.
+    */
+    pub fn reserve(&self) -> usize {
+        self.next_entity_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
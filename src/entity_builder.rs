@@ -0,0 +1,37 @@
+use std::any::Any;
+
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+/**
+Returned by `World::create_entity`, lets you chain component additions to the entity that
+is being created. Components added through the builder go through the world, so component
+hooks are run for them.
+*/
+pub struct EntityBuilder<'a> {
+    world: &'a mut World,
+    entity_id: usize,
+}
+
+impl<'a> EntityBuilder<'a> {
+    pub(crate) fn new(world: &'a mut World, entity_id: usize) -> Self {
+        Self { world, entity_id }
+    }
+
+    pub fn id(&self) -> usize {
+        self.entity_id
+    }
+
+    pub fn with_component(&mut self, data: impl Any) -> Result<&mut Self, ECSError> {
+        self.world.add_component_to_entity(self.entity_id, data)?;
+        Ok(self)
+    }
+
+    /**
+    Adds the default value of `T` to the entity, see `World::insert_default`.
+    */
+    pub fn insert_default<T: Any>(&mut self) -> Result<&mut Self, ECSError> {
+        self.world.insert_default::<T>(self.entity_id)?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,74 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+
+// Entities parked by `World::despawn_to_pool`, by the `TypeId` of the component set of their
+// pool.
+#[derive(Debug, Default)]
+pub(crate) struct EntityPools {
+    pools: HashMap<TypeId, EntityPool>,
+}
+
+#[derive(Debug)]
+struct EntityPool {
+    capacity: usize,
+    parked: Vec<usize>,
+}
+
+impl EntityPools {
+    pub fn enable(&mut self, set: TypeId, capacity: usize) {
+        self.pools
+            .entry(set)
+            .and_modify(|pool| pool.capacity = capacity)
+            .or_insert_with(|| EntityPool {
+                capacity,
+                parked: Vec::with_capacity(capacity),
+            });
+    }
+
+    pub fn is_enabled(&self, set: &TypeId) -> bool {
+        self.pools.contains_key(set)
+    }
+
+    pub fn is_full(&self, set: &TypeId) -> bool {
+        self.pools
+            .get(set)
+            .is_none_or(|pool| pool.parked.len() >= pool.capacity)
+    }
+
+    pub fn is_parked(&self, entity_id: usize) -> bool {
+        self.pools
+            .values()
+            .any(|pool| pool.parked.contains(&entity_id))
+    }
+
+    pub fn park(&mut self, set: &TypeId, entity_id: usize) {
+        if let Some(pool) = self.pools.get_mut(set) {
+            pool.parked.push(entity_id);
+        }
+    }
+
+    pub fn take(&mut self, set: &TypeId) -> Option<usize> {
+        self.pools.get_mut(set)?.parked.pop()
+    }
+
+    pub fn parked(&self, set: &TypeId) -> &[usize] {
+        self.pools
+            .get(set)
+            .map_or(&[], |pool| pool.parked.as_slice())
+    }
+
+    pub fn remap(&mut self, entity_map: &HashMap<usize, usize>) {
+        for pool in self.pools.values_mut() {
+            for entity_id in &mut pool.parked {
+                *entity_id = entity_map.get(entity_id).copied().unwrap_or(*entity_id);
+            }
+        }
+    }
+
+    // Forgets an entity that was removed for good while parked.
+    pub fn remove(&mut self, entity_id: usize) {
+        for pool in self.pools.values_mut() {
+            pool.parked.retain(|parked| *parked != entity_id);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::any::TypeId;
+use std::mem::size_of;
+
+use crate::dynamic_component::DynamicComponent;
+use crate::type_registry::TypeRegistry;
+use crate::World;
+
+/**
+Description of a component type, as listed by `EntityRef::component_types`.
+Dynamic components all share the `TypeId` of `DynamicComponent`, and are named after their
+descriptor.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentInfo {
+    pub type_id: TypeId,
+    pub name: String,
+    // Size of a value of the type, in bytes.
+    pub size: usize,
+}
+
+/**
+Read-only view of a living entity, returned by `World::entity`.
+*/
+#[derive(Clone, Copy)]
+pub struct EntityRef<'a> {
+    world: &'a World,
+    id: usize,
+}
+
+impl<'a> EntityRef<'a> {
+    pub(crate) fn new(world: &'a World, id: usize) -> Self {
+        Self { world, id }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /**
+    Lists the components of the entity, decoded from its bitmask and the type registry, and
+    sorted by name.
+    */
+    pub fn component_types(&self) -> Vec<ComponentInfo> {
+        let storage = &self.world.entitiy_storage;
+        let registry = self.world.resource_storage.borrow::<TypeRegistry>();
+
+        let mut components: Vec<ComponentInfo> = storage
+            .entity_component_type_ids(self.id)
+            .into_iter()
+            .map(|type_id| {
+                let registration = registry
+                    .as_ref()
+                    .and_then(|registry| registry.get(&type_id));
+
+                ComponentInfo {
+                    type_id,
+                    name: registration.map_or_else(
+                        || format!("{type_id:?}"),
+                        |registration| registration.name.to_owned(),
+                    ),
+                    size: registration.map_or(0, |registration| registration.size),
+                }
+            })
+            .chain(
+                storage
+                    .entity_dynamic_component_names(self.id)
+                    .into_iter()
+                    .map(|name| ComponentInfo {
+                        type_id: TypeId::of::<DynamicComponent>(),
+                        name: name.to_owned(),
+                        size: size_of::<DynamicComponent>(),
+                    }),
+            )
+            .collect();
+
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        components
+    }
+}
+
+impl std::fmt::Debug for EntityRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntityRef").field("id", &self.id).finish()
+    }
+}
@@ -1,136 +1,1434 @@
+mod labels;
 pub mod query;
 pub mod query_entity;
 
 use std::{
-    any::{Any, TypeId},
-    cell::RefCell,
-    collections::HashMap,
+    alloc::Layout,
+    any::{type_name, type_name_of_val, Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::{HashMap, HashSet},
     rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
     vec,
 };
 
+use crate::checkpoint::WorldCheckpoint;
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::Violation;
+use crate::dynamic_component::{DynamicComponent, DynamicComponentDescriptor};
 use crate::ecs_errors::ECSError;
+use crate::memory_report::{self, ColumnMemory, MemoryReport};
+use crate::type_registry::CloneFn;
+use crate::world_builder::EntityIdRecycling;
+use labels::Labels;
 
 pub type Component = Rc<RefCell<dyn Any>>;
 pub type Components = HashMap<TypeId, Vec<Option<Component>>>;
+type ComponentConstructor = fn(Box<dyn Any>) -> Component;
+
+/**
+Column of a component type as seen by queries. Zero-sized components have no per-entity
+storage: every entity with the bit set shares the same instance.
+*/
+#[derive(Debug, Clone, Copy)]
+pub enum ColumnRef<'a> {
+    PerEntity(&'a [Option<Component>]),
+    Shared(Option<&'a Component>),
+}
+
+impl<'a> ColumnRef<'a> {
+    pub fn get(&self, index: usize) -> Option<&'a Component> {
+        match self {
+            ColumnRef::PerEntity(components) => components.get(index)?.as_ref(),
+            ColumnRef::Shared(component) => *component,
+        }
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct EntityStorage {
     components: Components,
     component_bitmasks: HashMap<TypeId, u32>,
+    component_constructors: HashMap<TypeId, ComponentConstructor>,
     entity_component_bitmasks: Vec<u32>,
     next_free_entity_id: usize,
+    dynamic_components: HashMap<String, Vec<Option<Component>>>,
+    dynamic_component_bitmasks: HashMap<String, u32>,
+    dynamic_component_descriptors: HashMap<String, DynamicComponentDescriptor>,
+    groups: Labels,
+    tags: Labels,
+    disabled_entities: Vec<bool>,
+    // Number of `true` flags in `disabled_entities`, removed entities included.
+    disabled_count: usize,
+    // Number of entities having each bit of the component masks.
+    component_populations: [usize; 32],
+    // Zero-sized types only store their bit in the entity masks, and share a single instance
+    // created the first time one is added.
+    zero_sized_components: HashMap<TypeId, Option<Component>>,
+    component_names: HashMap<TypeId, &'static str>,
+    component_layouts: HashMap<TypeId, Layout>,
+    id_recycling: EntityIdRecycling,
+    // ID of the next reserved or appended entity, shared with `EntityReserver`s on other
+    // threads. Slots past the table up to it are reserved entities not added yet, so new slots
+    // take their ID from it too.
+    next_entity_id: Arc<AtomicUsize>,
+    // Slots of reserved entities that are still waiting for their first component.
+    reserved_slots: HashSet<usize>,
+    // Reserved entities added to the table since the last `flush_reserved_entities`.
+    unflushed_reserved: Vec<usize>,
+    // Capacity given to the columns of newly registered components.
+    entity_capacity: usize,
+    component_limit: Option<usize>,
 }
 
 impl EntityStorage {
     pub fn register_component<T: Any + 'static>(&mut self) {
         let type_id = TypeId::of::<T>();
 
-        self.components.insert(type_id, vec![]);
+        // Registering again would reset the column and take a new bit.
+        if self.component_bitmasks.contains_key(&type_id) {
+            return;
+        }
+
+        if std::mem::size_of::<T>() == 0 {
+            self.zero_sized_components.insert(type_id, None);
+        } else {
+            // Entities created before the registration get an empty slot, so the column is as
+            // long as the entity table.
+            let entity_count = self.entity_component_bitmasks.len();
+            let mut components = Vec::with_capacity(self.entity_capacity.max(entity_count));
+
+            components.resize(entity_count, None);
+            self.components.insert(type_id, components);
+        }
         self.component_bitmasks
-            .insert(type_id, 1 << self.component_bitmasks.len());
+            .insert(type_id, self.next_component_bitmask());
+        self.component_names.insert(type_id, type_name::<T>());
+        self.component_layouts.insert(type_id, Layout::new::<T>());
+        self.component_constructors.insert(type_id, |data| {
+            let data = data.downcast::<T>().unwrap();
+            Rc::new(RefCell::new(*data))
+        });
+    }
+
+    pub fn register_dynamic_component(
+        &mut self,
+        descriptor: DynamicComponentDescriptor,
+    ) -> Result<(), ECSError> {
+        let name = descriptor.name.clone();
+
+        // Like typed components, registering again keeps the column and the bit.
+        if let Some(registered) = self.dynamic_component_descriptors.get(&name) {
+            if *registered != descriptor {
+                return Err(ECSError::DynamicComponentMismatch);
+            }
+
+            return Ok(());
+        }
+
+        let bitmask = self.next_component_bitmask();
+
+        self.dynamic_components.insert(
+            name.clone(),
+            vec![None; self.entity_component_bitmasks.len()],
+        );
+        self.dynamic_component_bitmasks
+            .insert(name.clone(), bitmask);
+        self.dynamic_component_descriptors.insert(name, descriptor);
+
+        Ok(())
     }
 
     pub fn create_entity(&mut self) -> &mut Self {
-        if let Some((index, _)) = self
-            .entity_component_bitmasks
-            .iter()
-            .enumerate()
-            .find(|(_index, mask)| **mask == 0)
-        {
+        let free_slot = match self.id_recycling {
+            EntityIdRecycling::Reuse => self
+                .entity_component_bitmasks
+                .iter()
+                .enumerate()
+                .position(|(index, mask)| *mask == 0 && !self.reserved_slots.contains(&index)),
+            EntityIdRecycling::Never => None,
+        };
+
+        if let Some(index) = free_slot {
             self.next_free_entity_id = index;
+            self.groups.clear_entity(index);
+            self.tags.clear_entity(index);
+            self.set_disabled_flag(index, false);
         } else {
-            self.components
-                .iter_mut()
-                .for_each(|(_key, components)| components.push(None));
-
-            self.entity_component_bitmasks.push(0);
-            self.next_free_entity_id = self.entity_component_bitmasks.len() - 1;
+            self.next_free_entity_id = self.append_entity_slot();
         }
 
         self
     }
 
+    /**
+    Reserves the ID of a new entity without mutable access. The entity is added to the table by
+    the next call to `flush_reserved_entities`, and accepts components until it gets its first.
+    */
+    pub fn reserve_entity(&self) -> usize {
+        self.next_entity_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /**
+    Shares the reserved ID counter, so other threads can reserve entities.
+    */
+    pub fn reserved_entity_counter(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.next_entity_id)
+    }
+
+    /**
+    Adds the entities reserved since the last flush to the table, returning their IDs.
+    */
+    pub fn flush_reserved_entities(&mut self) -> Vec<usize> {
+        let end = self.next_entity_id.load(Ordering::Relaxed);
+
+        while self.entity_component_bitmasks.len() < end {
+            self.push_reserved_slot();
+        }
+
+        std::mem::take(&mut self.unflushed_reserved)
+    }
+
+    // Appends a slot whose ID is taken from the reserved counter, so it can't collide with an
+    // entity reserved on another thread in the meantime.
+    fn append_entity_slot(&mut self) -> usize {
+        let index = self.next_entity_id.fetch_add(1, Ordering::Relaxed);
+
+        while self.entity_component_bitmasks.len() < index {
+            self.push_reserved_slot();
+        }
+
+        self.push_entity_slot()
+    }
+
+    fn push_reserved_slot(&mut self) {
+        let index = self.push_entity_slot();
+
+        self.reserved_slots.insert(index);
+        self.unflushed_reserved.push(index);
+    }
+
+    fn push_entity_slot(&mut self) -> usize {
+        self.components
+            .iter_mut()
+            .for_each(|(_key, components)| components.push(None));
+        self.dynamic_components
+            .iter_mut()
+            .for_each(|(_key, components)| components.push(None));
+
+        self.entity_component_bitmasks.push(0);
+        self.groups.push_entity();
+        self.tags.push_entity();
+        self.disabled_entities.push(false);
+        self.entity_component_bitmasks.len() - 1
+    }
+
     pub fn with_component(&mut self, data: impl Any) -> Result<&mut Self, ECSError> {
         let type_id = data.type_id();
         let index = self.next_free_entity_id;
 
-        if let Some(components) = self.components.get_mut(&type_id) {
-            let component = components
-                .get_mut(index)
-                .ok_or(ECSError::CreateComponentNeverCalled)?;
-            *component = Some(Rc::new(RefCell::new(data)));
+        if !self.component_bitmasks.contains_key(&type_id) {
+            return Err(ECSError::ComponentNotRegistered {
+                type_name: type_name_of_val(&data).to_owned(),
+            });
+        }
 
-            let bitmask = self.component_bitmasks.get(&type_id).unwrap();
-            self.entity_component_bitmasks[index] |= *bitmask;
-        } else {
-            return Err(ECSError::ComponentNotRegistered.into());
+        if index >= self.entity_component_bitmasks.len() {
+            return Err(ECSError::CreateComponentNeverCalled);
         }
+
+        self.add_component_to_entity(index, data)?;
         Ok(self)
     }
 
+    pub fn next_free_entity_id(&self) -> usize {
+        self.next_free_entity_id
+    }
+
+    /**
+    Makes the empty slot at `index` the entity being built, so it accepts components, like
+    when filling the slots of a loaded snapshot.
+    */
+    pub fn set_next_free_entity_id(&mut self, index: usize) {
+        self.next_free_entity_id = index;
+    }
+
     pub fn get_bitmask(&self, type_id: &TypeId) -> Option<u32> {
         self.component_bitmasks.get(type_id).copied()
     }
 
+    pub fn get_dynamic_bitmask(&self, name: &str) -> Option<u32> {
+        self.dynamic_component_bitmasks.get(name).copied()
+    }
+
+    pub fn dynamic_component_descriptor(&self, name: &str) -> Option<&DynamicComponentDescriptor> {
+        self.dynamic_component_descriptors.get(name)
+    }
+
+    pub fn add_dynamic_component_to_entity(
+        &mut self,
+        index: usize,
+        component: DynamicComponent,
+    ) -> Result<(), ECSError> {
+        let descriptor = self
+            .dynamic_component_descriptors
+            .get(&component.name)
+            .ok_or_else(|| ECSError::ComponentNotRegistered {
+                type_name: component.name.clone(),
+            })?;
+
+        if !descriptor.matches(&component) {
+            return Err(ECSError::DynamicComponentMismatch);
+        }
+
+        if !self.accepts_components(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        let bitmask = self.dynamic_component_bitmasks[&component.name];
+        let components = self.dynamic_components.get_mut(&component.name).unwrap();
+
+        components[index] = Some(Rc::new(RefCell::new(component)));
+        self.set_entity_mask(index, self.entity_component_bitmasks[index] | bitmask);
+
+        Ok(())
+    }
+
+    pub fn remove_dynamic_component_from_entity(
+        &mut self,
+        index: usize,
+        name: &str,
+    ) -> Result<(), ECSError> {
+        let mask =
+            self.get_dynamic_bitmask(name)
+                .ok_or_else(|| ECSError::ComponentNotRegistered {
+                    type_name: name.to_owned(),
+                })?;
+
+        if index >= self.entity_component_bitmasks.len() {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        if self.has_component(index, mask) {
+            self.set_entity_mask(index, self.entity_component_bitmasks[index] & !mask);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_dynamic_component(
+        &self,
+        index: usize,
+        name: &str,
+    ) -> Result<Ref<'_, DynamicComponent>, ECSError> {
+        let component = self.get_dynamic_component_cell(index, name)?.borrow();
+
+        Ok(Ref::map(component, |any| {
+            any.downcast_ref::<DynamicComponent>().unwrap()
+        }))
+    }
+
+    pub fn get_dynamic_component_mut(
+        &self,
+        index: usize,
+        name: &str,
+    ) -> Result<RefMut<'_, DynamicComponent>, ECSError> {
+        let component = self.get_dynamic_component_cell(index, name)?.borrow_mut();
+
+        Ok(RefMut::map(component, |any| {
+            any.downcast_mut::<DynamicComponent>().unwrap()
+        }))
+    }
+
+    pub fn dynamic_component_column(&self, name: &str) -> Option<&Vec<Option<Component>>> {
+        self.dynamic_components.get(name)
+    }
+
     pub fn remove_entity_component<T: Any>(&mut self, index: usize) -> Result<(), ECSError> {
-        let type_id = TypeId::of::<T>();
+        self.remove_component_dynamic(index, &TypeId::of::<T>())
+            .map_err(name_unregistered::<T>)
+    }
 
-        let mask = if let Some(mask) = self.component_bitmasks.get(&type_id) {
+    pub fn remove_component_dynamic(
+        &mut self,
+        index: usize,
+        type_id: &TypeId,
+    ) -> Result<(), ECSError> {
+        let mask = if let Some(mask) = self.component_bitmasks.get(type_id) {
             mask
         } else {
-            return Err(ECSError::ComponentNotRegistered.into());
+            return Err(self.not_registered(type_id));
         };
 
+        if index >= self.entity_component_bitmasks.len() {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
         if self.has_component(index, *mask) {
-            self.entity_component_bitmasks[index] ^= *mask;
+            self.set_entity_mask(index, self.entity_component_bitmasks[index] & !*mask);
         }
 
         Ok(())
     }
 
-    pub fn add_component_to_entity(
+    /**
+    Adds a component to an entity. If the slot still holds the allocation of a component of
+    the same type that was removed or replaced, and nothing else references it, the new value
+    is written into it instead of allocating a new one. This keeps high-churn entities like
+    bullets from going through the allocator each time they're respawned.
+    */
+    pub fn add_component_to_entity<T: Any>(
         &mut self,
         index: usize,
-        data: impl Any,
+        data: T,
     ) -> Result<(), ECSError> {
-        let type_id = data.type_id();
-        let mask = if let Some(mask) = self.component_bitmasks.get(&type_id) {
-            mask
-        } else {
-            return Err(ECSError::ComponentNotRegistered.into());
-        };
-        self.entity_component_bitmasks[index] |= *mask;
+        let type_id = TypeId::of::<T>();
 
-        let components = self.components.get_mut(&type_id).unwrap();
-        components[index] = Some(Rc::new(RefCell::new(data)));
+        if !self.accepts_components(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
 
-        Ok(())
+        if let Some(component) = self.recyclable_component_mut::<T>(index) {
+            *component = data;
+            self.set_entity_mask(
+                index,
+                self.entity_component_bitmasks[index] | self.component_bitmasks[&type_id],
+            );
+            return Ok(());
+        }
+
+        self.insert_component(index, type_id, || Rc::new(RefCell::new(data)))
+            .map_err(name_unregistered::<T>)
+    }
+
+    fn recyclable_component_mut<T: Any>(&mut self, index: usize) -> Option<&mut T> {
+        let component = self
+            .components
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut(index)?
+            .as_mut()?;
+
+        Rc::get_mut(component)?.get_mut().downcast_mut::<T>()
+    }
+
+    /**
+    Adds a boxed component to an entity without knowing its type at compile time.
+    `type_id` must be the type of the boxed value.
+    */
+    pub fn add_component_dynamic(
+        &mut self,
+        index: usize,
+        type_id: TypeId,
+        data: Box<dyn Any>,
+    ) -> Result<(), ECSError> {
+        let constructor = *self
+            .component_constructors
+            .get(&type_id)
+            .ok_or_else(|| self.not_registered(&type_id))?;
+
+        if (*data).type_id() != type_id {
+            return Err(ECSError::DowncastToWrongType);
+        }
+
+        self.insert_component(index, type_id, || constructor(data))
     }
 
     pub fn remove_entity(&mut self, index: usize) -> Result<(), ECSError> {
-        match self.entity_component_bitmasks.get_mut(index) {
-            Some(map) => *map = 0,
-            None => return Err(ECSError::EntityDoesNotExist.into()),
+        if index >= self.entity_component_bitmasks.len() {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        self.set_entity_mask(index, 0);
+        if self.next_free_entity_id == index {
+            self.next_free_entity_id = self.entity_component_bitmasks.len();
+        }
+        self.reserved_slots.remove(&index);
+        self.groups.clear_entity(index);
+        self.tags.clear_entity(index);
+        self.set_disabled_flag(index, false);
+
+        Ok(())
+    }
+
+    pub fn set_entity_disabled(&mut self, index: usize, disabled: bool) -> Result<(), ECSError> {
+        if !self.is_alive(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        self.set_disabled_flag(index, disabled);
+        Ok(())
+    }
+
+    pub fn is_disabled(&self, index: usize) -> bool {
+        self.disabled_entities.get(index).copied().unwrap_or(false)
+    }
+
+    /**
+    Whether some entity may be disabled. Removed entities can keep their flag, so this can
+    be `true` while every living entity is enabled, but never the other way around.
+    */
+    pub fn has_disabled_entities(&self) -> bool {
+        self.disabled_count > 0
+    }
+
+    fn set_disabled_flag(&mut self, index: usize, disabled: bool) {
+        if self.disabled_entities[index] != disabled {
+            self.disabled_entities[index] = disabled;
+
+            if disabled {
+                self.disabled_count += 1;
+            } else {
+                self.disabled_count -= 1;
+            }
+        }
+    }
+
+    /**
+    Number of entities having every component of `mask` according to the population counts,
+    without looking at the entities. Only exact for masks of a single component; for more,
+    it's the population of the rarest one, an upper bound.
+    */
+    pub fn population(&self, mask: u32) -> usize {
+        bits(mask)
+            .map(|bit| self.component_populations[bit])
+            .min()
+            .unwrap_or_else(|| self.living_entity_count())
+    }
+
+    // Changes the component mask of an entity, keeping the population of each bit in sync.
+    fn set_entity_mask(&mut self, index: usize, mask: u32) {
+        let previous = std::mem::replace(&mut self.entity_component_bitmasks[index], mask);
+
+        for bit in bits(mask & !previous) {
+            self.component_populations[bit] += 1;
+        }
+
+        for bit in bits(previous & !mask) {
+            self.component_populations[bit] -= 1;
+        }
+    }
+
+    // Recounts the populations and disabled flags after the tables were replaced as a whole.
+    fn recount(&mut self) {
+        self.component_populations = [0; 32];
+
+        for mask in &self.entity_component_bitmasks {
+            for bit in bits(*mask) {
+                self.component_populations[bit] += 1;
+            }
+        }
+
+        self.disabled_count = self
+            .disabled_entities
+            .iter()
+            .filter(|disabled| **disabled)
+            .count();
+    }
+
+    pub fn add_entity_to_group(&mut self, index: usize, group: &str) -> Result<(), ECSError> {
+        if !self.is_alive(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        let bitmask = self.groups.intern(group).ok_or(ECSError::TooManyGroups)?;
+
+        self.groups.insert(index, bitmask);
+        Ok(())
+    }
+
+    pub fn remove_entity_from_group(&mut self, index: usize, group: &str) -> Result<(), ECSError> {
+        if !self.is_alive(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        self.groups.remove(index, group);
+        Ok(())
+    }
+
+    pub fn get_group_bitmask(&self, group: &str) -> Option<u64> {
+        self.groups.bitmask(group)
+    }
+
+    pub fn entity_group_bitmask(&self, index: usize) -> u64 {
+        self.groups.entity_bitmask(index)
+    }
+
+    pub fn entity_groups(&self, index: usize) -> Vec<&str> {
+        self.groups.entity_labels(index)
+    }
+
+    pub fn add_entity_tag(&mut self, index: usize, tag: &str) -> Result<(), ECSError> {
+        if !self.is_alive(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
         }
 
+        let bitmask = self.tags.intern(tag).ok_or(ECSError::TooManyTags)?;
+
+        self.tags.insert(index, bitmask);
+        Ok(())
+    }
+
+    pub fn remove_entity_tag(&mut self, index: usize, tag: &str) -> Result<(), ECSError> {
+        if !self.is_alive(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        self.tags.remove(index, tag);
         Ok(())
     }
 
+    pub fn get_tag_bitmask(&self, tag: &str) -> Option<u64> {
+        self.tags.bitmask(tag)
+    }
+
+    pub fn entity_tag_bitmask(&self, index: usize) -> u64 {
+        self.tags.entity_bitmask(index)
+    }
+
+    pub fn entity_tags(&self, index: usize) -> Vec<&str> {
+        self.tags.entity_labels(index)
+    }
+
+    pub fn entity_has_component(&self, index: usize, type_id: &TypeId) -> bool {
+        match (
+            self.component_bitmasks.get(type_id),
+            self.entity_component_bitmasks.get(index),
+        ) {
+            (Some(mask), Some(entity_mask)) => entity_mask & mask == *mask,
+            _ => false,
+        }
+    }
+
+    pub fn entity_has_dynamic_component(&self, index: usize, name: &str) -> bool {
+        match (
+            self.dynamic_component_bitmasks.get(name),
+            self.entity_component_bitmasks.get(index),
+        ) {
+            (Some(mask), Some(entity_mask)) => entity_mask & mask == *mask,
+            _ => false,
+        }
+    }
+
+    pub fn entity_dynamic_component_names(&self, index: usize) -> Vec<&str> {
+        self.dynamic_component_bitmasks
+            .keys()
+            .filter(|name| self.entity_has_dynamic_component(index, name))
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn entities_with_component<'a>(
+        &'a self,
+        type_id: &TypeId,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let mask = self.component_bitmasks.get(type_id).copied();
+
+        self.entity_component_bitmasks
+            .iter()
+            .enumerate()
+            .filter(move |(_index, entity_mask)| {
+                mask.is_some_and(|mask| *entity_mask & mask == mask)
+            })
+            .map(|(index, _entity_mask)| index)
+    }
+
+    /**
+    Every registered component type, dynamic components excluded.
+    */
+    pub fn component_type_ids(&self) -> impl Iterator<Item = &TypeId> {
+        self.component_bitmasks.keys()
+    }
+
+    pub fn is_zero_sized(&self, type_id: &TypeId) -> bool {
+        self.zero_sized_components.contains_key(type_id)
+    }
+
+    pub fn dynamic_component_names(&self) -> impl Iterator<Item = &str> {
+        self.dynamic_component_bitmasks.keys().map(String::as_str)
+    }
+
+    /**
+    Number of living entities whose mask contains every bit of `bitmask`.
+    */
+    pub fn count_entities_with(&self, bitmask: u32) -> usize {
+        self.entity_component_bitmasks
+            .iter()
+            .filter(|mask| **mask != 0 && *mask & bitmask == bitmask)
+            .count()
+    }
+
+    pub fn living_entity_count(&self) -> usize {
+        self.entity_component_bitmasks
+            .iter()
+            .filter(|mask| **mask != 0)
+            .count()
+    }
+
+    pub fn entity_component_type_ids(&self, index: usize) -> Vec<TypeId> {
+        self.component_bitmasks
+            .keys()
+            .filter(|type_id| self.entity_has_component(index, type_id))
+            .copied()
+            .collect()
+    }
+
+    /**
+    Every component of an entity with its type, in registration order. Dynamic components
+    are given the `TypeId` of `DynamicComponent`.
+    */
+    pub fn entity_components(&self, index: usize) -> Vec<(TypeId, &Component)> {
+        let entity_mask = self
+            .entity_component_bitmasks
+            .get(index)
+            .copied()
+            .unwrap_or(0);
+
+        let mut components: Vec<(u32, TypeId, &Component)> = self
+            .component_bitmasks
+            .iter()
+            .filter(|(_type_id, mask)| entity_mask & **mask != 0)
+            .filter_map(|(type_id, mask)| {
+                let component = self.column(type_id)?.get(index)?;
+                Some((*mask, *type_id, component))
+            })
+            .chain(
+                self.dynamic_component_bitmasks
+                    .iter()
+                    .filter(|(_name, mask)| entity_mask & **mask != 0)
+                    .filter_map(|(name, mask)| {
+                        let component = self.dynamic_components.get(name)?.get(index)?.as_ref()?;
+                        Some((*mask, TypeId::of::<DynamicComponent>(), component))
+                    }),
+            )
+            .collect();
+
+        components.sort_by_key(|(mask, _type_id, _component)| *mask);
+        components
+            .into_iter()
+            .map(|(_mask, type_id, component)| (type_id, component))
+            .collect()
+    }
+
+    pub fn get_component<T: Any>(&self, index: usize) -> Result<Ref<'_, T>, ECSError> {
+        let component = self
+            .get_component_by_type_id(index, &TypeId::of::<T>())
+            .map_err(name_unregistered::<T>)?;
+
+        Ok(Ref::map(component, |any| any.downcast_ref::<T>().unwrap()))
+    }
+
+    pub fn get_component_mut<T: Any>(&self, index: usize) -> Result<RefMut<'_, T>, ECSError> {
+        let component = self
+            .get_component_by_type_id_mut(index, &TypeId::of::<T>())
+            .map_err(name_unregistered::<T>)?;
+
+        Ok(RefMut::map(component, |any| {
+            any.downcast_mut::<T>().unwrap()
+        }))
+    }
+
+    /**
+    Like `get_component`, but fails with `ComponentAlreadyBorrowed` instead of panicking if the
+    component is mutably borrowed.
+    */
+    pub fn try_get_component<T: Any>(&self, index: usize) -> Result<Ref<'_, T>, ECSError> {
+        let type_id = TypeId::of::<T>();
+        let component = self
+            .get_component_cell(index, &type_id)
+            .map_err(name_unregistered::<T>)?
+            .try_borrow()
+            .map_err(|_| self.already_borrowed(index, &type_id))?;
+
+        Ok(Ref::map(component, |any| any.downcast_ref::<T>().unwrap()))
+    }
+
+    /**
+    Like `get_component_mut`, but fails with `ComponentAlreadyBorrowed` instead of panicking if
+    the component is borrowed.
+    */
+    pub fn try_get_component_mut<T: Any>(&self, index: usize) -> Result<RefMut<'_, T>, ECSError> {
+        let type_id = TypeId::of::<T>();
+        let component = self
+            .get_component_cell(index, &type_id)
+            .map_err(name_unregistered::<T>)?
+            .try_borrow_mut()
+            .map_err(|_| self.already_borrowed(index, &type_id))?;
+
+        Ok(RefMut::map(component, |any| {
+            any.downcast_mut::<T>().unwrap()
+        }))
+    }
+
+    /**
+    Borrows a component without checking that the entity exists and has it, or that the type
+    is registered. Only checked with debug assertions.
+
+    # Safety
+    The entity at `index` must be alive and have a component of type `T`.
+    */
+    pub unsafe fn get_component_unchecked<T: Any>(&self, index: usize) -> Ref<'_, T> {
+        let component = self.get_component_cell_unchecked(index, &TypeId::of::<T>());
+
+        Ref::map(component.borrow(), |any| {
+            &*(any as *const dyn Any as *const T)
+        })
+    }
+
+    /**
+    Mutable version of `get_component_unchecked`.
+
+    # Safety
+    The entity at `index` must be alive and have a component of type `T`.
+    */
+    pub unsafe fn get_component_unchecked_mut<T: Any>(&self, index: usize) -> RefMut<'_, T> {
+        let component = self.get_component_cell_unchecked(index, &TypeId::of::<T>());
+
+        RefMut::map(component.borrow_mut(), |any| {
+            &mut *(any as *mut dyn Any as *mut T)
+        })
+    }
+
+    pub fn get_component_by_type_id(
+        &self,
+        index: usize,
+        type_id: &TypeId,
+    ) -> Result<Ref<'_, dyn Any>, ECSError> {
+        Ok(self.get_component_cell(index, type_id)?.borrow())
+    }
+
+    pub fn get_component_by_type_id_mut(
+        &self,
+        index: usize,
+        type_id: &TypeId,
+    ) -> Result<RefMut<'_, dyn Any>, ECSError> {
+        Ok(self.get_component_cell(index, type_id)?.borrow_mut())
+    }
+
+    /**
+    Column of a registered component type, for queries.
+    */
+    pub fn column(&self, type_id: &TypeId) -> Option<ColumnRef<'_>> {
+        match self.zero_sized_components.get(type_id) {
+            Some(shared) => Some(ColumnRef::Shared(shared.as_ref())),
+            None => self
+                .components
+                .get(type_id)
+                .map(|components| ColumnRef::PerEntity(components)),
+        }
+    }
+
+    fn get_component_cell(&self, index: usize, type_id: &TypeId) -> Result<&Component, ECSError> {
+        let column = self
+            .column(type_id)
+            .ok_or_else(|| self.not_registered(type_id))?;
+
+        if index >= self.entity_component_bitmasks.len() {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        column
+            .get(index)
+            .filter(|_| self.entity_has_component(index, type_id))
+            .ok_or_else(|| ECSError::ComponentDoesNotExist {
+                id: index,
+                type_name: self.component_name(type_id),
+            })
+    }
+
+    unsafe fn get_component_cell_unchecked(&self, index: usize, type_id: &TypeId) -> &Component {
+        debug_assert!(
+            self.entity_has_component(index, type_id),
+            "Attempted to reference component data that does not exist."
+        );
+
+        self.column(type_id)
+            .and_then(|column| column.get(index))
+            .unwrap_unchecked()
+    }
+
+    // Stores a component and sets its bit. `component` is only called when the value is
+    // actually stored, so adding a zero-sized component that already has its shared instance
+    // doesn't allocate.
+    fn insert_component(
+        &mut self,
+        index: usize,
+        type_id: TypeId,
+        component: impl FnOnce() -> Component,
+    ) -> Result<(), ECSError> {
+        let mask = *self
+            .component_bitmasks
+            .get(&type_id)
+            .ok_or_else(|| self.not_registered(&type_id))?;
+
+        if !self.accepts_components(index) {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        match self.zero_sized_components.get_mut(&type_id) {
+            Some(shared) => {
+                shared.get_or_insert_with(component);
+            }
+            None => {
+                let slot = self
+                    .components
+                    .get_mut(&type_id)
+                    .and_then(|components| components.get_mut(index))
+                    .ok_or(ECSError::EntityDoesNotExist { id: index })?;
+                *slot = Some(component());
+            }
+        }
+
+        self.set_entity_mask(index, self.entity_component_bitmasks[index] | mask);
+        Ok(())
+    }
+
+    fn get_dynamic_component_cell(&self, index: usize, name: &str) -> Result<&Component, ECSError> {
+        let components =
+            self.dynamic_components
+                .get(name)
+                .ok_or_else(|| ECSError::ComponentNotRegistered {
+                    type_name: name.to_owned(),
+                })?;
+
+        if index >= self.entity_component_bitmasks.len() {
+            return Err(ECSError::EntityDoesNotExist { id: index });
+        }
+
+        components[index]
+            .as_ref()
+            .filter(|_| self.has_component(index, self.dynamic_component_bitmasks[name]))
+            .ok_or_else(|| ECSError::ComponentDoesNotExist {
+                id: index,
+                type_name: name.to_owned(),
+            })
+    }
+
+    /**
+    Checks that every column has a slot per entity, and that every bit set in the entity
+    bitmasks has a component in its slot. Slots without their bit are fine, they keep the
+    allocation of removed components around.
+    */
+    #[cfg(feature = "diagnostics")]
+    pub fn validate(&self) -> Vec<Violation> {
+        let entity_count = self.entity_count();
+        let mut columns: Vec<(String, u32, &Vec<Option<Component>>)> = self
+            .components
+            .iter()
+            .map(|(type_id, column)| {
+                (
+                    self.component_name(type_id),
+                    self.component_bitmasks[type_id],
+                    column,
+                )
+            })
+            .chain(self.dynamic_components.iter().map(|(name, column)| {
+                (name.clone(), self.dynamic_component_bitmasks[name], column)
+            }))
+            .collect();
+        let mut violations = vec![];
+
+        columns.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (component, mask, column) in columns {
+            if column.len() != entity_count {
+                violations.push(Violation::ColumnLength {
+                    component: component.clone(),
+                    length: column.len(),
+                    entity_count,
+                });
+            }
+
+            for (entity_id, _) in self
+                .entity_component_bitmasks
+                .iter()
+                .enumerate()
+                .filter(|(_, entity_mask)| **entity_mask & mask != 0)
+                .filter(|(entity_id, _)| column.get(*entity_id).is_none_or(Option::is_none))
+            {
+                violations.push(Violation::MissingComponent {
+                    entity_id,
+                    component: component.clone(),
+                });
+            }
+        }
+
+        violations
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entity_component_bitmasks.len()
+    }
+
+    /**
+    Whether components can be added to the entity at `index`: it's alive, or it was the last
+    one created or a reserved one and has no components yet. Removed entities can't get components back.
+    */
+    pub fn accepts_components(&self, index: usize) -> bool {
+        self.is_alive(index)
+            || self.reserved_slots.contains(&index)
+            || (index == self.next_free_entity_id && index < self.entity_component_bitmasks.len())
+    }
+
+    pub fn is_alive(&self, index: usize) -> bool {
+        self.entity_component_bitmasks
+            .get(index)
+            .is_some_and(|mask| *mask != 0)
+    }
+
+    pub fn reset(&mut self, entity_count: usize) {
+        for components in self
+            .components
+            .values_mut()
+            .chain(self.dynamic_components.values_mut())
+        {
+            components.clear();
+            components.resize_with(entity_count, || None);
+        }
+
+        self.entity_component_bitmasks.clear();
+        self.entity_component_bitmasks.resize(entity_count, 0);
+        self.groups.reset(&[], entity_count);
+        self.tags.reset(&[], entity_count);
+        self.disabled_entities.clear();
+        self.disabled_entities.resize(entity_count, false);
+        self.next_free_entity_id = 0;
+        self.reserved_slots.clear();
+        self.unflushed_reserved.clear();
+        self.next_entity_id.store(entity_count, Ordering::Relaxed);
+        self.recount();
+    }
+
+    /**
+    Drops the components left behind by removed entities, trims the slots past the last living
+    entity and shrinks every column to fit. Returns the number of slots trimmed.
+    */
+    pub fn compact(&mut self) -> usize {
+        let entity_count = match self.id_recycling {
+            EntityIdRecycling::Reuse => self
+                .entity_component_bitmasks
+                .iter()
+                .enumerate()
+                .rposition(|(index, mask)| *mask != 0 || self.reserved_slots.contains(&index))
+                .map_or(0, |index| index + 1),
+            EntityIdRecycling::Never => self.entity_component_bitmasks.len(),
+        };
+        let slot_count = self.entity_component_bitmasks.len();
+
+        // Trimming would hand out the IDs of entities reserved but not added yet again.
+        let entity_count = match self.next_entity_id.compare_exchange(
+            slot_count,
+            entity_count,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => entity_count,
+            Err(_) => slot_count,
+        };
+        let trimmed = self.entity_component_bitmasks.len() - entity_count;
+        let entity_masks = &self.entity_component_bitmasks;
+
+        for components in self
+            .components
+            .values_mut()
+            .chain(self.dynamic_components.values_mut())
+        {
+            components.truncate(entity_count);
+            for (component, mask) in components.iter_mut().zip(entity_masks) {
+                if *mask == 0 {
+                    *component = None;
+                }
+            }
+            components.shrink_to_fit();
+        }
+
+        self.entity_component_bitmasks.truncate(entity_count);
+        self.entity_component_bitmasks.shrink_to_fit();
+        self.groups.truncate(entity_count);
+        self.tags.truncate(entity_count);
+        self.disabled_entities.truncate(entity_count);
+        self.disabled_entities.shrink_to_fit();
+        self.recount();
+        self.next_free_entity_id = self.next_free_entity_id.min(entity_count);
+
+        trimmed
+    }
+
+    /**
+    Moves the living entities, reserved ones included, to the lowest slots while keeping their
+    order, then trims the slots left behind like `compact`. Returns the new slot of every
+    living entity, in order. Entities don't move when IDs are never reused.
+    */
+    pub fn defragment(&mut self) -> Vec<(usize, usize)> {
+        let mut moves = Vec::new();
+
+        if let EntityIdRecycling::Never = self.id_recycling {
+            return moves;
+        }
+
+        let live: Vec<usize> = (0..self.entity_component_bitmasks.len())
+            .filter(|index| {
+                self.entity_component_bitmasks[*index] != 0 || self.reserved_slots.contains(index)
+            })
+            .collect();
+
+        for (new_index, old_index) in live.into_iter().enumerate() {
+            moves.push((old_index, new_index));
+
+            if old_index == new_index {
+                continue;
+            }
+
+            // Every slot before `old_index` not taken by a living entity is empty, so the
+            // swap only moves an empty slot up.
+            for components in self
+                .components
+                .values_mut()
+                .chain(self.dynamic_components.values_mut())
+            {
+                components.swap(old_index, new_index);
+            }
+
+            self.entity_component_bitmasks.swap(old_index, new_index);
+            self.groups.swap_entities(old_index, new_index);
+            self.tags.swap_entities(old_index, new_index);
+            self.disabled_entities.swap(old_index, new_index);
+
+            if self.reserved_slots.remove(&old_index) {
+                self.reserved_slots.insert(new_index);
+            }
+        }
+
+        self.compact();
+        moves
+    }
+
+    /**
+    Memory used by entities and component columns, see `World::memory_report`. Resources are
+    left for the caller to fill in.
+    */
+    pub fn memory_report(&self, component_name: impl Fn(&TypeId) -> String) -> MemoryReport {
+        let mut components: Vec<ColumnMemory> = vec![];
+
+        for (type_id, column) in &self.components {
+            components.push(ColumnMemory {
+                name: component_name(type_id),
+                len: self.entities_with_component(type_id).count(),
+                capacity: column.capacity(),
+                heap_bytes: memory_report::column_bytes(column),
+            });
+        }
+
+        for (type_id, shared) in &self.zero_sized_components {
+            components.push(ColumnMemory {
+                name: component_name(type_id),
+                len: self.entities_with_component(type_id).count(),
+                capacity: 0,
+                heap_bytes: shared.as_ref().map_or(0, memory_report::component_bytes),
+            });
+        }
+
+        for (name, column) in &self.dynamic_components {
+            let mask = self.dynamic_component_bitmasks[name];
+
+            components.push(ColumnMemory {
+                name: name.clone(),
+                len: (0..self.entity_count())
+                    .filter(|index| self.has_component(*index, mask))
+                    .count(),
+                capacity: column.capacity(),
+                heap_bytes: memory_report::column_bytes(column),
+            });
+        }
+
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+
+        MemoryReport {
+            entity_count: self.entity_count(),
+            entity_bytes: self.entity_component_bitmasks.capacity() * size_of::<u32>()
+                + self.groups.heap_bytes()
+                + self.tags.heap_bytes()
+                + self.disabled_entities.capacity() * size_of::<bool>(),
+            components,
+            resources: vec![],
+        }
+    }
+
+    pub fn checkpoint(
+        &self,
+        clone_fn: impl Fn(&TypeId) -> Option<CloneFn>,
+    ) -> Result<WorldCheckpoint, ECSError> {
+        let mut checkpoint = WorldCheckpoint {
+            entity_component_bitmasks: self.entity_component_bitmasks.clone(),
+            entity_group_bitmasks: self.groups.entity_bitmasks().to_vec(),
+            entity_tag_bitmasks: self.tags.entity_bitmasks().to_vec(),
+            disabled_entities: self.disabled_entities.clone(),
+            ..Default::default()
+        };
+
+        for (type_id, components) in &self.components {
+            let mask = self.component_bitmasks[type_id];
+            let mut copies = Vec::with_capacity(components.len());
+
+            for (index, component) in components.iter().enumerate() {
+                let copy = match component {
+                    Some(component) if self.has_component(index, mask) => {
+                        let clone = clone_fn(type_id).ok_or(ECSError::TypeNotCloneable)?;
+                        Some(clone(&*component.borrow()))
+                    }
+                    _ => None,
+                };
+                copies.push(copy);
+            }
+
+            checkpoint.components.insert(*type_id, copies);
+        }
+
+        for (name, components) in &self.dynamic_components {
+            let mask = self.dynamic_component_bitmasks[name];
+            let copies = components
+                .iter()
+                .enumerate()
+                .map(|(index, component)| match component {
+                    Some(component) if self.has_component(index, mask) => component
+                        .borrow()
+                        .downcast_ref::<DynamicComponent>()
+                        .cloned(),
+                    _ => None,
+                })
+                .collect();
+
+            checkpoint.dynamic_components.insert(name.clone(), copies);
+        }
+
+        for (type_id, shared) in &self.zero_sized_components {
+            let Some(shared) = shared else {
+                continue;
+            };
+
+            if self.entities_with_component(type_id).next().is_some() {
+                let clone = clone_fn(type_id).ok_or(ECSError::TypeNotCloneable)?;
+                checkpoint
+                    .zero_sized_components
+                    .insert(*type_id, clone(&*shared.borrow()));
+            }
+        }
+
+        Ok(checkpoint)
+    }
+
+    // Columns are cleared and refilled rather than reallocated, so restoring the same
+    // checkpoint over and over (like during rollback) keeps reusing their capacity.
+    pub fn restore(
+        &mut self,
+        checkpoint: &WorldCheckpoint,
+        clone_fn: impl Fn(&TypeId) -> Option<CloneFn>,
+    ) -> Result<(), ECSError> {
+        let entity_count = checkpoint.entity_count();
+
+        self.reset(entity_count);
+        self.entity_component_bitmasks
+            .copy_from_slice(&checkpoint.entity_component_bitmasks);
+        self.groups
+            .reset(&checkpoint.entity_group_bitmasks, entity_count);
+        self.tags
+            .reset(&checkpoint.entity_tag_bitmasks, entity_count);
+        for (disabled, checkpoint_disabled) in self
+            .disabled_entities
+            .iter_mut()
+            .zip(&checkpoint.disabled_entities)
+        {
+            *disabled = *checkpoint_disabled;
+        }
+        self.recount();
+
+        for (type_id, copies) in &checkpoint.components {
+            let (Some(components), Some(constructor)) = (
+                self.components.get_mut(type_id),
+                self.component_constructors.get(type_id),
+            ) else {
+                continue;
+            };
+
+            for (component, copy) in components.iter_mut().zip(copies) {
+                if let Some(copy) = copy {
+                    let clone = clone_fn(type_id).ok_or(ECSError::TypeNotCloneable)?;
+                    *component = Some(constructor(clone(copy.as_ref())));
+                }
+            }
+        }
+
+        for (name, copies) in &checkpoint.dynamic_components {
+            let Some(components) = self.dynamic_components.get_mut(name) else {
+                continue;
+            };
+
+            for (component, copy) in components.iter_mut().zip(copies) {
+                if let Some(copy) = copy {
+                    *component = Some(Rc::new(RefCell::new(copy.clone())));
+                }
+            }
+        }
+
+        for (type_id, copy) in &checkpoint.zero_sized_components {
+            let (Some(shared), Some(constructor)) = (
+                self.zero_sized_components.get_mut(type_id),
+                self.component_constructors.get(type_id),
+            ) else {
+                continue;
+            };
+
+            if shared.is_none() {
+                let clone = clone_fn(type_id).ok_or(ECSError::TypeNotCloneable)?;
+                *shared = Some(constructor(clone(copy.as_ref())));
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+    Makes room for `capacity` entities in total, including in the columns of components
+    registered later.
+    */
+    pub fn reserve_entities(&mut self, capacity: usize) {
+        let additional = capacity.saturating_sub(self.entity_component_bitmasks.len());
+
+        self.entity_capacity = self.entity_capacity.max(capacity);
+        self.entity_component_bitmasks.reserve(additional);
+        self.disabled_entities.reserve(additional);
+        self.groups.reserve(additional);
+        self.tags.reserve(additional);
+        self.components
+            .values_mut()
+            .for_each(|components| components.reserve(additional));
+        self.dynamic_components
+            .values_mut()
+            .for_each(|components| components.reserve(additional));
+    }
+
+    pub fn set_id_recycling(&mut self, id_recycling: EntityIdRecycling) {
+        self.id_recycling = id_recycling;
+    }
+
+    pub fn set_component_limit(&mut self, limit: usize) {
+        self.component_limit = Some(limit.min(u32::BITS as usize));
+    }
+
+    /**
+    Whether no more component types fit, either because every bit of the component masks is
+    taken or because of the limit set with `set_component_limit`.
+    */
+    pub fn component_limit_reached(&self) -> bool {
+        let limit = self.component_limit.unwrap_or(u32::BITS as usize);
+
+        self.component_bitmasks.len() + self.dynamic_component_bitmasks.len() >= limit
+    }
+
+    /**
+    Type name of a registered component, or the debug form of its `TypeId` if it's unknown.
+    */
+    pub fn component_name(&self, type_id: &TypeId) -> String {
+        self.component_names
+            .get(type_id)
+            .map_or_else(|| format!("{type_id:?}"), |name| (*name).to_owned())
+    }
+
+    /**
+    Size and alignment of a registered component type.
+    */
+    pub fn component_layout(&self, type_id: &TypeId) -> Option<Layout> {
+        self.component_layouts.get(type_id).copied()
+    }
+
+    /**
+    `TypeId` of the registered component whose full type name is `name`.
+    */
+    pub fn component_id_by_type_name(&self, name: &str) -> Option<TypeId> {
+        self.component_names
+            .iter()
+            .find(|(_type_id, component_name)| **component_name == name)
+            .map(|(type_id, _component_name)| *type_id)
+    }
+
+    fn already_borrowed(&self, index: usize, type_id: &TypeId) -> ECSError {
+        ECSError::ComponentAlreadyBorrowed {
+            id: index,
+            type_name: self.component_name(type_id),
+        }
+    }
+
+    fn not_registered(&self, type_id: &TypeId) -> ECSError {
+        ECSError::ComponentNotRegistered {
+            type_name: self.component_name(type_id),
+        }
+    }
+
+    fn next_component_bitmask(&self) -> u32 {
+        assert!(
+            !self.component_limit_reached(),
+            "Attempted to register more component types than the component limit."
+        );
+
+        1 << (self.component_bitmasks.len() + self.dynamic_component_bitmasks.len())
+    }
+
     fn has_component(&self, index: usize, mask: u32) -> bool {
         self.entity_component_bitmasks[index] & mask == mask
     }
 }
 
+// Generic entry points know the name of the component type even when it wasn't registered.
+fn name_unregistered<T: Any>(error: ECSError) -> ECSError {
+    match error {
+        ECSError::ComponentNotRegistered { .. } => ECSError::ComponentNotRegistered {
+            type_name: type_name::<T>().to_owned(),
+        },
+        error => error,
+    }
+}
+
+// Indices of the set bits of a mask, lowest first.
+fn bits(mut mask: u32) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+
+        let bit = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(bit)
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::dynamic_component::FieldType;
     use crate::ecs_errors::ECSError;
     use std::any::TypeId;
 
     struct Health(pub u32);
     struct Speed(pub u32);
 
+    #[test]
+    fn registering_twice_keeps_the_components() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(10))?;
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        let health_mask = entities.get_bitmask(&TypeId::of::<Health>()).unwrap();
+        let speed_mask = entities.get_bitmask(&TypeId::of::<Speed>()).unwrap();
+
+        assert_ne!(health_mask, speed_mask);
+        assert_eq!(entities.entity_component_bitmasks[0], health_mask);
+        assert!(entities.components[&TypeId::of::<Health>()][0].is_some());
+        Ok(())
+    }
+
     #[test]
     fn register_an_entity() {
         let mut entities = EntityStorage::default();
@@ -142,6 +1440,69 @@ mod test {
         assert_eq!(health_components.len(), 0);
     }
 
+    #[test]
+    fn zero_sized_components_have_no_column() {
+        struct Dead;
+
+        let mut entities = EntityStorage::default();
+        entities.register_component::<Health>();
+        entities.register_component::<Dead>();
+
+        for health in 0..3 {
+            entities
+                .create_entity()
+                .with_component(Health(health))
+                .unwrap()
+                .with_component(Dead)
+                .unwrap();
+        }
+        entities.remove_entity_component::<Dead>(1).unwrap();
+
+        let dead_type_id = TypeId::of::<Dead>();
+        let dead: Vec<usize> = entities.entities_with_component(&dead_type_id).collect();
+
+        assert!(!entities.components.contains_key(&dead_type_id));
+        assert_eq!(dead, vec![0, 2]);
+        assert!(entities.get_component::<Dead>(2).is_ok());
+        assert!(matches!(
+            entities.get_component::<Dead>(1),
+            Err(ECSError::ComponentDoesNotExist { .. })
+        ));
+    }
+
+    #[test]
+    fn removed_component_allocations_are_reused() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+        entities.register_component::<Health>();
+
+        entities.create_entity().with_component(Health(100))?;
+        let first_allocation = entities.components[&TypeId::of::<Health>()][0]
+            .clone()
+            .unwrap();
+
+        entities.remove_entity(0)?;
+        entities.create_entity();
+        entities.add_component_to_entity(0, Health(50))?;
+
+        // Still referenced by `first_allocation`, so it can't be reused.
+        let second_allocation = entities.components[&TypeId::of::<Health>()][0]
+            .clone()
+            .unwrap();
+        assert!(!Rc::ptr_eq(&first_allocation, &second_allocation));
+
+        let second_allocation_ptr = Rc::as_ptr(&second_allocation);
+        drop(second_allocation);
+        entities.remove_entity(0)?;
+        entities.create_entity().with_component(Health(25))?;
+
+        let reused = entities.components[&TypeId::of::<Health>()][0]
+            .as_ref()
+            .unwrap();
+        assert_eq!(Rc::as_ptr(reused), second_allocation_ptr);
+        assert_eq!(entities.get_component::<Health>(0)?.0, 25);
+        Ok(())
+    }
+
     #[test]
     fn bitmask_updated_when_registering_entities() {
         let mut entities = EntityStorage::default();
@@ -230,6 +1591,10 @@ mod test {
         entities.remove_entity_component::<Health>(0)?;
 
         assert_eq!(entities.entity_component_bitmasks[0], 2);
+        assert!(matches!(
+            entities.remove_entity_component::<Health>(1),
+            Err(ECSError::EntityDoesNotExist { id: 1 })
+        ));
         Ok(())
     }
 
@@ -254,6 +1619,63 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn components_are_only_added_to_existing_entities() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        entities.remove_entity(0)?;
+
+        assert!(matches!(
+            entities.add_component_to_entity(0, Health(50)),
+            Err(ECSError::EntityDoesNotExist { id: 0 })
+        ));
+        assert!(matches!(
+            entities.add_component_to_entity(7, Health(50)),
+            Err(ECSError::EntityDoesNotExist { id: 7 })
+        ));
+        assert!(!entities.is_alive(0));
+        Ok(())
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn validation_finds_corrupted_slots() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        entities.create_entity().with_component(Health(50))?;
+        entities.remove_entity(1)?;
+
+        assert!(entities.validate().is_empty());
+
+        entities
+            .components
+            .get_mut(&TypeId::of::<Health>())
+            .unwrap()[0] = None;
+        entities.entity_component_bitmasks.push(0);
+
+        let component = type_name::<Health>().to_owned();
+
+        assert_eq!(
+            entities.validate(),
+            vec![
+                Violation::ColumnLength {
+                    component: component.clone(),
+                    length: 2,
+                    entity_count: 3,
+                },
+                Violation::MissingComponent {
+                    entity_id: 0,
+                    component,
+                },
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn remove_entity_by_id() -> Result<(), ECSError> {
         let mut entities = EntityStorage::default();
@@ -290,6 +1712,193 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn get_component_by_entity_id() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+        entities.create_entity().with_component(Health(100))?;
+
+        assert_eq!(entities.get_component::<Health>(0)?.0, 100);
+        assert!(matches!(
+            entities.get_component::<Speed>(0),
+            Err(ECSError::ComponentDoesNotExist { id: 0, type_name }) if type_name.ends_with("Speed")
+        ));
+        assert!(matches!(
+            entities.get_component::<Health>(1),
+            Err(ECSError::EntityDoesNotExist { id: 1 })
+        ));
+        assert!(matches!(
+            entities.get_component::<u32>(0),
+            Err(ECSError::ComponentNotRegistered { type_name }) if type_name == "u32"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn get_component_mut_by_entity_id() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        entities.get_component_mut::<Health>(0)?.0 -= 10;
+
+        assert_eq!(entities.get_component::<Health>(0)?.0, 90);
+        Ok(())
+    }
+
+    #[test]
+    fn dynamic_components_are_registered_once_per_name() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+        let mana = DynamicComponentDescriptor::new("Mana").with_field("current", FieldType::Int);
+
+        entities.register_dynamic_component(mana.clone())?;
+        entities.create_entity();
+        entities.add_dynamic_component_to_entity(0, mana.instantiate())?;
+        entities.register_dynamic_component(mana.clone())?;
+
+        assert!(matches!(
+            entities.register_dynamic_component(DynamicComponentDescriptor::new("Mana")),
+            Err(ECSError::DynamicComponentMismatch)
+        ));
+        assert_eq!(entities.get_dynamic_bitmask("Mana"), Some(1));
+        assert_eq!(entities.get_dynamic_component(0, "Mana")?.name, "Mana");
+        Ok(())
+    }
+
+    #[test]
+    fn dynamic_components_share_bitmasks_with_typed_ones() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+        let mana = DynamicComponentDescriptor::new("Mana");
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        entities.register_dynamic_component(mana.clone())?;
+        entities.register_component::<Speed>();
+
+        assert_eq!(entities.get_dynamic_bitmask("Mana"), Some(2));
+        assert_eq!(entities.get_bitmask(&TypeId::of::<Speed>()), Some(4));
+
+        entities.add_dynamic_component_to_entity(0, mana.instantiate())?;
+        assert_eq!(entities.entity_component_bitmasks[0], 3);
+        assert_eq!(entities.get_dynamic_component(0, "Mana")?.name, "Mana");
+
+        entities.remove_dynamic_component_from_entity(0, "Mana")?;
+        assert!(matches!(
+            entities.get_dynamic_component(0, "Mana"),
+            Err(ECSError::ComponentDoesNotExist { .. })
+        ));
+        assert!(matches!(
+            entities.add_dynamic_component_to_entity(0, DynamicComponent::new("Shield")),
+            Err(ECSError::ComponentNotRegistered { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn add_and_remove_components_by_type_id() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+        let type_id = TypeId::of::<Health>();
+
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+        entities.create_entity().with_component(Speed(1))?;
+
+        entities.add_component_dynamic(0, type_id, Box::new(Health(10)))?;
+        assert_eq!(entities.get_component::<Health>(0)?.0, 10);
+
+        assert!(matches!(
+            entities.add_component_dynamic(0, type_id, Box::new(Speed(2))),
+            Err(ECSError::DowncastToWrongType)
+        ));
+        assert!(matches!(
+            entities.add_component_dynamic(0, TypeId::of::<u8>(), Box::new(1_u8)),
+            Err(ECSError::ComponentNotRegistered { .. })
+        ));
+
+        entities.remove_component_dynamic(0, &type_id)?;
+        assert!(!entities.entity_has_component(0, &type_id));
+        Ok(())
+    }
+
+    #[test]
+    fn appended_slots_skip_reserved_ids() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+
+        let reserved = entities
+            .reserved_entity_counter()
+            .fetch_add(1, Ordering::Relaxed);
+        let created = entities
+            .create_entity()
+            .with_component(Health(1))?
+            .next_free_entity_id;
+
+        assert_eq!((reserved, created), (0, 1));
+        assert_eq!(entities.flush_reserved_entities(), vec![0]);
+        assert!(entities.accepts_components(0));
+        assert_eq!(entities.reserve_entity(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn restore_checkpoint_copies() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+        let clone_fn: CloneFn = |value| Box::new(value.downcast_ref::<u32>().copied().unwrap());
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+
+        let checkpoint = entities.checkpoint(|_| Some(clone_fn))?;
+
+        *entities.get_component_mut::<u32>(0)? = 10;
+        entities.remove_entity(1)?;
+        entities.create_entity();
+        entities.create_entity().with_component(3_u32)?;
+
+        entities.restore(&checkpoint, |_| Some(clone_fn))?;
+        entities.restore(&checkpoint, |_| Some(clone_fn))?;
+
+        assert_eq!(entities.entity_count(), 2);
+        assert_eq!(*entities.get_component::<u32>(0)?, 1);
+        assert_eq!(*entities.get_component::<u32>(1)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoint_fails_without_clone_functions() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+        entities.create_entity().with_component(Speed(1))?;
+
+        assert!(matches!(
+            entities.checkpoint(|_| None),
+            Err(ECSError::TypeNotCloneable)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn reset_empties_every_entity() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        entities.reset(3);
+
+        assert_eq!(entities.entity_count(), 3);
+        assert!(!entities.is_alive(0));
+        assert!(entities.components[&TypeId::of::<Health>()]
+            .iter()
+            .all(Option::is_none));
+        Ok(())
+    }
+
     #[test]
     fn should_not_add_component_back_after_deleting_twice() -> Result<(), ECSError> {
         let mut entities = EntityStorage::default();
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+/**
+Interned strings mapped to bits, with the set of them each entity has. Backs entity groups
+and tags, which only differ in how they are meant to be used. A label gets its bit the first
+time it's added to an entity, and there can be at most 64 of them.
+*/
+#[derive(Debug, Default)]
+pub struct Labels {
+    bitmasks: HashMap<String, u64>,
+    entity_bitmasks: Vec<u64>,
+}
+
+impl Labels {
+    pub fn bitmask(&self, label: &str) -> Option<u64> {
+        self.bitmasks.get(label).copied()
+    }
+
+    /**
+    Bit of `label`, assigning the next free one if the label is new. `None` once the 64 bits
+    are taken.
+    */
+    pub fn intern(&mut self, label: &str) -> Option<u64> {
+        if let Some(bitmask) = self.bitmasks.get(label) {
+            return Some(*bitmask);
+        }
+
+        let bitmask = 1_u64.checked_shl(self.bitmasks.len() as u32)?;
+        self.bitmasks.insert(label.to_owned(), bitmask);
+        Some(bitmask)
+    }
+
+    pub fn entity_bitmask(&self, index: usize) -> u64 {
+        self.entity_bitmasks.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn entity_bitmasks(&self) -> &[u64] {
+        &self.entity_bitmasks
+    }
+
+    pub fn insert(&mut self, index: usize, bitmask: u64) {
+        self.entity_bitmasks[index] |= bitmask;
+    }
+
+    pub fn remove(&mut self, index: usize, label: &str) {
+        if let Some(bitmask) = self.bitmasks.get(label) {
+            self.entity_bitmasks[index] &= !bitmask;
+        }
+    }
+
+    pub fn clear_entity(&mut self, index: usize) {
+        self.entity_bitmasks[index] = 0;
+    }
+
+    pub fn swap_entities(&mut self, a: usize, b: usize) {
+        self.entity_bitmasks.swap(a, b);
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.entity_bitmasks.reserve(additional);
+    }
+
+    pub fn push_entity(&mut self) {
+        self.entity_bitmasks.push(0);
+    }
+
+    /**
+    Replaces the labels of every entity, keeping the interned strings.
+    */
+    pub fn reset(&mut self, entity_bitmasks: &[u64], entity_count: usize) {
+        self.entity_bitmasks.clear();
+        self.entity_bitmasks.extend_from_slice(entity_bitmasks);
+        self.entity_bitmasks.resize(entity_count, 0);
+    }
+
+    pub fn heap_bytes(&self) -> usize {
+        self.entity_bitmasks.capacity() * size_of::<u64>()
+    }
+
+    pub fn truncate(&mut self, entity_count: usize) {
+        self.entity_bitmasks.truncate(entity_count);
+        self.entity_bitmasks.shrink_to_fit();
+    }
+
+    pub fn entity_labels(&self, index: usize) -> Vec<&str> {
+        let entity_bitmask = self.entity_bitmask(index);
+
+        self.bitmasks
+            .iter()
+            .filter(|(_label, bitmask)| entity_bitmask & **bitmask != 0)
+            .map(|(label, _bitmask)| label.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn labels_get_a_bit_each() {
+        let mut labels = Labels::default();
+
+        labels.push_entity();
+
+        let boss = labels.intern("boss").unwrap();
+        let flying = labels.intern("flying").unwrap();
+
+        assert_eq!((boss, flying), (1, 2));
+        assert_eq!(labels.intern("boss"), Some(boss));
+
+        labels.insert(0, boss | flying);
+        labels.remove(0, "boss");
+        labels.remove(0, "unknown");
+
+        assert_eq!(labels.entity_labels(0), vec!["flying"]);
+    }
+
+    #[test]
+    fn at_most_64_labels() {
+        let mut labels = Labels::default();
+
+        for label in 0..64 {
+            assert!(labels.intern(&label.to_string()).is_some());
+        }
+
+        assert_eq!(labels.intern("one too many"), None);
+    }
+}
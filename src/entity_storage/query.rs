@@ -1,7 +1,10 @@
-use std::any::{Any, TypeId};
+use std::any::{type_name, Any, TypeId};
+use std::cell::{Ref, RefMut};
 
-use super::{query_entity::QueryEntity, Component, EntityStorage};
+use super::{query_entity::QueryEntity, ColumnRef, Component, EntityStorage};
+use crate::dynamic_component::DynamicComponent;
 use crate::ecs_errors::ECSError;
+use crate::spatial::SpatialIndex;
 
 pub type MatchedEntityIds = Vec<usize>;
 pub type MatchedComponents = Vec<Vec<Component>>;
@@ -9,13 +12,129 @@ pub type MatchedComponents = Vec<Vec<Component>>;
 pub struct QueryResult {
     pub entity_ids: MatchedEntityIds,
     pub components: MatchedComponents,
+    // Type of the components of each column, `DynamicComponent` for dynamic ones.
+    pub component_type_ids: Vec<TypeId>,
+}
+
+impl QueryResult {
+    /**
+    Borrows the components of type `T` of every matched entity, in the order of `entity_ids`,
+    instead of finding the column by position and downcasting each component by hand. Fails
+    with `ComponentNotQueried` if the query didn't filter by `T`, and with
+    `ComponentAlreadyBorrowed` if one of the components is mutably borrowed. For dynamic
+    components, `T` is `DynamicComponent` and the first dynamic column is used.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Position(pub f32, pub f32);
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Position>();
+        world.register_component::<Health>();
+        world
+            .create_entity()
+            .with_component(Position(0.0, 0.0))?
+            .with_component(Health(100))?;
+
+        let result = world
+            .query()
+            .with_component_filter::<Position>()?
+            .with_component_filter::<Health>()?
+            .run();
+
+        for mut health in result.column_mut::<Health>()? {
+            health.0 -= 10;
+        }
+
+        let healths: Vec<u32> = result.column::<Health>()?.iter().map(|health| health.0).collect();
+        assert_eq!(healths, vec![90]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn column<T: Any>(&self) -> Result<Vec<Ref<'_, T>>, ECSError> {
+        self.typed_column::<T>()?
+            .map(|(entity_id, component)| {
+                let component = component
+                    .try_borrow()
+                    .map_err(|_| already_borrowed::<T>(entity_id))?;
+
+                Ok(Ref::map(component, |any| any.downcast_ref::<T>().unwrap()))
+            })
+            .collect()
+    }
+
+    /**
+    Mutable version of `column`, which also fails with `ComponentAlreadyBorrowed` if one of
+    the components is borrowed at all. That includes zero-sized components matched by more
+    than one entity, since they share a single instance.
+    */
+    pub fn column_mut<T: Any>(&self) -> Result<Vec<RefMut<'_, T>>, ECSError> {
+        self.typed_column::<T>()?
+            .map(|(entity_id, component)| {
+                let component = component
+                    .try_borrow_mut()
+                    .map_err(|_| already_borrowed::<T>(entity_id))?;
+
+                Ok(RefMut::map(component, |any| {
+                    any.downcast_mut::<T>().unwrap()
+                }))
+            })
+            .collect()
+    }
+
+    fn typed_column<T: Any>(&self) -> Result<impl Iterator<Item = (usize, &Component)>, ECSError> {
+        let column = self
+            .component_type_ids
+            .iter()
+            .position(|type_id| *type_id == TypeId::of::<T>())
+            .ok_or_else(|| ECSError::ComponentNotQueried {
+                type_name: type_name::<T>().to_owned(),
+            })?;
+
+        Ok(self
+            .entity_ids
+            .iter()
+            .copied()
+            .zip(&self.components[column]))
+    }
+}
+
+fn already_borrowed<T: Any>(id: usize) -> ECSError {
+    ECSError::ComponentAlreadyBorrowed {
+        id,
+        type_name: type_name::<T>().to_owned(),
+    }
 }
 
 #[derive(Debug)]
 pub struct Query<'a> {
     filter_mask: u32,
+    group_mask: u64,
+    tag_mask: u64,
+    // Set when filtering by a group or tag that no entity was ever given.
+    unknown_label: bool,
+    include_disabled: bool,
+    // Matches to skip and to keep at most, see `page`.
+    offset: usize,
+    limit: Option<usize>,
+    spatial_index: Option<&'a SpatialIndex>,
+    // Entities allowed by spatial constraints, sorted by ID. `None` means every entity.
+    candidates: Option<Vec<usize>>,
     entity_storage: &'a EntityStorage,
     component_type_ids: Vec<TypeId>,
+    columns: Vec<ColumnRef<'a>>,
+    // Bit of the component of each column.
+    column_masks: Vec<u32>,
+    // Name of the component of each column, to report missing components.
+    column_names: Vec<String>,
 }
 
 impl<'a> Query<'a> {
@@ -23,70 +142,511 @@ impl<'a> Query<'a> {
         Self {
             entity_storage,
             filter_mask: 0,
+            group_mask: 0,
+            tag_mask: 0,
+            unknown_label: false,
+            include_disabled: false,
+            offset: 0,
+            limit: None,
+            spatial_index: None,
+            candidates: None,
             component_type_ids: vec![],
+            columns: vec![],
+            column_masks: vec![],
+            column_names: vec![],
         }
     }
 
     pub fn with_component_filter<T: Any>(&mut self) -> Result<&mut Self, ECSError> {
-        let component_type_id = TypeId::of::<T>();
+        self.filter_by_type_id(TypeId::of::<T>(), type_name::<T>().to_owned())
+    }
 
-        match self.entity_storage.get_bitmask(&component_type_id) {
-            Some(bitmask) => {
+    /**
+    Filters by a component type only known by its `TypeId`, for example one looked up by name
+    with `World::component_id_by_name`.
+    */
+    pub fn with_component_id_filter(&mut self, type_id: TypeId) -> Result<&mut Self, ECSError> {
+        let name = self.entity_storage.component_name(&type_id);
+
+        self.filter_by_type_id(type_id, name)
+    }
+
+    fn filter_by_type_id(
+        &mut self,
+        component_type_id: TypeId,
+        name: String,
+    ) -> Result<&mut Self, ECSError> {
+        match (
+            self.entity_storage.get_bitmask(&component_type_id),
+            self.entity_storage.column(&component_type_id),
+        ) {
+            (Some(bitmask), Some(column)) => {
                 self.filter_mask |= bitmask;
                 self.component_type_ids.push(component_type_id);
+                self.columns.push(column);
+                self.column_masks.push(bitmask);
+                self.column_names.push(name);
+            }
+            _ => return Err(ECSError::ComponentNotRegistered { type_name: name }),
+        }
+        Ok(self)
+    }
+
+    /**
+    Filters by a dynamic component registered under `name`. Its column in the query result
+    holds `DynamicComponent`s.
+    */
+    pub fn with_dynamic_component_filter(&mut self, name: &str) -> Result<&mut Self, ECSError> {
+        match (
+            self.entity_storage.get_dynamic_bitmask(name),
+            self.entity_storage.dynamic_component_column(name),
+        ) {
+            (Some(bitmask), Some(column)) => {
+                self.filter_mask |= bitmask;
+                self.component_type_ids
+                    .push(TypeId::of::<DynamicComponent>());
+                self.columns.push(ColumnRef::PerEntity(column));
+                self.column_masks.push(bitmask);
+                self.column_names.push(name.to_owned());
+            }
+            _ => {
+                return Err(ECSError::ComponentNotRegistered {
+                    type_name: name.to_owned(),
+                })
             }
-            None => return Err(ECSError::ComponentNotRegistered),
         }
         Ok(self)
     }
 
+    /**
+    Only matches entities in `group`, see `World::add_to_group`.
+    */
+    pub fn in_group(&mut self, group: &str) -> &mut Self {
+        match self.entity_storage.get_group_bitmask(group) {
+            Some(bitmask) => self.group_mask |= bitmask,
+            None => self.unknown_label = true,
+        }
+        self
+    }
+
+    /**
+    Only matches entities tagged with `tag`, see `World::add_tag`.
+    */
+    pub fn with_tag(&mut self, tag: &str) -> &mut Self {
+        match self.entity_storage.get_tag_bitmask(tag) {
+            Some(bitmask) => self.tag_mask |= bitmask,
+            None => self.unknown_label = true,
+        }
+        self
+    }
+
+    /**
+    Also matches disabled entities, which are skipped by default. See `World::disable_entity`.
+    */
+    pub fn including_disabled(&mut self) -> &mut Self {
+        self.include_disabled = true;
+        self
+    }
+
+    /**
+    Keeps at most `count` matches, the ones with the lowest IDs. The entities are only scanned
+    until enough are found, for code that needs a few matches rather than all of them.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Idle;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Idle>();
+
+        for _ in 0..20 {
+            world.create_entity().with_component(Idle)?;
+        }
+
+        let workers = world.query().with_component_filter::<Idle>()?.take(5).run();
+        assert_eq!(workers.entity_ids, vec![0, 1, 2, 3, 4]);
+
+        let page = world.query().with_component_filter::<Idle>()?.page(15, 10).run();
+        assert_eq!(page.entity_ids, vec![15, 16, 17, 18, 19]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn take(&mut self, count: usize) -> &mut Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /**
+    Skips the first `offset` matches and keeps the `len` after them, in ID order, like a page
+    of a list in a UI.
+    */
+    pub fn page(&mut self, offset: usize, len: usize) -> &mut Self {
+        self.offset = offset;
+        self.limit = Some(len);
+        self
+    }
+
+    pub(crate) fn with_spatial_index(mut self, spatial_index: Option<&'a SpatialIndex>) -> Self {
+        self.spatial_index = spatial_index;
+        self
+    }
+
+    /**
+    Only matches entities at a distance of `radius` or less from `center`. The candidates come
+    from the spatial index, so entities far away are never looked at.
+    See `World::enable_spatial_index`.
+    */
+    pub fn within_radius(&mut self, center: [f32; 2], radius: f32) -> Result<&mut Self, ECSError> {
+        let spatial_index = self.spatial_index.ok_or(ECSError::SpatialIndexNotEnabled)?;
+
+        self.restrict_candidates(spatial_index.entities_within_radius(center, radius));
+        Ok(self)
+    }
+
+    /**
+    Only matches entities inside the axis-aligned box from `min` to `max`, using the spatial
+    index like `within_radius`.
+    */
+    pub fn in_aabb(&mut self, min: [f32; 2], max: [f32; 2]) -> Result<&mut Self, ECSError> {
+        let spatial_index = self.spatial_index.ok_or(ECSError::SpatialIndexNotEnabled)?;
+
+        self.restrict_candidates(spatial_index.entities_in_aabb(min, max));
+        Ok(self)
+    }
+
+    /**
+    Runs the query. Entities whose mask says they have a component that isn't actually stored
+    are left out of the result, see `try_run` to have them reported instead.
+    */
     pub fn run(&self) -> QueryResult {
-        let matched_entity_ids: Vec<usize> = self
-            .entity_storage
-            .entity_component_bitmasks
-            .iter()
-            .enumerate()
-            .filter_map(|(index, entity_map)| {
-                match entity_map & self.filter_mask == self.filter_mask {
-                    true => Some(index),
-                    false => None,
-                }
-            })
-            .collect();
+        self.collect(false)
+            .expect("skipping inconsistent entities never fails")
+    }
 
-        let mut matched_components = vec![];
+    /**
+    Runs the query, failing with `ComponentDoesNotExist` for the first entity whose mask says
+    it has one of the components while the component isn't actually stored.
+    */
+    pub fn try_run(&self) -> Result<QueryResult, ECSError> {
+        self.collect(true)
+    }
 
-        for type_id in &self.component_type_ids {
-            let entity_components = self.entity_storage.components.get(type_id).unwrap();
-            let mut components_to_keep = vec![];
+    /**
+    Number of entities the query matches, going by their component masks like `get_entities`.
+    Queries on a single component, without groups, tags or spatial constraints, are answered
+    from the population counts kept for every component, as long as no entity is disabled.
+    The others skip scanning entities when one of their components has no entity at all.
 
-            for index in &matched_entity_ids {
-                components_to_keep.push(entity_components[*index].as_ref().unwrap().clone());
-            }
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Enemy;
+    struct Boss;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
 
-            matched_components.push(components_to_keep);
+        world.register_component::<Enemy>();
+        world.register_component::<Boss>();
+
+        for _ in 0..10 {
+            world.create_entity().with_component(Enemy)?;
         }
 
-        QueryResult {
-            entity_ids: matched_entity_ids,
-            components: matched_components,
+        assert_eq!(world.query().with_component_filter::<Enemy>()?.count(), 10);
+        assert_eq!(world.query().with_component_filter::<Boss>()?.count(), 0);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn count(&self) -> usize {
+        if self.unknown_label || self.rarest_population() == Some(0) {
+            return 0;
         }
+
+        let unfiltered = self.candidates.is_none()
+            && self.group_mask == 0
+            && self.tag_mask == 0
+            && (self.include_disabled || !self.entity_storage.has_disabled_entities());
+
+        if unfiltered && self.filter_mask.count_ones() == 1 {
+            let population = self.entity_storage.population(self.filter_mask);
+
+            return population
+                .saturating_sub(self.offset)
+                .min(self.limit.unwrap_or(usize::MAX));
+        }
+
+        self.matched_entity_ids().len()
+    }
+
+    pub fn get_entities(&self) -> Vec<QueryEntity<'a>> {
+        self.iter().collect()
     }
 
-    pub fn get_entities(&self) -> Vec<QueryEntity> {
-        self.entity_storage
-            .entity_component_bitmasks
+    /**
+    Iterates over the matched entities like `get_entities`, finding them as it goes instead
+    of collecting them first. Queries can also be used directly in `for` loops.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.create_entity().with_component(Health(10))?;
+        world.create_entity().with_component(Health(20))?;
+
+        for mut entity in world.query().with_component_filter::<Health>()? {
+            entity.get_component_mut::<Health>()?.0 += 5;
+        }
+
+        let total: u32 = world
+            .query()
+            .with_component_filter::<Health>()?
             .iter()
-            .enumerate()
-            .filter_map(|(entity_id, entity_map)| {
-                if entity_map & self.filter_mask == self.filter_mask {
-                    Some(QueryEntity::new(entity_id, self.entity_storage))
-                } else {
-                    None
+            .map(|entity| entity.get_component::<Health>().unwrap().0)
+            .sum();
+
+        assert_eq!(total, 40);
+        Ok(())
+    }
+    ```
+    */
+    pub fn iter(&self) -> QueryIter<'_, 'a> {
+        QueryIter {
+            entity_ids: Box::new(
+                self.matching_entity_ids()
+                    .skip(self.offset)
+                    .take(self.limit.unwrap_or(usize::MAX)),
+            ),
+            entity_storage: self.entity_storage,
+        }
+    }
+
+    fn collect(&self, report_missing: bool) -> Result<QueryResult, ECSError> {
+        let mut entity_ids = vec![];
+        let mut components = vec![vec![]; self.columns.len()];
+        let extraction_order = self.extraction_order();
+        let mut skipped = 0;
+
+        for entity_id in self.matching_entity_ids() {
+            if self.limit == Some(entity_ids.len()) {
+                break;
+            }
+
+            let missing = extraction_order
+                .iter()
+                .copied()
+                .find(|column| self.columns[*column].get(entity_id).is_none());
+
+            match missing {
+                Some(column) if report_missing => {
+                    return Err(ECSError::ComponentDoesNotExist {
+                        id: entity_id,
+                        type_name: self.column_names[column].clone(),
+                    });
                 }
-            })
+                Some(_) => continue,
+                None => {}
+            }
+
+            if skipped < self.offset {
+                skipped += 1;
+                continue;
+            }
+
+            for (column, components) in self.columns.iter().zip(&mut components) {
+                components.push(column.get(entity_id).unwrap().clone());
+            }
+            entity_ids.push(entity_id);
+        }
+
+        Ok(QueryResult {
+            entity_ids,
+            components,
+            component_type_ids: self.component_type_ids.clone(),
+        })
+    }
+
+    // Columns from the one of the rarest component to the most common, so the check for
+    // missing components looks first where they're most likely.
+    fn extraction_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.columns.len()).collect();
+
+        order.sort_by_key(|column| self.entity_storage.population(self.column_masks[*column]));
+        order
+    }
+
+    // Population of the rarest component filtered by, if any.
+    fn rarest_population(&self) -> Option<usize> {
+        self.column_masks
+            .iter()
+            .map(|mask| self.entity_storage.population(*mask))
+            .min()
+    }
+
+    fn matched_entity_ids(&self) -> Vec<usize> {
+        self.matching_entity_ids()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
             .collect()
     }
+
+    // Lazily finds the matches, ignoring `offset` and `limit`.
+    fn matching_entity_ids(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        if self.rarest_population() == Some(0) {
+            return Box::new(std::iter::empty());
+        }
+
+        let mut cursor = 0;
+        Box::new(std::iter::from_fn(move || self.next_match(&mut cursor)))
+    }
+
+    // Finds the next matching entity from `cursor`, which indexes the candidates if there are
+    // any and the entity table otherwise.
+    fn next_match(&self, cursor: &mut usize) -> Option<usize> {
+        let bitmasks = &self.entity_storage.entity_component_bitmasks;
+
+        loop {
+            let entity_id = match &self.candidates {
+                Some(candidates) => *candidates.get(*cursor)?,
+                None if *cursor < bitmasks.len() => *cursor,
+                None => return None,
+            };
+            *cursor += 1;
+
+            if bitmasks
+                .get(entity_id)
+                .is_some_and(|entity_map| self.matches(entity_id, *entity_map))
+            {
+                return Some(entity_id);
+            }
+        }
+    }
+
+    fn restrict_candidates(&mut self, entity_ids: Vec<usize>) {
+        self.candidates = Some(match self.candidates.take() {
+            Some(candidates) => candidates
+                .into_iter()
+                .filter(|entity_id| entity_ids.binary_search(entity_id).is_ok())
+                .collect(),
+            None => entity_ids,
+        });
+    }
+
+    fn matches(&self, entity_id: usize, entity_map: u32) -> bool {
+        let entity_groups = self.entity_storage.entity_group_bitmask(entity_id);
+        let entity_tags = self.entity_storage.entity_tag_bitmask(entity_id);
+
+        !self.unknown_label
+            && (self.include_disabled || !self.entity_storage.is_disabled(entity_id))
+            && entity_map & self.filter_mask == self.filter_mask
+            && entity_groups & self.group_mask == self.group_mask
+            && entity_tags & self.tag_mask == self.tag_mask
+    }
+}
+
+/**
+Iterator over the entities matched by a query, created with `Query::iter` or by using a query
+in a `for` loop.
+*/
+pub struct QueryIter<'q, 'a> {
+    entity_ids: Box<dyn Iterator<Item = usize> + 'q>,
+    entity_storage: &'a EntityStorage,
+}
+
+impl<'a> Iterator for QueryIter<'_, 'a> {
+    type Item = QueryEntity<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity_id = self.entity_ids.next()?;
+
+        Some(QueryEntity::new(entity_id, self.entity_storage))
+    }
+}
+
+impl<'q, 'a> IntoIterator for &'q Query<'a> {
+    type Item = QueryEntity<'a>;
+    type IntoIter = QueryIter<'q, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'q, 'a> IntoIterator for &'q mut Query<'a> {
+    type Item = QueryEntity<'a>;
+    type IntoIter = QueryIter<'q, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/**
+Iterator that owns its query, created by using a query by value in a `for` loop. Like
+`QueryIter`, it finds the matched entities as it goes.
+*/
+pub struct QueryIntoIter<'a> {
+    query: Query<'a>,
+    cursor: usize,
+    skip: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for QueryIntoIter<'a> {
+    type Item = QueryEntity<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            let entity_id = self.query.next_match(&mut self.cursor)?;
+
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+
+            self.remaining -= 1;
+            return Some(QueryEntity::new(entity_id, self.query.entity_storage));
+        }
+
+        None
+    }
+}
+
+impl<'a> IntoIterator for Query<'a> {
+    type Item = QueryEntity<'a>;
+    type IntoIter = QueryIntoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let remaining = match self.rarest_population() {
+            Some(0) => 0,
+            _ => self.limit.unwrap_or(usize::MAX),
+        };
+
+        QueryIntoIter {
+            skip: self.offset,
+            query: self,
+            cursor: 0,
+            remaining,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -266,4 +826,329 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn query_in_group() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+        entities.create_entity().with_component(3_u32)?;
+        entities.add_entity_to_group(0, "enemies")?;
+        entities.add_entity_to_group(2, "enemies")?;
+        entities.add_entity_to_group(2, "flying")?;
+
+        let mut query = Query::new(&entities);
+        query.with_component_filter::<u32>()?.in_group("enemies");
+
+        assert_eq!(query.run().entity_ids, vec![0, 2]);
+
+        query.in_group("flying");
+        assert_eq!(query.run().entity_ids, vec![2]);
+
+        assert!(Query::new(&entities)
+            .in_group("allies")
+            .run()
+            .entity_ids
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn query_with_tag() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+        entities.add_entity_tag(1, "boss")?;
+        entities.add_entity_to_group(0, "boss")?;
+
+        let mut query = Query::new(&entities);
+        query.with_component_filter::<u32>()?.with_tag("boss");
+
+        assert_eq!(query.run().entity_ids, vec![1]);
+
+        entities.remove_entity_tag(1, "boss")?;
+        assert!(Query::new(&entities)
+            .with_tag("boss")
+            .run()
+            .entity_ids
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn query_skips_disabled_entities() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+        entities.set_entity_disabled(0, true)?;
+
+        let mut query = Query::new(&entities);
+        query.with_component_filter::<u32>()?;
+
+        assert_eq!(query.run().entity_ids, vec![1]);
+        assert_eq!(query.get_entities().len(), 1);
+        assert_eq!(query.including_disabled().run().entity_ids, vec![0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn unchecked_component_access() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(100_u32)?;
+        entities.create_entity().with_component(50_u32)?;
+
+        let mut query = Query::new(&entities);
+
+        for mut entity in query.with_component_filter::<u32>()?.get_entities() {
+            // SAFETY: the query only matches entities with a `u32`.
+            unsafe { *entity.get_component_unchecked_mut::<u32>() += 1 };
+        }
+
+        let entity = QueryEntity::new(1, &entities);
+
+        // SAFETY: entity 1 was given a `u32` above.
+        assert_eq!(unsafe { *entity.get_component_unchecked::<u32>() }, 51);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_borrows_can_fail_gracefully() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+
+        let mut query = Query::new(&entities);
+        let mut outer = query.with_component_filter::<u32>()?.get_entities();
+        let inner = query.get_entities();
+        let mut sum = 0;
+
+        for entity in &mut outer {
+            let _value = entity.get_component_mut::<u32>()?;
+
+            for other in &inner {
+                match other.try_get_component::<u32>() {
+                    Ok(value) => sum += *value,
+                    Err(ECSError::ComponentAlreadyBorrowed { id, .. }) => {
+                        assert_eq!(id, other.id);
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+
+        assert_eq!(sum, 3);
+        assert!(inner[0].try_get_component::<u32>().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn inconsistent_entities_are_skipped_or_reported() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+        entities.components.get_mut(&TypeId::of::<u32>()).unwrap()[0] = None;
+
+        let mut query = Query::new(&entities);
+        query.with_component_filter::<u32>()?;
+
+        assert_eq!(query.run().entity_ids, vec![1]);
+        assert!(matches!(
+            query.try_run(),
+            Err(ECSError::ComponentDoesNotExist { id: 0, type_name }) if type_name == "u32"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn query_entities_list_all_their_components() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+        entities.register_component::<u8>();
+        entities
+            .create_entity()
+            .with_component(1_u32)?
+            .with_component(2.0_f32)?;
+
+        let mut query = Query::new(&entities);
+        let entity = &query.with_component_filter::<u32>()?.get_entities()[0];
+        let type_ids: Vec<TypeId> = entity.components().map(|(type_id, _)| type_id).collect();
+
+        assert_eq!(type_ids, vec![TypeId::of::<u32>(), TypeId::of::<f32>()]);
+
+        let (_, component) = entity.components().nth(1).unwrap();
+        assert_eq!(component.borrow().downcast_ref::<f32>(), Some(&2.0));
+        Ok(())
+    }
+
+    #[test]
+    fn counts_follow_component_populations() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+        entities.register_component::<u8>();
+
+        for index in 0..6_u32 {
+            entities.create_entity().with_component(index)?;
+
+            if index % 2 == 0 {
+                entities.add_component_to_entity(index as usize, 1.0_f32)?;
+            }
+        }
+
+        entities.remove_entity_component::<f32>(4)?;
+        entities.remove_entity(5)?;
+
+        let u32_mask = entities.get_bitmask(&TypeId::of::<u32>()).unwrap();
+        assert_eq!(entities.population(u32_mask), 5);
+        assert_eq!(
+            Query::new(&entities)
+                .with_component_filter::<u32>()?
+                .count(),
+            5
+        );
+        assert_eq!(
+            Query::new(&entities).with_component_filter::<u8>()?.count(),
+            0
+        );
+
+        let mut both = Query::new(&entities);
+        both.with_component_filter::<u32>()?
+            .with_component_filter::<f32>()?;
+        assert_eq!(both.count(), 2);
+
+        entities.set_entity_disabled(0, true)?;
+        entities.add_entity_to_group(1, "workers")?;
+
+        let mut workers = Query::new(&entities);
+        workers.with_component_filter::<u32>()?.in_group("workers");
+
+        assert_eq!(
+            Query::new(&entities)
+                .with_component_filter::<u32>()?
+                .count(),
+            4
+        );
+        assert_eq!(workers.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn pages_skip_and_limit_matches() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+
+        for index in 0..10_u32 {
+            entities.create_entity().with_component(index)?;
+        }
+
+        entities.add_component_to_entity(3, 1.0_f32)?;
+        entities.components.get_mut(&TypeId::of::<u32>()).unwrap()[1] = None;
+
+        let mut query = Query::new(&entities);
+        query.with_component_filter::<u32>()?.page(2, 3);
+
+        // Entity 1 is inconsistent, so `run` skips it while `get_entities` doesn't.
+        assert_eq!(query.run().entity_ids, vec![3, 4, 5]);
+        assert_eq!(query.get_entities()[0].id, 2);
+        assert_eq!(query.count(), 3);
+        assert_eq!(query.page(8, 5).count(), 2);
+        assert!(query.page(20, 5).run().entity_ids.is_empty());
+
+        let mut rare = Query::new(&entities);
+        rare.with_component_filter::<f32>()?.take(5);
+
+        assert_eq!(rare.run().entity_ids, vec![3]);
+        assert_eq!(rare.take(0).count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn result_columns_are_found_by_type() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+        entities.register_component::<u8>();
+
+        for index in 0..3_u32 {
+            entities
+                .create_entity()
+                .with_component(index)?
+                .with_component(index as f32 * 0.5)?;
+        }
+
+        let mut query = Query::new(&entities);
+        let result = query
+            .with_component_filter::<f32>()?
+            .with_component_filter::<u32>()?
+            .run();
+
+        for mut value in result.column_mut::<u32>()? {
+            *value *= 10;
+        }
+
+        let values: Vec<u32> = result
+            .column::<u32>()?
+            .iter()
+            .map(|value| **value)
+            .collect();
+        assert_eq!(values, vec![0, 10, 20]);
+
+        let halves = result.column::<f32>()?;
+        assert_eq!(*halves[2], 1.0);
+        assert!(matches!(
+            result.column_mut::<f32>(),
+            Err(ECSError::ComponentAlreadyBorrowed { id: 0, .. })
+        ));
+        assert!(matches!(
+            result.column::<u8>(),
+            Err(ECSError::ComponentNotQueried { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn queries_iterate_in_for_loops() -> Result<(), ECSError> {
+        let mut entities = EntityStorage::default();
+
+        entities.register_component::<u32>();
+
+        for index in 0..5_u32 {
+            entities.create_entity().with_component(index)?;
+        }
+
+        for mut entity in Query::new(&entities).with_component_filter::<u32>()? {
+            *entity.get_component_mut::<u32>()? += 1;
+        }
+
+        let mut query = Query::new(&entities);
+        query.with_component_filter::<u32>()?.page(1, 3);
+
+        let ids: Vec<usize> = (&query).into_iter().map(|entity| entity.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        let values: Vec<u32> = query
+            .into_iter()
+            .map(|entity| *entity.get_component::<u32>().unwrap())
+            .collect();
+        assert_eq!(values, vec![2, 3, 4]);
+        Ok(())
+    }
 }
@@ -1,14 +1,12 @@
 use std::{
     any::{Any, TypeId},
-    cell::{Ref, RefCell, RefMut},
-    rc::Rc,
+    cell::{Ref, RefMut},
 };
 
-use super::EntityStorage;
+use super::{Component, EntityStorage};
+use crate::commands::{Commands, EntityCommands};
 use crate::ecs_errors::ECSError;
 
-type ExtractedComponents<'a> = &'a Vec<Option<Rc<RefCell<dyn Any>>>>;
-
 pub struct QueryEntity<'a> {
     pub id: usize,
     entities: &'a EntityStorage,
@@ -19,38 +17,66 @@ impl<'a> QueryEntity<'a> {
         Self { id, entities }
     }
 
-    pub fn get_component<T: Any>(&self) -> Result<Ref<T>, ECSError> {
-        let components = self.extract_components::<T>()?;
-        let borrowed_component = components[self.id]
-            .as_ref()
-            .ok_or(ECSError::ComponentDoesNotExist)?
-            .borrow();
+    /**
+    Every component of the entity, not only the ones the query filtered by, with its type.
+    Useful for code that handles entities generically, like serializers or inspectors.
+    Dynamic components are given the `TypeId` of `DynamicComponent`.
+    */
+    pub fn components(&self) -> impl Iterator<Item = (TypeId, &'a Component)> {
+        self.entities.entity_components(self.id).into_iter()
+    }
+
+    /**
+    Records changes to the entity into `commands`, to be applied with `World::apply_commands`
+    once the query is done.
+    */
+    pub fn commands<'c>(&self, commands: &'c mut Commands) -> EntityCommands<'c> {
+        commands.entity(self.id)
+    }
+
+    pub fn get_component<T: Any>(&self) -> Result<Ref<'_, T>, ECSError> {
+        self.entities.get_component::<T>(self.id)
+    }
+
+    pub fn get_component_mut<T: Any>(&mut self) -> Result<RefMut<'_, T>, ECSError> {
+        self.entities.get_component_mut::<T>(self.id)
+    }
+
+    /**
+    Like `get_component`, but fails with `ComponentAlreadyBorrowed` instead of panicking if the
+    component is mutably borrowed, for example by an outer loop over the same entities.
+    */
+    pub fn try_get_component<T: Any>(&self) -> Result<Ref<'_, T>, ECSError> {
+        self.entities.try_get_component::<T>(self.id)
+    }
 
-        Ok(Ref::map(borrowed_component, |any| {
-            any.downcast_ref::<T>().unwrap()
-        }))
+    /**
+    Like `get_component_mut`, but fails with `ComponentAlreadyBorrowed` instead of panicking if
+    the component is borrowed.
+    */
+    pub fn try_get_component_mut<T: Any>(&mut self) -> Result<RefMut<'_, T>, ECSError> {
+        self.entities.try_get_component_mut::<T>(self.id)
     }
 
-    pub fn get_component_mut<T: Any>(&mut self) -> Result<RefMut<T>, ECSError> {
-        let components = self.extract_components::<T>()?;
-        let borrowed_component = components[self.id]
-            .as_ref()
-            .ok_or(ECSError::ComponentDoesNotExist)?
-            .borrow_mut();
+    /**
+    Borrows a component without checking that the entity has it, for hot loops over the
+    components a query filtered by. Only checked with debug assertions.
 
-        Ok(RefMut::map(borrowed_component, |any| {
-            any.downcast_mut::<T>().unwrap()
-        }))
+    # Safety
+    The entity must have a component of type `T`, which is always the case for the types the
+    query was filtered by while the world isn't modified.
+    */
+    pub unsafe fn get_component_unchecked<T: Any>(&self) -> Ref<'_, T> {
+        self.entities.get_component_unchecked::<T>(self.id)
     }
 
-    fn extract_components<T: Any>(&self) -> Result<ExtractedComponents, ECSError> {
-        let component_type_id = TypeId::of::<T>();
-        let components = self
-            .entities
-            .components
-            .get(&component_type_id)
-            .ok_or(ECSError::ComponentNotRegistered);
+    /**
+    Mutable version of `get_component_unchecked`.
 
-        components
+    # Safety
+    The entity must have a component of type `T`.
+    */
+    pub unsafe fn get_component_unchecked_mut<T: Any>(&mut self) -> RefMut<'_, T> {
+        self.entities.get_component_unchecked_mut::<T>(self.id)
     }
 }
@@ -0,0 +1,130 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/**
+Stable identifier of an entity. Unlike entity IDs, which are recycled, UUIDs stay the same
+across save/load and when entities move between worlds, so they can be stored in save files
+or sent over the network. Assigned to every new entity once `World::enable_entity_uuids`
+has been called.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityUuid(pub u128);
+
+impl EntityUuid {
+    /**
+    Generates a random version 4 UUID. The randomness comes from the standard library's
+    hasher keys, which is fine for identifiers but not for anything security related.
+    */
+    pub fn new_v4() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        // The clock can't be read on `wasm32`, the counter and hasher keys still vary.
+        #[cfg(not(target_arch = "wasm32"))]
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+        #[cfg(target_arch = "wasm32")]
+        let nanos = 0_u128;
+
+        let random_u64 = || {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(count);
+            hasher.write_u128(nanos);
+            hasher.finish()
+        };
+
+        let bits = ((random_u64() as u128) << 64) | random_u64() as u128;
+        let version = 0x4 << 76;
+        let variant = 0x8 << 60;
+
+        EntityUuid((bits & !(0xf << 76) & !(0xc << 60)) | version | variant)
+    }
+
+    /**
+    Parses the hyphenated form written by `Display`, like `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    */
+    pub fn parse_str(text: &str) -> Option<Self> {
+        let digits: String = text.chars().filter(|character| *character != '-').collect();
+
+        if digits.len() != 32 {
+            return None;
+        }
+
+        u128::from_str_radix(&digits, 16).ok().map(EntityUuid)
+    }
+}
+
+impl fmt::Display for EntityUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.0;
+
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            value >> 96,
+            (value >> 80) & 0xffff,
+            (value >> 64) & 0xffff,
+            (value >> 48) & 0xffff,
+            value & 0xffff_ffff_ffff
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EntityUuid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EntityUuid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        EntityUuid::parse_str(&text)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid entity UUID: {text}")))
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct EntityUuids {
+    pub enabled: bool,
+    // May hold stale entries, for example after a snapshot is loaded, so lookups have to
+    // check that the entity still has the UUID.
+    pub index: HashMap<EntityUuid, usize>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generated_uuids_are_unique_version_4() {
+        let first = EntityUuid::new_v4();
+        let second = EntityUuid::new_v4();
+
+        assert_ne!(first, second);
+        assert_eq!((first.0 >> 76) & 0xf, 4);
+        assert_eq!((first.0 >> 62) & 0x3, 0b10);
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        let uuid = EntityUuid(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8);
+
+        assert_eq!(uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(EntityUuid::parse_str(&uuid.to_string()), Some(uuid));
+        assert_eq!(EntityUuid::parse_str("67e55044"), None);
+        assert_eq!(
+            EntityUuid::parse_str("zze55044-10b1-426f-9247-bb680e5fe0c8"),
+            None
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::marker::PhantomData;
+
+/**
+Sent by the world every time an entity is created, if the event type was registered
+with `World::add_event`. Holds the ID of the new entity.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntitySpawned(pub usize);
+
+/**
+Sent by the world every time an entity is removed, if the event type was registered
+with `World::add_event`. Holds the ID of the removed entity.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityDespawned(pub usize);
+
+/**
+Double-buffered storage for events of type `T`.
+
+Events sent during a frame are kept in the current buffer. Each call to `update()` moves them to
+the previous buffer and drops whatever was there before, so an event lives for two update cycles
+and readers that run later in the frame (or early in the next one) still get to see it.
+*/
+#[derive(Debug)]
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    previous_start: usize,
+    current_start: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: vec![],
+            current: vec![],
+            previous_start: 0,
+            current_start: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    pub fn update(&mut self) {
+        self.previous_start = self.current_start;
+        self.current_start += self.current.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    pub fn clear(&mut self) {
+        self.current_start = self.event_count();
+        self.previous_start = self.current_start;
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(self.current.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn event_count(&self) -> usize {
+        self.current_start + self.current.len()
+    }
+}
+
+/**
+Keeps track of which events a reader has already seen, so iterating the same `Events<T>` every
+frame yields each event only once.
+*/
+#[derive(Debug)]
+pub struct EventReader<T> {
+    last_event_count: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self {
+            last_event_count: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> EventReader<T> {
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let skip = self.last_event_count.saturating_sub(events.previous_start);
+        self.last_event_count = events.event_count();
+
+        events.iter().skip(skip)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Jumped(pub u32);
+
+    #[test]
+    fn sent_events_are_readable() {
+        let mut events = Events::default();
+
+        events.send(Jumped(1));
+        events.send(Jumped(2));
+
+        let jumps: Vec<&Jumped> = events.iter().collect();
+
+        assert_eq!(jumps, vec![&Jumped(1), &Jumped(2)]);
+    }
+
+    #[test]
+    fn events_survive_one_update() {
+        let mut events = Events::default();
+
+        events.send(Jumped(1));
+        events.update();
+
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn events_are_dropped_after_two_updates() {
+        let mut events = Events::default();
+
+        events.send(Jumped(1));
+        events.update();
+        events.send(Jumped(2));
+        events.update();
+
+        let jumps: Vec<&Jumped> = events.iter().collect();
+        assert_eq!(jumps, vec![&Jumped(2)]);
+
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn clear_drops_both_buffers() {
+        let mut events = Events::default();
+
+        events.send(Jumped(1));
+        events.update();
+        events.send(Jumped(2));
+        events.clear();
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn reader_only_sees_new_events() {
+        let mut events = Events::default();
+        let mut reader = EventReader::default();
+
+        events.send(Jumped(1));
+        assert_eq!(reader.read(&events).count(), 1);
+
+        events.update();
+        events.send(Jumped(2));
+
+        let jumps: Vec<&Jumped> = reader.read(&events).collect();
+        assert_eq!(jumps, vec![&Jumped(2)]);
+        assert_eq!(reader.read(&events).count(), 0);
+    }
+
+    #[test]
+    fn reader_skips_cleared_events() {
+        let mut events = Events::default();
+        let mut reader = EventReader::default();
+
+        events.send(Jumped(1));
+        events.clear();
+        events.send(Jumped(2));
+
+        let jumps: Vec<&Jumped> = reader.read(&events).collect();
+        assert_eq!(jumps, vec![&Jumped(2)]);
+    }
+}
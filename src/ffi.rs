@@ -0,0 +1,697 @@
+use std::cell::RefMut;
+use std::ffi::{c_char, c_void, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::dynamic_component::{
+    DynamicComponent, DynamicComponentDescriptor, DynamicValue, FieldType,
+};
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+/**
+World handle of the C API, opaque to C code. The API embeds the ECS in C and C++ engines, or
+any language with a C FFI: components are dynamic components looked up by their registered
+name, with their fields read and written one at a time. Functions return a `SaraStatus` and
+write their results through out pointers. Requires the `ffi` feature; a header can be
+generated with cbindgen.
+
+Entities only exist while they have components, so `sara_entity_create` reserves an ID that
+becomes a living entity once a component is inserted.
+
+```c
+SaraWorld *world = sara_world_new();
+const char *fields[] = {"current"};
+SaraFieldType types[] = {SARA_FIELD_TYPE_INT};
+size_t player;
+
+sara_entity_create(world, &player);
+
+sara_component_register(world, "Health", fields, types, 1);
+sara_component_insert(world, player, "Health");
+sara_component_set_int(world, player, "Health", "current", 100);
+sara_world_free(world);
+```
+*/
+pub type SaraWorld = World;
+
+/**
+Callback registered as a named system. Receives the world it runs on and the `user_data`
+pointer given at registration.
+*/
+pub type SaraSystem = extern "C" fn(world: *mut SaraWorld, user_data: *mut c_void);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaraStatus {
+    Ok = 0,
+    /// A pointer argument was null, or a string wasn't valid UTF-8.
+    InvalidArgument,
+    EntityDoesNotExist,
+    ComponentNotRegistered,
+    ComponentDoesNotExist,
+    /// The field doesn't exist, or holds a value of another type.
+    FieldMismatch,
+    SystemNotFound,
+    /// The string doesn't fit in the buffer, the required length was still written.
+    BufferTooSmall,
+    /// Any other error of the world.
+    Failed,
+    /// The call panicked, the world may be left in an inconsistent state.
+    Panicked,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaraFieldType {
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+impl From<ECSError> for SaraStatus {
+    fn from(error: ECSError) -> Self {
+        match error {
+            ECSError::EntityDoesNotExist { .. } => SaraStatus::EntityDoesNotExist,
+            ECSError::ComponentNotRegistered { .. } => SaraStatus::ComponentNotRegistered,
+            ECSError::ComponentDoesNotExist { .. } => SaraStatus::ComponentDoesNotExist,
+            ECSError::DynamicComponentMismatch => SaraStatus::FieldMismatch,
+            ECSError::SystemNotFound => SaraStatus::SystemNotFound,
+            _ => SaraStatus::Failed,
+        }
+    }
+}
+
+impl From<SaraFieldType> for FieldType {
+    fn from(field_type: SaraFieldType) -> Self {
+        match field_type {
+            SaraFieldType::Bool => FieldType::Bool,
+            SaraFieldType::Int => FieldType::Int,
+            SaraFieldType::Float => FieldType::Float,
+            SaraFieldType::String => FieldType::String,
+        }
+    }
+}
+
+/**
+Creates an empty world, to be freed with `sara_world_free`.
+*/
+#[no_mangle]
+pub extern "C" fn sara_world_new() -> *mut SaraWorld {
+    Box::into_raw(Box::new(World::new()))
+}
+
+/**
+# Safety
+`world` must come from `sara_world_new` and not be used afterwards. Null is ignored.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_world_free(world: *mut SaraWorld) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/**
+Reserves the ID of a new entity, which comes alive once a component is inserted into it, and
+writes it to `out`.
+
+# Safety
+`world` must be a valid world pointer and `out` a valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_entity_create(world: *mut SaraWorld, out: *mut usize) -> SaraStatus {
+    call(|| {
+        let world = world_mut(world)?;
+        let out = out_arg(out)?;
+
+        *out = world.entities().reserve();
+        Ok(())
+    })
+}
+
+/**
+# Safety
+`world` must be a valid world pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_entity_destroy(
+    world: *mut SaraWorld,
+    entity_id: usize,
+) -> SaraStatus {
+    call(|| Ok(world_mut(world)?.remove_entity(entity_id)?))
+}
+
+/**
+# Safety
+`world` must be a valid world pointer and `out` a valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_entity_is_alive(
+    world: *const SaraWorld,
+    entity_id: usize,
+    out: *mut bool,
+) -> SaraStatus {
+    call(|| {
+        let world = world.as_ref().ok_or(SaraStatus::InvalidArgument)?;
+
+        *out_arg(out)? = world.entities().is_alive(entity_id);
+        Ok(())
+    })
+}
+
+/**
+Registers a dynamic component named `name`, with `field_count` fields whose names and types
+are read from the two arrays. Registering a name again with other fields fails with
+`FieldMismatch`.
+
+# Safety
+`world` must be a valid world pointer, the strings valid null-terminated strings, and both
+arrays at least `field_count` long.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_register(
+    world: *mut SaraWorld,
+    name: *const c_char,
+    field_names: *const *const c_char,
+    field_types: *const SaraFieldType,
+    field_count: usize,
+) -> SaraStatus {
+    call(|| {
+        let world = world_mut(world)?;
+        let mut descriptor = DynamicComponentDescriptor::new(str_arg(name)?);
+
+        if field_count > 0 && (field_names.is_null() || field_types.is_null()) {
+            return Err(SaraStatus::InvalidArgument);
+        }
+
+        for index in 0..field_count {
+            descriptor = descriptor.with_field(
+                str_arg(*field_names.add(index))?,
+                FieldType::from(*field_types.add(index)),
+            );
+        }
+
+        world.register_dynamic_component(descriptor)?;
+        Ok(())
+    })
+}
+
+/**
+Inserts the dynamic component named `component` into an entity, with every field set to the
+default value of its type.
+
+# Safety
+`world` must be a valid world pointer and `component` a valid null-terminated string.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_insert(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+) -> SaraStatus {
+    call(|| {
+        let world = world_mut(world)?;
+        let name = str_arg(component)?;
+        let instance = world
+            .dynamic_component_descriptor(name)
+            .ok_or(SaraStatus::ComponentNotRegistered)?
+            .instantiate();
+
+        Ok(world.add_dynamic_component(entity_id, instance)?)
+    })
+}
+
+/**
+# Safety
+`world` must be a valid world pointer and `component` a valid null-terminated string.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_remove(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+) -> SaraStatus {
+    call(|| Ok(world_mut(world)?.remove_dynamic_component(entity_id, str_arg(component)?)?))
+}
+
+/**
+# Safety
+`world` must be a valid world pointer, `component` a valid null-terminated string and `out` a
+valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_has(
+    world: *const SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    out: *mut bool,
+) -> SaraStatus {
+    call(|| {
+        let world = world.as_ref().ok_or(SaraStatus::InvalidArgument)?;
+        let name = str_arg(component)?;
+
+        *out_arg(out)? = world.get_dynamic_component(entity_id, name).is_ok();
+        Ok(())
+    })
+}
+
+/**
+# Safety
+`world` must be a valid world pointer and the strings valid null-terminated strings.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_set_int(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: i64,
+) -> SaraStatus {
+    set_field(world, entity_id, component, field, DynamicValue::Int(value))
+}
+
+/**
+# Safety
+`world` must be a valid world pointer and the strings valid null-terminated strings.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_set_float(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: f64,
+) -> SaraStatus {
+    set_field(
+        world,
+        entity_id,
+        component,
+        field,
+        DynamicValue::Float(value),
+    )
+}
+
+/**
+# Safety
+`world` must be a valid world pointer and the strings valid null-terminated strings.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_set_bool(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: bool,
+) -> SaraStatus {
+    set_field(
+        world,
+        entity_id,
+        component,
+        field,
+        DynamicValue::Bool(value),
+    )
+}
+
+/**
+Copies `value` into the field, the pointer isn't kept.
+
+# Safety
+`world` must be a valid world pointer and the strings valid null-terminated strings.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_set_string(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: *const c_char,
+) -> SaraStatus {
+    match str_arg(value) {
+        Ok(value) => set_field(
+            world,
+            entity_id,
+            component,
+            field,
+            DynamicValue::String(value.to_owned()),
+        ),
+        Err(status) => status,
+    }
+}
+
+/**
+# Safety
+`world` must be a valid world pointer, the strings valid null-terminated strings and `value`
+a valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_get_int(
+    world: *const SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: *mut i64,
+) -> SaraStatus {
+    get_field(
+        world,
+        entity_id,
+        component,
+        field,
+        value,
+        |field| match field {
+            DynamicValue::Int(value) => Some(*value),
+            _ => None,
+        },
+    )
+}
+
+/**
+# Safety
+`world` must be a valid world pointer, the strings valid null-terminated strings and `value`
+a valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_get_float(
+    world: *const SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: *mut f64,
+) -> SaraStatus {
+    get_field(
+        world,
+        entity_id,
+        component,
+        field,
+        value,
+        |field| match field {
+            DynamicValue::Float(value) => Some(*value),
+            _ => None,
+        },
+    )
+}
+
+/**
+# Safety
+`world` must be a valid world pointer, the strings valid null-terminated strings and `value`
+a valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_get_bool(
+    world: *const SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: *mut bool,
+) -> SaraStatus {
+    get_field(
+        world,
+        entity_id,
+        component,
+        field,
+        value,
+        |field| match field {
+            DynamicValue::Bool(value) => Some(*value),
+            _ => None,
+        },
+    )
+}
+
+/**
+Copies the string field into `buffer`, null-terminated, and writes its length without the
+terminator to `length`. Fails with `BufferTooSmall` if `capacity` can't hold it, in which case
+`length` is still written so the call can be retried with a bigger buffer.
+
+# Safety
+`world` must be a valid world pointer, the strings valid null-terminated strings, `buffer`
+valid for `capacity` bytes and `length` a valid pointer.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_component_get_string(
+    world: *const SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    buffer: *mut c_char,
+    capacity: usize,
+    length: *mut usize,
+) -> SaraStatus {
+    call(|| {
+        let world = world.as_ref().ok_or(SaraStatus::InvalidArgument)?;
+        let instance = world.get_dynamic_component(entity_id, str_arg(component)?)?;
+        let Some(DynamicValue::String(text)) = instance.get(str_arg(field)?) else {
+            return Err(SaraStatus::FieldMismatch);
+        };
+
+        if length.is_null() {
+            return Err(SaraStatus::InvalidArgument);
+        }
+        *length = text.len();
+
+        if buffer.is_null() || capacity <= text.len() {
+            return Err(SaraStatus::BufferTooSmall);
+        }
+
+        ptr::copy_nonoverlapping(text.as_ptr().cast::<c_char>(), buffer, text.len());
+        *buffer.add(text.len()) = 0;
+        Ok(())
+    })
+}
+
+/**
+Registers `system` under `name`, see `World::register_named_system`. `user_data` is passed back
+to each call, the world doesn't own it.
+
+# Safety
+`world` must be a valid world pointer and `name` a valid null-terminated string. `user_data`
+must stay valid for as long as the system is registered.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_system_register(
+    world: *mut SaraWorld,
+    name: *const c_char,
+    system: SaraSystem,
+    user_data: *mut c_void,
+) -> SaraStatus {
+    call(|| {
+        let world = world_mut(world)?;
+
+        world.register_named_system(str_arg(name)?, move |world| {
+            system(world, user_data);
+        });
+        Ok(())
+    })
+}
+
+/**
+Runs the system registered under `name`, whether it was registered from C or from Rust.
+
+# Safety
+`world` must be a valid world pointer and `name` a valid null-terminated string.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sara_system_run(world: *mut SaraWorld, name: *const c_char) -> SaraStatus {
+    call(|| Ok(world_mut(world)?.run_named_system(str_arg(name)?)?))
+}
+
+// Runs the body of a call, turning panics into a status since they can't unwind into C.
+fn call(body: impl FnOnce() -> Result<(), SaraStatus>) -> SaraStatus {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => SaraStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => SaraStatus::Panicked,
+    }
+}
+
+unsafe fn world_mut<'a>(world: *mut SaraWorld) -> Result<&'a mut World, SaraStatus> {
+    world.as_mut().ok_or(SaraStatus::InvalidArgument)
+}
+
+unsafe fn out_arg<'a, T>(out: *mut T) -> Result<&'a mut T, SaraStatus> {
+    out.as_mut().ok_or(SaraStatus::InvalidArgument)
+}
+
+unsafe fn str_arg<'a>(text: *const c_char) -> Result<&'a str, SaraStatus> {
+    if text.is_null() {
+        return Err(SaraStatus::InvalidArgument);
+    }
+
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| SaraStatus::InvalidArgument)
+}
+
+unsafe fn component_mut<'a>(
+    world: &'a World,
+    entity_id: usize,
+    component: *const c_char,
+) -> Result<RefMut<'a, DynamicComponent>, SaraStatus> {
+    Ok(world.get_dynamic_component_mut(entity_id, str_arg(component)?)?)
+}
+
+unsafe fn set_field(
+    world: *mut SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    value: DynamicValue,
+) -> SaraStatus {
+    call(|| {
+        let world = world_mut(world)?;
+        let mut instance = component_mut(world, entity_id, component)?;
+
+        if !instance.set(str_arg(field)?, value) {
+            return Err(SaraStatus::FieldMismatch);
+        }
+        Ok(())
+    })
+}
+
+unsafe fn get_field<T>(
+    world: *const SaraWorld,
+    entity_id: usize,
+    component: *const c_char,
+    field: *const c_char,
+    out: *mut T,
+    read: impl FnOnce(&DynamicValue) -> Option<T>,
+) -> SaraStatus {
+    call(|| {
+        let world = world.as_ref().ok_or(SaraStatus::InvalidArgument)?;
+        let instance = world.get_dynamic_component(entity_id, str_arg(component)?)?;
+        let value = instance
+            .get(str_arg(field)?)
+            .and_then(read)
+            .ok_or(SaraStatus::FieldMismatch)?;
+
+        if out.is_null() {
+            return Err(SaraStatus::InvalidArgument);
+        }
+
+        *out = value;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern "C" fn count_calls(_world: *mut SaraWorld, user_data: *mut c_void) {
+        unsafe { *user_data.cast::<u32>() += 1 };
+    }
+
+    #[test]
+    fn components_are_written_and_read_through_the_c_api() {
+        unsafe {
+            let world = sara_world_new();
+            let field_names = [c"current".as_ptr(), c"owner".as_ptr()];
+            let field_types = [SaraFieldType::Int, SaraFieldType::String];
+            let mut player = usize::MAX;
+            let mut alive = false;
+            let mut has_health = true;
+
+            assert_eq!(sara_entity_create(world, &mut player), SaraStatus::Ok);
+
+            assert_eq!(
+                sara_component_register(
+                    world,
+                    c"Health".as_ptr(),
+                    field_names.as_ptr(),
+                    field_types.as_ptr(),
+                    2
+                ),
+                SaraStatus::Ok
+            );
+            assert_eq!(
+                sara_component_insert(world, player, c"Health".as_ptr()),
+                SaraStatus::Ok
+            );
+            assert_eq!(
+                sara_entity_is_alive(world, player, &mut alive),
+                SaraStatus::Ok
+            );
+            assert!(alive);
+
+            sara_component_set_int(world, player, c"Health".as_ptr(), c"current".as_ptr(), 90);
+            sara_component_set_string(
+                world,
+                player,
+                c"Health".as_ptr(),
+                c"owner".as_ptr(),
+                c"Sara".as_ptr(),
+            );
+
+            let mut current = 0;
+            let mut buffer = [0 as c_char; 3];
+            let mut length = 0;
+
+            assert_eq!(
+                sara_component_get_int(
+                    world,
+                    player,
+                    c"Health".as_ptr(),
+                    c"current".as_ptr(),
+                    &mut current
+                ),
+                SaraStatus::Ok
+            );
+            assert_eq!(current, 90);
+            assert_eq!(
+                sara_component_get_string(
+                    world,
+                    player,
+                    c"Health".as_ptr(),
+                    c"owner".as_ptr(),
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut length
+                ),
+                SaraStatus::BufferTooSmall
+            );
+            assert_eq!(length, 4);
+            assert_eq!(
+                sara_component_set_float(
+                    world,
+                    player,
+                    c"Health".as_ptr(),
+                    c"current".as_ptr(),
+                    1.0
+                ),
+                SaraStatus::FieldMismatch
+            );
+
+            assert_eq!(sara_entity_destroy(world, player), SaraStatus::Ok);
+            assert_eq!(
+                sara_component_has(world, player, c"Health".as_ptr(), &mut has_health),
+                SaraStatus::Ok
+            );
+            assert!(!has_health);
+            assert_eq!(
+                sara_entity_create(world, ptr::null_mut()),
+                SaraStatus::InvalidArgument
+            );
+            sara_world_free(world);
+        }
+    }
+
+    #[test]
+    fn c_systems_run_by_name() {
+        unsafe {
+            let world = sara_world_new();
+            let mut calls = 0_u32;
+            let user_data = (&mut calls as *mut u32).cast::<c_void>();
+
+            sara_system_register(world, c"count".as_ptr(), count_calls, user_data);
+
+            assert_eq!(sara_system_run(world, c"count".as_ptr()), SaraStatus::Ok);
+            assert_eq!(
+                sara_system_run(world, c"missing".as_ptr()),
+                SaraStatus::SystemNotFound
+            );
+            sara_world_free(world);
+
+            assert_eq!(calls, 1);
+        }
+    }
+}
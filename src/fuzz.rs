@@ -0,0 +1,531 @@
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use arbitrary::Arbitrary;
+
+use crate::ecs_errors::ECSError;
+use crate::rng::Rng;
+use crate::World;
+
+/**
+Components the operations work with: two with data and a zero-sized one, which is stored
+differently.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Arbitrary)]
+pub enum FuzzComponent {
+    Small(u8),
+    Large(u64),
+    Marker,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Small(u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Large(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Marker;
+
+/**
+A step of a generated test case. Most operations pick entities by position among the living
+ones, wrapping around, rather than by ID, so every sequence is valid and removing any operation
+from a failing one leaves another valid sequence to try, which is what makes shrinking work.
+The `*Id` operations take a raw ID instead, which may be dead or out of range, and check that
+the world refuses them, or ignores them for removals from dead entities, without panicking.
+
+Operations implement `Arbitrary`, so a coverage-guided fuzzer can build them from its input.
+
+Example:
+```
+use arbitrary::{Arbitrary, Unstructured};
+use sara_ecs::fuzz::{self, Operation};
+
+// What a `cargo fuzz` target would do with the bytes it's given.
+let data = [7, 1, 3, 0, 42, 9, 9, 2, 5, 8];
+let operations = Vec::<Operation>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+
+assert_eq!(fuzz::run(&operations), Ok(()));
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Arbitrary)]
+pub enum Operation {
+    Spawn(FuzzComponent),
+    Despawn {
+        entity: u8,
+    },
+    Insert {
+        entity: u8,
+        component: FuzzComponent,
+    },
+    /// `kind` picks the component type, wrapping around.
+    Remove {
+        entity: u8,
+        kind: u8,
+    },
+    DespawnId {
+        id: u8,
+    },
+    InsertId {
+        id: u8,
+        component: FuzzComponent,
+    },
+    RemoveId {
+        id: u8,
+        kind: u8,
+    },
+    /// Queries the entities having every component type whose bit is set in `mask`.
+    Query {
+        mask: u8,
+    },
+    Compact,
+}
+
+impl FuzzComponent {
+    fn random(rng: &mut Rng) -> Self {
+        match rng.range_u32(0..3) {
+            0 => FuzzComponent::Small(rng.next_u32() as u8),
+            1 => FuzzComponent::Large(rng.next_u64()),
+            _ => FuzzComponent::Marker,
+        }
+    }
+}
+
+impl Operation {
+    /**
+    Generates `length` random operations, biased towards spawning so the world isn't empty
+    most of the time.
+    */
+    pub fn random_sequence(rng: &mut Rng, length: usize) -> Vec<Operation> {
+        (0..length)
+            .map(|_| {
+                let entity = rng.next_u32() as u8;
+
+                match rng.range_u32(0..12) {
+                    0..=2 => Operation::Spawn(FuzzComponent::random(rng)),
+                    3 => Operation::Despawn { entity },
+                    4..=5 => Operation::Insert {
+                        entity,
+                        component: FuzzComponent::random(rng),
+                    },
+                    6..=7 => Operation::Remove {
+                        entity,
+                        kind: rng.range_u32(0..3) as u8,
+                    },
+                    8 => match rng.range_u32(0..3) {
+                        0 => Operation::DespawnId { id: entity },
+                        1 => Operation::InsertId {
+                            id: entity,
+                            component: FuzzComponent::random(rng),
+                        },
+                        _ => Operation::RemoveId {
+                            id: entity,
+                            kind: rng.range_u32(0..3) as u8,
+                        },
+                    },
+                    9..=10 => Operation::Query {
+                        mask: rng.range_u32(0..8) as u8,
+                    },
+                    _ => Operation::Compact,
+                }
+            })
+            .collect()
+    }
+}
+
+/**
+Why running a sequence of operations failed: an operation returned an error or panicked, or
+the world stopped matching the model of what it should contain.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    // Index of the operation after which the failure was found.
+    pub step: usize,
+    pub operation: Operation,
+    pub reason: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "step {} ({:?}): {}",
+            self.step, self.operation, self.reason
+        )
+    }
+}
+
+// Components each living entity should have, the model the world is checked against.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Expected {
+    small: Option<u8>,
+    large: Option<u64>,
+    marker: bool,
+}
+
+impl Expected {
+    fn is_empty(&self) -> bool {
+        self.small.is_none() && self.large.is_none() && !self.marker
+    }
+
+    fn matches(&self, mask: u8) -> bool {
+        (mask & 1 == 0 || self.small.is_some())
+            && (mask & 2 == 0 || self.large.is_some())
+            && (mask & 4 == 0 || self.marker)
+    }
+}
+
+/**
+Runs operations against a fresh world, checking after each one that the world agrees with a
+simple model of the entities and components it should contain, and that `World::validate`
+finds no violations. Panics are caught and reported as failures. Requires the `fuzzing`
+feature.
+
+Example:
+```
+use sara_ecs::fuzz::{self, Operation};
+use sara_ecs::rng::Rng;
+
+let mut rng = Rng::seed_from_u64(3);
+
+for _ in 0..10 {
+    let operations = Operation::random_sequence(&mut rng, 50);
+
+    if let Err(failure) = fuzz::run(&operations) {
+        panic!("{failure}, shrunk to {:?}", fuzz::shrink(operations));
+    }
+}
+```
+*/
+pub fn run(operations: &[Operation]) -> Result<(), Failure> {
+    let mut world = World::new();
+    let mut expected: BTreeMap<usize, Expected> = BTreeMap::new();
+
+    world.register_component::<Small>();
+    world.register_component::<Large>();
+    world.register_component::<Marker>();
+
+    for (step, operation) in operations.iter().enumerate() {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            apply(&mut world, &mut expected, *operation)?;
+            check(&world, &expected)
+        }));
+
+        let reason = match result {
+            Ok(Ok(())) => continue,
+            Ok(Err(error)) => error.to_string(),
+            Err(payload) => panic_message(payload.as_ref()),
+        };
+
+        return Err(Failure {
+            step,
+            operation: *operation,
+            reason,
+        });
+    }
+
+    Ok(())
+}
+
+/**
+Shrinks a failing sequence by removing operations for as long as what's left still fails,
+to get a minimal reproduction. Returns the sequence unchanged if it doesn't fail.
+*/
+pub fn shrink(mut operations: Vec<Operation>) -> Vec<Operation> {
+    if let Err(failure) = run(&operations) {
+        operations.truncate(failure.step + 1);
+    }
+
+    let mut index = 0;
+
+    while index < operations.len() {
+        let mut candidate = operations.clone();
+        candidate.remove(index);
+
+        if run(&candidate).is_err() {
+            operations = candidate;
+        } else {
+            index += 1;
+        }
+    }
+
+    operations
+}
+
+fn apply(
+    world: &mut World,
+    expected: &mut BTreeMap<usize, Expected>,
+    operation: Operation,
+) -> Result<(), Box<dyn Error>> {
+    let living: Vec<usize> = expected.keys().copied().collect();
+    let pick = |entity: u8| (!living.is_empty()).then(|| living[entity as usize % living.len()]);
+
+    match operation {
+        Operation::Spawn(component) => {
+            let mut model = Expected::default();
+            let entity_id = match component {
+                FuzzComponent::Small(value) => {
+                    model.small = Some(value);
+                    world.create_entity().with_component(Small(value))?.id()
+                }
+                FuzzComponent::Large(value) => {
+                    model.large = Some(value);
+                    world.create_entity().with_component(Large(value))?.id()
+                }
+                FuzzComponent::Marker => {
+                    model.marker = true;
+                    world.create_entity().with_component(Marker)?.id()
+                }
+            };
+
+            if expected.insert(entity_id, model).is_some() {
+                return Err(format!("entity {entity_id} was spawned while alive").into());
+            }
+        }
+        Operation::Despawn { entity } => {
+            if let Some(entity_id) = pick(entity) {
+                despawn(world, expected, entity_id)?;
+            }
+        }
+        Operation::Insert { entity, component } => {
+            if let Some(entity_id) = pick(entity) {
+                insert(world, expected, entity_id, component)?;
+            }
+        }
+        Operation::Remove { entity, kind } => {
+            if let Some(entity_id) = pick(entity) {
+                remove(world, expected, entity_id, kind)?;
+            }
+        }
+        Operation::DespawnId { id } => {
+            let entity_id = usize::from(id);
+
+            if expected.contains_key(&entity_id) {
+                despawn(world, expected, entity_id)?;
+            } else {
+                refused(entity_id, world.remove_entity(entity_id))?;
+            }
+        }
+        Operation::InsertId { id, component } => {
+            let entity_id = usize::from(id);
+
+            if expected.contains_key(&entity_id) {
+                insert(world, expected, entity_id, component)?;
+            } else {
+                let result = match component {
+                    FuzzComponent::Small(value) => {
+                        world.add_component_to_entity(entity_id, Small(value))
+                    }
+                    FuzzComponent::Large(value) => {
+                        world.add_component_to_entity(entity_id, Large(value))
+                    }
+                    FuzzComponent::Marker => world.add_component_to_entity(entity_id, Marker),
+                };
+                refused(entity_id, result)?;
+            }
+        }
+        Operation::RemoveId { id, kind } => {
+            let entity_id = usize::from(id);
+
+            if expected.contains_key(&entity_id) {
+                remove(world, expected, entity_id, kind)?;
+            } else {
+                let result = match kind % 3 {
+                    0 => world.remove_entity_component::<Small>(entity_id),
+                    1 => world.remove_entity_component::<Large>(entity_id),
+                    _ => world.remove_entity_component::<Marker>(entity_id),
+                };
+
+                // Removing from a dead entity does nothing, like removing a component it
+                // doesn't have, so only IDs past the last slot have to be refused.
+                if entity_id >= world.entities().len() {
+                    refused(entity_id, result)?;
+                }
+            }
+        }
+        Operation::Query { mask } => {
+            let mut query = world.query();
+
+            if mask & 1 != 0 {
+                query.with_component_filter::<Small>()?;
+            }
+            if mask & 2 != 0 {
+                query.with_component_filter::<Large>()?;
+            }
+            if mask & 4 != 0 {
+                query.with_component_filter::<Marker>()?;
+            }
+
+            let found = query.try_run()?.entity_ids;
+            let wanted: Vec<usize> = expected
+                .iter()
+                .filter(|(_entity_id, model)| model.matches(mask))
+                .map(|(entity_id, _model)| *entity_id)
+                .collect();
+
+            if mask & 7 != 0 && found != wanted {
+                return Err(
+                    format!("query {mask:#b} found {found:?} instead of {wanted:?}").into(),
+                );
+            }
+        }
+        Operation::Compact => {
+            world.compact();
+        }
+    }
+
+    Ok(())
+}
+
+fn despawn(
+    world: &mut World,
+    expected: &mut BTreeMap<usize, Expected>,
+    entity_id: usize,
+) -> Result<(), Box<dyn Error>> {
+    world.remove_entity(entity_id)?;
+    expected.remove(&entity_id);
+    Ok(())
+}
+
+fn insert(
+    world: &mut World,
+    expected: &mut BTreeMap<usize, Expected>,
+    entity_id: usize,
+    component: FuzzComponent,
+) -> Result<(), Box<dyn Error>> {
+    let model = expected.get_mut(&entity_id).unwrap();
+
+    match component {
+        FuzzComponent::Small(value) => {
+            model.small = Some(value);
+            world.add_component_to_entity(entity_id, Small(value))?;
+        }
+        FuzzComponent::Large(value) => {
+            model.large = Some(value);
+            world.add_component_to_entity(entity_id, Large(value))?;
+        }
+        FuzzComponent::Marker => {
+            model.marker = true;
+            world.add_component_to_entity(entity_id, Marker)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn remove(
+    world: &mut World,
+    expected: &mut BTreeMap<usize, Expected>,
+    entity_id: usize,
+    kind: u8,
+) -> Result<(), Box<dyn Error>> {
+    let model = expected.get_mut(&entity_id).unwrap();
+
+    match kind % 3 {
+        0 => {
+            model.small = None;
+            world.remove_entity_component::<Small>(entity_id)?;
+        }
+        1 => {
+            model.large = None;
+            world.remove_entity_component::<Large>(entity_id)?;
+        }
+        _ => {
+            model.marker = false;
+            world.remove_entity_component::<Marker>(entity_id)?;
+        }
+    }
+
+    // Entities without components are gone.
+    if model.is_empty() {
+        expected.remove(&entity_id);
+    }
+
+    Ok(())
+}
+
+// Operations on entities that are dead or were never spawned must fail rather than panic. Either
+// way the world has to be left as it was, which `check` verifies afterwards.
+fn refused(entity_id: usize, result: Result<(), ECSError>) -> Result<(), Box<dyn Error>> {
+    match result {
+        Ok(()) => Err(format!("operation on missing entity {entity_id} succeeded").into()),
+        Err(_) => Ok(()),
+    }
+}
+
+fn check(world: &World, expected: &BTreeMap<usize, Expected>) -> Result<(), Box<dyn Error>> {
+    let violations = world.validate();
+
+    if !violations.is_empty() {
+        return Err(format!("invariants broken: {violations:?}").into());
+    }
+
+    let entities = world.entities();
+    let living: Vec<usize> = (0..entities.len())
+        .filter(|entity_id| entities.is_alive(*entity_id))
+        .collect();
+
+    if !living.iter().eq(expected.keys()) {
+        return Err(format!(
+            "entities {living:?} are alive instead of {:?}",
+            expected.keys()
+        )
+        .into());
+    }
+
+    for (entity_id, model) in expected {
+        let found = stored(world, *entity_id)?;
+
+        if found != *model {
+            return Err(format!("entity {entity_id} has {found:?} instead of {model:?}").into());
+        }
+    }
+
+    Ok(())
+}
+
+fn stored(world: &World, entity_id: usize) -> Result<Expected, ECSError> {
+    let mut found = Expected {
+        marker: world
+            .entity(entity_id)?
+            .component_types()
+            .iter()
+            .any(|info| info.type_id == TypeId::of::<Marker>()),
+        ..Expected::default()
+    };
+
+    for query_entity in world
+        .query()
+        .with_component_filter::<Small>()?
+        .get_entities()
+    {
+        if query_entity.id == entity_id {
+            found.small = Some(query_entity.get_component::<Small>()?.0);
+        }
+    }
+    for query_entity in world
+        .query()
+        .with_component_filter::<Large>()?
+        .get_entities()
+    {
+        if query_entity.id == entity_id {
+            found.large = Some(query_entity.get_component::<Large>()?.0);
+        }
+    }
+
+    Ok(found)
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .map_or_else(
+            || "panicked".to_owned(),
+            |message| format!("panicked: {message}"),
+        )
+}
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use crate::serialization::{EntityMap, MapEntities};
+use crate::World;
+
+/**
+Component pointing to the parent of an entity. Maintained by `World::set_parent` together with
+the parent's `Children`, so it shouldn't be added or modified by hand.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parent(pub usize);
+
+/**
+Component listing the children of an entity, in the order they were attached. Maintained by
+`World::set_parent` together with the children's `Parent`.
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Children(pub Vec<usize>);
+
+#[cfg(feature = "serde")]
+impl MapEntities for Parent {
+    fn map_entities(&mut self, entity_map: &EntityMap) {
+        self.0 = entity_map[&self.0];
+    }
+}
+
+#[cfg(feature = "serde")]
+impl MapEntities for Children {
+    fn map_entities(&mut self, entity_map: &EntityMap) {
+        for child in &mut self.0 {
+            *child = entity_map[child];
+        }
+    }
+}
+
+/**
+Iterator over the ancestors of an entity, from its parent up to the root. Created with
+`World::ancestors`.
+*/
+pub struct Ancestors<'w> {
+    world: &'w World,
+    next: Option<usize>,
+}
+
+impl<'w> Ancestors<'w> {
+    pub(crate) fn new(world: &'w World, entity_id: usize) -> Self {
+        Self {
+            world,
+            next: world.parent(entity_id),
+        }
+    }
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let ancestor = self.next?;
+
+        self.next = self.world.parent(ancestor);
+        Some(ancestor)
+    }
+}
+
+/**
+Iterator over the descendants of an entity, visiting each child's subtree before its next
+sibling. Created with `World::descendants_depth_first`.
+*/
+pub struct DescendantsDepthFirst<'w> {
+    world: &'w World,
+    stack: Vec<usize>,
+}
+
+impl<'w> DescendantsDepthFirst<'w> {
+    pub(crate) fn new(world: &'w World, entity_id: usize) -> Self {
+        let mut stack: Vec<usize> = world.children(entity_id).collect();
+        stack.reverse();
+
+        Self { world, stack }
+    }
+}
+
+impl Iterator for DescendantsDepthFirst<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let descendant = self.stack.pop()?;
+        let first_child = self.stack.len();
+
+        self.stack.extend(self.world.children(descendant));
+        self.stack[first_child..].reverse();
+        Some(descendant)
+    }
+}
+
+/**
+Iterator over the descendants of an entity, level by level: children first, then
+grandchildren, and so on. Created with `World::descendants_breadth_first`.
+*/
+pub struct DescendantsBreadthFirst<'w> {
+    world: &'w World,
+    queue: VecDeque<usize>,
+}
+
+impl<'w> DescendantsBreadthFirst<'w> {
+    pub(crate) fn new(world: &'w World, entity_id: usize) -> Self {
+        Self {
+            world,
+            queue: world.children(entity_id).collect(),
+        }
+    }
+}
+
+impl Iterator for DescendantsBreadthFirst<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let descendant = self.queue.pop_front()?;
+
+        self.queue.extend(self.world.children(descendant));
+        Some(descendant)
+    }
+}
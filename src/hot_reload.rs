@@ -0,0 +1,287 @@
+use std::alloc::Layout;
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+
+use crate::schedule::System;
+use crate::World;
+
+/**
+Identifies a component type by a hash of its full type name. Unlike `TypeId`, it stays the same
+across builds, so a reloaded library and the host agree on it as long as the type keeps its
+name and module path.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::hot_reload::StableComponentId;
+
+struct Health(pub u32);
+
+let mut world = World::new();
+world.register_component::<Health>();
+
+let id = world.stable_component_id("Health").unwrap();
+
+assert_eq!(id, StableComponentId::of::<Health>());
+assert_eq!(
+    world.component_id_by_stable_id(id),
+    world.component_id_by_name("Health")
+);
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StableComponentId(u64);
+
+impl StableComponentId {
+    /**
+    ID of the component with the full type name `name`, like `my_game::Health`.
+    */
+    pub fn from_name(name: &str) -> Self {
+        // 64-bit FNV-1a.
+        let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+
+        StableComponentId(hash)
+    }
+
+    pub fn of<T: Any>() -> Self {
+        Self::from_name(type_name::<T>())
+    }
+
+    pub fn value(self) -> u64 {
+        self.0
+    }
+}
+
+/**
+Full type name, size and alignment of a component type, as seen by the code compiled against it.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentLayout {
+    pub name: String,
+    pub layout: Layout,
+}
+
+impl ComponentLayout {
+    pub fn of<T: Any>() -> Self {
+        ComponentLayout {
+            name: type_name::<T>().to_owned(),
+            layout: Layout::new::<T>(),
+        }
+    }
+}
+
+/**
+Systems handed over by a reloadable library, together with the layouts of the components it was
+compiled against. The library fills it from an exported function, and the host passes it to
+`World::reload_systems`, which only swaps the systems if every layout still matches the world.
+
+Components have to live in a crate that isn't reloaded: the world keeps their values across
+reloads, and only the systems come from the library.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::hot_reload::SystemHandoff;
+
+struct Health(pub u32);
+
+// Exported by the library, and looked up by the host after every rebuild.
+fn register_systems(handoff: &mut SystemHandoff) {
+    handoff
+        .expect_component::<Health>()
+        .add_system("regenerate", |world| {
+            let mut query = world.query();
+
+            for mut entity in query.with_component_filter::<Health>().unwrap().get_entities() {
+                entity.get_component_mut::<Health>().unwrap().0 += 1;
+            }
+        });
+}
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+    world.register_component::<Health>();
+
+    let mut handoff = SystemHandoff::new();
+    register_systems(&mut handoff);
+    world.reload_systems("gameplay", handoff)?;
+
+    world.run_named_system("regenerate")?;
+
+    // The old systems must be dropped before the library is unloaded.
+    world.unload_systems("gameplay");
+
+    Ok(())
+}
+```
+*/
+#[derive(Default)]
+pub struct SystemHandoff {
+    pub(crate) layouts: Vec<ComponentLayout>,
+    pub(crate) systems: Vec<(String, System)>,
+}
+
+impl SystemHandoff {
+    pub fn new() -> Self {
+        SystemHandoff::default()
+    }
+
+    /**
+    Requires the world to have `T` registered with the same layout the library sees.
+    */
+    pub fn expect_component<T: Any>(&mut self) -> &mut Self {
+        self.expect_layout(ComponentLayout::of::<T>())
+    }
+
+    pub fn expect_layout(&mut self, layout: ComponentLayout) -> &mut Self {
+        self.layouts.push(layout);
+        self
+    }
+
+    /**
+    Adds a system that replaces the named system of the same name once the handoff is accepted.
+    */
+    pub fn add_system(
+        &mut self,
+        name: impl Into<String>,
+        system: impl FnMut(&mut World) + 'static,
+    ) -> &mut Self {
+        self.systems.push((name.into(), Box::new(system)));
+        self
+    }
+}
+
+impl std::fmt::Debug for SystemHandoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let systems: Vec<&str> = self.systems.iter().map(|(name, _)| name.as_str()).collect();
+
+        f.debug_struct("SystemHandoff")
+            .field("layouts", &self.layouts)
+            .field("systems", &systems)
+            .finish()
+    }
+}
+
+// Names of the named systems registered by each reloadable library.
+#[derive(Debug, Default)]
+pub(crate) struct ReloadableLibraries {
+    systems: HashMap<String, Vec<String>>,
+}
+
+impl ReloadableLibraries {
+    pub fn insert(&mut self, library: &str, systems: Vec<String>) {
+        self.systems.insert(library.to_owned(), systems);
+    }
+
+    pub fn remove(&mut self, library: &str) -> Option<Vec<String>> {
+        self.systems.remove(library)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn handoff(amount: u32) -> SystemHandoff {
+        let mut handoff = SystemHandoff::new();
+
+        handoff
+            .expect_component::<Health>()
+            .add_system("heal", move |world| {
+                let mut query = world.query();
+
+                for mut entity in query
+                    .with_component_filter::<Health>()
+                    .unwrap()
+                    .get_entities()
+                {
+                    entity.get_component_mut::<Health>().unwrap().0 += amount;
+                }
+            });
+        handoff
+    }
+
+    #[test]
+    fn stable_ids_follow_type_names() {
+        let mut world = World::new();
+        world.register_component::<Health>();
+
+        assert_eq!(
+            StableComponentId::of::<Health>(),
+            StableComponentId::from_name(type_name::<Health>())
+        );
+        assert_ne!(
+            StableComponentId::of::<Health>(),
+            StableComponentId::of::<u32>()
+        );
+        assert_eq!(
+            world.stable_component_id("Health"),
+            Some(StableComponentId::of::<Health>())
+        );
+        assert_eq!(world.stable_component_id("Mana"), None);
+        assert_eq!(
+            world.component_id_by_stable_id(StableComponentId::of::<u32>()),
+            None
+        );
+    }
+
+    #[test]
+    fn reloading_replaces_the_library_systems() {
+        let mut world = World::new();
+        world.register_component::<Health>();
+        let player = world
+            .create_entity()
+            .with_component(Health(10))
+            .unwrap()
+            .id();
+
+        world.reload_systems("gameplay", handoff(1)).unwrap();
+        world.run_named_system("heal").unwrap();
+        world.reload_systems("gameplay", handoff(5)).unwrap();
+        world.run_named_system("heal").unwrap();
+
+        let mut query = world.query();
+        let entities = query
+            .with_component_filter::<Health>()
+            .unwrap()
+            .get_entities();
+
+        assert_eq!(entities[0].id, player);
+        assert_eq!(entities[0].get_component::<Health>().unwrap().0, 16);
+        drop(entities);
+
+        assert!(world.unload_systems("gameplay"));
+        assert!(world.named_systems().is_empty());
+        assert!(!world.unload_systems("gameplay"));
+    }
+
+    #[test]
+    fn mismatched_layouts_keep_the_old_systems() {
+        let mut world = World::new();
+        world.register_component::<Health>();
+        world.reload_systems("gameplay", handoff(1)).unwrap();
+
+        let mut grown = handoff(5);
+        grown.layouts[0].layout = Layout::new::<u64>();
+
+        assert!(matches!(
+            world.reload_systems("gameplay", grown),
+            Err(ECSError::ComponentLayoutMismatch { .. })
+        ));
+
+        let mut unknown = SystemHandoff::new();
+        unknown.expect_component::<u8>();
+
+        assert!(matches!(
+            world.reload_systems("gameplay", unknown),
+            Err(ECSError::ComponentNotRegistered { .. })
+        ));
+        assert_eq!(world.named_systems(), vec!["heal"]);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+use crate::resource_hooks::ResourceChange;
+
+/**
+A structural mutation of the world, as recorded by the `Journal`.
+Components and resources are identified by their type names.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructuralChange {
+    Spawned {
+        entity_id: usize,
+    },
+    Despawned {
+        entity_id: usize,
+    },
+    ComponentInserted {
+        entity_id: usize,
+        component: String,
+    },
+    ComponentRemoved {
+        entity_id: usize,
+        component: String,
+    },
+    ResourceChanged {
+        resource: String,
+        change: ResourceChange,
+    },
+}
+
+impl StructuralChange {
+    /**
+    The entity affected by the change, if any.
+    */
+    pub fn entity_id(&self) -> Option<usize> {
+        match self {
+            StructuralChange::Spawned { entity_id }
+            | StructuralChange::Despawned { entity_id }
+            | StructuralChange::ComponentInserted { entity_id, .. }
+            | StructuralChange::ComponentRemoved { entity_id, .. } => Some(*entity_id),
+            StructuralChange::ResourceChanged { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub tick: u64,
+    pub change: StructuralChange,
+}
+
+/**
+Bounded log of the structural changes made to a world: spawns, despawns, component insertions
+and removals, and resource changes. The world only records changes while a `Journal` resource
+exists, so journaling costs nothing unless it's enabled. Once `capacity` entries are recorded,
+the oldest ones are dropped.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::journal::{Journal, StructuralChange};
+
+struct Health(pub u32);
+
+let mut world = World::new();
+
+world.add_resource(Journal::new(100)).unwrap();
+world.register_component::<Health>();
+world.create_entity().with_component(Health(10)).unwrap();
+world.remove_entity(0).unwrap();
+
+let journal = world.get_resource::<Journal>().unwrap();
+let despawns = journal
+    .entries_for(0)
+    .filter(|entry| matches!(entry.change, StructuralChange::Despawned { .. }))
+    .count();
+
+assert_eq!(despawns, 1);
+```
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    capacity: usize,
+    tick: u64,
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /**
+    Moves on to the next tick, usually called once per frame. New entries are recorded with
+    the current tick.
+    */
+    pub fn advance_tick(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn record(&mut self, change: StructuralChange) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(JournalEntry {
+            tick: self.tick,
+            change,
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter()
+    }
+
+    pub fn entries_for(&self, entity_id: usize) -> impl Iterator<Item = &JournalEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.change.entity_id() == Some(entity_id))
+    }
+
+    pub fn entries_since(&self, tick: u64) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().filter(move |entry| entry.tick >= tick)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut journal = Journal::new(2);
+
+        for entity_id in 0..3 {
+            journal.record(StructuralChange::Spawned { entity_id });
+        }
+
+        let entity_ids: Vec<Option<usize>> = journal
+            .entries()
+            .map(|entry| entry.change.entity_id())
+            .collect();
+
+        assert_eq!(entity_ids, vec![Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn entries_are_recorded_with_the_current_tick() {
+        let mut journal = Journal::new(10);
+
+        journal.record(StructuralChange::Spawned { entity_id: 0 });
+        journal.advance_tick();
+        journal.record(StructuralChange::Despawned { entity_id: 0 });
+
+        assert_eq!(journal.entries_since(1).count(), 1);
+        assert_eq!(journal.entries_for(0).count(), 2);
+
+        journal.clear();
+        assert!(journal.is_empty());
+    }
+}
@@ -1,16 +1,156 @@
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefMut};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::rc::Rc;
 
+#[cfg(feature = "log")]
+use change_log::ChangeLogger;
+use checkpoint::WorldCheckpoint;
+use commands::Commands;
+use component_defaults::ComponentDefaults;
+use component_hooks::ComponentHook;
+use component_registration::{ComponentRegistration, StorageKind};
+use component_set::ComponentSet;
+use debug_dump::{ComponentDump, EntityDump, ResourceDump, WorldDump};
+use dynamic_component::{DynamicComponent, DynamicComponentDescriptor};
 use ecs_errors::ECSError;
+use entities::Entities;
+use entity_builder::EntityBuilder;
+use entity_pool::EntityPools;
+use entity_ref::EntityRef;
 use entity_storage::query::Query;
+use entity_uuid::EntityUuid;
+use events::{EntityDespawned, EntitySpawned, Events};
+use hierarchy::{Ancestors, Children, DescendantsBreadthFirst, DescendantsDepthFirst, Parent};
+use hot_reload::{ComponentLayout, ReloadableLibraries, StableComponentId, SystemHandoff};
+use journal::{Journal, StructuralChange};
+use memory_report::{MemoryReport, ResourceMemory};
+use name::Name;
+use observers::Trigger;
+use previous::Prev;
+use query_param::{Each, EachMut};
+use reflect::Reflect;
+use relation::Relation;
+use required_components::{RequiredComponent, RequiredComponents};
+use resource_hooks::{ResourceChange, ResourceHook};
+use schedule::Schedule;
+use scripting::{ScriptContext, ScriptSandbox};
+use spatial::{SpatialIndex, SpatialPosition};
+use time::Time;
+use type_registry::{short_name, ReflectFn, ReflectMutFn, TypeRegistry};
+use value_index::{AnyValueIndex, ValueIndexes};
+use world_builder::WorldBuilder;
 
+pub mod async_systems;
+#[cfg(feature = "log")]
+pub mod change_log;
+pub mod checkpoint;
+pub mod commands;
+pub mod component_defaults;
+pub mod component_hooks;
+pub mod component_registration;
+pub mod component_set;
+pub mod debug_dump;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod dynamic_component;
 pub mod ecs_errors;
+pub mod entities;
+pub mod entity_builder;
+mod entity_pool;
+pub mod entity_ref;
 mod entity_storage;
+pub mod entity_uuid;
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
+pub mod hierarchy;
+pub mod hot_reload;
+pub mod journal;
+pub mod memory_report;
+pub mod name;
+pub mod observers;
+#[cfg(feature = "serde")]
+pub mod prefab;
+pub mod previous;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query_param;
+pub mod reflect;
+pub mod relation;
+#[cfg(feature = "serde")]
+pub mod replication;
+pub mod required_components;
+pub mod resource_hooks;
 mod resource_storage;
+pub mod rng;
+pub mod rollback;
+pub mod schedule;
+pub mod scripting;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod spatial;
+pub mod tasks;
+pub mod time;
+pub mod timer;
+#[cfg(feature = "transform")]
+pub mod transform;
+pub mod type_registry;
+pub mod undo;
+pub mod value_index;
+pub mod world_builder;
+
+#[cfg(feature = "serde")]
+pub use serialization::{
+    ComponentMigration, EntityMap, EntitySnapshot, MapEntities, WorldSnapshot,
+};
+
+/**
+Creates a value using the world, which lets resources be built from other resources.
+Every type that implements `Default` gets this trait for free.
+*/
+pub trait FromWorld {
+    fn from_world(world: &mut World) -> Self;
+}
+
+impl<T: Default> FromWorld for T {
+    fn from_world(_world: &mut World) -> Self {
+        T::default()
+    }
+}
 
 #[derive(Default, Debug)]
 pub struct World {
     resource_storage: resource_storage::ResourceStorage,
     entitiy_storage: entity_storage::EntityStorage,
+    event_updaters: Vec<fn(&mut resource_storage::ResourceStorage)>,
+    previous_value_updaters: Vec<fn(&entity_storage::EntityStorage)>,
+    component_hooks: component_hooks::ComponentHooks,
+    observers: observers::Observers,
+    resource_hooks: resource_hooks::ResourceHooks,
+    entity_uuids: entity_uuid::EntityUuids,
+    entity_names: name::EntityNames,
+    relations: relation::Relations,
+    spatial_index: Option<SpatialIndex>,
+    value_indexes: ValueIndexes,
+    unique_components: HashSet<TypeId>,
+    required_components: RequiredComponents,
+    component_defaults: ComponentDefaults,
+    named_systems: schedule::NamedSystems,
+    entity_pools: EntityPools,
+    reloadable_libraries: ReloadableLibraries,
+    schedule: Schedule,
+    #[cfg(feature = "diagnostics")]
+    diagnostics_counters: diagnostics::DiagnosticsCounters,
+    #[cfg(feature = "serde")]
+    serialization: serialization::SerializationRegistry,
+    #[cfg(feature = "serde")]
+    prefabs: prefab::Prefabs,
+    #[cfg(feature = "serde")]
+    replication: replication::Replication,
 }
 
 impl World {
@@ -18,6 +158,14 @@ impl World {
         World::default()
     }
 
+    /**
+    Creates a `WorldBuilder`, to set the initial entity capacity, the component limit and
+    which optional features start enabled.
+    */
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+
     /**
     Adds a new resource to the world. The resource can be of any type that implements `Any`.
     Once added, the resource can be retrieved or modified by its type. This function consumes
@@ -35,36 +183,121 @@ impl World {
     ```
     */
     pub fn add_resource(&mut self, resource: impl Any) -> Result<(), ECSError> {
-        self.resource_storage.insert(resource)
+        let type_id = resource.type_id();
+        let type_name = std::any::type_name_of_val(&resource);
+
+        self.resource_storage.insert(resource)?;
+        self.run_resource_hooks(&type_id, type_name, ResourceChange::Added);
+
+        Ok(())
+    }
+
+    /**
+    Adds a resource of type `T` built with `FromWorld` (or `Default`), unless a resource of that
+    type is already present. Calling it several times is harmless, which makes it useful for
+    plugins that share resources.
+
+    Example:
+    ```
+    use sara_ecs::{FromWorld, World};
+
+    #[derive(Default)]
+    struct Score(pub u32);
+
+    struct ScoreText(pub String);
+
+    impl FromWorld for ScoreText {
+        fn from_world(world: &mut World) -> Self {
+            let score = world.get_resource::<Score>().unwrap();
+            ScoreText(format!("Score: {}", score.0))
+        }
+    }
+
+    let mut world = World::new();
+
+    world.init_resource::<Score>();
+    world.init_resource::<ScoreText>();
+    world.init_resource::<Score>(); // Does nothing, Score is already present.
+
+    assert_eq!(world.get_resource::<ScoreText>().unwrap().0, "Score: 0");
+    ```
+    */
+    pub fn init_resource<T: FromWorld + Any>(&mut self) {
+        if self.resource_storage.contains::<T>() {
+            return;
+        }
+
+        let resource = T::from_world(self);
+
+        if !self.resource_storage.contains::<T>() {
+            self.replace_resource(resource);
+        }
     }
 
     /**
-    Query for a resource and get a mutable reference to it.
+    Query for a resource and get a mutable guard to it.
     The type of the resource must be added in advance.
 
+    Only a shared reference to the world is needed, so several different resources can be
+    borrowed mutably at the same time. Borrowing the same resource twice while a mutable guard
+    is alive panics, like `RefCell::borrow_mut`.
+
     Example:
     ```
     use sara_ecs::World;
     let mut world = World::new();
 
     world.add_resource(10_u32);
+    world.add_resource(0.5_f32);
 
     {
-        let resource = world.get_resource_mut::<u32>().unwrap();
-        *resource += 1;
+        let mut counter = world.get_resource_mut::<u32>().unwrap();
+        let mut ratio = world.get_resource_mut::<f32>().unwrap();
+        *counter += 1;
+        *ratio *= 2.0;
     }
 
     let resource = world.get_resource::<u32>().unwrap();
     assert_eq!(*resource, 11);
     ```
     */
-    pub fn get_resource_mut<T: Any>(&mut self) -> Option<&mut T> {
-        self.resource_storage.get_mut::<T>()
+    pub fn get_resource_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        self.resource_storage.borrow_mut::<T>()
     }
 
     /**
-    Retrieves a reference to a resource by its type. If the resource exists, it will be returned as a reference.
-    Otherwise, `None` is returned.
+    Retrieves a mutable reference to a resource of type `T`, creating it with the given closure
+    first if the world does not contain one yet. The closure is not called otherwise.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    let mut world = World::new();
+
+    *world.get_resource_or_insert_with(|| 0_u32) += 1;
+    *world.get_resource_or_insert_with(|| 100_u32) += 1;
+
+    assert_eq!(*world.get_resource::<u32>().unwrap(), 2);
+    ```
+    */
+    pub fn get_resource_or_insert_with<T: Any>(&mut self, create: impl FnOnce() -> T) -> &mut T {
+        if !self.resource_storage.contains::<T>() {
+            self.resource_storage.replace(create());
+            self.run_resource_hooks(
+                &TypeId::of::<T>(),
+                std::any::type_name::<T>(),
+                ResourceChange::Added,
+            );
+        }
+
+        self.resource_storage
+            .get_mut::<T>()
+            .expect("resource removed by its own insertion hook")
+    }
+
+    /**
+    Retrieves a reference to a resource by its type. If the resource exists, it will be returned as a guard
+    that derefs to the resource. Otherwise, `None` is returned.
 
     Example:
     ```
@@ -75,11 +308,11 @@ impl World {
 
     if let Some(resource) = world.get_resource::<u32>() {
         assert_eq!(*resource, 10);
-    }
+    };
     ```
     */
-    pub fn get_resource<T: Any>(&self) -> Option<&T> {
-        self.resource_storage.get::<T>()
+    pub fn get_resource<T: Any>(&self) -> Option<Ref<'_, T>> {
+        self.resource_storage.borrow::<T>()
     }
 
     /**
@@ -97,224 +330,4927 @@ impl World {
     ```
     */
     pub fn remove_resource<T: Any>(&mut self) {
-        self.resource_storage.remove::<T>();
+        if self.resource_storage.remove::<T>() {
+            self.run_resource_hooks(
+                &TypeId::of::<T>(),
+                std::any::type_name::<T>(),
+                ResourceChange::Removed,
+            );
+        }
     }
 
     /**
-    Checks whether a resource of type `T` exists in the world.
-
-    This function returns `true` if the specified resource type is present in the world; otherwise, it returns `false`.
+    Removes a resource from the world and returns it, giving back ownership of the value.
+    Returns `None` if the world does not contain a resource of type `T`.
 
     Example:
     ```
     use sara_ecs::World;
     let mut world = World::new();
 
-    world.add_resource(10_u32); // Adds a u32 resource to the world
+    world.add_resource(10_u32);
 
-    assert!(world.contains_resource::<u32>()); // Checks if the u32 resource exists
-    assert!(!world.contains_resource::<f32>()); // Checks if a f32 resource does not exist
+    assert_eq!(world.take_resource::<u32>(), Some(10));
+    assert!(!world.contains_resource::<u32>());
     ```
     */
-    pub fn contains_resource<T: Any>(&self) -> bool {
-        self.resource_storage.contains::<T>()
+    pub fn take_resource<T: Any>(&mut self) -> Option<T> {
+        let resource = self.resource_storage.take::<T>()?;
+        self.run_resource_hooks(
+            &TypeId::of::<T>(),
+            std::any::type_name::<T>(),
+            ResourceChange::Removed,
+        );
+
+        Some(resource)
     }
 
     /**
-    Replaces an existing resource of type `T` with a new one.
-
-    This function will replace the current resource of the specified type in the world with the provided resource. If no resource of that type exists, the resource will be added.
+    Temporarily removes the resource of type `T` from the world and runs the closure with both
+    the world and the resource borrowed mutably, putting the resource back afterwards.
+    Resource hooks are not run for the temporary removal. Returns an error if the resource
+    does not exist.
 
     Example:
     ```
     use sara_ecs::World;
-    let mut world = World::new();
+    use sara_ecs::ecs_errors::ECSError;
 
-    world.add_resource(10_u32); // Adds a u32 resource to the world
-    world.replace_resource(20_u32); // Replaces the u32 resource with a new value
+    struct Health(pub u32);
+    struct SpawnedCount(pub u32);
 
-    assert_eq!(world.get_resource::<u32>(), Some(&20)); // Verifies the resource is replaced
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.add_resource(SpawnedCount(0))?;
+
+        world.resource_scope::<SpawnedCount, _>(|world, count| {
+            world.create_entity().with_component(Health(100))?;
+            count.0 += 1;
+            Ok::<(), ECSError>(())
+        })??;
+
+        assert_eq!(world.get_resource::<SpawnedCount>().unwrap().0, 1);
+        Ok(())
+    }
     ```
     */
-    pub fn replace_resource<T: Any>(&mut self, resource: T) {
+    pub fn resource_scope<T: Any, R>(
+        &mut self,
+        scope: impl FnOnce(&mut World, &mut T) -> R,
+    ) -> Result<R, ECSError> {
+        let mut resource =
+            self.resource_storage
+                .take::<T>()
+                .ok_or_else(|| ECSError::ResourceDoesNotExist {
+                    type_name: std::any::type_name::<T>().to_owned(),
+                })?;
+
+        let result = scope(self, &mut resource);
         self.resource_storage.replace(resource);
+
+        Ok(result)
     }
 
     /**
-    Registers a new component type in the world. This component can later be added to entities.
-    The type must implement `Any` and have a static lifetime.
+    Adds a resource stored under a key, so several resources of the same type can live in the
+    world at once. Keyed resources are independent from the regular resource of that type.
+    Returns an error if a resource of the same type is already stored under that key.
 
     Example:
     ```
     use sara_ecs::World;
-    struct Health(pub u32);
+
+    #[derive(Default)]
+    struct Inputs(pub u32);
+
     let mut world = World::new();
 
-    world.register_component::<Health>();
+    world.add_keyed_resource("player1", Inputs::default()).unwrap();
+    world.add_keyed_resource("player2", Inputs(5)).unwrap();
 
-    // Health component can now be used for entities
+    world.get_keyed_resource_mut::<Inputs>("player1").unwrap().0 += 1;
+
+    assert_eq!(world.get_keyed_resource::<Inputs>("player1").unwrap().0, 1);
+    assert_eq!(world.get_keyed_resource::<Inputs>("player2").unwrap().0, 5);
     ```
     */
-    pub fn register_component<T: Any + 'static>(&mut self) {
-        self.entitiy_storage.register_component::<T>();
+    pub fn add_keyed_resource<T: Any>(
+        &mut self,
+        key: impl Into<String>,
+        resource: T,
+    ) -> Result<(), ECSError> {
+        self.resource_storage.insert_keyed(key.into(), resource)
     }
 
     /**
-    Creates a new entity. The entity is initially empty and can later be populated with components.
-    This function returns a mutable reference to the entity system, allowing you to chain component
-    additions to the created entity.
+    Retrieves a resource of type `T` stored under the given key.
+    Returns `None` if there is no such resource.
 
     Example:
     ```
     use sara_ecs::World;
-    use sara_ecs::ecs_errors::ECSError;
+    let mut world = World::new();
 
-    struct Health(pub u32);
-    struct Speed(pub f32);
+    world.add_keyed_resource("volume", 0.5_f32).unwrap();
 
-    fn example() -> Result<(), ECSError> {
-        let mut world = World::new();
+    assert_eq!(*world.get_keyed_resource::<f32>("volume").unwrap(), 0.5);
+    assert!(world.get_keyed_resource::<f32>("pitch").is_none());
+    ```
+    */
+    pub fn get_keyed_resource<T: Any>(&self, key: &str) -> Option<Ref<'_, T>> {
+        self.resource_storage.borrow_keyed::<T>(key)
+    }
 
-        world.register_component::<Health>();
-        world.register_component::<Speed>();
+    /**
+    Retrieves a mutable guard to a resource of type `T` stored under the given key.
+    Returns `None` if there is no such resource.
 
-        let entity = world.create_entity()
-            .with_component(Health(100))?
-            .with_component(Speed(15.0))?;
+    Example:
+    ```
+    use sara_ecs::World;
+    let mut world = World::new();
 
-        Ok(())
-    }
+    world.add_keyed_resource("volume", 0.5_f32).unwrap();
+    *world.get_keyed_resource_mut::<f32>("volume").unwrap() = 1.0;
 
-    // The entity now has Health and Speed components
+    assert_eq!(*world.get_keyed_resource::<f32>("volume").unwrap(), 1.0);
     ```
     */
-    pub fn create_entity(&mut self) -> &mut entity_storage::EntityStorage {
-        self.entitiy_storage.create_entity()
+    pub fn get_keyed_resource_mut<T: Any>(&self, key: &str) -> Option<RefMut<'_, T>> {
+        self.resource_storage.borrow_keyed_mut::<T>(key)
     }
 
     /**
-    Adds a component to an entity by its ID. The component must be registered beforehand.
-    This function updates the entity with the provided component data.
+    Removes a resource of type `T` stored under the given key and returns it.
+    Returns `None` if there is no such resource.
 
     Example:
     ```
     use sara_ecs::World;
-    use sara_ecs::ecs_errors::ECSError;
+    let mut world = World::new();
 
-    struct Health(pub u32);
-    struct Speed(pub f32);
+    world.add_keyed_resource("volume", 0.5_f32).unwrap();
 
-    fn example() -> Result<(), ECSError> {
-        let mut world = World::new();
+    assert_eq!(world.remove_keyed_resource::<f32>("volume"), Some(0.5));
+    assert!(world.get_keyed_resource::<f32>("volume").is_none());
+    ```
+    */
+    pub fn remove_keyed_resource<T: Any>(&mut self, key: &str) -> Option<T> {
+        self.resource_storage.take_keyed::<T>(key)
+    }
 
-        world.register_component::<Health>();
-        let entity = world.create_entity().with_component(Health(100))?;
+    /**
+    Adds a boxed trait object as a resource, so it can later be retrieved by the trait alone
+    without the world knowing the concrete type. Only one resource per trait can be stored,
+    it lives in the world as a `Box<dyn Trait>` resource.
 
-        // Add a new component to the entity with ID 0
-        world.add_component_to_entity(0, Speed(15.0))?;
+    Example:
+    ```
+    use sara_ecs::World;
 
-        Ok(())
+    trait Renderer {
+        fn name(&self) -> &str;
+    }
+
+    struct VulkanRenderer;
+
+    impl Renderer for VulkanRenderer {
+        fn name(&self) -> &str {
+            "vulkan"
+        }
     }
+
+    let mut world = World::new();
+
+    world.add_trait_resource::<dyn Renderer>(Box::new(VulkanRenderer)).unwrap();
+
+    let renderer = world.get_trait_resource::<dyn Renderer>().unwrap();
+    assert_eq!(renderer.name(), "vulkan");
     ```
     */
-    pub fn add_component_to_entity(
+    pub fn add_trait_resource<T: ?Sized + 'static>(
         &mut self,
-        entity_id: usize,
-        component_data: impl Any,
+        resource: Box<T>,
     ) -> Result<(), ECSError> {
-        self.entitiy_storage
-            .add_component_to_entity(entity_id, component_data)
+        self.add_resource(resource)
     }
 
     /**
-    Removes an entity by its ID. The entity and its associated components will be removed from the world.
-    If the entity does not exist, an error will be returned.
+    Retrieves a resource added with `add_trait_resource` by its trait.
+    Returns `None` if no resource was added for that trait.
 
     Example:
     ```
     use sara_ecs::World;
-    use sara_ecs::ecs_errors::ECSError;
+    use std::fmt::Display;
 
-    struct Health(pub u32);
-    struct Speed(pub f32);
+    let mut world = World::new();
 
-    fn example() -> Result<(), ECSError> {
-        let mut world = World::new();
+    world.add_trait_resource::<dyn Display>(Box::new(10_u32)).unwrap();
 
-        world.register_component::<Health>();
-        let entity = world.create_entity().with_component(Health(100))?;
+    let resource = world.get_trait_resource::<dyn Display>().unwrap();
+    assert_eq!(resource.to_string(), "10");
+    ```
+    */
+    pub fn get_trait_resource<T: ?Sized + 'static>(&self) -> Option<Ref<'_, T>> {
+        let resource = self.resource_storage.borrow::<Box<T>>()?;
+        Some(Ref::map(resource, |resource| resource.as_ref()))
+    }
 
-        // Remove the entity with ID 0
-        world.remove_entity(0)?;
+    /**
+    Retrieves a mutable guard to a resource added with `add_trait_resource` by its trait.
+    Returns `None` if no resource was added for that trait.
 
-        Ok(())
+    Example:
+    ```
+    use sara_ecs::World;
+
+    trait Counter {
+        fn increment(&mut self);
+        fn count(&self) -> u32;
+    }
+
+    struct FrameCounter(u32);
+
+    impl Counter for FrameCounter {
+        fn increment(&mut self) {
+            self.0 += 1;
+        }
+
+        fn count(&self) -> u32 {
+            self.0
+        }
     }
+
+    let mut world = World::new();
+
+    world.add_trait_resource::<dyn Counter>(Box::new(FrameCounter(0))).unwrap();
+    world.get_trait_resource_mut::<dyn Counter>().unwrap().increment();
+
+    assert_eq!(world.get_trait_resource::<dyn Counter>().unwrap().count(), 1);
     ```
     */
-    pub fn remove_entity(&mut self, entity_id: usize) -> Result<(), ECSError> {
-        self.entitiy_storage.remove_entity(entity_id)
+    pub fn get_trait_resource_mut<T: ?Sized + 'static>(&self) -> Option<RefMut<'_, T>> {
+        let resource = self.resource_storage.borrow_mut::<Box<T>>()?;
+        Some(RefMut::map(resource, |resource| resource.as_mut()))
     }
 
     /**
-    Removes a specific component from an entity by its ID. The component type must be registered
-    in advance. The function will attempt to remove the component from the entity and return any errors
-    if the component is not registered or if there is an issue.
+    Marks resources of type `T` as serializable, so they can be saved and loaded with
+    `serialize_resource` and `deserialize_resource`, and are included in world snapshots.
+    Snapshots key them by type name, which may change with the compiler version, see
+    `register_serializable_resource_with_name`. Requires the `serde` feature.
 
     Example:
     ```
     use sara_ecs::World;
-    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
 
-    struct Health(pub u32);
-    struct Speed(pub f32);
+    #[derive(Serialize, Deserialize)]
+    struct Settings {
+        volume: f32,
+    }
 
-    fn example() -> Result<(), ECSError> {
-        let mut world = World::new();
+    let mut world = World::new();
 
-        world.register_component::<Health>();
-        let entity = world.create_entity().with_component(Health(100))?;
+    world.register_serializable_resource::<Settings>();
+    world.add_resource(Settings { volume: 0.5 }).unwrap();
 
-        // Remove the Health component from entity 0
-        world.remove_entity_component::<Health>(0)?;
+    let saved = world.serialize_resource::<Settings>().unwrap();
+    assert_eq!(saved.to_string(), r#"{"volume":0.5}"#);
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn register_serializable_resource<T>(&mut self)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Any,
+    {
+        self.serialization.register_resource::<T>();
+        self.type_registry_mut().register_serde::<T>();
+    }
 
-        Ok(())
+    /**
+    Like `register_serializable_resource`, but snapshots key the resource by `name` instead of
+    its type name, so they keep loading after a toolchain upgrade or a module move. Snapshots
+    saved under the type name are still accepted. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Settings {
+        volume: f32,
     }
+
+    let mut world = World::new();
+
+    world.register_serializable_resource_with_name::<Settings>("Settings");
+    world.add_resource(Settings { volume: 0.5 }).unwrap();
+
+    let saved = world.serialize_resources().unwrap();
+    assert!(saved.contains_key("Settings"));
     ```
     */
-    pub fn remove_entity_component<T: Any>(&mut self, entity_id: usize) -> Result<(), ECSError> {
-        self.entitiy_storage.remove_entity_component::<T>(entity_id)
+    #[cfg(feature = "serde")]
+    pub fn register_serializable_resource_with_name<T>(&mut self, name: &'static str)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Any,
+    {
+        self.serialization.register_resource_with_name::<T>(name);
+        self.type_registry_mut().register_serde::<T>();
     }
 
     /**
-    Query the entities in the world to retrieve components based on the registered component types.
-    You can use this to filter and retrieve entities with specific combinations of components.
+    Serializes the resource of type `T` into a `serde_json::Value`, which can then be written with
+    any serde format. The resource type must be registered with `register_serializable_resource`.
+    Requires the `serde` feature.
 
     Example:
     ```
     use sara_ecs::World;
-    use sara_ecs::ecs_errors::ECSError;
 
-    struct Health(pub u32);
-    struct Speed(pub f32);
+    let mut world = World::new();
 
-    fn example() -> Result<(), ECSError> {
-        let mut world = World::new();
+    world.register_serializable_resource::<u32>();
+    world.add_resource(10_u32).unwrap();
 
-        world.register_component::<Health>();
-        world.register_component::<Speed>();
+    assert_eq!(world.serialize_resource::<u32>().unwrap(), 10);
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn serialize_resource<T: Any>(&self) -> Result<serde_json::Value, ECSError> {
+        self.serialization
+            .serialize_resource(&TypeId::of::<T>(), &self.resource_storage)
+    }
 
-        let mut entity = world.create_entity();
-        entity.with_component(Health(100))?;
-        entity.with_component(Speed(15.0))?;
+    /**
+    Loads a resource of type `T` from a value produced by `serialize_resource`, replacing the
+    current resource of that type if there is one. The resource type must be registered with
+    `register_serializable_resource`. Requires the `serde` feature.
 
-        let query = world.query();
+    Example:
+    ```
+    use sara_ecs::World;
 
-        Ok(())
+    let mut world = World::new();
+
+    world.register_serializable_resource::<u32>();
+    world.deserialize_resource::<u32>(serde_json::json!(42)).unwrap();
+
+    assert_eq!(*world.get_resource::<u32>().unwrap(), 42);
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn deserialize_resource<T: Any>(
+        &mut self,
+        value: serde_json::Value,
+    ) -> Result<(), ECSError> {
+        self.deserialize_resource_dynamic(TypeId::of::<T>(), value)
     }
-    // Perform queries based on your component types
+
+    /**
+    Serializes every resource registered with `register_serializable_resource` that is present
+    in the world, keyed by type name. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+
+    let mut world = World::new();
+
+    world.register_serializable_resource::<u32>();
+    world.register_serializable_resource::<f32>();
+    world.add_resource(10_u32).unwrap();
+
+    let saved = world.serialize_resources().unwrap();
+
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved["u32"], 10);
     ```
     */
-    pub fn query(&self) -> Query {
-        Query::new(&self.entitiy_storage)
+    #[cfg(feature = "serde")]
+    pub fn serialize_resources(
+        &self,
+    ) -> Result<std::collections::HashMap<String, serde_json::Value>, ECSError> {
+        self.serialization
+            .serialize_resources(&self.resource_storage)
+    }
+
+    /**
+    Loads resources produced by `serialize_resources`, replacing the current ones and running
+    their resource hooks like `deserialize_resource`. Every resource type must be registered
+    with `register_serializable_resource`. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+
+    let mut world = World::new();
+    world.register_serializable_resource::<u32>();
+    world.add_resource(10_u32).unwrap();
+
+    let saved = world.serialize_resources().unwrap();
+
+    let mut loaded_world = World::new();
+    loaded_world.register_serializable_resource::<u32>();
+    loaded_world.deserialize_resources(saved).unwrap();
+
+    assert_eq!(*loaded_world.get_resource::<u32>().unwrap(), 10);
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn deserialize_resources(
+        &mut self,
+        serialized: std::collections::HashMap<String, serde_json::Value>,
+    ) -> Result<(), ECSError> {
+        for (name, value) in serialized {
+            let type_id = self
+                .serialization
+                .resource_type_id(&name)
+                .ok_or(ECSError::TypeNotSerializable)?;
+
+            self.deserialize_resource_dynamic(type_id, value)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize_resource_dynamic(
+        &mut self,
+        type_id: TypeId,
+        value: serde_json::Value,
+    ) -> Result<(), ECSError> {
+        let change = if self.resource_storage.contains_type(&type_id) {
+            ResourceChange::Replaced
+        } else {
+            ResourceChange::Added
+        };
+
+        self.serialization
+            .deserialize_resource(&type_id, &mut self.resource_storage, value)?;
+
+        let type_name = self
+            .serialization
+            .resource_type_name(&type_id)
+            .unwrap_or_default();
+        self.run_resource_hooks(&type_id, type_name, change);
+
+        Ok(())
+    }
+
+    /**
+    Checks whether a resource of type `T` exists in the world.
+
+    This function returns `true` if the specified resource type is present in the world; otherwise, it returns `false`.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    let mut world = World::new();
+
+    world.add_resource(10_u32); // Adds a u32 resource to the world
+
+    assert!(world.contains_resource::<u32>()); // Checks if the u32 resource exists
+    assert!(!world.contains_resource::<f32>()); // Checks if a f32 resource does not exist
+    ```
+    */
+    pub fn contains_resource<T: Any>(&self) -> bool {
+        self.resource_storage.contains::<T>()
+    }
+
+    /**
+    Iterates over every resource without knowing its type, yielding its `TypeId`, its type name
+    and a reference to it, sorted by name. The reference can be downcast, or passed to the
+    functions of the type registry to reflect or debug-print it. Resources that are mutably
+    borrowed are skipped, and keyed resources are not included.
+
+    Example:
+    ```
+    use std::any::TypeId;
+    use sara_ecs::World;
+
+    struct Score(pub u32);
+
+    let mut world = World::new();
+
+    world.add_resource(Score(10)).unwrap();
+
+    let (type_id, name, score) = world
+        .resources_iter()
+        .find(|(type_id, _name, _resource)| *type_id == TypeId::of::<Score>())
+        .unwrap();
+
+    assert!(name.ends_with("Score"));
+    assert_eq!(score.downcast_ref::<Score>().unwrap().0, 10);
+    ```
+    */
+    pub fn resources_iter(&self) -> impl Iterator<Item = (TypeId, &'static str, Ref<'_, dyn Any>)> {
+        let mut resources: Vec<(TypeId, &'static str, Ref<'_, dyn Any>)> = self
+            .resource_storage
+            .iter()
+            .filter(|(_type_id, key, _resource)| key.is_none())
+            .filter_map(|(type_id, _key, resource)| {
+                let name = self.resource_storage.type_name(&type_id)?;
+                let resource = Ref::map(resource.try_borrow().ok()?, |resource| &**resource);
+
+                Some((type_id, name, resource))
+            })
+            .collect();
+
+        resources.sort_by_key(|(_type_id, name, _resource)| *name);
+        resources.into_iter()
+    }
+
+    /**
+    Replaces an existing resource of type `T` with a new one.
+
+    This function will replace the current resource of the specified type in the world with the provided resource. If no resource of that type exists, the resource will be added.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    let mut world = World::new();
+
+    world.add_resource(10_u32); // Adds a u32 resource to the world
+    world.replace_resource(20_u32); // Replaces the u32 resource with a new value
+
+    assert_eq!(*world.get_resource::<u32>().unwrap(), 20); // Verifies the resource is replaced
+    ```
+    */
+    pub fn replace_resource<T: Any>(&mut self, resource: T) {
+        let change = if self.resource_storage.contains::<T>() {
+            ResourceChange::Replaced
+        } else {
+            ResourceChange::Added
+        };
+
+        self.resource_storage.replace(resource);
+        self.run_resource_hooks(&TypeId::of::<T>(), std::any::type_name::<T>(), change);
+    }
+
+    /**
+    Registers a hook that runs every time a resource of type `T` is added, replaced or removed.
+    The hook runs after the change was made and receives the kind of change that happened.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::resource_hooks::ResourceChange;
+
+    struct GraphicsSettings(pub u32);
+    struct RendererRebuilds(pub u32);
+
+    let mut world = World::new();
+    world.add_resource(RendererRebuilds(0)).unwrap();
+
+    world.on_resource_change::<GraphicsSettings>(|world, change| {
+        if change == ResourceChange::Replaced {
+            world.get_resource_mut::<RendererRebuilds>().unwrap().0 += 1;
+        }
+    });
+
+    world.add_resource(GraphicsSettings(1)).unwrap();
+    world.replace_resource(GraphicsSettings(2));
+
+    assert_eq!(world.get_resource::<RendererRebuilds>().unwrap().0, 1);
+    ```
+    */
+    pub fn on_resource_change<T: Any>(&mut self, hook: ResourceHook) {
+        self.resource_hooks.add(TypeId::of::<T>(), hook);
+    }
+
+    /**
+    Registers a new event type in the world. The events are stored as an `Events<T>` resource,
+    which is double-buffered: every call to `update_events` drops the events that were sent two
+    updates ago. Registering the same event type twice does nothing.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    struct Jumped(pub u32);
+    let mut world = World::new();
+
+    world.add_event::<Jumped>();
+
+    assert!(world.get_events::<Jumped>().unwrap().is_empty());
+    ```
+    */
+    pub fn add_event<T: Any>(&mut self) {
+        if self.resource_storage.contains::<Events<T>>() {
+            return;
+        }
+
+        self.resource_storage.replace(Events::<T>::default());
+        self.event_updaters.push(|resources| {
+            if let Some(events) = resources.get_mut::<Events<T>>() {
+                events.update();
+            }
+        });
+    }
+
+    /**
+    Sends an event of type `T`. The event type must be registered with `add_event` beforehand.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Jumped(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.add_event::<Jumped>();
+        world.send_event(Jumped(0))?;
+
+        assert_eq!(world.get_events::<Jumped>().unwrap().len(), 1);
+        Ok(())
+    }
+    ```
+    */
+    pub fn send_event<T: Any>(&mut self, event: T) -> Result<(), ECSError> {
+        self.resource_storage
+            .get_mut::<Events<T>>()
+            .ok_or(ECSError::EventNotRegistered)?
+            .send(event);
+
+        Ok(())
+    }
+
+    /**
+    Retrieves the events of type `T` sent during the current and the previous update.
+    Returns `None` if the event type was not registered.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    struct Jumped(pub u32);
+    let mut world = World::new();
+
+    world.add_event::<Jumped>();
+    world.send_event(Jumped(5)).unwrap();
+
+    let jumps = world.get_events::<Jumped>().unwrap();
+    assert_eq!(jumps.iter().next().unwrap().0, 5);
+    ```
+    */
+    pub fn get_events<T: Any>(&self) -> Option<Ref<'_, Events<T>>> {
+        self.resource_storage.borrow::<Events<T>>()
+    }
+
+    /**
+    Advances every registered event type by one update cycle. Events are kept for two cycles,
+    so anything sent before the previous call to this function is dropped. Call it once per frame.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    struct Jumped(pub u32);
+    let mut world = World::new();
+
+    world.add_event::<Jumped>();
+    world.send_event(Jumped(5)).unwrap();
+
+    world.update_events(); // Still readable during the next frame.
+    assert_eq!(world.get_events::<Jumped>().unwrap().len(), 1);
+
+    world.update_events(); // Dropped after two updates.
+    assert!(world.get_events::<Jumped>().unwrap().is_empty());
+    ```
+    */
+    pub fn update_events(&mut self) {
+        for update in &self.event_updaters {
+            update(&mut self.resource_storage);
+        }
+    }
+
+    /**
+    Registers a new component type in the world. This component can later be added to entities.
+    The type must implement `Any` and have a static lifetime.
+
+    Zero-sized components, like marker structs, only take their bit in the entity masks. Every
+    entity with one shares a single instance, so borrowing it mutably from two entities at the
+    same time fails.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    struct Health(pub u32);
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+
+    // Health component can now be used for entities
+    ```
+    */
+    pub fn register_component<T: Any + 'static>(&mut self) {
+        self.entitiy_storage.register_component::<T>();
+        self.type_registry_mut().register::<T>();
+    }
+
+    /**
+    Registers every component type of a tuple at once, skipping the ones already registered.
+    Fails with `TooManyComponents`, listing the types that didn't fit, if the set goes past
+    the limit of 32 component types. The types before the limit are registered regardless.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Position(pub f32, pub f32);
+    struct Velocity(pub f32, pub f32);
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_components::<(Position, Velocity, Health)>()?;
+
+        world
+            .create_entity()
+            .with_component(Position(0.0, 0.0))?
+            .with_component(Health(100))?;
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_components<C: ComponentSet>(&mut self) -> Result<(), ECSError> {
+        let mut rejected = Vec::new();
+
+        C::register(self, &mut rejected);
+
+        if rejected.is_empty() {
+            Ok(())
+        } else {
+            Err(ECSError::TooManyComponents(rejected))
+        }
+    }
+
+    /**
+    Keeps up to `capacity` entities despawned with `despawn_to_pool::<C>` around, so
+    `spawn_from_pool` can hand them out again instead of creating new ones. Their component
+    values are overwritten in place, which saves the allocations of spawning and despawning
+    entities at a high rate, like bullets. Also registers the components of `C`.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Bullet;
+    struct Velocity(pub f32, pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.enable_entity_pool::<(Bullet, Velocity)>(256)?;
+
+        let bullet = world.spawn_from_pool((Bullet, Velocity(0.0, 10.0)))?;
+        world.despawn_to_pool::<(Bullet, Velocity)>(bullet)?;
+
+        // Parked entities are disabled, so queries skip them.
+        assert!(world.query().with_component_filter::<Bullet>()?.run().entity_ids.is_empty());
+
+        let reused = world.spawn_from_pool((Bullet, Velocity(5.0, 5.0)))?;
+        assert_eq!(reused, bullet);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn enable_entity_pool<C: ComponentSet + 'static>(
+        &mut self,
+        capacity: usize,
+    ) -> Result<(), ECSError> {
+        self.register_components::<C>()?;
+        self.entity_pools.enable(TypeId::of::<C>(), capacity);
+
+        Ok(())
+    }
+
+    /**
+    Despawns an entity into the pool of `C`. Its components outside of the set are removed,
+    running their hooks, and it's disabled until `spawn_from_pool` hands it out again. When the
+    pool is full, the entity is removed for good instead. Fails with `EntityPoolNotEnabled` if
+    `C` has no pool, and with `EntityAlreadyPooled` if the entity is already parked.
+    */
+    pub fn despawn_to_pool<C: ComponentSet + 'static>(
+        &mut self,
+        entity_id: usize,
+    ) -> Result<(), ECSError> {
+        let set = TypeId::of::<C>();
+
+        if !self.entity_pools.is_enabled(&set) {
+            return Err(ECSError::EntityPoolNotEnabled);
+        }
+
+        if !self.entities().is_alive(entity_id) {
+            return Err(ECSError::EntityDoesNotExist { id: entity_id });
+        }
+
+        if self.entity_pools.is_parked(entity_id) {
+            return Err(ECSError::EntityAlreadyPooled { id: entity_id });
+        }
+
+        if self.entity_pools.is_full(&set) {
+            return self.remove_entity(entity_id);
+        }
+
+        let kept = C::type_ids();
+
+        for type_id in self.entitiy_storage.entity_component_type_ids(entity_id) {
+            if !kept.contains(&type_id) {
+                self.remove_component_dynamic(entity_id, type_id)?;
+            }
+        }
+
+        let dynamic_components: Vec<String> = self
+            .entitiy_storage
+            .entity_dynamic_component_names(entity_id)
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+
+        for name in dynamic_components {
+            self.remove_dynamic_component(entity_id, &name)?;
+        }
+
+        // Without any component of the set, removing the others despawned it.
+        if !self.entities().is_alive(entity_id) {
+            return Ok(());
+        }
+
+        self.disable_entity(entity_id)?;
+        self.entity_pools.park(&set, entity_id);
+
+        Ok(())
+    }
+
+    /**
+    Spawns an entity with the components of `components`, reusing one parked in the pool of
+    their set if there is any. Fails with `EntityPoolNotEnabled` if the set has no pool.
+    */
+    pub fn spawn_from_pool<C: ComponentSet + 'static>(
+        &mut self,
+        components: C,
+    ) -> Result<usize, ECSError> {
+        let set = TypeId::of::<C>();
+
+        if !self.entity_pools.is_enabled(&set) {
+            return Err(ECSError::EntityPoolNotEnabled);
+        }
+
+        let entity_id = match self.entity_pools.take(&set) {
+            Some(entity_id) => {
+                self.enable_entity(entity_id)?;
+                entity_id
+            }
+            None => self.entities().reserve(),
+        };
+
+        components.insert(self, entity_id)?;
+        Ok(entity_id)
+    }
+
+    /**
+    IDs of the entities parked in the pool of `C`, waiting to be spawned again.
+    */
+    pub fn pooled_entities<C: ComponentSet + 'static>(&self) -> &[usize] {
+        self.entity_pools.parked(&TypeId::of::<C>())
+    }
+
+    // Registers `T` unless it already is. Returns `false` if it doesn't fit in the masks.
+    pub(crate) fn try_register_component<T: Any>(&mut self) -> bool {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_some()
+        {
+            return true;
+        }
+
+        if self.entitiy_storage.component_limit_reached() {
+            return false;
+        }
+
+        self.register_component::<T>();
+        true
+    }
+
+    /**
+    Looks up the `TypeId` of a registered component by its type name. Both the full name
+    (like `my_game::Health`) and the short one, without the module path, are accepted.
+    Returns `None` for unknown names and for registered types that aren't components.
+
+    Example:
+    ```
+    use std::any::TypeId;
+    use sara_ecs::World;
+
+    struct Health(pub u32);
+
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+
+    assert_eq!(world.component_id_by_name("Health"), Some(TypeId::of::<Health>()));
+    assert_eq!(world.component_id_by_name("Speed"), None);
+    ```
+    */
+    pub fn component_id_by_name(&self, name: &str) -> Option<TypeId> {
+        let type_id = self
+            .resource_storage
+            .borrow::<TypeRegistry>()?
+            .type_id_by_name(name)?;
+
+        self.entitiy_storage.get_bitmask(&type_id).map(|_| type_id)
+    }
+
+    /**
+    Lists every registered component type, dynamic ones included, sorted by name. Useful for
+    editors and tools that have to show the components a world supports.
+
+    Example:
+    ```
+    use std::any::TypeId;
+    use sara_ecs::World;
+    use sara_ecs::component_registration::StorageKind;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Player;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Player>();
+        world.create_entity().with_component(Health(100))?;
+
+        let registrations = world.component_registrations();
+        let health = registrations
+            .iter()
+            .find(|registration| registration.type_id == TypeId::of::<Health>())
+            .unwrap();
+
+        assert_eq!(registrations.len(), 2);
+        assert_eq!(health.storage, StorageKind::Column);
+        assert_eq!(health.count, 1);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn component_registrations(&self) -> Vec<ComponentRegistration> {
+        let storage = &self.entitiy_storage;
+        let mut registrations: Vec<ComponentRegistration> = storage
+            .component_type_ids()
+            .map(|type_id| {
+                let bitmask = storage.get_bitmask(type_id).unwrap_or_default();
+
+                ComponentRegistration {
+                    type_id: *type_id,
+                    name: self.component_name(type_id),
+                    bitmask,
+                    storage: if storage.is_zero_sized(type_id) {
+                        StorageKind::ZeroSized
+                    } else {
+                        StorageKind::Column
+                    },
+                    count: storage.count_entities_with(bitmask),
+                }
+            })
+            .collect();
+
+        for name in storage.dynamic_component_names() {
+            let bitmask = storage.get_dynamic_bitmask(name).unwrap_or_default();
+
+            registrations.push(ComponentRegistration {
+                type_id: TypeId::of::<DynamicComponent>(),
+                name: name.to_owned(),
+                bitmask,
+                storage: StorageKind::Dynamic,
+                count: storage.count_entities_with(bitmask),
+            });
+        }
+
+        registrations.sort_by(|a, b| a.name.cmp(&b.name));
+        registrations
+    }
+
+    /**
+    Gives every entity created from now on a random `EntityUuid` component, and keeps an index
+    to find entities by UUID. With the `serde` feature, UUIDs are also registered as
+    serializable, so they are kept in snapshots and when spawning them in other worlds.
+    Spawned entities whose UUID is already taken by another entity get a new one.
+
+    Example:
+    ```
+    use sara_ecs::World;
+
+    let mut world = World::new();
+
+    world.enable_entity_uuids();
+
+    let entity_id = world.create_entity().id();
+    let uuid = world.entity_uuid(entity_id).unwrap();
+
+    assert_eq!(world.entity_by_uuid(uuid), Some(entity_id));
+    ```
+    */
+    pub fn enable_entity_uuids(&mut self) {
+        if self.entity_uuids.enabled {
+            return;
+        }
+
+        self.entity_uuids.enabled = true;
+        self.register_cloneable_component::<EntityUuid>();
+        #[cfg(feature = "serde")]
+        self.register_serializable_component::<EntityUuid>();
+
+        // A UUID copied onto another entity doesn't take the entry of the living original.
+        self.on_add::<EntityUuid>(|world, entity_id| {
+            if let Some(uuid) = world.entity_uuid(entity_id) {
+                if world.entity_by_uuid(uuid).is_none() {
+                    world.entity_uuids.index.insert(uuid, entity_id);
+                }
+            }
+        });
+        self.on_remove::<EntityUuid>(|world, entity_id| {
+            if let Some(uuid) = world.entity_uuid(entity_id) {
+                if world.entity_uuids.index.get(&uuid) == Some(&entity_id) {
+                    world.entity_uuids.index.remove(&uuid);
+                }
+            }
+        });
+    }
+
+    /**
+    Retrieves the UUID of an entity, if it has one. See `enable_entity_uuids`.
+    */
+    pub fn entity_uuid(&self, entity_id: usize) -> Option<EntityUuid> {
+        self.entitiy_storage
+            .get_component::<EntityUuid>(entity_id)
+            .ok()
+            .map(|uuid| *uuid)
+    }
+
+    /**
+    Finds the entity with a UUID. See `enable_entity_uuids`.
+    */
+    pub fn entity_by_uuid(&self, uuid: EntityUuid) -> Option<usize> {
+        let entity_id = *self.entity_uuids.index.get(&uuid)?;
+
+        (self.entity_uuid(entity_id) == Some(uuid)).then_some(entity_id)
+    }
+
+    /**
+    Registers the `Name` component and keeps an index of entity names, so entities can be
+    found with `entity_by_name`. If several entities share a name, the last one named wins.
+    With the `serde` feature, names are also registered as serializable.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::name::Name;
+
+    let mut world = World::new();
+
+    world.enable_entity_names();
+
+    let player = world
+        .create_entity()
+        .with_component(Name::new("Player"))
+        .unwrap()
+        .id();
+
+    assert_eq!(world.entity_by_name("Player"), Some(player));
+    ```
+    */
+    pub fn enable_entity_names(&mut self) {
+        if self.entity_names.enabled {
+            return;
+        }
+
+        self.entity_names.enabled = true;
+        self.register_cloneable_component::<Name>();
+        #[cfg(feature = "serde")]
+        self.register_serializable_component::<Name>();
+
+        self.on_add::<Name>(|world, entity_id| {
+            if let Some(name) = world.entity_name(entity_id) {
+                world.entity_names.index.insert(name, entity_id);
+            }
+        });
+        self.on_remove::<Name>(|world, entity_id| {
+            if let Some(name) = world.entity_name(entity_id) {
+                if world.entity_names.index.get(&name) == Some(&entity_id) {
+                    world.entity_names.index.remove(&name);
+                }
+            }
+        });
+    }
+
+    /**
+    Retrieves the name of an entity, if it has a `Name` component.
+    */
+    pub fn entity_name(&self, entity_id: usize) -> Option<String> {
+        self.entitiy_storage
+            .get_component::<Name>(entity_id)
+            .ok()
+            .map(|name| name.as_str().to_owned())
+    }
+
+    /**
+    Finds the entity with a name. See `enable_entity_names`.
+    */
+    pub fn entity_by_name(&self, name: &str) -> Option<usize> {
+        let entity_id = *self.entity_names.index.get(name)?;
+
+        (self.entity_name(entity_id).as_deref() == Some(name)).then_some(entity_id)
+    }
+
+    /**
+    Adds an entity to a group, creating the group the first time it's used. Groups are ad-hoc
+    categories that can be queried with `Query::in_group` without defining a marker component
+    for each of them. An entity can be in any number of groups, and a world can have up to 64.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+
+        let goblin = world.create_entity().with_component(Health(10))?.id();
+        world.create_entity().with_component(Health(100))?;
+
+        world.add_to_group(goblin, "enemies")?;
+
+        let enemies = world.query().in_group("enemies").run();
+        assert_eq!(enemies.entity_ids, vec![goblin]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn add_to_group(&mut self, entity_id: usize, group: &str) -> Result<(), ECSError> {
+        self.entitiy_storage.add_entity_to_group(entity_id, group)
+    }
+
+    pub fn remove_from_group(&mut self, entity_id: usize, group: &str) -> Result<(), ECSError> {
+        self.entitiy_storage
+            .remove_entity_from_group(entity_id, group)
+    }
+
+    pub fn is_in_group(&self, entity_id: usize, group: &str) -> bool {
+        self.entitiy_storage
+            .get_group_bitmask(group)
+            .is_some_and(|bitmask| {
+                self.entitiy_storage.entity_group_bitmask(entity_id) & bitmask != 0
+            })
+    }
+
+    /**
+    Tags an entity with a string, so scripts and mods can categorize entities without a Rust
+    marker type for each category. Tagged entities can be queried with `Query::with_tag`. Tags
+    are interned the first time they're used, and a world can have up to 64 of them.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+
+        let dragon = world.create_entity().with_component(Health(500))?.id();
+        world.create_entity().with_component(Health(10))?;
+
+        world.add_tag(dragon, "boss")?;
+
+        let bosses = world.query().with_tag("boss").run();
+        assert_eq!(bosses.entity_ids, vec![dragon]);
+        assert!(world.has_tag(dragon, "boss"));
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn add_tag(&mut self, entity_id: usize, tag: &str) -> Result<(), ECSError> {
+        self.entitiy_storage.add_entity_tag(entity_id, tag)
+    }
+
+    pub fn remove_tag(&mut self, entity_id: usize, tag: &str) -> Result<(), ECSError> {
+        self.entitiy_storage.remove_entity_tag(entity_id, tag)
+    }
+
+    pub fn has_tag(&self, entity_id: usize, tag: &str) -> bool {
+        self.entitiy_storage
+            .get_tag_bitmask(tag)
+            .is_some_and(|bitmask| {
+                self.entitiy_storage.entity_tag_bitmask(entity_id) & bitmask != 0
+            })
+    }
+
+    /**
+    Retrieves the tags of an entity, in no particular order.
+    */
+    pub fn entity_tags(&self, entity_id: usize) -> Vec<&str> {
+        self.entitiy_storage.entity_tags(entity_id)
+    }
+
+    /**
+    Disables an entity, hiding it from queries without removing its components. Queries can
+    still match it with `Query::including_disabled`. Disabling an entity twice has no effect.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+
+        let ghost = world.create_entity().with_component(Health(10))?.id();
+
+        world.disable_entity(ghost)?;
+        assert!(world.query().run().entity_ids.is_empty());
+        assert_eq!(world.query().including_disabled().run().entity_ids, vec![ghost]);
+
+        world.enable_entity(ghost)?;
+        assert_eq!(world.query().run().entity_ids, vec![ghost]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn disable_entity(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        self.entitiy_storage.set_entity_disabled(entity_id, true)
+    }
+
+    pub fn enable_entity(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        self.entitiy_storage.set_entity_disabled(entity_id, false)
+    }
+
+    pub fn is_entity_disabled(&self, entity_id: usize) -> bool {
+        self.entitiy_storage.is_disabled(entity_id)
+    }
+
+    /**
+    Retrieves the groups an entity is in, in no particular order.
+    */
+    pub fn entity_groups(&self, entity_id: usize) -> Vec<&str> {
+        self.entitiy_storage.entity_groups(entity_id)
+    }
+
+    /**
+    Registers the `Parent` and `Children` components used by `set_parent`, along with hooks
+    that keep both sides of the relation in sync when entities or components are removed:
+    a removed child is detached from its parent, and the children of a removed parent become
+    roots. With the `serde` feature, both components are also registered as serializable with
+    their entity IDs remapped.
+    */
+    pub fn register_hierarchy(&mut self) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<Parent>())
+            .is_some()
+        {
+            return;
+        }
+
+        self.register_cloneable_component::<Parent>();
+        self.register_cloneable_component::<Children>();
+        #[cfg(feature = "serde")]
+        {
+            self.register_serializable_component::<Parent>();
+            self.register_serializable_component::<Children>();
+            self.register_map_entities::<Parent>();
+            self.register_map_entities::<Children>();
+        }
+
+        self.on_remove::<Parent>(|world, entity_id| {
+            if let Some(parent) = world.parent(entity_id) {
+                world.detach_child(parent, entity_id);
+            }
+        });
+        self.on_remove::<Children>(|world, entity_id| {
+            for child in world.children(entity_id).collect::<Vec<usize>>() {
+                let _ = world.remove_entity_component::<Parent>(child);
+            }
+        });
+    }
+
+    /**
+    Registers the `Transform` and `GlobalTransform` components along with the hierarchy, so
+    `transform::propagate_transforms` can be scheduled. Requires the `transform` feature.
+    */
+    #[cfg(feature = "transform")]
+    pub fn register_transforms(&mut self) {
+        self.register_hierarchy();
+        self.register_cloneable_component::<transform::Transform>();
+        self.register_cloneable_component::<transform::GlobalTransform>();
+        #[cfg(feature = "serde")]
+        self.register_serializable_component::<transform::Transform>();
+    }
+
+    /**
+    Makes `parent` the parent of `child`, detaching it from its previous parent if it had one.
+    The hierarchy must be registered with `register_hierarchy`. Fails with `HierarchyCycle` if
+    `child` is `parent` or one of its ancestors.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_hierarchy();
+
+        let player = world.create_entity().with_component(Health(100))?.id();
+        let sword = world.create_entity().with_component(Health(10))?.id();
+
+        world.set_parent(sword, player)?;
+
+        assert_eq!(world.parent(sword), Some(player));
+        assert_eq!(world.children(player).collect::<Vec<usize>>(), vec![sword]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn set_parent(&mut self, child: usize, parent: usize) -> Result<(), ECSError> {
+        for entity_id in [child, parent] {
+            if !self.entitiy_storage.is_alive(entity_id) {
+                return Err(ECSError::EntityDoesNotExist { id: entity_id });
+            }
+        }
+
+        if child == parent || self.is_ancestor_of(child, parent) {
+            return Err(ECSError::HierarchyCycle);
+        }
+
+        match self.parent(child) {
+            Some(previous_parent) if previous_parent == parent => return Ok(()),
+            Some(previous_parent) => self.detach_child(previous_parent, child),
+            None => {}
+        }
+
+        self.add_component_to_entity(child, Parent(parent))?;
+
+        let has_children = self
+            .entitiy_storage
+            .entity_has_component(parent, &TypeId::of::<Children>());
+
+        if has_children {
+            self.entitiy_storage
+                .get_component_mut::<Children>(parent)?
+                .0
+                .push(child);
+        } else {
+            self.add_component_to_entity(parent, Children(vec![child]))?;
+        }
+
+        Ok(())
+    }
+
+    /**
+    Detaches an entity from its parent, making it a root. Does nothing if it has no parent.
+    */
+    pub fn remove_parent(&mut self, child: usize) -> Result<(), ECSError> {
+        if self.parent(child).is_none() {
+            return Ok(());
+        }
+
+        self.remove_entity_component::<Parent>(child)
+    }
+
+    pub fn parent(&self, child: usize) -> Option<usize> {
+        self.entitiy_storage
+            .get_component::<Parent>(child)
+            .ok()
+            .map(|parent| parent.0)
+    }
+
+    /**
+    Iterates over the children of an entity, in the order they were attached.
+    */
+    pub fn children(&self, parent: usize) -> impl Iterator<Item = usize> {
+        self.entitiy_storage
+            .get_component::<Children>(parent)
+            .map(|children| children.0.clone())
+            .unwrap_or_default()
+            .into_iter()
+    }
+
+    /**
+    Iterates over the ancestors of an entity, starting with its parent and ending with the
+    root of its hierarchy.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_hierarchy();
+
+        let root = world.create_entity().with_component(Health(1))?.id();
+        let child = world.create_entity().with_component(Health(2))?.id();
+        let grandchild = world.create_entity().with_component(Health(3))?.id();
+
+        world.set_parent(child, root)?;
+        world.set_parent(grandchild, child)?;
+
+        assert_eq!(world.ancestors(grandchild).collect::<Vec<usize>>(), vec![child, root]);
+        assert_eq!(world.descendants_depth_first(root).collect::<Vec<usize>>(), vec![child, grandchild]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn ancestors(&self, entity_id: usize) -> Ancestors<'_> {
+        Ancestors::new(self, entity_id)
+    }
+
+    /**
+    Iterates over the descendants of an entity in depth-first order: each child is followed by
+    its own descendants before moving on to its next sibling.
+    */
+    pub fn descendants_depth_first(&self, entity_id: usize) -> DescendantsDepthFirst<'_> {
+        DescendantsDepthFirst::new(self, entity_id)
+    }
+
+    /**
+    Iterates over the descendants of an entity in breadth-first order: all of its children,
+    then all of its grandchildren, and so on.
+    */
+    pub fn descendants_breadth_first(&self, entity_id: usize) -> DescendantsBreadthFirst<'_> {
+        DescendantsBreadthFirst::new(self, entity_id)
+    }
+
+    /**
+    Registers a relation component, so the world keeps track of the entities pointing at each
+    target and `relations_to` can find them without scanning every entity. When a target is
+    removed, `Relation::ON_TARGET_DESPAWN` decides what happens to the entities pointing at it.
+    The component type must be registered as well.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::relation::Relation;
+
+    struct Health(pub u32);
+    struct Targets(pub usize);
+
+    impl Relation for Targets {
+        fn target(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Targets>();
+        world.register_relation::<Targets>();
+
+        let player = world.create_entity().with_component(Health(100))?.id();
+        let archer = world.create_entity().with_component(Targets(player))?.id();
+        let mage = world.create_entity().with_component(Targets(player))?.id();
+
+        assert_eq!(world.relations_to::<Targets>(player), vec![archer, mage]);
+        assert_eq!(world.relation_target::<Targets>(mage), Some(player));
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_relation<T: Relation>(&mut self) {
+        if self.relations.is_registered(&TypeId::of::<T>()) {
+            return;
+        }
+
+        self.relations.register::<T>();
+
+        self.on_add::<T>(|world, entity_id| {
+            if let Some(target) = world.relation_target::<T>(entity_id) {
+                world.relations.insert(TypeId::of::<T>(), target, entity_id);
+            }
+        });
+        self.on_remove::<T>(|world, entity_id| {
+            if let Some(target) = world.relation_target::<T>(entity_id) {
+                world
+                    .relations
+                    .remove(&TypeId::of::<T>(), target, entity_id);
+            }
+        });
+    }
+
+    /**
+    Retrieves the entities pointing at `target` through a relation of type `T`, sorted by ID.
+    See `register_relation`.
+    */
+    pub fn relations_to<T: Relation>(&self, target: usize) -> Vec<usize> {
+        self.relations
+            .sources(&TypeId::of::<T>(), &self.entitiy_storage, target)
+    }
+
+    /**
+    Retrieves the entity that `source` points at through a relation of type `T`, if it has one.
+    */
+    pub fn relation_target<T: Relation>(&self, source: usize) -> Option<usize> {
+        self.entitiy_storage
+            .get_component::<T>(source)
+            .ok()
+            .map(|relation| relation.target())
+    }
+
+    /**
+    Enables a spatial index tracking the entities with a `P` component, so they can be found
+    by position with `entities_in_aabb` and `entities_within_radius` without going through
+    every entity. Entities are indexed when `P` is added or removed; positions modified in
+    place are picked up by `update_spatial_index`, which is meant to run once per frame.
+    A world has a single spatial index, so calling this again has no effect.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::spatial::SpatialPosition;
+
+    struct Position(pub f32, pub f32);
+
+    impl SpatialPosition for Position {
+        fn position(&self) -> [f32; 2] {
+            [self.0, self.1]
+        }
+    }
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Position>();
+        world.enable_spatial_index::<Position>(16.0);
+
+        let near = world.create_entity().with_component(Position(1.0, 1.0))?.id();
+        world.create_entity().with_component(Position(100.0, 0.0))?;
+
+        assert_eq!(world.entities_within_radius([0.0, 0.0], 5.0)?, vec![near]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn enable_spatial_index<P: SpatialPosition>(&mut self, cell_size: f32) {
+        if self.spatial_index.is_some() {
+            return;
+        }
+
+        let mut index = SpatialIndex::new::<P>(cell_size);
+        index.rebuild(&self.entitiy_storage);
+        self.spatial_index = Some(index);
+
+        self.on_add::<P>(|world, entity_id| {
+            if let Some(index) = &mut world.spatial_index {
+                if let Some(position) = index.current_position(&world.entitiy_storage, entity_id) {
+                    index.insert(entity_id, position);
+                }
+            }
+        });
+        self.on_remove::<P>(|world, entity_id| {
+            if let Some(index) = &mut world.spatial_index {
+                index.remove(entity_id);
+            }
+        });
+    }
+
+    /**
+    Moves the entities whose position was modified in place since the last update, and
+    returns how many moved. Does nothing if the spatial index is not enabled.
+    */
+    pub fn update_spatial_index(&mut self) -> usize {
+        match &mut self.spatial_index {
+            Some(index) => index.update(&self.entitiy_storage),
+            None => 0,
+        }
+    }
+
+    pub fn spatial_index(&self) -> Option<&SpatialIndex> {
+        self.spatial_index.as_ref()
+    }
+
+    /**
+    Finds the entities inside the axis-aligned box from `min` to `max`, sorted by ID, using the
+    positions from the last update of the spatial index.
+    */
+    pub fn entities_in_aabb(&self, min: [f32; 2], max: [f32; 2]) -> Result<Vec<usize>, ECSError> {
+        self.spatial_index
+            .as_ref()
+            .map(|index| index.entities_in_aabb(min, max))
+            .ok_or(ECSError::SpatialIndexNotEnabled)
+    }
+
+    /**
+    Finds the entities at a distance of `radius` or less from `center`, sorted by ID, using the
+    positions from the last update of the spatial index.
+    */
+    pub fn entities_within_radius(
+        &self,
+        center: [f32; 2],
+        radius: f32,
+    ) -> Result<Vec<usize>, ECSError> {
+        self.spatial_index
+            .as_ref()
+            .map(|index| index.entities_within_radius(center, radius))
+            .ok_or(ECSError::SpatialIndexNotEnabled)
+    }
+
+    /**
+    Registers an index on the values of component `T`, so `entities_with_value` can find the
+    entities holding a given value with a hash lookup instead of a query. The index follows
+    components as they're added and removed; values modified in place are picked up by
+    `update_value_indexes`. The component type must be registered as well.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    enum Team {
+        Red,
+        Blue,
+    }
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Team>();
+        world.register_value_index::<Team>();
+
+        let red = world.create_entity().with_component(Team::Red)?.id();
+        world.create_entity().with_component(Team::Blue)?;
+
+        assert_eq!(world.entities_with_value(Team::Red), vec![red]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_value_index<T: Hash + Eq + Clone + Any>(&mut self) {
+        if self.value_indexes.contains::<T>() {
+            return;
+        }
+
+        self.value_indexes
+            .register::<T>()
+            .rebuild(&self.entitiy_storage);
+
+        self.on_add::<T>(|world, entity_id| {
+            let Ok(value) = world.entitiy_storage.get_component::<T>(entity_id) else {
+                return;
+            };
+
+            if let Some(index) = world.value_indexes.get_mut::<T>() {
+                index.insert(entity_id, value.clone());
+            }
+        });
+        self.on_remove::<T>(|world, entity_id| {
+            if let Some(index) = world.value_indexes.get_mut::<T>() {
+                index.remove(entity_id);
+            }
+        });
+    }
+
+    /**
+    Finds the entities whose `T` component equals `value`, sorted by ID. Finds nothing if no
+    index was registered for `T`, see `register_value_index`.
+    Values modified in place since the last `update_value_indexes` are not found under their
+    new value.
+    */
+    pub fn entities_with_value<T: Hash + Eq + Clone + Any>(&self, value: T) -> Vec<usize> {
+        let Some(index) = self.value_indexes.get::<T>() else {
+            return vec![];
+        };
+
+        index
+            .get(&value)
+            .filter(|entity_id| {
+                self.entitiy_storage
+                    .get_component::<T>(*entity_id)
+                    .is_ok_and(|current| *current == value)
+            })
+            .collect()
+    }
+
+    /**
+    Reindexes the components modified in place since the last update, and returns how many
+    changed value. Meant to run once per frame when value indexes are registered.
+    */
+    pub fn update_value_indexes(&mut self) -> usize {
+        self.value_indexes.update(&self.entitiy_storage)
+    }
+
+    /**
+    Keeps the previous value of component `T` in a `Prev<T>` component next to it, so systems
+    don't need hand-written shadow components. `Prev<T>` is added with the same value when `T`
+    is added, removed along with `T`, and overwritten with the current value of `T` by
+    `update_previous_values`, which should run once per tick before the systems that modify
+    `T`. The component type must be registered as well.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::previous::Prev;
+
+    #[derive(Clone)]
+    struct Position(pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Position>();
+        world.track_previous_values::<Position>();
+
+        world.create_entity().with_component(Position(0.0))?;
+        world.update_previous_values();
+
+        let mut entities = world.query().with_component_filter::<Position>()?.get_entities();
+        entities[0].get_component_mut::<Position>()?.0 += 2.0;
+
+        let position = entities[0].get_component::<Position>()?;
+        let previous = entities[0].get_component::<Prev<Position>>()?;
+        assert_eq!(position.0 - previous.0 .0, 2.0);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn track_previous_values<T: Clone + Any>(&mut self) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<Prev<T>>())
+            .is_some()
+        {
+            return;
+        }
+
+        self.register_cloneable_component::<Prev<T>>();
+
+        self.on_add::<T>(|world, entity_id| {
+            let Ok(value) = world.entitiy_storage.get_component::<T>(entity_id) else {
+                return;
+            };
+            let previous = Prev(value.clone());
+
+            drop(value);
+            let _ = world.add_component_to_entity(entity_id, previous);
+        });
+        self.on_remove::<T>(|world, entity_id| {
+            let _ = world.remove_entity_component::<Prev<T>>(entity_id);
+        });
+        self.previous_value_updaters.push(|entities| {
+            let type_id = TypeId::of::<Prev<T>>();
+
+            for entity_id in entities.entities_with_component(&type_id) {
+                let (Ok(value), Ok(mut previous)) = (
+                    entities.get_component::<T>(entity_id),
+                    entities.get_component_mut::<Prev<T>>(entity_id),
+                ) else {
+                    continue;
+                };
+
+                previous.0 = value.clone();
+            }
+        });
+    }
+
+    /**
+    Copies the current value of every tracked component into its `Prev`, see
+    `track_previous_values`. Can be added to a schedule as the first system of each tick.
+    */
+    pub fn update_previous_values(&mut self) {
+        for update in &self.previous_value_updaters {
+            update(&self.entitiy_storage);
+        }
+    }
+
+    /**
+    Removes an entity along with all of its descendants in the hierarchy. Descendants are
+    removed before their ancestors, so `on_remove` hooks still see their parents.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_hierarchy();
+
+        let ship = world.create_entity().with_component(Health(100))?.id();
+        let turret = world.create_entity().with_component(Health(10))?.id();
+        world.set_parent(turret, ship)?;
+
+        world.despawn_recursive(ship)?;
+
+        let remaining = world.query().with_component_filter::<Health>()?.run();
+        assert!(remaining.entity_ids.is_empty());
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn despawn_recursive(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        self.despawn_descendants(entity_id)?;
+        self.remove_entity(entity_id)
+    }
+
+    /**
+    Removes all the descendants of an entity, keeping the entity itself.
+    */
+    pub fn despawn_descendants(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        let descendants: Vec<usize> = self.descendants_breadth_first(entity_id).collect();
+
+        for descendant in descendants.into_iter().rev() {
+            self.remove_entity(descendant)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+    Retrieves the world's type registry, creating it if needed. Every registered component
+    type is added to it automatically, other types (like resources) can be added by hand.
+    The registry is stored as a `TypeRegistry` resource.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::type_registry::TypeRegistry;
+
+    #[derive(Clone, Default)]
+    struct Health(pub u32);
+
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.type_registry_mut().register_clone::<Health>();
+    world.type_registry_mut().register_default::<Health>();
+
+    let registry = world.get_resource::<TypeRegistry>().unwrap();
+    let registration = registry.get_by_name("Health").unwrap();
+
+    assert!(registration.clone.is_some() && registration.default.is_some());
+    ```
+    */
+    pub fn type_registry_mut(&mut self) -> &mut TypeRegistry {
+        if !self.resource_storage.contains::<TypeRegistry>() {
+            self.resource_storage.replace(TypeRegistry::default());
+        }
+
+        self.resource_storage.get_mut::<TypeRegistry>().unwrap()
+    }
+
+    /**
+    Registers a component type that implements `Reflect`, so its fields can be read and
+    written by name through `reflect_component` and `reflect_component_mut`.
+
+    Example:
+    ```
+    use sara_ecs::{impl_reflect, World};
+
+    struct Health(pub u32);
+    impl_reflect!(Health(0));
+
+    let mut world = World::new();
+
+    world.register_reflectable_component::<Health>();
+    ```
+    */
+    pub fn register_reflectable_component<T: Reflect>(&mut self) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_none()
+        {
+            self.register_component::<T>();
+        }
+
+        self.type_registry_mut().register_reflect::<T>();
+    }
+
+    /**
+    Borrows a component of an entity as `dyn Reflect`, looking its type up by name.
+    The name can be the full type name or the short one, without the module path.
+
+    Example:
+    ```
+    use sara_ecs::{impl_reflect, World};
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+    impl_reflect!(Position { x, y });
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_reflectable_component::<Position>();
+        let entity_id = world
+            .create_entity()
+            .with_component(Position { x: 1.0, y: 2.0 })?
+            .id();
+
+        let position = world.reflect_component(entity_id, "Position")?;
+        assert_eq!(position.get::<f32>("y"), Some(&2.0));
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn reflect_component(
+        &self,
+        entity_id: usize,
+        component_name: &str,
+    ) -> Result<Ref<'_, dyn Reflect>, ECSError> {
+        let (type_id, reflect, _) = self.reflect_functions(component_name)?;
+        let component = self
+            .entitiy_storage
+            .get_component_by_type_id(entity_id, &type_id)?;
+
+        Ok(Ref::map(component, |any| reflect(any).unwrap()))
+    }
+
+    /**
+    Mutably borrows a component of an entity as `dyn Reflect`, looking its type up by name.
+
+    Example:
+    ```
+    use sara_ecs::{impl_reflect, World};
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+    impl_reflect!(Position { x, y });
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_reflectable_component::<Position>();
+        let entity_id = world
+            .create_entity()
+            .with_component(Position { x: 1.0, y: 2.0 })?
+            .id();
+
+        world.reflect_component_mut(entity_id, "Position")?.set("x", 10.0_f32);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn reflect_component_mut(
+        &self,
+        entity_id: usize,
+        component_name: &str,
+    ) -> Result<RefMut<'_, dyn Reflect>, ECSError> {
+        let (type_id, _, reflect_mut) = self.reflect_functions(component_name)?;
+        let component = self
+            .entitiy_storage
+            .get_component_by_type_id_mut(entity_id, &type_id)?;
+
+        Ok(RefMut::map(component, |any| reflect_mut(any).unwrap()))
+    }
+
+    /**
+    Registers an observer for events of type `E`. Unlike buffered events, observers run
+    immediately when the event is triggered with `trigger`, receiving the targeted entity
+    and the event alongside the world.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    struct OnDamage(pub u32);
+    struct LastDamaged(pub usize);
+
+    let mut world = World::new();
+    world.add_resource(LastDamaged(0)).unwrap();
+
+    world.observe::<OnDamage>(|trigger, world| {
+        world.replace_resource(LastDamaged(trigger.entity_id));
+    });
+
+    world.trigger(3, OnDamage(10));
+
+    assert_eq!(world.get_resource::<LastDamaged>().unwrap().0, 3);
+    ```
+    */
+    pub fn observe<E: Any>(&mut self, observer: impl Fn(&Trigger<E>, &mut World) + 'static) {
+        self.observers.add::<E>(Rc::new(observer));
+    }
+
+    /**
+    Triggers an event targeting the given entity, running every observer registered for
+    events of type `E` right away, in registration order.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    struct OnDamage(pub u32);
+    struct TotalDamage(pub u32);
+
+    let mut world = World::new();
+    world.add_resource(TotalDamage(0)).unwrap();
+    world.observe::<OnDamage>(|trigger, world| {
+        world.get_resource_mut::<TotalDamage>().unwrap().0 += trigger.event.0;
+    });
+
+    world.trigger(0, OnDamage(10));
+
+    assert_eq!(world.get_resource::<TotalDamage>().unwrap().0, 10);
+    ```
+    */
+    pub fn trigger<E: Any>(&mut self, entity_id: usize, event: E) {
+        let trigger = Trigger {
+            entity_id,
+            event: &event,
+        };
+
+        for observer in self.observers.get::<E>() {
+            observer(&trigger, self);
+        }
+    }
+
+    /**
+    Registers a hook that runs every time a component of type `T` is added to an entity.
+    The hook receives the world and the ID of the entity, after the component was inserted.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Collider;
+    struct ColliderCount(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Collider>();
+        world.add_resource(ColliderCount(0))?;
+        world.on_add::<Collider>(|world, _entity_id| {
+            world.get_resource_mut::<ColliderCount>().unwrap().0 += 1;
+        });
+
+        world.create_entity().with_component(Collider)?;
+
+        assert_eq!(world.get_resource::<ColliderCount>().unwrap().0, 1);
+        Ok(())
+    }
+    ```
+    */
+    pub fn on_add<T: Any>(&mut self, hook: ComponentHook) {
+        self.component_hooks.add_on_add(TypeId::of::<T>(), hook);
+    }
+
+    /**
+    Registers a hook that runs every time a component of type `T` is removed from an entity,
+    either directly or because the entity itself was removed. The hook runs before the
+    component is removed, so it can still be read.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Collider;
+    struct ColliderCount(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Collider>();
+        world.add_resource(ColliderCount(1))?;
+        world.on_remove::<Collider>(|world, _entity_id| {
+            world.get_resource_mut::<ColliderCount>().unwrap().0 -= 1;
+        });
+
+        world.create_entity().with_component(Collider)?;
+        world.remove_entity(0)?;
+
+        assert_eq!(world.get_resource::<ColliderCount>().unwrap().0, 0);
+        Ok(())
+    }
+    ```
+    */
+    pub fn on_remove<T: Any>(&mut self, hook: ComponentHook) {
+        self.component_hooks.add_on_remove(TypeId::of::<T>(), hook);
+    }
+
+    /**
+    Creates a new entity. The entity is initially empty and can later be populated with components.
+    This function returns an entity builder, allowing you to chain component additions to the
+    created entity.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Speed(pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Speed>();
+
+        let entity = world.create_entity()
+            .with_component(Health(100))?
+            .with_component(Speed(15.0))?;
+
+        Ok(())
+    }
+
+    // The entity now has Health and Speed components
+    ```
+    */
+    pub fn create_entity(&mut self) -> EntityBuilder<'_> {
+        self.flush_reserved_entities();
+
+        let entity_id = self.entitiy_storage.create_entity().next_free_entity_id();
+        self.entity_spawned(entity_id);
+
+        EntityBuilder::new(self, entity_id)
+    }
+
+    /**
+    Gives access to the entity table through a shared reference, for example to reserve
+    entity IDs from a system that only has `&World`. See `Entities::reserve`.
+    */
+    pub fn entities(&self) -> Entities<'_> {
+        Entities::new(&self.entitiy_storage)
+    }
+
+    /**
+    Gives read-only access to a living entity, for example to list its components.
+
+    Example:
+    ```
+    use std::any::TypeId;
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Player;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Player>();
+
+        let player = world
+            .create_entity()
+            .with_component(Health(100))?
+            .with_component(Player)?
+            .id();
+
+        let components = world.entity(player)?.component_types();
+
+        assert_eq!(components.len(), 2);
+        assert!(components
+            .iter()
+            .any(|component| component.type_id == TypeId::of::<Health>() && component.size == 4));
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn entity(&self, entity_id: usize) -> Result<EntityRef<'_>, ECSError> {
+        if !self.entitiy_storage.is_alive(entity_id) {
+            return Err(ECSError::EntityDoesNotExist { id: entity_id });
+        }
+
+        Ok(EntityRef::new(self, entity_id))
+    }
+
+    /**
+    Borrows one component of one entity, without building a query. Fails with
+    `ComponentNotRegistered` if `T` isn't a registered component, `EntityDoesNotExist` if the
+    entity isn't alive, `ComponentDoesNotExist` if it doesn't have the component, and
+    `ComponentAlreadyBorrowed` if the component is mutably borrowed.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Armor(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Armor>();
+
+        let player = world.create_entity().with_component(Health(100))?.id();
+
+        world.query_one_mut::<Health>(player)?.0 -= 30;
+
+        assert_eq!(world.query_one::<Health>(player)?.0, 70);
+        assert!(matches!(
+            world.query_one::<Armor>(player),
+            Err(ECSError::ComponentDoesNotExist { .. })
+        ));
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn query_one<T: Any>(&self, entity_id: usize) -> Result<Ref<'_, T>, ECSError> {
+        self.check_query_one::<T>(entity_id)?;
+        self.entitiy_storage.try_get_component::<T>(entity_id)
+    }
+
+    /**
+    Mutable version of `query_one`, which also fails with `ComponentAlreadyBorrowed` if the
+    component is borrowed at all.
+    */
+    pub fn query_one_mut<T: Any>(&self, entity_id: usize) -> Result<RefMut<'_, T>, ECSError> {
+        self.check_query_one::<T>(entity_id)?;
+        self.entitiy_storage.try_get_component_mut::<T>(entity_id)
+    }
+
+    // Registration is checked first, so an unregistered type is reported even for dead
+    // entities.
+    fn check_query_one<T: Any>(&self, entity_id: usize) -> Result<(), ECSError> {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_none()
+        {
+            return Err(ECSError::ComponentNotRegistered {
+                type_name: std::any::type_name::<T>().to_owned(),
+            });
+        }
+
+        if !self.entitiy_storage.is_alive(entity_id) {
+            return Err(ECSError::EntityDoesNotExist { id: entity_id });
+        }
+
+        Ok(())
+    }
+
+    /**
+    Adds a component to an entity by its ID. The component must be registered beforehand.
+    This function updates the entity with the provided component data.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Speed(pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        let entity = world.create_entity().with_component(Health(100))?;
+
+        // Add a new component to the entity with ID 0
+        world.add_component_to_entity(0, Speed(15.0))?;
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn add_component_to_entity(
+        &mut self,
+        entity_id: usize,
+        component_data: impl Any,
+    ) -> Result<(), ECSError> {
+        let type_id = component_data.type_id();
+
+        self.flush_reserved_entities();
+        self.check_unique(entity_id, &type_id)?;
+        self.entitiy_storage
+            .add_component_to_entity(entity_id, component_data)?;
+        self.record_change(|world| StructuralChange::ComponentInserted {
+            entity_id,
+            component: world.component_name(&type_id),
+        });
+        self.insert_required_components(entity_id, &type_id)?;
+
+        for hook in self.component_hooks.on_add(&type_id) {
+            hook(self, entity_id);
+        }
+
+        Ok(())
+    }
+
+    /**
+    Removes an entity by its ID. The entity and its associated components will be removed from the world.
+    If the entity does not exist or was already removed, an error will be returned.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Speed(pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        let entity = world.create_entity().with_component(Health(100))?;
+
+        // Remove the entity with ID 0
+        world.remove_entity(0)?;
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn remove_entity(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        // Entities without components yet can be removed, already removed ones can't.
+        if !self.entitiy_storage.accepts_components(entity_id) {
+            return Err(ECSError::EntityDoesNotExist { id: entity_id });
+        }
+
+        for type_id in self.entitiy_storage.entity_component_type_ids(entity_id) {
+            for hook in self.component_hooks.on_remove(&type_id) {
+                hook(self, entity_id);
+            }
+        }
+
+        self.entitiy_storage.remove_entity(entity_id)?;
+        self.entity_pools.remove(entity_id);
+        self.send_event_if_registered(EntityDespawned(entity_id));
+        self.record_change(|_| StructuralChange::Despawned { entity_id });
+        #[cfg(feature = "diagnostics")]
+        self.diagnostics_counters.count_despawn();
+        self.clean_up_relations(entity_id)?;
+
+        Ok(())
+    }
+
+    /**
+    Releases the memory held for removed entities, which the world otherwise keeps to reuse
+    their slots. Slots after the last living entity are trimmed, components left behind by
+    removed entities are dropped, and every column is shrunk to fit. Living entities keep
+    their IDs. Returns the number of slots trimmed. Nothing is trimmed when the world never
+    reuses entity IDs, see `EntityIdRecycling`.
+
+    Useful for long-running worlds after a mass despawn, like a server emptying a level.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Bullet;
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Bullet>();
+
+        let player = world.create_entity().with_component(Health(100))?.id();
+        for _ in 0..1000 {
+            world.create_entity().with_component(Bullet)?;
+        }
+        for bullet in 1..=1000 {
+            world.remove_entity(bullet)?;
+        }
+
+        assert_eq!(world.compact(), 1000);
+        assert_eq!(world.query().with_component_filter::<Health>()?.run().entity_ids, vec![player]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn compact(&mut self) -> usize {
+        self.flush_reserved_entities();
+        self.entitiy_storage.compact()
+    }
+
+    /**
+    Moves every living entity to the lowest IDs, keeping their order, so iterating touches
+    dense columns again after a long session of spawning and despawning left them full of
+    holes. Then trims the memory left behind, like `compact`. Returns the new ID of every
+    living entity, keyed by its old one.
+
+    Entity IDs aren't generational, so IDs kept outside of the world have to be updated with
+    the returned table. Inside the world, the hierarchy, entity pools and lookup indexes are
+    updated. With the `serde` feature, so are the components registered with
+    `register_map_entities`, prefab instances and replicated entities. Entities don't move
+    when the world never reuses entity IDs, see `EntityIdRecycling`.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+
+        for health in 0..4 {
+            world.create_entity().with_component(Health(health))?;
+        }
+        world.remove_entity(0)?;
+        world.remove_entity(2)?;
+
+        let remap = world.defragment();
+
+        assert_eq!(remap[&1], 0);
+        assert_eq!(remap[&3], 1);
+        assert_eq!(world.query().with_component_filter::<Health>()?.run().entity_ids, vec![0, 1]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn defragment(&mut self) -> HashMap<usize, usize> {
+        self.flush_reserved_entities();
+
+        let entity_map: HashMap<usize, usize> =
+            self.entitiy_storage.defragment().into_iter().collect();
+
+        if entity_map.iter().all(|(old, new)| old == new) {
+            return entity_map;
+        }
+
+        self.remap_entity_references(&entity_map);
+        self.entity_pools.remap(&entity_map);
+        self.rebuild_indexes();
+
+        entity_map
+    }
+
+    /**
+    Refreshes the `WorldDiagnostics` resource, if the world has one, and starts counting the
+    next tick. See `WorldDiagnostics`.
+    */
+    #[cfg(feature = "diagnostics")]
+    pub fn update_diagnostics(&mut self) {
+        let Some(mut diagnostics) = self
+            .resource_storage
+            .borrow_mut::<diagnostics::WorldDiagnostics>()
+        else {
+            self.diagnostics_counters
+                .flush(&mut diagnostics::WorldDiagnostics::default());
+            return;
+        };
+
+        self.diagnostics_counters.flush(&mut diagnostics);
+        diagnostics.entity_count = self.entitiy_storage.living_entity_count();
+        diagnostics.component_counts = self
+            .entitiy_storage
+            .component_type_ids()
+            .map(|type_id| {
+                let count = self
+                    .entitiy_storage
+                    .entities_with_component(type_id)
+                    .count();
+                (self.component_name(type_id), count)
+            })
+            .collect();
+    }
+
+    /**
+    Checks the invariants of the world's storage and returns every violation found, to narrow
+    down storage bugs: component columns have a slot per entity, component bits match filled
+    slots, `Parent` and `Children` agree with each other, and hierarchy links and registered
+    relations point at living entities. It goes through every entity, so it's meant for tests
+    and debug builds. Requires the `diagnostics` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::diagnostics::Violation;
+    use sara_ecs::relation::Relation;
+
+    struct Targets(pub usize);
+
+    impl Relation for Targets {
+        fn target(&self) -> usize {
+            self.0
+        }
+    }
+
+    let mut world = World::new();
+
+    world.register_component::<Targets>();
+    world.register_relation::<Targets>();
+
+    let target = world.create_entity().with_component(Targets(0)).unwrap().id();
+    let source = world.create_entity().with_component(Targets(target)).unwrap().id();
+
+    assert!(world.validate().is_empty());
+
+    world.remove_entity(target).unwrap();
+
+    assert_eq!(
+        world.validate(),
+        vec![Violation::DeadTarget {
+            source,
+            target,
+            relation: std::any::type_name::<Targets>().to_owned(),
+        }]
+    );
+    ```
+    */
+    #[cfg(feature = "diagnostics")]
+    pub fn validate(&self) -> Vec<diagnostics::Violation> {
+        use diagnostics::Violation;
+
+        let mut violations = self.entitiy_storage.validate();
+        let mut relations: Vec<&TypeId> = self.relations.type_ids().collect();
+        relations.sort_by_key(|type_id| self.entitiy_storage.component_name(type_id));
+
+        let living = (0..self.entitiy_storage.entity_count())
+            .filter(|entity_id| self.entitiy_storage.is_alive(*entity_id));
+
+        for entity_id in living {
+            if let Some(parent) = self.parent(entity_id) {
+                if !self.entitiy_storage.is_alive(parent) {
+                    violations.push(Violation::DeadTarget {
+                        source: entity_id,
+                        target: parent,
+                        relation: std::any::type_name::<Parent>().to_owned(),
+                    });
+                } else if !self.children(parent).any(|child| child == entity_id) {
+                    violations.push(Violation::ChildNotListed {
+                        child: entity_id,
+                        parent,
+                    });
+                }
+            }
+
+            for child in self.children(entity_id) {
+                if !self.entitiy_storage.is_alive(child) {
+                    violations.push(Violation::DeadTarget {
+                        source: entity_id,
+                        target: child,
+                        relation: std::any::type_name::<Children>().to_owned(),
+                    });
+                } else if self.parent(child) != Some(entity_id) {
+                    violations.push(Violation::ParentNotSet {
+                        parent: entity_id,
+                        child,
+                    });
+                }
+            }
+
+            for type_id in &relations {
+                let Some(target) = self
+                    .relations
+                    .target(type_id, &self.entitiy_storage, entity_id)
+                else {
+                    continue;
+                };
+
+                if !self.entitiy_storage.is_alive(target) {
+                    violations.push(Violation::DeadTarget {
+                        source: entity_id,
+                        target,
+                        relation: self.entitiy_storage.component_name(type_id),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+
+    /**
+    Estimates the memory used by the world, per component column and per resource, sorted by
+    name. Resources that are mutably borrowed while the report is made are reported with
+    zero bytes.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.add_resource(0_u64)?;
+
+        for _ in 0..100 {
+            world.create_entity().with_component(Health(100))?;
+        }
+
+        let report = world.memory_report();
+        let health = report.component(std::any::type_name::<Health>()).unwrap();
+
+        assert_eq!(health.len, 100);
+        assert!(health.capacity >= 100);
+        assert!(report.total_bytes() > 100 * std::mem::size_of::<Health>());
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = self
+            .entitiy_storage
+            .memory_report(|type_id| self.component_name(type_id));
+
+        for (type_id, key, resource) in self.resource_storage.iter() {
+            let name = self
+                .resource_storage
+                .type_name(&type_id)
+                .map_or_else(|| format!("{type_id:?}"), str::to_owned);
+            let heap_bytes = resource
+                .try_borrow()
+                .map_or(0, |resource| size_of_val(&**resource));
+
+            report.resources.push(ResourceMemory {
+                name,
+                key: key.map(str::to_owned),
+                heap_bytes,
+            });
+        }
+
+        report
+            .resources
+            .sort_by(|a, b| (&a.name, &a.key).cmp(&(&b.name, &b.key)));
+        report
+    }
+
+    /**
+    Removes a specific component from an entity by its ID. The component type must be registered
+    in advance. The function will attempt to remove the component from the entity and return any errors
+    if the component is not registered or if there is an issue.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Speed(pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        let entity = world.create_entity().with_component(Health(100))?;
+
+        // Remove the Health component from entity 0
+        world.remove_entity_component::<Health>(0)?;
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn remove_entity_component<T: Any>(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        self.remove_component_dynamic(entity_id, TypeId::of::<T>())
+    }
+
+    /**
+    Adds a boxed component to an entity, identified by its `TypeId` instead of a type
+    parameter. Useful for tools like deserializers and scripting bridges that only know
+    component types at runtime. Runs the same hooks as `add_component_to_entity`.
+
+    Example:
+    ```
+    use std::any::{Any, TypeId};
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        let entity_id = world.create_entity().with_component(Health(100))?.id();
+
+        let component: Box<dyn Any> = Box::new(Health(50));
+        world.add_component_dynamic(entity_id, TypeId::of::<Health>(), component)?;
+        world.remove_component_dynamic(entity_id, TypeId::of::<Health>())?;
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn add_component_dynamic(
+        &mut self,
+        entity_id: usize,
+        type_id: TypeId,
+        component_data: Box<dyn Any>,
+    ) -> Result<(), ECSError> {
+        self.flush_reserved_entities();
+        self.check_unique(entity_id, &type_id)?;
+        self.entitiy_storage
+            .add_component_dynamic(entity_id, type_id, component_data)?;
+        self.record_change(|world| StructuralChange::ComponentInserted {
+            entity_id,
+            component: world.component_name(&type_id),
+        });
+        self.insert_required_components(entity_id, &type_id)?;
+
+        for hook in self.component_hooks.on_add(&type_id) {
+            hook(self, entity_id);
+        }
+
+        Ok(())
+    }
+
+    /**
+    Removes a component from an entity by its `TypeId`. Runs the same hooks as
+    `remove_entity_component`.
+    */
+    pub fn remove_component_dynamic(
+        &mut self,
+        entity_id: usize,
+        type_id: TypeId,
+    ) -> Result<(), ECSError> {
+        let had_component = self
+            .entitiy_storage
+            .entity_has_component(entity_id, &type_id);
+
+        if had_component {
+            for hook in self.component_hooks.on_remove(&type_id) {
+                hook(self, entity_id);
+            }
+        }
+
+        self.entitiy_storage
+            .remove_component_dynamic(entity_id, &type_id)?;
+
+        if had_component {
+            self.record_change(|world| StructuralChange::ComponentRemoved {
+                entity_id,
+                component: world.component_name(&type_id),
+            });
+        }
+
+        Ok(())
+    }
+
+    /**
+    Registers a component whose layout is described at runtime. Dynamic components are stored
+    and queried like regular ones, but are addressed by the name of their descriptor.
+    Registering a name again does nothing if the fields are the same, and fails with
+    `DynamicComponentMismatch` otherwise.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::dynamic_component::{DynamicComponentDescriptor, DynamicValue, FieldType};
+    use sara_ecs::ecs_errors::ECSError;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        let mana = DynamicComponentDescriptor::new("Mana").with_field("current", FieldType::Int);
+        world.register_dynamic_component(mana.clone())?;
+
+        let entity_id = world.create_entity().id();
+        world.add_dynamic_component(entity_id, mana.instantiate())?;
+        world
+            .get_dynamic_component_mut(entity_id, "Mana")?
+            .set("current", DynamicValue::Int(50));
+
+        let result = world.query().with_dynamic_component_filter("Mana")?.run();
+        assert_eq!(result.entity_ids, vec![entity_id]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_dynamic_component(
+        &mut self,
+        descriptor: DynamicComponentDescriptor,
+    ) -> Result<(), ECSError> {
+        self.entitiy_storage.register_dynamic_component(descriptor)
+    }
+
+    /**
+    Adds a dynamic component to an entity. Fails with `DynamicComponentMismatch` if its fields
+    don't match the registered descriptor.
+    */
+    pub fn add_dynamic_component(
+        &mut self,
+        entity_id: usize,
+        component: DynamicComponent,
+    ) -> Result<(), ECSError> {
+        let name = component.name.clone();
+
+        self.flush_reserved_entities();
+        self.entitiy_storage
+            .add_dynamic_component_to_entity(entity_id, component)?;
+        self.record_change(|_| StructuralChange::ComponentInserted {
+            entity_id,
+            component: name,
+        });
+
+        Ok(())
+    }
+
+    pub fn dynamic_component_descriptor(&self, name: &str) -> Option<&DynamicComponentDescriptor> {
+        self.entitiy_storage.dynamic_component_descriptor(name)
+    }
+
+    pub fn remove_dynamic_component(
+        &mut self,
+        entity_id: usize,
+        name: &str,
+    ) -> Result<(), ECSError> {
+        let had_component = self
+            .entitiy_storage
+            .entity_has_dynamic_component(entity_id, name);
+
+        self.entitiy_storage
+            .remove_dynamic_component_from_entity(entity_id, name)?;
+
+        if had_component {
+            self.record_change(|_| StructuralChange::ComponentRemoved {
+                entity_id,
+                component: name.to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn get_dynamic_component(
+        &self,
+        entity_id: usize,
+        name: &str,
+    ) -> Result<Ref<'_, DynamicComponent>, ECSError> {
+        self.entitiy_storage.get_dynamic_component(entity_id, name)
+    }
+
+    pub fn get_dynamic_component_mut(
+        &self,
+        entity_id: usize,
+        name: &str,
+    ) -> Result<RefMut<'_, DynamicComponent>, ECSError> {
+        self.entitiy_storage
+            .get_dynamic_component_mut(entity_id, name)
+    }
+
+    /**
+    Registers a component type that can be held by a single entity at a time, like a camera or
+    the player. Adding it to a second entity fails with `UniqueComponentTaken` until it is
+    removed from the first one. Types that are already registered keep their components.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct MainCamera;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_unique_component::<MainCamera>();
+
+        let camera = world.create_entity().with_component(MainCamera)?.id();
+
+        assert!(world.create_entity().with_component(MainCamera).is_err());
+        assert_eq!(world.single::<MainCamera>(), Some(camera));
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_unique_component<T: Any>(&mut self) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_none()
+        {
+            self.register_component::<T>();
+        }
+
+        self.unique_components.insert(TypeId::of::<T>());
+    }
+
+    /**
+    Retrieves the entity holding a `T` component. Meant for unique components, see
+    `register_unique_component`; for other types it returns the entity with the lowest ID.
+    */
+    pub fn single<T: Any>(&self) -> Option<usize> {
+        self.entitiy_storage
+            .entities_with_component(&TypeId::of::<T>())
+            .next()
+    }
+
+    /**
+    Registers a component type along with a function creating its default value, so the
+    component can be added with `insert_default` without providing a value.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component_with_default(|| Health(100));
+
+        let entity_id = world.create_entity().insert_default::<Health>()?.id();
+
+        let query = world.query().with_component_filter::<Health>()?.run();
+        assert_eq!(query.entity_ids, vec![entity_id]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_component_with_default<T: Any>(&mut self, default: impl Fn() -> T + 'static) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_none()
+        {
+            self.register_component::<T>();
+        }
+
+        self.component_defaults.insert(default);
+    }
+
+    /**
+    Creates the default value of a component type, using the function registered with
+    `register_component_with_default`, or the type registry's default function otherwise.
+    */
+    pub fn default_component(&self, type_id: &TypeId) -> Option<Box<dyn Any>> {
+        if let Some(component) = self.component_defaults.create(type_id) {
+            return Some(component);
+        }
+
+        let registry = self.resource_storage.borrow::<TypeRegistry>()?;
+        let default = registry.get(type_id)?.default?;
+
+        Some(default())
+    }
+
+    /**
+    Adds the default value of `T` to an entity. Fails with `NoDefaultValue` if `T` has no
+    default, see `default_component`.
+    */
+    pub fn insert_default<T: Any>(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        self.insert_default_dynamic(entity_id, TypeId::of::<T>())
+    }
+
+    /**
+    Adds the default value of a component to an entity by its `TypeId`, see `insert_default`.
+    */
+    pub fn insert_default_dynamic(
+        &mut self,
+        entity_id: usize,
+        type_id: TypeId,
+    ) -> Result<(), ECSError> {
+        let component = self
+            .default_component(&type_id)
+            .ok_or(ECSError::NoDefaultValue)?;
+
+        self.add_component_dynamic(entity_id, type_id, component)
+    }
+
+    /**
+    Declares that entities with a `T` component also need a `U` component. Whenever `T` is
+    added to an entity that doesn't have `U` yet, `U::default()` is added as well, before the
+    `on_add` hooks of `T` run. Requirements are followed recursively, and both types must be
+    registered.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Sprite(pub &'static str);
+
+    #[derive(Default)]
+    struct Position(pub f32, pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Sprite>();
+        world.register_component::<Position>();
+        world.register_required_component::<Sprite, Position>();
+
+        world.create_entity().with_component(Sprite("player.png"))?;
+
+        let positioned = world.query().with_component_filter::<Position>()?.run();
+        assert_eq!(positioned.entity_ids, vec![0]);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_required_component<T: Any, U: Default + Any>(&mut self) {
+        self.register_required_component_with::<T, U>(U::default);
+    }
+
+    /**
+    Like `register_required_component`, but the required component is created by `constructor`
+    instead of `Default`.
+    */
+    pub fn register_required_component_with<T: Any, U: Any>(&mut self, constructor: fn() -> U) {
+        self.required_components.add(
+            TypeId::of::<T>(),
+            RequiredComponent {
+                type_id: TypeId::of::<U>(),
+                constructor: Rc::new(move || Box::new(constructor())),
+            },
+        );
+    }
+
+    /**
+    Registers a component type and its clone function, so it can be copied by `checkpoint`.
+
+    Example:
+    ```
+    use sara_ecs::World;
+
+    #[derive(Clone)]
+    struct Health(pub u32);
+
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+    ```
+    */
+    pub fn register_cloneable_component<T: Clone + Any>(&mut self) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_none()
+        {
+            self.register_component::<T>();
+        }
+
+        self.type_registry_mut().register_clone::<T>();
+    }
+
+    /**
+    Registers a component type and its `Debug` implementation, so `debug_dump` can show its
+    values.
+
+    Example:
+    ```
+    use sara_ecs::World;
+
+    #[derive(Debug)]
+    struct Health(pub u32);
+
+    let mut world = World::new();
+
+    world.register_debuggable_component::<Health>();
+    ```
+    */
+    pub fn register_debuggable_component<T: std::fmt::Debug + Any>(&mut self) {
+        if self
+            .entitiy_storage
+            .get_bitmask(&TypeId::of::<T>())
+            .is_none()
+        {
+            self.register_component::<T>();
+        }
+
+        self.type_registry_mut().register_debug::<T>();
+    }
+
+    /**
+    Lists the living entities with their components, and the resources, in a `WorldDump`
+    that can be inspected or printed. Types are shown by their names without module paths,
+    and their values are shown for types with a debug function in the type registry, see
+    `register_debuggable_component`.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::name::Name;
+
+    #[derive(Debug)]
+    struct Health(pub u32);
+    struct Player;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.enable_entity_names();
+        world.register_debuggable_component::<Health>();
+        world.register_component::<Player>();
+
+        world
+            .create_entity()
+            .with_component(Name::new("Player"))?
+            .with_component(Health(100))?
+            .with_component(Player)?;
+
+        let dump = world.debug_dump();
+        let player = dump.entity(0).unwrap();
+
+        assert_eq!(player.name.as_deref(), Some("Player"));
+        assert_eq!(player.component("Health").unwrap().value.as_deref(), Some("Health(100)"));
+        assert_eq!(player.component("Player").unwrap().value, None);
+
+        println!("{dump}");
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn debug_dump(&self) -> WorldDump {
+        let registry = self.resource_storage.borrow::<TypeRegistry>();
+        let debug_fn = |type_id: &TypeId| {
+            registry
+                .as_ref()
+                .and_then(|registry| registry.get(type_id)?.debug)
+        };
+        let mut dump = WorldDump::default();
+
+        for entity_id in 0..self.entitiy_storage.entity_count() {
+            if !self.entitiy_storage.is_alive(entity_id) {
+                continue;
+            }
+
+            let mut components: Vec<ComponentDump> = self
+                .entitiy_storage
+                .entity_component_type_ids(entity_id)
+                .into_iter()
+                .map(|type_id| ComponentDump {
+                    name: short_name(&self.component_name(&type_id)),
+                    value: debug_fn(&type_id).and_then(|debug| {
+                        let component = self
+                            .entitiy_storage
+                            .get_component_by_type_id(entity_id, &type_id)
+                            .ok()?;
+                        Some(debug(&*component))
+                    }),
+                })
+                .collect();
+
+            for name in self
+                .entitiy_storage
+                .entity_dynamic_component_names(entity_id)
+            {
+                let value = self
+                    .entitiy_storage
+                    .get_dynamic_component(entity_id, name)
+                    .ok()
+                    .map(|component| format!("{:?}", component.fields));
+
+                components.push(ComponentDump {
+                    name: name.to_owned(),
+                    value,
+                });
+            }
+
+            components.sort_by(|a, b| a.name.cmp(&b.name));
+            dump.entities.push(EntityDump {
+                id: entity_id,
+                name: self.entity_name(entity_id),
+                disabled: self.entitiy_storage.is_disabled(entity_id),
+                components,
+            });
+        }
+
+        for (type_id, key, resource) in self.resource_storage.iter() {
+            let name = self
+                .resource_storage
+                .type_name(&type_id)
+                .map_or_else(|| format!("{type_id:?}"), short_name);
+            let value = debug_fn(&type_id).and_then(|debug| {
+                let resource = resource.try_borrow().ok()?;
+                Some(debug(&**resource))
+            });
+
+            dump.resources.push(ResourceDump {
+                name,
+                key: key.map(str::to_owned),
+                value,
+            });
+        }
+
+        dump.resources
+            .sort_by(|a, b| (&a.name, &a.key).cmp(&(&b.name, &b.key)));
+        dump
+    }
+
+    /**
+    Copies every entity and its components into a `WorldCheckpoint`, which can later be restored
+    with `restore`. Components are copied with their clone functions from the type registry, so
+    it is much faster than taking a serialized snapshot. Fails with `TypeNotCloneable` if a living
+    entity has a component without a clone function. Resources are not included.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    #[derive(Clone)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_cloneable_component::<Health>();
+        world.create_entity().with_component(Health(100))?;
+
+        let checkpoint = world.checkpoint()?;
+
+        world.remove_entity(0)?;
+        world.restore(&checkpoint)?;
+
+        let query = world.query().with_component_filter::<Health>()?.run();
+        assert_eq!(query.entity_ids, vec![0]);
+        Ok(())
+    }
+    ```
+    */
+    pub fn checkpoint(&self) -> Result<WorldCheckpoint, ECSError> {
+        let registry = self.resource_storage.borrow::<TypeRegistry>();
+
+        self.entitiy_storage
+            .checkpoint(|type_id| registry.as_ref()?.get(type_id)?.clone)
+    }
+
+    /**
+    Restores the entities and components saved in a checkpoint, discarding the current ones.
+    The checkpoint is left untouched, so it can be restored as many times as needed.
+    Component hooks don't run and no lifecycle events are sent.
+    */
+    pub fn restore(&mut self, checkpoint: &WorldCheckpoint) -> Result<(), ECSError> {
+        let registry = self.resource_storage.borrow::<TypeRegistry>();
+
+        self.entitiy_storage
+            .restore(checkpoint, |type_id| registry.as_ref()?.get(type_id)?.clone)?;
+        drop(registry);
+
+        self.rebuild_indexes();
+
+        Ok(())
+    }
+
+    /**
+    Checkpoints the world, then simulates one tick with `input` using the simulation of the
+    `Rollback<I>` resource, recording both so the tick can be rolled back later with
+    `rollback_to`. Returns the simulated tick.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::rollback::Rollback;
+
+    #[derive(Clone)]
+    struct Position(pub i32);
+
+    fn simulate(world: &mut World, input: &i32) {
+        let query = world.query().with_component_filter::<Position>().unwrap().get_entities();
+
+        for mut entity in query {
+            entity.get_component_mut::<Position>().unwrap().0 += input;
+        }
+    }
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_cloneable_component::<Position>();
+        world.create_entity().with_component(Position(0))?;
+        world.add_resource(Rollback::new(8, simulate))?;
+
+        world.advance_rollback(1)?;
+        world.advance_rollback(1)?;
+
+        // The input of tick 0 was actually 5.
+        world.get_resource_mut::<Rollback<i32>>().unwrap().set_input(0, 5);
+        world.rollback_to::<i32>(0)?;
+
+        let entities = world.query().with_component_filter::<Position>()?.get_entities();
+        assert_eq!(entities[0].get_component::<Position>()?.0, 6);
+        Ok(())
+    }
+    ```
+    */
+    pub fn advance_rollback<I: Any>(&mut self, input: I) -> Result<u64, ECSError> {
+        let checkpoint = self.checkpoint()?;
+
+        self.resource_scope::<rollback::Rollback<I>, _>(|world, rollback| {
+            let tick = rollback.current_tick();
+
+            (rollback.simulation())(world, &input);
+            rollback.push(checkpoint, input);
+
+            tick
+        })
+    }
+
+    /**
+    Restores the world to how it was before `tick` was simulated, then resimulates every tick
+    up to the current one with the recorded inputs, which may have been corrected with
+    `Rollback::set_input` in the meantime. Fails with `TickNotBuffered` if the tick is older
+    than the buffer or hasn't been simulated yet.
+    */
+    pub fn rollback_to<I: Any>(&mut self, tick: u64) -> Result<(), ECSError> {
+        self.resource_scope::<rollback::Rollback<I>, _>(|world, rollback| {
+            // Restored before rewinding, so the frames are kept if the checkpoint can't be.
+            let checkpoint = rollback.checkpoint(tick).ok_or(ECSError::TickNotBuffered)?;
+            world.restore(checkpoint)?;
+
+            let frames = rollback.rewind(tick).expect("the tick is buffered");
+            let simulate = rollback.simulation();
+
+            for (index, frame) in frames.into_iter().enumerate() {
+                let checkpoint = if index == 0 {
+                    frame.checkpoint
+                } else {
+                    world.checkpoint()?
+                };
+
+                simulate(world, &frame.input);
+                rollback.push(checkpoint, frame.input);
+            }
+
+            Ok(())
+        })?
+    }
+
+    /**
+    Marks components of type `T` as serializable, so they are included in world snapshots.
+    The component still has to be registered with `register_component`. Snapshots key it by
+    type name, which may change with the compiler version, see
+    `register_serializable_component_with_name`. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_serializable_component::<Health>();
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn register_serializable_component<T>(&mut self)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Any,
+    {
+        self.serialization.register_component::<T>();
+        self.type_registry_mut().register_serde::<T>();
+    }
+
+    /**
+    Like `register_serializable_component`, but snapshots key the component by `name` instead
+    of its type name, so they keep loading after a toolchain upgrade or a module move.
+    Snapshots saved under the type name are still accepted. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component_with_name::<Health>("Health");
+        world.create_entity().with_component(Health(100))?;
+
+        let snapshot = world.snapshot()?;
+        assert_eq!(snapshot.entities[0].components["Health"], 100);
+
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn register_serializable_component_with_name<T>(&mut self, name: &'static str)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Any,
+    {
+        self.serialization.register_component_with_name::<T>(name);
+        self.type_registry_mut().register_serde::<T>();
+    }
+
+    /**
+    Sets the version of a serializable component. Snapshots record the version of every
+    component, and when a snapshot saved with an older version is loaded, the migrations added
+    with `add_component_migration` upgrade the saved values one version at a time.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    // Version 0 stored health as a bare number, version 1 as { "current": .., "max": .. }.
+    #[derive(Serialize, Deserialize)]
+    struct Health {
+        current: u32,
+        max: u32,
+    }
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component::<Health>();
+        world.set_component_version::<Health>(1)?;
+        world.add_component_migration::<Health>(0, |value| {
+            json!({ "current": value, "max": value })
+        })?;
+
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn set_component_version<T: Any>(&mut self, version: u32) -> Result<(), ECSError> {
+        self.serialization.set_component_version::<T>(version)
+    }
+
+    /**
+    Adds a migration that upgrades serialized components of type `T` from `from_version` to
+    `from_version + 1`. See `set_component_version`. Requires the `serde` feature.
+    */
+    #[cfg(feature = "serde")]
+    pub fn add_component_migration<T: Any>(
+        &mut self,
+        from_version: u32,
+        migration: ComponentMigration,
+    ) -> Result<(), ECSError> {
+        self.serialization
+            .add_component_migration::<T>(from_version, migration)
+    }
+
+    /**
+    Takes a snapshot of the world containing the serializable components of every living entity,
+    the entity layout, and the serializable resources. The snapshot can be written with any serde
+    format and restored with `load_snapshot`. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component::<Health>();
+        world.create_entity().with_component(Health(100))?;
+
+        let snapshot = world.snapshot()?;
+
+        assert_eq!(snapshot.entities.len(), 1);
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Result<WorldSnapshot, ECSError> {
+        Ok(WorldSnapshot {
+            entity_count: self.entitiy_storage.entity_count(),
+            entities: self
+                .serialization
+                .serialize_entities(&self.entitiy_storage)?,
+            resources: self
+                .serialization
+                .serialize_resources(&self.resource_storage)?,
+            component_versions: self.serialization.component_versions(),
+        })
+    }
+
+    /**
+    Takes a snapshot like `snapshot`, but only of the entities that have a marker component `M`
+    and of the serializable resources listed in `resources`. Useful for save games, which should
+    skip transient entities like particles and UI. Entities keep their IDs, so the snapshot can
+    be restored with `load_snapshot`. Requires the `serde` feature.
+
+    Example:
+    ```
+    use std::any::TypeId;
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+    struct SaveMe;
+
+    #[derive(Serialize, Deserialize)]
+    struct Score(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<SaveMe>();
+        world.register_serializable_component::<Health>();
+        world.register_serializable_resource::<Score>();
+        world.add_resource(Score(10))?;
+
+        world.create_entity().with_component(Health(100))?.with_component(SaveMe)?;
+        world.create_entity().with_component(Health(5))?;
+
+        let snapshot = world.snapshot_filtered::<SaveMe>(&[TypeId::of::<Score>()])?;
+
+        assert_eq!(snapshot.entities.len(), 1);
+        assert_eq!(snapshot.resources.len(), 1);
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn snapshot_filtered<M: Any>(
+        &self,
+        resources: &[TypeId],
+    ) -> Result<WorldSnapshot, ECSError> {
+        let marker = TypeId::of::<M>();
+
+        if self.entitiy_storage.get_bitmask(&marker).is_none() {
+            return Err(ECSError::ComponentNotRegistered {
+                type_name: std::any::type_name::<M>().to_owned(),
+            });
+        }
+
+        Ok(WorldSnapshot {
+            entity_count: self.entitiy_storage.entity_count(),
+            entities: self.serialization.serialize_entities_where(
+                &self.entitiy_storage,
+                |entity_id| {
+                    self.entitiy_storage
+                        .entity_has_component(entity_id, &marker)
+                },
+            )?,
+            resources: self
+                .serialization
+                .serialize_resources_where(&self.resource_storage, |type_id| {
+                    resources.contains(type_id)
+                })?,
+            component_versions: self.serialization.component_versions(),
+        })
+    }
+
+    /**
+    Restores a snapshot taken with `snapshot`. Every current entity is discarded and the entities
+    of the snapshot are recreated with the same IDs, then the serializable resources are replaced.
+    The world must have the same components and resources registered as serializable.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component::<Health>();
+        world.create_entity().with_component(Health(100))?;
+
+        let snapshot = world.snapshot()?;
+        world.remove_entity(0)?;
+        world.load_snapshot(snapshot)?;
+
+        let query = world.query().with_component_filter::<Health>()?.run();
+        assert_eq!(query.entity_ids, vec![0]);
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn load_snapshot(&mut self, snapshot: WorldSnapshot) -> Result<(), ECSError> {
+        self.entitiy_storage.reset(snapshot.entity_count);
+
+        for entity in snapshot.entities {
+            self.entitiy_storage.set_next_free_entity_id(entity.id);
+
+            for (name, value) in entity.components {
+                let version = snapshot.component_versions.get(&name).copied();
+                self.deserialize_snapshot_component(entity.id, &name, version, value)?;
+            }
+        }
+
+        self.deserialize_resources(snapshot.resources)
+    }
+
+    /**
+    Registers a component type that holds entity IDs, so those IDs are remapped when a snapshot
+    is spawned with `spawn_snapshot`. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::{EntityMap, MapEntities, World};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Follows(pub usize);
+
+    impl MapEntities for Follows {
+        fn map_entities(&mut self, entity_map: &EntityMap) {
+            self.0 = entity_map[&self.0];
+        }
+    }
+
+    let mut world = World::new();
+
+    world.register_component::<Follows>();
+    world.register_serializable_component::<Follows>();
+    world.register_map_entities::<Follows>();
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn register_map_entities<T: MapEntities + Any>(&mut self) {
+        self.serialization.register_entity_mapper::<T>();
+    }
+
+    /**
+    Spawns the entities of a snapshot into the world as new entities, keeping the entities that
+    already exist. Every spawned entity gets a freshly allocated ID, and components registered
+    with `register_map_entities` get their entity references patched. Entities whose
+    `EntityUuid` is already used in this world get a new one. Resources in the snapshot
+    are ignored. Returns the map from the IDs in the snapshot to the new IDs.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component::<Health>();
+        world.create_entity().with_component(Health(100))?;
+
+        let scene = world.snapshot()?;
+        let entity_map = world.spawn_snapshot(scene)?;
+
+        assert_eq!(entity_map[&0], 1);
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn spawn_snapshot(&mut self, snapshot: WorldSnapshot) -> Result<EntityMap, ECSError> {
+        let mut entity_map = EntityMap::new();
+
+        for entity in snapshot.entities {
+            let new_entity_id = self.create_entity().id();
+
+            for (name, value) in entity.components {
+                let version = snapshot.component_versions.get(&name).copied();
+                self.deserialize_snapshot_component(new_entity_id, &name, version, value)?;
+            }
+
+            if let Some(uuid) = self.entity_uuid(new_entity_id) {
+                if self.entity_by_uuid(uuid) != Some(new_entity_id) {
+                    self.add_component_to_entity(new_entity_id, EntityUuid::new_v4())?;
+                }
+            }
+
+            entity_map.insert(entity.id, new_entity_id);
+        }
+
+        self.serialization
+            .map_entities(&self.entitiy_storage, &entity_map);
+
+        Ok(entity_map)
+    }
+
+    /**
+    Marks components of type `T` as replicated, so their changes are included in the change
+    sets returned by `collect_changes`. Also registers the type as serializable. The component
+    still has to be registered with `register_component`. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Position(pub f32, pub f32);
+
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_replicated_component::<Position>();
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn register_replicated_component<T>(&mut self)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Any,
+    {
+        self.register_serializable_component::<T>();
+        self.replication.replicated.insert(TypeId::of::<T>());
+    }
+
+    /**
+    Collects what changed in the replicated components since the last call: entities that
+    gained their first replicated component, entities that lost all of them or were removed,
+    and every replicated component that was added, modified or removed. Changes are detected
+    by comparing serialized values, so only what actually differs is sent. The first call
+    returns the whole replicated state. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Position(pub f32, pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut server = World::new();
+        let mut client = World::new();
+
+        for world in [&mut server, &mut client] {
+            world.register_component::<Position>();
+            world.register_replicated_component::<Position>();
+        }
+
+        server.create_entity().with_component(Position(1.0, 2.0))?;
+
+        let changes = server.collect_changes()?;
+        let bytes = serde_json::to_vec(&changes).unwrap();
+        client.apply_changes(serde_json::from_slice(&bytes).unwrap())?;
+
+        assert!(server.collect_changes()?.is_empty());
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn collect_changes(&mut self) -> Result<replication::ChangeSet, ECSError> {
+        let current = self.replicated_state(|_| true)?;
+        Ok(self.replication.tracker.diff(current))
+    }
+
+    /**
+    Like `collect_changes`, but only for the entities relevant to one peer: the ones it owns,
+    marked with an `Owner` component, and the ones listing it in their `InterestedPeers`.
+    Each peer keeps track of what it was sent, so entities that stop being relevant are
+    reported as despawned. Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::replication::{InterestedPeers, Owner, PeerId};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Position(pub f32, pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut server = World::new();
+
+        server.register_component::<Position>();
+        server.register_component::<Owner>();
+        server.register_component::<InterestedPeers>();
+        server.register_replicated_component::<Position>();
+
+        server
+            .create_entity()
+            .with_component(Position(0.0, 0.0))?
+            .with_component(Owner(PeerId(1)))?;
+        server
+            .create_entity()
+            .with_component(Position(5.0, 0.0))?
+            .with_component(InterestedPeers(vec![PeerId(2)]))?;
+
+        assert_eq!(server.collect_changes_for(PeerId(1))?.spawned, vec![0]);
+        assert_eq!(server.collect_changes_for(PeerId(2))?.spawned, vec![1]);
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn collect_changes_for(
+        &mut self,
+        peer: replication::PeerId,
+    ) -> Result<replication::ChangeSet, ECSError> {
+        let current = self.replicated_state(|entity_id| self.is_relevant_to(entity_id, peer))?;
+
+        Ok(self
+            .replication
+            .peer_trackers
+            .entry(peer)
+            .or_default()
+            .diff(current))
+    }
+
+    /**
+    Forgets what was sent to a peer, for example after it disconnects. The next
+    `collect_changes_for` for that peer returns every relevant entity again.
+    Requires the `serde` feature.
+    */
+    #[cfg(feature = "serde")]
+    pub fn forget_peer(&mut self, peer: replication::PeerId) {
+        self.replication.peer_trackers.remove(&peer);
+    }
+
+    /**
+    Retrieves the IDs of every entity whose `Owner` component is `peer`.
+    Requires the `serde` feature.
+    */
+    #[cfg(feature = "serde")]
+    pub fn entities_owned_by(&self, peer: replication::PeerId) -> Vec<usize> {
+        (0..self.entitiy_storage.entity_count())
+            .filter(|entity_id| {
+                self.entitiy_storage
+                    .get_component::<replication::Owner>(*entity_id)
+                    .is_ok_and(|owner| owner.0 == peer)
+            })
+            .collect()
+    }
+
+    /**
+    Applies a change set collected from another world with `collect_changes`. Spawned entities
+    get new local IDs, and the map from the remote IDs is kept so later change sets update the
+    same entities. Components registered with `register_map_entities` get their entity
+    references translated to local IDs. Requires the `serde` feature.
+    */
+    #[cfg(feature = "serde")]
+    pub fn apply_changes(&mut self, changes: replication::ChangeSet) -> Result<(), ECSError> {
+        for remote_id in changes.despawned {
+            if let Some(local_id) = self.replication.remote_entities.remove(&remote_id) {
+                self.remove_entity(local_id)?;
+            }
+        }
+
+        let mut received = vec![];
+        let mut changed = changes.changed;
+
+        for remote_id in changes.spawned {
+            // Entities without components are free slots, so the components of each spawned
+            // entity are added right away.
+            let local_id = self.create_entity().id();
+            self.replication.remote_entities.insert(remote_id, local_id);
+
+            let (spawned_changes, other_changes) = changed
+                .into_iter()
+                .partition(|change| change.entity_id == remote_id);
+            changed = other_changes;
+
+            self.apply_component_changes(spawned_changes, &mut received)?;
+        }
+
+        self.apply_component_changes(changed, &mut received)?;
+
+        for removal in changes.removed {
+            let local_id = self.local_entity(removal.entity_id)?;
+            let type_id = self
+                .component_id_by_name(&removal.component)
+                .ok_or_else(|| ECSError::ComponentNotRegistered {
+                    type_name: removal.component.clone(),
+                })?;
+
+            self.remove_component_dynamic(local_id, type_id)?;
+        }
+
+        // Only the components that were just received hold remote IDs, the others were
+        // already mapped when they arrived.
+        for (local_id, component) in received {
+            self.serialization.map_component_entities(
+                &component,
+                &self.entitiy_storage,
+                local_id,
+                &self.replication.remote_entities,
+            );
+        }
+
+        Ok(())
+    }
+
+    /**
+    Retrieves the local ID of an entity replicated from another world with `apply_changes`.
+    Requires the `serde` feature.
+    */
+    #[cfg(feature = "serde")]
+    pub fn replicated_entity(&self, remote_id: usize) -> Option<usize> {
+        self.replication.remote_entities.get(&remote_id).copied()
+    }
+
+    /**
+    Stores a prefab in the world so it can be instantiated with `instantiate_prefab`.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::prefab::Prefab;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    let mut world = World::new();
+
+    let goblin = world.add_prefab(Prefab::new().with_component(&Health(30)));
+
+    assert!(world.get_prefab(goblin).is_some());
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn add_prefab(&mut self, prefab: prefab::Prefab) -> prefab::PrefabId {
+        self.prefabs.prefabs.push(prefab);
+        prefab::PrefabId(self.prefabs.prefabs.len() - 1)
+    }
+
+    /**
+    Retrieves a prefab stored with `add_prefab`. Requires the `serde` feature.
+    */
+    #[cfg(feature = "serde")]
+    pub fn get_prefab(&self, prefab_id: prefab::PrefabId) -> Option<&prefab::Prefab> {
+        self.prefabs.prefabs.get(prefab_id.0)
+    }
+
+    /**
+    Creates an entity from a prefab, plus one entity for each of its children. The component
+    types used by the prefab must be registered as serializable. Returns the ID of the root entity.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::prefab::Prefab;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component::<Health>();
+
+        let goblin = world.add_prefab(Prefab::new().with_component(&Health(30)));
+        let first = world.instantiate_prefab(goblin)?;
+        let second = world.instantiate_prefab(goblin)?;
+
+        assert_ne!(first, second);
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn instantiate_prefab(&mut self, prefab_id: prefab::PrefabId) -> Result<usize, ECSError> {
+        let node_count = self
+            .get_prefab(prefab_id)
+            .ok_or(ECSError::PrefabDoesNotExist)?
+            .flatten()
+            .len();
+
+        self.prefabs.instances.push(prefab::PrefabInstance {
+            prefab_id,
+            entity_ids: vec![],
+            overrides: std::collections::HashMap::new(),
+        });
+        let instance_index = self.prefabs.instances.len() - 1;
+
+        for _ in 0..node_count {
+            // Entities without components are free slots, so each entity is resolved right away
+            // to keep the next `create_entity` from handing out the same ID again.
+            let entity_id = self.create_entity().id();
+            self.prefabs.instances[instance_index]
+                .entity_ids
+                .push(entity_id);
+            self.resolve_prefab_entity(entity_id)?;
+        }
+
+        Ok(self.prefabs.instances[instance_index].entity_ids[0])
+    }
+
+    /**
+    Overrides fields of a prefab component on one instance entity. Only the fields present in
+    `patch` are recorded, everything else keeps following the prefab when it is updated.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::prefab::Prefab;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Stats {
+        health: u32,
+        speed: u32,
+    }
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Stats>();
+        world.register_serializable_component::<Stats>();
+
+        let goblin = world.add_prefab(Prefab::new().with_component(&Stats { health: 30, speed: 2 }));
+        let boss = world.instantiate_prefab(goblin)?;
+
+        world.set_prefab_override::<Stats>(boss, serde_json::json!({ "health": 300 }))?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn set_prefab_override<T: Any>(
+        &mut self,
+        entity_id: usize,
+        patch: serde_json::Value,
+    ) -> Result<(), ECSError> {
+        let instance = self
+            .prefabs
+            .instance_of_mut(entity_id)
+            .ok_or(ECSError::NotAPrefabInstance)?;
+        let position = instance
+            .entity_ids
+            .iter()
+            .position(|id| *id == entity_id)
+            .unwrap();
+        let key = (position, std::any::type_name::<T>().to_owned());
+
+        let merged = match instance.overrides.get(&key) {
+            Some(current) => prefab::merge(current, &patch),
+            None => patch,
+        };
+        instance.overrides.insert(key, merged);
+
+        self.resolve_prefab_entity(entity_id)
+    }
+
+    /**
+    Replaces a prefab and re-resolves every instance against it, keeping each instance's overrides.
+    Requires the `serde` feature.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+    use sara_ecs::prefab::Prefab;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(pub u32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_serializable_component::<Health>();
+
+        let goblin = world.add_prefab(Prefab::new().with_component(&Health(30)));
+        world.instantiate_prefab(goblin)?;
+
+        // Every goblin now has 40 health.
+        world.update_prefab(goblin, Prefab::new().with_component(&Health(40)))?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "serde")]
+    pub fn update_prefab(
+        &mut self,
+        prefab_id: prefab::PrefabId,
+        prefab: prefab::Prefab,
+    ) -> Result<(), ECSError> {
+        *self
+            .prefabs
+            .prefabs
+            .get_mut(prefab_id.0)
+            .ok_or(ECSError::PrefabDoesNotExist)? = prefab;
+
+        let entity_ids: Vec<usize> = self
+            .prefabs
+            .instances
+            .iter()
+            .filter(|instance| instance.prefab_id == prefab_id)
+            .flat_map(|instance| instance.entity_ids.clone())
+            .collect();
+
+        for entity_id in entity_ids {
+            self.resolve_prefab_entity(entity_id)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+    Query the entities in the world to retrieve components based on the registered component types.
+    You can use this to filter and retrieve entities with specific combinations of components.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Health(pub u32);
+    struct Speed(pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Health>();
+        world.register_component::<Speed>();
+
+        let mut entity = world.create_entity();
+        entity.with_component(Health(100))?;
+        entity.with_component(Speed(15.0))?;
+
+        let query = world.query();
+
+        Ok(())
+    }
+    // Perform queries based on your component types
+    ```
+    */
+    pub fn query(&self) -> Query<'_> {
+        #[cfg(feature = "diagnostics")]
+        self.diagnostics_counters.count_query();
+
+        Query::new(&self.entitiy_storage).with_spatial_index(self.spatial_index.as_ref())
+    }
+
+    /**
+    Calls `f` with the ID and the components of every entity having all the component types
+    of the tuple `C`, in ID order. A shorthand for building the query and downcasting its
+    columns, for simple systems. Fails with `ComponentNotRegistered` if a type isn't registered.
+    See `each_mut` to modify the components.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Position(pub f32, pub f32);
+    struct Velocity(pub f32, pub f32);
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Position>();
+        world.register_component::<Velocity>();
+        world
+            .create_entity()
+            .with_component(Position(0.0, 0.0))?
+            .with_component(Velocity(1.0, 2.0))?;
+
+        world.each_mut::<(Position, Velocity), _>(|_entity, position, velocity| {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        })?;
+
+        let mut positions = vec![];
+        world.each::<(Position,), _>(|entity, position| positions.push((entity, position.1)))?;
+
+        assert_eq!(positions, vec![(0, 2.0)]);
+        Ok(())
+    }
+    ```
+    */
+    pub fn each<C: Each<F>, F>(&self, f: F) -> Result<(), ECSError> {
+        C::each(self, f)
+    }
+
+    /**
+    Like `each`, with mutable references to the components. Listing the same type twice
+    panics, as the component would be borrowed mutably twice.
+    */
+    pub fn each_mut<C: EachMut<F>, F>(&self, f: F) -> Result<(), ECSError> {
+        C::each_mut(self, f)
+    }
+
+    /**
+    Advances the `Time` resource by `delta`, scaled by its time scale, adding the resource if
+    the world doesn't have one. See `Time`.
+    */
+    pub fn advance_time(&mut self, delta: std::time::Duration) {
+        self.get_resource_or_insert_with(Time::new).advance(delta);
+    }
+
+    /**
+    Sets the schedule run by `step`, `step_n` and `step_system`, returning the previous one.
+    */
+    pub fn set_schedule(&mut self, schedule: Schedule) -> Schedule {
+        std::mem::replace(&mut self.schedule, schedule)
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    /**
+    Advances the simulation by exactly one tick, running the world's schedule once. Meant for
+    tests and debuggers that need to drive the simulation deterministically. The schedule is
+    taken out of the world while it runs, so systems see an empty one.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::schedule::Schedule;
+
+    struct Frames(pub u32);
+
+    let mut world = World::new();
+    let mut schedule = Schedule::new();
+
+    schedule.add_system(|world| world.get_resource_mut::<Frames>().unwrap().0 += 1);
+    world.add_resource(Frames(0)).unwrap();
+    world.set_schedule(schedule);
+
+    world.step();
+    world.step_n(3);
+
+    assert_eq!(world.get_resource::<Frames>().unwrap().0, 4);
+    ```
+    */
+    pub fn step(&mut self) {
+        let mut schedule = std::mem::take(&mut self.schedule);
+
+        schedule.run(self);
+        self.schedule = schedule;
+    }
+
+    pub fn step_n(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.step();
+        }
+    }
+
+    /**
+    Runs only the next system of the world's schedule, returning `true` once the tick is over.
+    Lets a debugger pause between systems, see `Schedule::step_system`.
+    */
+    pub fn step_system(&mut self) -> bool {
+        let mut schedule = std::mem::take(&mut self.schedule);
+        let tick_over = schedule.step_system(self);
+
+        self.schedule = schedule;
+        tick_over
+    }
+
+    /**
+    Applies the changes recorded in a command buffer, in order. Stops at the first command
+    that fails and returns its error, the commands after it are dropped.
+    */
+    pub fn apply_commands(&mut self, commands: Commands) -> Result<(), ECSError> {
+        self.flush_reserved_entities();
+
+        for command in commands.drain() {
+            command(self)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+    Runs `script` with a `ScriptContext` restricted by `sandbox`, then applies the changes it
+    recorded. The script's error is returned without applying anything. See `ScriptContext`.
+    */
+    pub fn run_script(
+        &mut self,
+        sandbox: ScriptSandbox,
+        script: impl FnOnce(&mut ScriptContext) -> Result<(), ECSError>,
+    ) -> Result<(), ECSError> {
+        let mut context = ScriptContext::new(self, sandbox);
+
+        script(&mut context)?;
+
+        let commands = context.into_commands();
+        self.apply_commands(commands)
+    }
+
+    /**
+    Runs `f` with the changes it makes logged under the caller tag `caller`, then puts back
+    the previous tag. Does nothing special without a `ChangeLogger` resource. Requires the
+    `log` feature.
+    */
+    #[cfg(feature = "log")]
+    pub fn with_caller<R>(&mut self, caller: &str, f: impl FnOnce(&mut World) -> R) -> R {
+        let previous = self
+            .resource_storage
+            .get_mut::<ChangeLogger>()
+            .map(|logger| logger.caller.replace(caller.to_owned()));
+        let result = f(self);
+
+        if let (Some(previous), Some(logger)) =
+            (previous, self.resource_storage.get_mut::<ChangeLogger>())
+        {
+            logger.caller = previous;
+        }
+
+        result
+    }
+
+    /**
+    Registers a system under a name, so it can be run later with `run_named_system` by code that
+    only knows the name, like debug consoles, scripts or admin commands. Replaces the system
+    previously registered under the same name, if any.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::ecs_errors::ECSError;
+
+    struct Enemy;
+
+    fn example() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_component::<Enemy>();
+        world.register_named_system("spawn_wave", |world| {
+            for _ in 0..3 {
+                world.create_entity().with_component(Enemy).unwrap();
+            }
+        });
+
+        world.run_named_system("spawn_wave")?;
+
+        let query = world.query().with_component_filter::<Enemy>()?.run();
+        assert_eq!(query.entity_ids.len(), 3);
+
+        Ok(())
+    }
+    ```
+    */
+    pub fn register_named_system(
+        &mut self,
+        name: impl Into<String>,
+        system: impl FnMut(&mut World) + 'static,
+    ) {
+        self.named_systems.insert(name.into(), Box::new(system));
+    }
+
+    /**
+    Unregisters a named system. Returns `false` if there was no system with that name.
+    */
+    pub fn remove_named_system(&mut self, name: &str) -> bool {
+        self.named_systems.remove(name).is_some()
+    }
+
+    /**
+    Names of the registered systems, in alphabetical order.
+    */
+    pub fn named_systems(&self) -> Vec<&str> {
+        self.named_systems.names()
+    }
+
+    /**
+    Runs the system registered under `name`. Fails with `SystemNotFound` if there is none, which
+    is also the case when a system tries to run itself.
+    */
+    pub fn run_named_system(&mut self, name: &str) -> Result<(), ECSError> {
+        let mut system = self
+            .named_systems
+            .remove(name)
+            .ok_or(ECSError::SystemNotFound)?;
+
+        system(self);
+        self.named_systems.restore(name, system);
+
+        Ok(())
+    }
+
+    /**
+    Swaps the named systems of a reloadable library for the ones in `handoff`, after checking
+    that every component the library expects is registered with the same layout. On failure
+    the systems registered by the previous version of the library are kept. Shouldn't be
+    called from one of the library's own systems.
+    */
+    pub fn reload_systems(
+        &mut self,
+        library: &str,
+        handoff: SystemHandoff,
+    ) -> Result<(), ECSError> {
+        for expected in &handoff.layouts {
+            let found = self
+                .entitiy_storage
+                .component_id_by_type_name(&expected.name)
+                .and_then(|type_id| self.component_layout(&type_id))
+                .ok_or_else(|| ECSError::ComponentNotRegistered {
+                    type_name: expected.name.clone(),
+                })?;
+
+            if found != *expected {
+                return Err(ECSError::ComponentLayoutMismatch {
+                    component: expected.name.clone(),
+                });
+            }
+        }
+
+        self.unload_systems(library);
+
+        let mut names = Vec::with_capacity(handoff.systems.len());
+
+        for (name, system) in handoff.systems {
+            self.named_systems.insert(name.clone(), system);
+            names.push(name);
+        }
+
+        self.reloadable_libraries.insert(library, names);
+        Ok(())
+    }
+
+    /**
+    Drops the named systems registered by a reloadable library, which has to happen before the
+    library is unloaded. Returns `false` if the library had no systems.
+    */
+    pub fn unload_systems(&mut self, library: &str) -> bool {
+        let Some(names) = self.reloadable_libraries.remove(library) else {
+            return false;
+        };
+
+        for name in names {
+            self.named_systems.remove(&name);
+        }
+
+        true
+    }
+
+    /**
+    Stable ID of a registered component, looked up by name like `component_id_by_name`. See
+    `StableComponentId`.
+    */
+    pub fn stable_component_id(&self, name: &str) -> Option<StableComponentId> {
+        let type_id = self.component_id_by_name(name)?;
+
+        self.component_layout(&type_id)
+            .map(|layout| StableComponentId::from_name(&layout.name))
+    }
+
+    /**
+    `TypeId` of the registered component with the given stable ID, in the current build.
+    */
+    pub fn component_id_by_stable_id(&self, id: StableComponentId) -> Option<TypeId> {
+        self.entitiy_storage
+            .component_type_ids()
+            .find(|type_id| {
+                StableComponentId::from_name(&self.entitiy_storage.component_name(type_id)) == id
+            })
+            .copied()
+    }
+
+    /**
+    Full type name, size and alignment of a registered component. Dynamic components have no
+    layout.
+    */
+    pub fn component_layout(&self, type_id: &TypeId) -> Option<ComponentLayout> {
+        Some(ComponentLayout {
+            name: self.entitiy_storage.component_name(type_id),
+            layout: self.entitiy_storage.component_layout(type_id)?,
+        })
+    }
+
+    #[cfg(feature = "serde")]
+    fn resolve_prefab_entity(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        let instance = self
+            .prefabs
+            .instance_of(entity_id)
+            .ok_or(ECSError::NotAPrefabInstance)?;
+        let position = instance
+            .entity_ids
+            .iter()
+            .position(|id| *id == entity_id)
+            .unwrap();
+        let prefab = self
+            .prefabs
+            .prefabs
+            .get(instance.prefab_id.0)
+            .ok_or(ECSError::PrefabDoesNotExist)?;
+
+        let mut components = match prefab.flatten().get(position) {
+            Some(node) => node.components.clone(),
+            None => std::collections::HashMap::new(),
+        };
+
+        for ((override_position, name), patch) in &instance.overrides {
+            if *override_position != position {
+                continue;
+            }
+
+            let merged = match components.get(name) {
+                Some(value) => prefab::merge(value, patch),
+                None => patch.clone(),
+            };
+            components.insert(name.clone(), merged);
+        }
+
+        for (name, value) in components {
+            let deserialize = self.serialization.component_deserializer(&name)?;
+            deserialize(self, entity_id, value)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn reflect_functions(
+        &self,
+        component_name: &str,
+    ) -> Result<(TypeId, ReflectFn, ReflectMutFn), ECSError> {
+        let not_registered = || ECSError::ComponentNotRegistered {
+            type_name: component_name.to_owned(),
+        };
+        let registry = self
+            .resource_storage
+            .borrow::<TypeRegistry>()
+            .ok_or_else(not_registered)?;
+        let registration = registry
+            .get_by_name(component_name)
+            .ok_or_else(not_registered)?;
+
+        match (registration.reflect, registration.reflect_mut) {
+            (Some(reflect), Some(reflect_mut)) => Ok((registration.type_id, reflect, reflect_mut)),
+            _ => Err(ECSError::TypeNotReflectable),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize_snapshot_component(
+        &mut self,
+        entity_id: usize,
+        name: &str,
+        version: Option<u32>,
+        value: serde_json::Value,
+    ) -> Result<(), ECSError> {
+        let value = self
+            .serialization
+            .migrate_component(name, version.unwrap_or(0), value)?;
+        let deserialize = self.serialization.component_deserializer(name)?;
+
+        deserialize(self, entity_id, value)
+    }
+
+    #[cfg(feature = "serde")]
+    fn replicated_state(
+        &self,
+        keep: impl Fn(usize) -> bool,
+    ) -> Result<replication::ReplicatedState, ECSError> {
+        let mut state = replication::ReplicatedState::new();
+
+        for entity_id in 0..self.entitiy_storage.entity_count() {
+            if !keep(entity_id) {
+                continue;
+            }
+
+            let mut components = std::collections::HashMap::new();
+
+            for type_id in &self.replication.replicated {
+                if let Some(component) = self.serialization.serialize_component(
+                    type_id,
+                    &self.entitiy_storage,
+                    entity_id,
+                ) {
+                    let (name, value) = component?;
+                    components.insert(name.to_owned(), value);
+                }
+            }
+
+            if !components.is_empty() {
+                state.insert(entity_id, components);
+            }
+        }
+
+        Ok(state)
+    }
+
+    #[cfg(feature = "serde")]
+    fn is_relevant_to(&self, entity_id: usize, peer: replication::PeerId) -> bool {
+        let owned = self
+            .entitiy_storage
+            .get_component::<replication::Owner>(entity_id)
+            .is_ok_and(|owner| owner.0 == peer);
+        let interested = self
+            .entitiy_storage
+            .get_component::<replication::InterestedPeers>(entity_id)
+            .is_ok_and(|peers| peers.0.contains(&peer));
+
+        owned || interested
+    }
+
+    #[cfg(feature = "serde")]
+    fn apply_component_changes(
+        &mut self,
+        changes: Vec<replication::ComponentChange>,
+        received: &mut Vec<(usize, String)>,
+    ) -> Result<(), ECSError> {
+        for change in changes {
+            let local_id = self.local_entity(change.entity_id)?;
+            let deserialize = self
+                .serialization
+                .component_deserializer(&change.component)?;
+
+            deserialize(self, local_id, change.value)?;
+            received.push((local_id, change.component));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    fn local_entity(&self, remote_id: usize) -> Result<usize, ECSError> {
+        self.replicated_entity(remote_id)
+            .ok_or(ECSError::EntityDoesNotExist { id: remote_id })
+    }
+
+    fn run_resource_hooks(
+        &mut self,
+        type_id: &TypeId,
+        type_name: &'static str,
+        change: ResourceChange,
+    ) {
+        self.record_change(|_| StructuralChange::ResourceChanged {
+            resource: type_name.to_owned(),
+            change,
+        });
+
+        for hook in self.resource_hooks.get(type_id) {
+            hook(self, change);
+        }
+    }
+
+    fn is_ancestor_of(&self, ancestor: usize, entity_id: usize) -> bool {
+        self.ancestors(entity_id).any(|parent| parent == ancestor)
+    }
+
+    fn detach_child(&mut self, parent: usize, child: usize) {
+        if let Ok(mut children) = self.entitiy_storage.get_component_mut::<Children>(parent) {
+            children.0.retain(|entity_id| *entity_id != child);
+        }
+    }
+
+    // Patches the components holding entity IDs after entities moved. With the `serde`
+    // feature, the hierarchy has registered entity mappers like any other component.
+    fn remap_entity_references(&mut self, entity_map: &HashMap<usize, usize>) {
+        #[cfg(feature = "serde")]
+        {
+            self.serialization
+                .map_entities(&self.entitiy_storage, entity_map);
+
+            for instance in &mut self.prefabs.instances {
+                for entity_id in &mut instance.entity_ids {
+                    *entity_id = entity_map.get(entity_id).copied().unwrap_or(*entity_id);
+                }
+
+                instance.overrides = std::mem::take(&mut instance.overrides)
+                    .into_iter()
+                    .map(|((entity_id, component), value)| {
+                        let entity_id = entity_map.get(&entity_id).copied().unwrap_or(entity_id);
+                        ((entity_id, component), value)
+                    })
+                    .collect();
+            }
+
+            for entity_id in self.replication.remote_entities.values_mut() {
+                *entity_id = entity_map.get(entity_id).copied().unwrap_or(*entity_id);
+            }
+        }
+
+        #[cfg(not(feature = "serde"))]
+        for entity_id in entity_map.values() {
+            let storage = &self.entitiy_storage;
+
+            if let Ok(mut parent) = storage.get_component_mut::<Parent>(*entity_id) {
+                parent.0 = entity_map.get(&parent.0).copied().unwrap_or(parent.0);
+            }
+
+            if let Ok(mut children) = storage.get_component_mut::<Children>(*entity_id) {
+                for child in &mut children.0 {
+                    *child = entity_map.get(child).copied().unwrap_or(*child);
+                }
+            }
+        }
+    }
+
+    // Hooks don't run when a checkpoint is restored, so the lookup indexes are rebuilt from
+    // the restored components.
+    fn rebuild_indexes(&mut self) {
+        let entity_ids = 0..self.entitiy_storage.entity_count();
+
+        if self.entity_uuids.enabled {
+            self.entity_uuids.index = entity_ids
+                .clone()
+                .filter_map(|entity_id| Some((self.entity_uuid(entity_id)?, entity_id)))
+                .collect();
+        }
+
+        if self.entity_names.enabled {
+            self.entity_names.index = entity_ids
+                .filter_map(|entity_id| Some((self.entity_name(entity_id)?, entity_id)))
+                .collect();
+        }
+
+        self.relations.rebuild(&self.entitiy_storage);
+
+        if let Some(index) = &mut self.spatial_index {
+            index.rebuild(&self.entitiy_storage);
+        }
+
+        self.value_indexes.rebuild(&self.entitiy_storage);
+    }
+
+    // Records a change in the journal, if there is one. The change is only built when it's
+    // going to be recorded, so journaling costs nothing while it's disabled.
+    fn record_change(&mut self, change: impl FnOnce(&World) -> StructuralChange) {
+        #[cfg(feature = "log")]
+        let logging = self
+            .resource_storage
+            .get_mut::<ChangeLogger>()
+            .is_some_and(|logger| logger.is_logging());
+        #[cfg(not(feature = "log"))]
+        let logging = false;
+
+        if !logging && !self.resource_storage.contains::<Journal>() {
+            return;
+        }
+
+        let change = change(self);
+
+        #[cfg(feature = "log")]
+        if let Some(logger) = self.resource_storage.get_mut::<ChangeLogger>() {
+            if logging {
+                logger.log(&change);
+            }
+        }
+
+        if let Some(journal) = self.resource_storage.get_mut::<Journal>() {
+            journal.record(change);
+        }
+    }
+
+    // Adds the entities reserved through `Entities::reserve` to the world.
+    fn flush_reserved_entities(&mut self) {
+        for entity_id in self.entitiy_storage.flush_reserved_entities() {
+            self.entity_spawned(entity_id);
+        }
+    }
+
+    fn entity_spawned(&mut self, entity_id: usize) {
+        self.send_event_if_registered(EntitySpawned(entity_id));
+        self.record_change(|_| StructuralChange::Spawned { entity_id });
+        #[cfg(feature = "diagnostics")]
+        self.diagnostics_counters.count_spawn();
+
+        if self.entity_uuids.enabled {
+            self.add_component_to_entity(entity_id, EntityUuid::new_v4())
+                .expect("EntityUuid is registered by enable_entity_uuids");
+        }
+    }
+
+    fn component_name(&self, type_id: &TypeId) -> String {
+        self.resource_storage
+            .borrow::<TypeRegistry>()
+            .and_then(|registry| registry.get(type_id).map(|registration| registration.name))
+            .map_or_else(|| format!("{type_id:?}"), str::to_owned)
+    }
+
+    fn insert_required_components(
+        &mut self,
+        entity_id: usize,
+        type_id: &TypeId,
+    ) -> Result<(), ECSError> {
+        for required in self.required_components.of(type_id) {
+            if !self
+                .entitiy_storage
+                .entity_has_component(entity_id, &required.type_id)
+            {
+                self.add_component_dynamic(entity_id, required.type_id, (required.constructor)())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_unique(&self, entity_id: usize, type_id: &TypeId) -> Result<(), ECSError> {
+        if !self.unique_components.contains(type_id) {
+            return Ok(());
+        }
+
+        match self.entitiy_storage.entities_with_component(type_id).next() {
+            Some(holder) if holder != entity_id => Err(ECSError::UniqueComponentTaken),
+            _ => Ok(()),
+        }
+    }
+
+    // Applies the `RelationCleanup` of every relation pointing at a removed entity.
+    fn clean_up_relations(&mut self, target: usize) -> Result<(), ECSError> {
+        for (type_id, cleanup) in self.relations.cleanups() {
+            for source in self
+                .relations
+                .sources(&type_id, &self.entitiy_storage, target)
+            {
+                cleanup(self, source, target)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn send_event_if_registered<T: Any>(&mut self, event: T) {
+        if let Some(events) = self.resource_storage.get_mut::<Events<T>>() {
+            events.send(event);
+        }
     }
 }
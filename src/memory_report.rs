@@ -0,0 +1,118 @@
+use std::mem::size_of;
+
+use crate::entity_storage::Component;
+
+/**
+Memory used by the column of a component type.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMemory {
+    pub name: String,
+    // Number of entities that have the component.
+    pub len: usize,
+    // Number of slots the column has room for.
+    pub capacity: usize,
+    pub heap_bytes: usize,
+}
+
+/**
+Memory used by a resource. Keyed resources are listed once per key.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceMemory {
+    pub name: String,
+    pub key: Option<String>,
+    pub heap_bytes: usize,
+}
+
+/**
+Estimate of the memory used by a world, returned by `World::memory_report`. Byte counts
+include the columns and the allocation of every component and resource, but not memory
+owned by the values themselves, like the contents of a `String` or a `Vec`.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    pub entity_count: usize,
+    // Entity masks, groups, tags and disabled flags.
+    pub entity_bytes: usize,
+    pub components: Vec<ColumnMemory>,
+    pub resources: Vec<ResourceMemory>,
+}
+
+impl MemoryReport {
+    pub fn total_bytes(&self) -> usize {
+        self.entity_bytes
+            + self
+                .components
+                .iter()
+                .map(|column| column.heap_bytes)
+                .sum::<usize>()
+            + self
+                .resources
+                .iter()
+                .map(|resource| resource.heap_bytes)
+                .sum::<usize>()
+    }
+
+    pub fn component(&self, name: &str) -> Option<&ColumnMemory> {
+        self.components.iter().find(|column| column.name == name)
+    }
+}
+
+// Size of the allocation behind a component: the reference counts of the `Rc` followed by
+// the `RefCell` holding the value.
+pub(crate) fn component_bytes(component: &Component) -> usize {
+    2 * size_of::<usize>() + size_of_val(&**component)
+}
+
+// Removed components stay allocated until their slot is reused or the world is compacted,
+// so every allocation in the column is counted, not only those of living entities.
+pub(crate) fn column_bytes(column: &Vec<Option<Component>>) -> usize {
+    column.capacity() * size_of::<Option<Component>>()
+        + column.iter().flatten().map(component_bytes).sum::<usize>()
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn columns_count_their_slots_and_allocations() {
+        let component: Component = Rc::new(RefCell::new(10_u64));
+        let mut column = Vec::with_capacity(4);
+
+        column.push(Some(component.clone()));
+        column.push(None);
+
+        assert_eq!(
+            column_bytes(&column),
+            4 * size_of::<Option<Component>>() + component_bytes(&component)
+        );
+        assert!(component_bytes(&component) >= 2 * size_of::<usize>() + size_of::<u64>());
+    }
+
+    #[test]
+    fn total_adds_everything_up() {
+        let report = MemoryReport {
+            entity_count: 1,
+            entity_bytes: 8,
+            components: vec![ColumnMemory {
+                name: "Health".to_owned(),
+                len: 1,
+                capacity: 1,
+                heap_bytes: 16,
+            }],
+            resources: vec![ResourceMemory {
+                name: "Score".to_owned(),
+                key: None,
+                heap_bytes: 4,
+            }],
+        };
+
+        assert_eq!(report.total_bytes(), 28);
+        assert_eq!(report.component("Health").map(|column| column.len), Some(1));
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/**
+Human-readable name of an entity, used to find it with `World::entity_by_name`. Names are
+indexed when the component is added, so rename an entity by adding a new `Name` rather than
+modifying the existing one in place.
+*/
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Name(String);
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Name(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Name {
+    fn from(name: &str) -> Self {
+        Name::new(name)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct EntityNames {
+    pub enabled: bool,
+    // May hold stale entries, lookups have to check that the entity still has the name.
+    pub index: HashMap<String, usize>,
+}
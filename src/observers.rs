@@ -0,0 +1,88 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::World;
+
+/**
+Passed to observers when an event is triggered, holds the event and the entity it targets.
+*/
+#[derive(Debug)]
+pub struct Trigger<'a, E> {
+    pub entity_id: usize,
+    pub event: &'a E,
+}
+
+pub type Observer<E> = Rc<dyn Fn(&Trigger<E>, &mut World)>;
+
+#[derive(Default)]
+pub struct Observers {
+    observers: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Observers {
+    pub fn add<E: Any>(&mut self, observer: Observer<E>) {
+        self.observers
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Vec::<Observer<E>>::new()))
+            .downcast_mut::<Vec<Observer<E>>>()
+            .unwrap()
+            .push(observer);
+    }
+
+    pub fn get<E: Any>(&self) -> Vec<Observer<E>> {
+        self.observers
+            .get(&TypeId::of::<E>())
+            .and_then(|observers| observers.downcast_ref::<Vec<Observer<E>>>())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers")
+            .field("event_types", &self.observers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct OnDamage(pub u32);
+
+    #[test]
+    fn observers_are_stored_per_event_type() {
+        let mut observers = Observers::default();
+
+        observers.add::<OnDamage>(Rc::new(|_trigger, _world| {}));
+        observers.add::<OnDamage>(Rc::new(|_trigger, _world| {}));
+
+        assert_eq!(observers.get::<OnDamage>().len(), 2);
+        assert!(observers.get::<u32>().is_empty());
+    }
+
+    #[test]
+    fn observers_receive_the_trigger() {
+        let mut observers = Observers::default();
+        let mut world = World::new();
+
+        observers.add::<OnDamage>(Rc::new(|trigger, world| {
+            world.replace_resource(trigger.event.0 + trigger.entity_id as u32);
+        }));
+
+        for observer in observers.get::<OnDamage>() {
+            observer(
+                &Trigger {
+                    entity_id: 2,
+                    event: &OnDamage(10),
+                },
+                &mut world,
+            );
+        }
+
+        assert_eq!(*world.get_resource::<u32>().unwrap(), 12);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/**
+Template for an entity and its children. Components are stored serialized, keyed by type name,
+so the prefab can be edited and saved like any other asset.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Prefab {
+    pub components: HashMap<String, Value>,
+    pub children: Vec<Prefab>,
+}
+
+impl Prefab {
+    pub fn new() -> Self {
+        Prefab::default()
+    }
+
+    pub fn with_component<T: Serialize>(mut self, component: &T) -> Self {
+        let value = serde_json::to_value(component).unwrap_or(Value::Null);
+        self.components
+            .insert(std::any::type_name::<T>().to_owned(), value);
+        self
+    }
+
+    pub fn with_child(mut self, child: Prefab) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    // Depth-first list of the prefab and every descendant, matching the order in which
+    // instances spawn their entities.
+    pub fn flatten(&self) -> Vec<&Prefab> {
+        let mut flattened = vec![self];
+
+        for child in &self.children {
+            flattened.extend(child.flatten());
+        }
+
+        flattened
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrefabId(pub usize);
+
+#[derive(Debug, Clone)]
+pub struct PrefabInstance {
+    pub prefab_id: PrefabId,
+    pub entity_ids: Vec<usize>,
+    pub overrides: HashMap<(usize, String), Value>,
+}
+
+#[derive(Default, Debug)]
+pub struct Prefabs {
+    pub prefabs: Vec<Prefab>,
+    pub instances: Vec<PrefabInstance>,
+}
+
+impl Prefabs {
+    pub fn instance_of(&self, entity_id: usize) -> Option<&PrefabInstance> {
+        self.instances
+            .iter()
+            .find(|instance| instance.entity_ids.contains(&entity_id))
+    }
+
+    pub fn instance_of_mut(&mut self, entity_id: usize) -> Option<&mut PrefabInstance> {
+        self.instances
+            .iter_mut()
+            .find(|instance| instance.entity_ids.contains(&entity_id))
+    }
+}
+
+/**
+Applies `patch` on top of `base`, merging objects field by field and replacing anything else.
+*/
+pub fn merge(base: &Value, patch: &Value) -> Value {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            let mut merged = base.clone();
+
+            for (key, patch_value) in patch {
+                let merged_value = match base.get(key) {
+                    Some(base_value) => merge(base_value, patch_value),
+                    None => patch_value.clone(),
+                };
+                merged.insert(key.clone(), merged_value);
+            }
+
+            Value::Object(merged)
+        }
+        (_, patch) => patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_only_overrides_patched_fields() {
+        let base = json!({ "speed": 1.0, "color": { "r": 1, "g": 2 } });
+        let patch = json!({ "color": { "g": 5 } });
+
+        assert_eq!(
+            merge(&base, &patch),
+            json!({ "speed": 1.0, "color": { "r": 1, "g": 5 } })
+        );
+    }
+
+    #[test]
+    fn merge_replaces_non_object_values() {
+        assert_eq!(merge(&json!([1, 2]), &json!([3])), json!([3]));
+        assert_eq!(merge(&json!({ "a": 1 }), &json!(5)), json!(5));
+    }
+
+    #[test]
+    fn flatten_is_depth_first() {
+        let prefab = Prefab::new()
+            .with_component(&1_u32)
+            .with_child(
+                Prefab::new()
+                    .with_component(&2_u32)
+                    .with_child(Prefab::new().with_component(&3_u32)),
+            )
+            .with_child(Prefab::new().with_component(&4_u32));
+
+        let values: Vec<&Value> = prefab
+            .flatten()
+            .iter()
+            .map(|prefab| &prefab.components["u32"])
+            .collect();
+
+        assert_eq!(values, vec![&json!(1), &json!(2), &json!(3), &json!(4)]);
+    }
+}
@@ -0,0 +1,8 @@
+/**
+Value that component `T` had when `World::update_previous_values` was last called, usually
+the end of the previous tick. Enabled per component type with `World::track_previous_values`,
+after which every entity with a `T` also gets a `Prev<T>` that can be queried like any other
+component, for example to interpolate rendering or derive velocities from positions.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Prev<T>(pub T);
@@ -0,0 +1,319 @@
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::IntoPyObjectExt;
+
+use crate::dynamic_component::{
+    DynamicComponent, DynamicComponentDescriptor, DynamicValue, FieldType,
+};
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+/**
+Python wrapper of a world, exposed as `sara_ecs.World`. Components are dynamic components,
+registered with a name and a dict of field types (`"bool"`, `"int"`, `"float"` or `"str"`),
+and passed around as dicts of field values. Requires the `python` feature; build the module
+with maturin.
+
+```python
+from sara_ecs import World
+
+world = World()
+world.register_component("Position", {"x": "float", "y": "float"})
+world.register_component("Velocity", {"x": "float", "y": "float"})
+
+ball = world.spawn({"Position": {"x": 0.0, "y": 0.0}, "Velocity": {"x": 1.0, "y": 0.5}})
+
+for entity in world.query(["Position", "Velocity"]):
+    position, velocity = world.get(entity, "Position"), world.get(entity, "Velocity")
+    world.set(entity, "Position", "x", position["x"] + velocity["x"])
+```
+*/
+#[pyclass(name = "World", unsendable)]
+#[derive(Debug, Default)]
+pub struct PyWorld {
+    world: World,
+}
+
+impl PyWorld {
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    fn instantiate(
+        &self,
+        component: &str,
+        fields: &Bound<'_, PyDict>,
+    ) -> PyResult<DynamicComponent> {
+        let descriptor = self
+            .world
+            .dynamic_component_descriptor(component)
+            .ok_or_else(|| PyKeyError::new_err(format!("unknown component {component}")))?;
+        let mut instance = descriptor.instantiate();
+
+        for (field, value) in fields.iter() {
+            let field: String = field.extract()?;
+            let field_type = field_type_of(descriptor, &field)?;
+
+            instance.set(&field, to_dynamic(&value, field_type)?);
+        }
+
+        Ok(instance)
+    }
+}
+
+#[pymethods]
+impl PyWorld {
+    #[new]
+    fn new() -> Self {
+        PyWorld::default()
+    }
+
+    /**
+    Registers a component named `name` whose fields map to `"bool"`, `"int"`, `"float"` or
+    `"str"`.
+    */
+    fn register_component(&mut self, name: &str, fields: &Bound<'_, PyDict>) -> PyResult<()> {
+        let mut descriptor = DynamicComponentDescriptor::new(name);
+
+        for (field, field_type) in fields.iter() {
+            let field_type = match field_type.extract::<String>()?.as_str() {
+                "bool" => FieldType::Bool,
+                "int" => FieldType::Int,
+                "float" => FieldType::Float,
+                "str" => FieldType::String,
+                other => return Err(PyValueError::new_err(format!("unknown field type {other}"))),
+            };
+
+            descriptor = descriptor.with_field(field.extract::<String>()?, field_type);
+        }
+
+        self.world
+            .register_dynamic_component(descriptor)
+            .map_err(to_python_error)
+    }
+
+    /**
+    Spawns an entity with the components of `components`, a dict from component names to
+    dicts of field values. Fields left out get the default value of their type. Returns the
+    ID of the entity, which only stays alive while it has components.
+    */
+    #[pyo3(signature = (components = None))]
+    fn spawn(&mut self, components: Option<&Bound<'_, PyDict>>) -> PyResult<usize> {
+        let entity_id = self.world.entities().reserve();
+
+        for (component, fields) in components
+            .into_iter()
+            .flat_map(|components| components.iter())
+        {
+            self.insert(entity_id, &component.extract::<String>()?, fields.cast()?)?;
+        }
+
+        Ok(entity_id)
+    }
+
+    fn despawn(&mut self, entity_id: usize) -> PyResult<()> {
+        self.world.remove_entity(entity_id).map_err(to_python_error)
+    }
+
+    fn is_alive(&self, entity_id: usize) -> bool {
+        self.world.entities().is_alive(entity_id)
+    }
+
+    fn insert(
+        &mut self,
+        entity_id: usize,
+        component: &str,
+        fields: &Bound<'_, PyDict>,
+    ) -> PyResult<()> {
+        let instance = self.instantiate(component, fields)?;
+
+        self.world
+            .add_dynamic_component(entity_id, instance)
+            .map_err(to_python_error)
+    }
+
+    fn remove(&mut self, entity_id: usize, component: &str) -> PyResult<()> {
+        self.world
+            .remove_dynamic_component(entity_id, component)
+            .map_err(to_python_error)
+    }
+
+    /**
+    Copies the fields of a component into a new dict.
+    */
+    fn get<'py>(
+        &self,
+        py: Python<'py>,
+        entity_id: usize,
+        component: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let instance = self
+            .world
+            .get_dynamic_component(entity_id, component)
+            .map_err(to_python_error)?;
+        let fields = PyDict::new(py);
+
+        for (field, value) in &instance.fields {
+            fields.set_item(field, to_python(py, value)?)?;
+        }
+
+        Ok(fields)
+    }
+
+    fn set(
+        &mut self,
+        entity_id: usize,
+        component: &str,
+        field: &str,
+        value: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let mut instance = self
+            .world
+            .get_dynamic_component_mut(entity_id, component)
+            .map_err(to_python_error)?;
+        let field_type = instance
+            .get(field)
+            .map(DynamicValue::field_type)
+            .ok_or_else(|| PyKeyError::new_err(format!("unknown field {field}")))?;
+
+        instance.set(field, to_dynamic(value, field_type)?);
+        Ok(())
+    }
+
+    /**
+    IDs of the enabled entities having every component of `components`, sorted.
+    */
+    fn query(&self, components: Vec<String>) -> PyResult<Vec<usize>> {
+        let mut query = self.world.query();
+
+        for component in &components {
+            query
+                .with_dynamic_component_filter(component)
+                .map_err(to_python_error)?;
+        }
+
+        Ok(query.run().entity_ids)
+    }
+
+    fn run_system(&mut self, name: &str) -> PyResult<()> {
+        self.world.run_named_system(name).map_err(to_python_error)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("World(entities={})", self.world.entities().len())
+    }
+}
+
+/**
+Entry point of the `sara_ecs` Python module.
+*/
+#[pymodule]
+fn sara_ecs(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyWorld>()
+}
+
+fn field_type_of(descriptor: &DynamicComponentDescriptor, field: &str) -> PyResult<FieldType> {
+    descriptor
+        .fields
+        .iter()
+        .find(|(name, _field_type)| name == field)
+        .map(|(_name, field_type)| *field_type)
+        .ok_or_else(|| PyKeyError::new_err(format!("unknown field {field}")))
+}
+
+fn to_dynamic(value: &Bound<'_, PyAny>, field_type: FieldType) -> PyResult<DynamicValue> {
+    Ok(match field_type {
+        FieldType::Bool => DynamicValue::Bool(value.extract()?),
+        FieldType::Int => DynamicValue::Int(value.extract()?),
+        FieldType::Float => DynamicValue::Float(value.extract()?),
+        FieldType::String => DynamicValue::String(value.extract()?),
+    })
+}
+
+fn to_python<'py>(py: Python<'py>, value: &DynamicValue) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        DynamicValue::Bool(value) => value.into_bound_py_any(py),
+        DynamicValue::Int(value) => value.into_bound_py_any(py),
+        DynamicValue::Float(value) => value.into_bound_py_any(py),
+        DynamicValue::String(value) => value.into_bound_py_any(py),
+    }
+}
+
+fn to_python_error(error: ECSError) -> PyErr {
+    match error {
+        ECSError::ComponentNotRegistered { .. } | ECSError::SystemNotFound => {
+            PyKeyError::new_err(error.to_string())
+        }
+        ECSError::DynamicComponentMismatch => PyTypeError::new_err(error.to_string()),
+        _ => PyValueError::new_err(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn components_roundtrip_through_python_values() {
+        Python::initialize();
+        Python::attach(|py| {
+            let mut world = PyWorld::new();
+            let fields = PyDict::new(py);
+            let health = PyDict::new(py);
+            let components = PyDict::new(py);
+
+            fields.set_item("current", "int").unwrap();
+            fields.set_item("owner", "str").unwrap();
+            world.register_component("Health", &fields).unwrap();
+
+            health.set_item("current", 90).unwrap();
+            components.set_item("Health", health).unwrap();
+
+            let player = world.spawn(Some(&components)).unwrap();
+            world
+                .set(
+                    player,
+                    "Health",
+                    "owner",
+                    &"Sara".into_bound_py_any(py).unwrap(),
+                )
+                .unwrap();
+
+            let read = world.get(py, player, "Health").unwrap();
+
+            assert_eq!(
+                read.get_item("current")
+                    .unwrap()
+                    .unwrap()
+                    .extract::<i64>()
+                    .unwrap(),
+                90
+            );
+            assert_eq!(
+                read.get_item("owner")
+                    .unwrap()
+                    .unwrap()
+                    .extract::<String>()
+                    .unwrap(),
+                "Sara"
+            );
+            assert_eq!(
+                world.query(vec!["Health".to_owned()]).unwrap(),
+                vec![player]
+            );
+            assert!(world
+                .set(
+                    player,
+                    "Health",
+                    "current",
+                    &"ninety".into_bound_py_any(py).unwrap()
+                )
+                .is_err());
+        });
+    }
+}
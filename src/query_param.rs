@@ -0,0 +1,243 @@
+use std::any::Any;
+use std::cell::{Ref, RefCell, RefMut};
+use std::rc::Rc;
+
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+/**
+Parameter of a `query!` closure: `&T` borrows the component immutably and `&mut T` mutably.
+Only meant to be used by the macro.
+*/
+pub trait QueryParam {
+    type Component: Any;
+    type Guard<'r>;
+    type Item<'g>;
+
+    fn fetch(component: &Rc<RefCell<dyn Any>>) -> Self::Guard<'_>;
+
+    fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g>;
+}
+
+impl<T: Any> QueryParam for &T {
+    type Component = T;
+    type Guard<'r> = Ref<'r, T>;
+    type Item<'g> = &'g T;
+
+    fn fetch(component: &Rc<RefCell<dyn Any>>) -> Self::Guard<'_> {
+        Ref::map(component.borrow(), |component| {
+            component.downcast_ref::<T>().unwrap()
+        })
+    }
+
+    fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g> {
+        guard
+    }
+}
+
+impl<T: Any> QueryParam for &mut T {
+    type Component = T;
+    type Guard<'r> = RefMut<'r, T>;
+    type Item<'g> = &'g mut T;
+
+    fn fetch(component: &Rc<RefCell<dyn Any>>) -> Self::Guard<'_> {
+        RefMut::map(component.borrow_mut(), |component| {
+            component.downcast_mut::<T>().unwrap()
+        })
+    }
+
+    fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g> {
+        guard
+    }
+}
+
+/**
+Tuple of component types whose values can be visited with `World::each`, by a closure taking
+the entity ID and a shared reference to each component. Implemented for tuples of up to 8
+types.
+*/
+pub trait Each<F> {
+    fn each(world: &World, f: F) -> Result<(), ECSError>;
+}
+
+/**
+Like `Each`, with mutable references, for `World::each_mut`.
+*/
+pub trait EachMut<F> {
+    fn each_mut(world: &World, f: F) -> Result<(), ECSError>;
+}
+
+macro_rules! impl_each {
+    ($($component:ident),+) => {
+        impl<$($component: Any,)+ Func> Each<Func> for ($($component,)+)
+        where
+            Func: FnMut(usize, $(&$component),+),
+        {
+            #[allow(non_snake_case)]
+            fn each(world: &World, mut f: Func) -> Result<(), ECSError> {
+                let mut query = world.query();
+                $(query.with_component_filter::<$component>()?;)+
+                let result = query.try_run()?;
+
+                for (row, entity_id) in result.entity_ids.iter().copied().enumerate() {
+                    let mut columns = result.components.iter();
+                    $(
+                        let mut $component = <&$component as QueryParam>::fetch(
+                            &columns.next().unwrap()[row],
+                        );
+                    )+
+
+                    f(
+                        entity_id,
+                        $(<&$component as QueryParam>::item(&mut $component)),+
+                    );
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+macro_rules! impl_each_mut {
+    ($($component:ident),+) => {
+        impl<$($component: Any,)+ Func> EachMut<Func> for ($($component,)+)
+        where
+            Func: FnMut(usize, $(&mut $component),+),
+        {
+            #[allow(non_snake_case)]
+            fn each_mut(world: &World, mut f: Func) -> Result<(), ECSError> {
+                let mut query = world.query();
+                $(query.with_component_filter::<$component>()?;)+
+                let result = query.try_run()?;
+
+                for (row, entity_id) in result.entity_ids.iter().copied().enumerate() {
+                    let mut columns = result.components.iter();
+                    $(
+                        let mut $component = <&mut $component as QueryParam>::fetch(
+                            &columns.next().unwrap()[row],
+                        );
+                    )+
+
+                    f(
+                        entity_id,
+                        $(<&mut $component as QueryParam>::item(&mut $component)),+
+                    );
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+macro_rules! impl_each_for_tuples {
+    ($($component:ident),+) => {
+        impl_each!($($component),+);
+        impl_each_mut!($($component),+);
+    };
+}
+
+impl_each_for_tuples!(A);
+impl_each_for_tuples!(A, B);
+impl_each_for_tuples!(A, B, C);
+impl_each_for_tuples!(A, B, C, D);
+impl_each_for_tuples!(A, B, C, D, E);
+impl_each_for_tuples!(A, B, C, D, E, F);
+impl_each_for_tuples!(A, B, C, D, E, F, G);
+impl_each_for_tuples!(A, B, C, D, E, F, G, H);
+
+/**
+Runs a block for every entity that has all the components listed as closure parameters,
+borrowing them as `&T` or `&mut T`. An optional last parameter without a type receives the
+entity ID. Evaluates to a `Result<(), ECSError>`, which fails with `ComponentNotRegistered`
+if a component type isn't registered.
+
+Components are borrowed from the query result rather than the world, so the block can still
+use the world, even mutably.
+
+Example:
+```
+use sara_ecs::{query, World};
+use sara_ecs::ecs_errors::ECSError;
+
+struct Position(pub f32);
+struct Velocity(pub f32);
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Velocity>();
+    world
+        .create_entity()
+        .with_component(Position(0.0))?
+        .with_component(Velocity(2.0))?;
+
+    query!(world, |position: &mut Position, velocity: &Velocity| {
+        position.0 += velocity.0;
+    })?;
+
+    let mut moved = vec![];
+    query!(world, |position: &Position, entity| {
+        moved.push((entity, position.0));
+    })?;
+
+    assert_eq!(moved, vec![(0, 2.0)]);
+    Ok(())
+}
+# example().unwrap();
+```
+*/
+#[macro_export]
+macro_rules! query {
+    ($world:expr, |$($params:tt)*) => {
+        $crate::query!(@parse $world, [], $($params)*)
+    };
+    (@parse $world:expr, [$($typed:tt)*], $name:ident : $param:ty, $($rest:tt)*) => {
+        $crate::query!(@parse $world, [$($typed)* ($name, $param)], $($rest)*)
+    };
+    (@parse $world:expr, [$($typed:tt)*], $name:ident : $param:ty | $body:block) => {
+        $crate::query!(@expand $world, $body, [$($typed)* ($name, $param)], [])
+    };
+    (@parse $world:expr, [$($typed:tt)*], $entity:ident $(,)? | $body:block) => {
+        $crate::query!(@expand $world, $body, [$($typed)*], [$entity])
+    };
+    (@parse $world:expr, [$($typed:tt)*], | $body:block) => {
+        $crate::query!(@expand $world, $body, [$($typed)*], [])
+    };
+    (@expand $world:expr, $body:block, [$(($name:ident, $param:ty))+], [$($entity:ident)?]) => {
+        {
+            let result = (|| {
+                let mut query = $world.query();
+                $(
+                    query.with_component_filter::<
+                        <$param as $crate::query_param::QueryParam>::Component
+                    >()?;
+                )+
+                query.try_run()
+            })();
+
+            match result {
+                Ok(result) => {
+                    for (row, entity_id) in result.entity_ids.iter().copied().enumerate() {
+                        let mut columns = result.components.iter();
+                        $(
+                            let mut $name = <$param as $crate::query_param::QueryParam>::fetch(
+                                &columns.next().unwrap()[row],
+                            );
+                        )+
+                        $(
+                            let $name = <$param as $crate::query_param::QueryParam>::item(&mut $name);
+                        )+
+                        $(let $entity = entity_id;)?
+                        let _ = entity_id;
+                        $body
+                    }
+                    Ok(())
+                }
+                Err(error) => Err(error),
+            }
+        }
+    };
+}
@@ -0,0 +1,248 @@
+use std::any::Any;
+
+/**
+Gives access to the fields of a value by name, so tools like inspectors and animation systems
+can read and write components they don't know at compile time. Implement it for your own
+structs with `impl_reflect!`.
+
+Fields are addressed by their names, or by their index for tuple structs. Nested fields are
+reached with a dotted path like `"position.x"`.
+*/
+pub trait Reflect: Any {
+    fn field(&self, name: &str) -> Option<&dyn Reflect>;
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect>;
+    fn field_names(&self) -> Vec<&'static str>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl dyn Reflect {
+    pub fn path(&self, path: &str) -> Option<&dyn Reflect> {
+        path.split('.')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |value, name| value.field(name))
+    }
+
+    pub fn path_mut(&mut self, path: &str) -> Option<&mut dyn Reflect> {
+        path.split('.')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |value, name| value.field_mut(name))
+    }
+
+    pub fn get<T: Any>(&self, path: &str) -> Option<&T> {
+        self.path(path)?.as_any().downcast_ref::<T>()
+    }
+
+    pub fn get_mut<T: Any>(&mut self, path: &str) -> Option<&mut T> {
+        self.path_mut(path)?.as_any_mut().downcast_mut::<T>()
+    }
+
+    /**
+    Writes `value` into the field at `path`. Returns `false` if the field does not exist
+    or holds a different type.
+    */
+    pub fn set<T: Any>(&mut self, path: &str, value: T) -> bool {
+        match self.get_mut::<T>(path) {
+            Some(field) => {
+                *field = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/**
+Implements `Reflect` for a struct. List the named fields between braces, or the field indices
+of a tuple struct between parentheses. Every listed field must implement `Reflect` too.
+
+Example:
+```
+use sara_ecs::impl_reflect;
+use sara_ecs::reflect::Reflect;
+
+struct Vec2 {
+    x: f32,
+    y: f32,
+}
+
+struct Transform {
+    position: Vec2,
+    rotation: f32,
+}
+
+struct Health(pub u32);
+
+impl_reflect!(Vec2 { x, y });
+impl_reflect!(Transform { position, rotation });
+impl_reflect!(Health(0));
+
+let mut transform = Transform {
+    position: Vec2 { x: 1.0, y: 2.0 },
+    rotation: 0.0,
+};
+let reflect: &mut dyn Reflect = &mut transform;
+
+reflect.set("position.x", 5.0_f32);
+
+assert_eq!(reflect.get::<f32>("position.x"), Some(&5.0));
+assert_eq!(reflect.field_names(), vec!["position", "rotation"]);
+```
+*/
+#[macro_export]
+macro_rules! impl_reflect {
+    ($type:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::reflect::Reflect for $type {
+            fn field(&self, name: &str) -> Option<&dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn field_mut(&mut self, name: &str) -> Option<&mut dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn field_names(&self) -> Vec<&'static str> {
+                vec![$(stringify!($field)),*]
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+    };
+    ($type:ident ( $($index:tt),* $(,)? )) => {
+        impl $crate::reflect::Reflect for $type {
+            fn field(&self, name: &str) -> Option<&dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($index) => Some(&self.$index),)*
+                    _ => None,
+                }
+            }
+
+            fn field_mut(&mut self, name: &str) -> Option<&mut dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($index) => Some(&mut self.$index),)*
+                    _ => None,
+                }
+            }
+
+            fn field_names(&self) -> Vec<&'static str> {
+                vec![$(stringify!($index)),*]
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+    };
+}
+
+macro_rules! impl_reflect_leaf {
+    ($($type:ty),*) => {
+        $(
+            impl Reflect for $type {
+                fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+                    None
+                }
+
+                fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+                    None
+                }
+
+                fn field_names(&self) -> Vec<&'static str> {
+                    vec![]
+                }
+
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn Any {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_reflect_leaf!(
+    bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, char, String
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Color(pub u8, pub u8, pub u8);
+
+    struct Sprite {
+        color: Color,
+        visible: bool,
+    }
+
+    impl_reflect!(Color(0, 1, 2));
+    impl_reflect!(Sprite { color, visible });
+
+    fn sprite() -> Sprite {
+        Sprite {
+            color: Color(10, 20, 30),
+            visible: true,
+        }
+    }
+
+    #[test]
+    fn read_nested_fields() {
+        let sprite = sprite();
+        let reflect: &dyn Reflect = &sprite;
+
+        assert_eq!(reflect.get::<u8>("color.1"), Some(&20));
+        assert_eq!(reflect.get::<bool>("visible"), Some(&true));
+    }
+
+    #[test]
+    fn write_nested_fields() {
+        let mut sprite = sprite();
+        let reflect: &mut dyn Reflect = &mut sprite;
+
+        assert!(reflect.set("color.2", 99_u8));
+        assert!(reflect.set("visible", false));
+
+        assert_eq!(sprite.color.2, 99);
+        assert!(!sprite.visible);
+    }
+
+    #[test]
+    fn wrong_paths_and_types_are_rejected() {
+        let mut sprite = sprite();
+        let reflect: &mut dyn Reflect = &mut sprite;
+
+        assert!(reflect.path("color.3").is_none());
+        assert!(reflect.get::<u32>("color.0").is_none());
+        assert!(!reflect.set("visible", 1_u32));
+    }
+
+    #[test]
+    fn empty_path_is_the_value_itself() {
+        let sprite = sprite();
+        let reflect: &dyn Reflect = &sprite;
+
+        assert_eq!(
+            reflect.path("").unwrap().field_names(),
+            vec!["color", "visible"]
+        );
+    }
+}
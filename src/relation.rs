@@ -0,0 +1,205 @@
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+use crate::ecs_errors::ECSError;
+use crate::entity_storage::EntityStorage;
+use crate::World;
+
+/**
+What happens to the entities pointing at another one through a relation when that entity is
+removed.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationCleanup {
+    /// Nothing, the relation is left pointing at the removed entity.
+    Keep,
+    /// The relation component is removed from the entities pointing at the removed one.
+    RemoveRelation,
+    /// The entities pointing at the removed one are removed as well.
+    DespawnSource,
+    /// A `RelationTargetDespawned<T>` event is sent for each entity pointing at the removed
+    /// one, if the event type was registered with `World::add_event`.
+    SendEvent,
+}
+
+/**
+Sent for relations declaring `RelationCleanup::SendEvent` when their target is removed.
+*/
+#[derive(Debug)]
+pub struct RelationTargetDespawned<T> {
+    pub source: usize,
+    pub target: usize,
+    relation: PhantomData<fn() -> T>,
+}
+
+impl<T> RelationTargetDespawned<T> {
+    pub fn new(source: usize, target: usize) -> Self {
+        Self {
+            source,
+            target,
+            relation: PhantomData,
+        }
+    }
+}
+
+/**
+Implemented by components that point at another entity, like `Targets(usize)` or
+`OwnedBy(usize)`. Once registered with `World::register_relation`, the world keeps a reverse
+index so `World::relations_to` can find every entity pointing at a given one. Change the
+target by adding the component again rather than modifying it in place, otherwise the index
+won't see the new target.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::relation::Relation;
+
+struct Targets(pub usize);
+
+impl Relation for Targets {
+    fn target(&self) -> usize {
+        self.0
+    }
+}
+
+let mut world = World::new();
+
+world.register_component::<Targets>();
+world.register_relation::<Targets>();
+```
+*/
+pub trait Relation: Any {
+    /**
+    What happens to the entities holding this relation when their target is removed.
+    */
+    const ON_TARGET_DESPAWN: RelationCleanup = RelationCleanup::Keep;
+
+    fn target(&self) -> usize;
+}
+
+type TargetFn = fn(&EntityStorage, usize) -> Option<usize>;
+pub(crate) type CleanupFn = fn(&mut World, usize, usize) -> Result<(), ECSError>;
+
+#[derive(Default, Debug)]
+pub struct Relations {
+    targets: HashMap<TypeId, TargetFn>,
+    cleanups: HashMap<TypeId, CleanupFn>,
+    // Maps each relation type and target to the entities pointing at it. May hold stale
+    // entries when components are modified in place, so lookups check the current target.
+    index: HashMap<TypeId, HashMap<usize, HashSet<usize>>>,
+}
+
+impl Relations {
+    pub fn register<T: Relation>(&mut self) {
+        self.targets
+            .insert(TypeId::of::<T>(), |entity_storage, entity_id| {
+                entity_storage
+                    .get_component::<T>(entity_id)
+                    .ok()
+                    .map(|relation| relation.target())
+            });
+        self.index.entry(TypeId::of::<T>()).or_default();
+
+        let cleanup: Option<CleanupFn> = match T::ON_TARGET_DESPAWN {
+            RelationCleanup::Keep => None,
+            RelationCleanup::RemoveRelation => {
+                Some(|world, source, _target| world.remove_entity_component::<T>(source))
+            }
+            RelationCleanup::DespawnSource => {
+                Some(|world, source, _target| world.remove_entity(source))
+            }
+            RelationCleanup::SendEvent => Some(|world, source, target| {
+                world.send_event_if_registered(RelationTargetDespawned::<T>::new(source, target));
+                Ok(())
+            }),
+        };
+
+        if let Some(cleanup) = cleanup {
+            self.cleanups.insert(TypeId::of::<T>(), cleanup);
+        }
+    }
+
+    pub(crate) fn cleanups(&self) -> Vec<(TypeId, CleanupFn)> {
+        self.cleanups
+            .iter()
+            .map(|(type_id, cleanup)| (*type_id, *cleanup))
+            .collect()
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn type_ids(&self) -> impl Iterator<Item = &TypeId> {
+        self.targets.keys()
+    }
+
+    pub fn is_registered(&self, type_id: &TypeId) -> bool {
+        self.targets.contains_key(type_id)
+    }
+
+    pub(crate) fn target(
+        &self,
+        type_id: &TypeId,
+        entity_storage: &EntityStorage,
+        entity_id: usize,
+    ) -> Option<usize> {
+        self.targets.get(type_id)?(entity_storage, entity_id)
+    }
+
+    pub(crate) fn insert(&mut self, type_id: TypeId, target: usize, source: usize) {
+        self.index
+            .entry(type_id)
+            .or_default()
+            .entry(target)
+            .or_default()
+            .insert(source);
+    }
+
+    pub(crate) fn remove(&mut self, type_id: &TypeId, target: usize, source: usize) {
+        if let Some(sources) = self
+            .index
+            .get_mut(type_id)
+            .and_then(|targets| targets.get_mut(&target))
+        {
+            sources.remove(&source);
+        }
+    }
+
+    /**
+    Entities pointing at `target` through a relation of type `type_id`, sorted by ID.
+    */
+    pub(crate) fn sources(
+        &self,
+        type_id: &TypeId,
+        entity_storage: &EntityStorage,
+        target: usize,
+    ) -> Vec<usize> {
+        let Some(sources) = self
+            .index
+            .get(type_id)
+            .and_then(|targets| targets.get(&target))
+        else {
+            return vec![];
+        };
+
+        let mut sources: Vec<usize> = sources
+            .iter()
+            .copied()
+            .filter(|source| self.target(type_id, entity_storage, *source) == Some(target))
+            .collect();
+        sources.sort_unstable();
+        sources
+    }
+
+    pub(crate) fn rebuild(&mut self, entity_storage: &EntityStorage) {
+        for (type_id, target_fn) in &self.targets {
+            let index = self.index.entry(*type_id).or_default();
+            index.clear();
+
+            for source in 0..entity_storage.entity_count() {
+                if let Some(target) = target_fn(entity_storage, source) {
+                    index.entry(target).or_default().insert(source);
+                }
+            }
+        }
+    }
+}
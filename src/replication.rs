@@ -0,0 +1,204 @@
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::serialization::EntityMap;
+
+/**
+Everything that changed in the replicated part of a world since the previous change set:
+entities that appeared or disappeared, and components that were added, modified or removed.
+Collected on the server with `World::collect_changes` and applied on clients with
+`World::apply_changes`. Entity IDs are the server's.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ChangeSet {
+    pub spawned: Vec<usize>,
+    pub despawned: Vec<usize>,
+    pub changed: Vec<ComponentChange>,
+    pub removed: Vec<ComponentRemoval>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty()
+            && self.despawned.is_empty()
+            && self.changed.is_empty()
+            && self.removed.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComponentChange {
+    pub entity_id: usize,
+    pub component: String,
+    pub value: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComponentRemoval {
+    pub entity_id: usize,
+    pub component: String,
+}
+
+pub type ReplicatedState = HashMap<usize, HashMap<String, Value>>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PeerId(pub u32);
+
+/**
+Component marking the peer that has authority over an entity, usually the player controlling
+it. Peers receive the entities they own in `World::collect_changes_for`.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Owner(pub PeerId);
+
+/**
+Component listing the peers that should receive an entity they don't own, for example the
+players close enough to see it.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterestedPeers(pub Vec<PeerId>);
+
+/**
+Remembers the replicated state that was last sent, and detects changes by comparing it with
+the current one.
+*/
+#[derive(Default, Debug)]
+pub struct ReplicationTracker {
+    sent: ReplicatedState,
+}
+
+impl ReplicationTracker {
+    pub fn diff(&mut self, current: ReplicatedState) -> ChangeSet {
+        let mut changes = ChangeSet::default();
+        let no_components = HashMap::new();
+
+        for (entity_id, components) in &current {
+            let sent_components = match self.sent.get(entity_id) {
+                Some(sent_components) => sent_components,
+                None => {
+                    changes.spawned.push(*entity_id);
+                    &no_components
+                }
+            };
+
+            for (component, value) in components {
+                if sent_components.get(component) != Some(value) {
+                    changes.changed.push(ComponentChange {
+                        entity_id: *entity_id,
+                        component: component.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+
+            for component in sent_components.keys() {
+                if !components.contains_key(component) {
+                    changes.removed.push(ComponentRemoval {
+                        entity_id: *entity_id,
+                        component: component.clone(),
+                    });
+                }
+            }
+        }
+
+        for entity_id in self.sent.keys() {
+            if !current.contains_key(entity_id) {
+                changes.despawned.push(*entity_id);
+            }
+        }
+
+        changes.spawned.sort_unstable();
+        changes.despawned.sort_unstable();
+        changes
+            .changed
+            .sort_by(|a, b| (a.entity_id, &a.component).cmp(&(b.entity_id, &b.component)));
+        changes
+            .removed
+            .sort_by(|a, b| (a.entity_id, &a.component).cmp(&(b.entity_id, &b.component)));
+
+        self.sent = current;
+        changes
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Replication {
+    pub replicated: HashSet<TypeId>,
+    pub tracker: ReplicationTracker,
+    pub peer_trackers: HashMap<PeerId, ReplicationTracker>,
+    // Maps the server's entity IDs to the local ones, on the receiving side.
+    pub remote_entities: EntityMap,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn state(entities: &[(usize, &[(&str, Value)])]) -> ReplicatedState {
+        entities
+            .iter()
+            .map(|(entity_id, components)| {
+                let components = components
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect();
+                (*entity_id, components)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_diff_spawns_everything() {
+        let mut tracker = ReplicationTracker::default();
+
+        let changes = tracker.diff(state(&[(0, &[("Health", json!(10))]), (2, &[])]));
+
+        assert_eq!(changes.spawned, vec![0, 2]);
+        assert_eq!(changes.changed.len(), 1);
+        assert!(changes.despawned.is_empty() && changes.removed.is_empty());
+    }
+
+    #[test]
+    fn unchanged_state_produces_no_changes() {
+        let mut tracker = ReplicationTracker::default();
+
+        tracker.diff(state(&[(0, &[("Health", json!(10))])]));
+
+        assert!(tracker
+            .diff(state(&[(0, &[("Health", json!(10))])]))
+            .is_empty());
+    }
+
+    #[test]
+    fn diff_detects_changes_removals_and_despawns() {
+        let mut tracker = ReplicationTracker::default();
+
+        tracker.diff(state(&[
+            (0, &[("Health", json!(10)), ("Speed", json!(1))]),
+            (1, &[("Health", json!(5))]),
+        ]));
+        let changes = tracker.diff(state(&[(0, &[("Health", json!(8))])]));
+
+        assert_eq!(
+            changes.changed,
+            vec![ComponentChange {
+                entity_id: 0,
+                component: "Health".to_owned(),
+                value: json!(8)
+            }]
+        );
+        assert_eq!(
+            changes.removed,
+            vec![ComponentRemoval {
+                entity_id: 0,
+                component: "Speed".to_owned()
+            }]
+        );
+        assert_eq!(changes.despawned, vec![1]);
+        assert!(changes.spawned.is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use crate::component_defaults::ComponentConstructor;
+
+#[derive(Clone)]
+pub struct RequiredComponent {
+    pub type_id: TypeId,
+    pub constructor: ComponentConstructor,
+}
+
+/**
+Components that have to be inserted along with others, registered with
+`World::register_required_component`.
+*/
+#[derive(Default)]
+pub struct RequiredComponents {
+    required: HashMap<TypeId, Vec<RequiredComponent>>,
+}
+
+impl std::fmt::Debug for RequiredComponents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequiredComponents")
+            .field("required", &self.required.len())
+            .finish()
+    }
+}
+
+impl RequiredComponents {
+    /**
+    Declares that `type_id` requires `required`. Declaring the same requirement again replaces
+    its constructor.
+    */
+    pub fn add(&mut self, type_id: TypeId, required: RequiredComponent) {
+        let requirements = self.required.entry(type_id).or_default();
+
+        match requirements
+            .iter_mut()
+            .find(|requirement| requirement.type_id == required.type_id)
+        {
+            Some(requirement) => *requirement = required,
+            None => requirements.push(required),
+        }
+    }
+
+    pub fn of(&self, type_id: &TypeId) -> Vec<RequiredComponent> {
+        self.required.get(type_id).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn requirements_are_replaced_not_duplicated() {
+        let mut required = RequiredComponents::default();
+        let requirement = |value: f32| RequiredComponent {
+            type_id: TypeId::of::<f32>(),
+            constructor: Rc::new(move || Box::new(value)),
+        };
+
+        required.add(TypeId::of::<u32>(), requirement(1.0));
+        required.add(TypeId::of::<u32>(), requirement(2.0));
+
+        let requirements = required.of(&TypeId::of::<u32>());
+        assert_eq!(requirements.len(), 1);
+        assert_eq!(
+            (requirements[0].constructor)().downcast_ref::<f32>(),
+            Some(&2.0)
+        );
+        assert!(required.of(&TypeId::of::<f32>()).is_empty());
+    }
+}
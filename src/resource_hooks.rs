@@ -0,0 +1,46 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use crate::World;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceChange {
+    Added,
+    Replaced,
+    Removed,
+}
+
+pub type ResourceHook = fn(&mut World, ResourceChange);
+
+#[derive(Default, Debug)]
+pub struct ResourceHooks {
+    hooks: HashMap<TypeId, Vec<ResourceHook>>,
+}
+
+impl ResourceHooks {
+    pub fn add(&mut self, type_id: TypeId, hook: ResourceHook) {
+        self.hooks.entry(type_id).or_default().push(hook);
+    }
+
+    pub fn get(&self, type_id: &TypeId) -> Vec<ResourceHook> {
+        self.hooks.get(type_id).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hook(_world: &mut World, _change: ResourceChange) {}
+
+    #[test]
+    fn hooks_are_stored_per_type() {
+        let mut hooks = ResourceHooks::default();
+
+        hooks.add(TypeId::of::<u32>(), hook);
+        hooks.add(TypeId::of::<u32>(), hook);
+
+        assert_eq!(hooks.get(&TypeId::of::<u32>()).len(), 2);
+        assert!(hooks.get(&TypeId::of::<f32>()).is_empty());
+    }
+}
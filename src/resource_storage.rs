@@ -1,12 +1,15 @@
 use std::any::Any;
-use std::any::TypeId;
+use std::any::{type_name, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
 use std::collections::HashMap;
 
 use crate::ecs_errors::ECSError;
 
 #[derive(Default, Debug)]
 pub struct ResourceStorage {
-    data: HashMap<TypeId, Box<dyn Any>>,
+    data: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+    keyed_data: HashMap<(TypeId, String), RefCell<Box<dyn Any>>>,
+    type_names: HashMap<TypeId, &'static str>,
 }
 
 impl ResourceStorage {
@@ -17,23 +20,34 @@ impl ResourceStorage {
             return Err(ECSError::ResourceAlreadyRegistered);
         }
 
-        self.data.insert(type_id, Box::new(data));
+        self.data.insert(type_id, RefCell::new(Box::new(data)));
+        self.type_names.insert(type_id, type_name::<T>());
         Ok(())
     }
 
     pub fn replace<T: Any>(&mut self, data: T) {
         let type_id = TypeId::of::<T>();
-        self.data.insert(type_id, Box::new(data));
+        self.data.insert(type_id, RefCell::new(Box::new(data)));
+        self.type_names.insert(type_id, type_name::<T>());
     }
 
-    pub fn get<T: Any>(&self) -> Option<&T> {
+    pub fn borrow<T: Any>(&self) -> Option<Ref<'_, T>> {
         let type_id = TypeId::of::<T>();
-        self.data.get(&type_id)?.downcast_ref()
+        let data = self.data.get(&type_id)?.borrow();
+
+        Ref::filter_map(data, |data| data.downcast_ref()).ok()
+    }
+
+    pub fn borrow_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        let type_id = TypeId::of::<T>();
+        let data = self.data.get(&type_id)?.borrow_mut();
+
+        RefMut::filter_map(data, |data| data.downcast_mut()).ok()
     }
 
     pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
         let type_id = TypeId::of::<T>();
-        self.data.get_mut(&type_id)?.downcast_mut()
+        self.data.get_mut(&type_id)?.get_mut().downcast_mut()
     }
 
     pub fn remove<T: Any>(&mut self) -> bool {
@@ -41,11 +55,81 @@ impl ResourceStorage {
         self.data.remove(&type_id).is_some()
     }
 
+    pub fn take<T: Any>(&mut self) -> Option<T> {
+        let type_id = TypeId::of::<T>();
+        let data = self.data.remove(&type_id)?.into_inner();
+
+        data.downcast::<T>().ok().map(|data| *data)
+    }
+
+    pub fn insert_keyed<T: Any>(&mut self, key: String, data: T) -> Result<(), ECSError> {
+        let type_id = TypeId::of::<T>();
+
+        if self.keyed_data.contains_key(&(type_id, key.clone())) {
+            return Err(ECSError::ResourceAlreadyRegistered);
+        }
+
+        self.keyed_data
+            .insert((type_id, key), RefCell::new(Box::new(data)));
+        self.type_names.insert(type_id, type_name::<T>());
+        Ok(())
+    }
+
+    pub fn borrow_keyed<T: Any>(&self, key: &str) -> Option<Ref<'_, T>> {
+        let type_id = TypeId::of::<T>();
+        let data = self.keyed_data.get(&(type_id, key.to_owned()))?.borrow();
+
+        Ref::filter_map(data, |data| data.downcast_ref()).ok()
+    }
+
+    pub fn borrow_keyed_mut<T: Any>(&self, key: &str) -> Option<RefMut<'_, T>> {
+        let type_id = TypeId::of::<T>();
+        let data = self
+            .keyed_data
+            .get(&(type_id, key.to_owned()))?
+            .borrow_mut();
+
+        RefMut::filter_map(data, |data| data.downcast_mut()).ok()
+    }
+
+    pub fn take_keyed<T: Any>(&mut self, key: &str) -> Option<T> {
+        let type_id = TypeId::of::<T>();
+        let data = self
+            .keyed_data
+            .remove(&(type_id, key.to_owned()))?
+            .into_inner();
+
+        data.downcast::<T>().ok().map(|data| *data)
+    }
+
     #[must_use]
     pub fn contains<T: Any>(&self) -> bool {
         let type_id = TypeId::of::<T>();
         self.data.contains_key(&type_id)
     }
+
+    #[cfg(feature = "serde")]
+    pub fn contains_type(&self, type_id: &TypeId) -> bool {
+        self.data.contains_key(type_id)
+    }
+
+    pub fn type_name(&self, type_id: &TypeId) -> Option<&'static str> {
+        self.type_names.get(type_id).copied()
+    }
+
+    /**
+    Every resource with its type and key, keyed resources included.
+    */
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, Option<&str>, &RefCell<Box<dyn Any>>)> {
+        self.data
+            .iter()
+            .map(|(type_id, data)| (*type_id, None, data))
+            .chain(
+                self.keyed_data
+                    .iter()
+                    .map(|((type_id, key), data)| (*type_id, Some(key.as_str()), data)),
+            )
+    }
 }
 
 #[cfg(test)]
@@ -79,7 +163,7 @@ mod test {
         resources.insert(WorldWidth(100.0)).unwrap();
         resources.replace(WorldWidth(200.0));
 
-        let world_width = resources.get::<WorldWidth>().unwrap();
+        let world_width = resources.borrow::<WorldWidth>().unwrap();
 
         assert_eq!(world_width.0, 200.0);
     }
@@ -89,11 +173,11 @@ mod test {
         let mut resources = ResourceStorage::default();
         resources.insert(WorldWidth(100.0)).unwrap();
 
-        if let Some(world_width) = resources.get::<WorldWidth>() {
+        if let Some(world_width) = resources.borrow::<WorldWidth>() {
             assert_eq!(world_width.0, 100.0);
         } else {
             panic!("Resource not found");
-        }
+        };
     }
 
     #[test]
@@ -106,11 +190,27 @@ mod test {
             world_width.0 += 1.0;
         }
 
-        let world_width = resources.get::<WorldWidth>().unwrap();
+        let world_width = resources.borrow::<WorldWidth>().unwrap();
 
         assert_eq!(world_width.0, 101.0);
     }
 
+    #[test]
+    fn borrow_different_resources_mutably_at_once() {
+        let mut resources = ResourceStorage::default();
+        resources.insert(WorldWidth(100.0)).unwrap();
+        resources.insert(10_u32).unwrap();
+
+        let mut world_width = resources.borrow_mut::<WorldWidth>().unwrap();
+        let mut counter = resources.borrow_mut::<u32>().unwrap();
+
+        world_width.0 += 1.0;
+        *counter += 1;
+
+        assert_eq!(world_width.0, 101.0);
+        assert_eq!(*counter, 11);
+    }
+
     #[test]
     fn remove_resource() {
         let mut resources = ResourceStorage::default();
@@ -120,6 +220,67 @@ mod test {
         assert!(!resources.contains::<WorldWidth>());
     }
 
+    #[test]
+    fn take_resource() {
+        let mut resources = ResourceStorage::default();
+        resources.insert(WorldWidth(100.0)).unwrap();
+
+        let world_width = resources.take::<WorldWidth>().unwrap();
+
+        assert_eq!(world_width.0, 100.0);
+        assert!(!resources.contains::<WorldWidth>());
+        assert!(resources.take::<WorldWidth>().is_none());
+    }
+
+    #[test]
+    fn keyed_resources_are_independent() {
+        let mut resources = ResourceStorage::default();
+
+        resources
+            .insert_keyed("left".to_owned(), WorldWidth(10.0))
+            .unwrap();
+        resources
+            .insert_keyed("right".to_owned(), WorldWidth(20.0))
+            .unwrap();
+
+        resources.borrow_keyed_mut::<WorldWidth>("left").unwrap().0 += 1.0;
+
+        assert_eq!(
+            resources.borrow_keyed::<WorldWidth>("left").unwrap().0,
+            11.0
+        );
+        assert_eq!(
+            resources.borrow_keyed::<WorldWidth>("right").unwrap().0,
+            20.0
+        );
+        assert!(!resources.contains::<WorldWidth>());
+    }
+
+    #[test]
+    fn insert_duplicate_keyed_resource_fails() {
+        let mut resources = ResourceStorage::default();
+        resources
+            .insert_keyed("left".to_owned(), WorldWidth(10.0))
+            .unwrap();
+
+        let result = resources.insert_keyed("left".to_owned(), WorldWidth(20.0));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn take_keyed_resource() {
+        let mut resources = ResourceStorage::default();
+        resources
+            .insert_keyed("left".to_owned(), WorldWidth(10.0))
+            .unwrap();
+
+        let world_width = resources.take_keyed::<WorldWidth>("left").unwrap();
+
+        assert_eq!(world_width.0, 10.0);
+        assert!(resources.borrow_keyed::<WorldWidth>("left").is_none());
+    }
+
     #[test]
     fn contains_resource() {
         let mut resources = ResourceStorage::default();
@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+/**
+Seedable pseudo-random number generator (xoshiro256**), meant to be added as a resource so a
+simulation makes the same choices on every run with the same seed. Systems that need their own
+stream can `fork` one, which keeps their results independent of the order other systems draw
+numbers in. Not suitable for anything security related.
+
+With the `serde` feature it can be registered with `World::register_serializable_resource` to
+be saved in snapshots. Rollback checkpoints only contain components, so to rewind it along
+with the world it has to be stored in a component instead.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::rng::Rng;
+
+let mut world = World::new();
+
+world.add_resource(Rng::seed_from_u64(42)).unwrap();
+
+let mut rng = world.get_resource_mut::<Rng>().unwrap();
+let roll = rng.range_u32(1..7);
+let mut enemy_rng = rng.fork();
+
+assert!((1..7).contains(&roll));
+assert_ne!(enemy_rng.next_u64(), rng.next_u64());
+assert_eq!(Rng::seed_from_u64(42).range_u32(1..7), roll);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn seed_from_u64(seed: u64) -> Self {
+        let mut seed = seed;
+
+        Self {
+            state: std::array::from_fn(|_| split_mix(&mut seed)),
+        }
+    }
+
+    /**
+    New generator seeded from this one, advancing it once.
+    */
+    pub fn fork(&mut self) -> Self {
+        Rng::seed_from_u64(self.next_u64())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+
+        result
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /**
+    Uniform number in `0.0..1.0`.
+    */
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1_u64 << 24) as f32
+    }
+
+    /**
+    Uniform number in `0.0..1.0`.
+    */
+    pub fn f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /**
+    `true` with the given probability, from `0.0` to `1.0`.
+    */
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.f64() < probability
+    }
+
+    /**
+    Uniform number in `range`, without modulo bias. Panics if the range is empty.
+    */
+    pub fn range_u64(&mut self, range: Range<u64>) -> u64 {
+        assert!(
+            !range.is_empty(),
+            "Cannot pick a number from an empty range"
+        );
+
+        let span = range.end - range.start;
+        let zone = u64::MAX - (u64::MAX - span + 1) % span;
+
+        loop {
+            let value = self.next_u64();
+
+            if value <= zone {
+                return range.start + value % span;
+            }
+        }
+    }
+
+    pub fn range_u32(&mut self, range: Range<u32>) -> u32 {
+        self.range_u64(u64::from(range.start)..u64::from(range.end)) as u32
+    }
+
+    pub fn range_usize(&mut self, range: Range<usize>) -> usize {
+        self.range_u64(range.start as u64..range.end as u64) as usize
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+
+        items.get(self.range_usize(0..items.len()))
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.range_usize(0..index + 1));
+        }
+    }
+}
+
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut first = Rng::seed_from_u64(7);
+        let mut second = Rng::seed_from_u64(7);
+        let mut other = Rng::seed_from_u64(8);
+
+        let numbers: Vec<u64> = (0..8).map(|_| first.next_u64()).collect();
+
+        assert_eq!(
+            numbers,
+            (0..8).map(|_| second.next_u64()).collect::<Vec<_>>()
+        );
+        assert_ne!(
+            numbers,
+            (0..8).map(|_| other.next_u64()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn numbers_stay_in_range() {
+        let mut rng = Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            assert!((3..5).contains(&rng.range_u32(3..5)));
+            assert!((0.0..1.0).contains(&rng.f32()));
+            assert!((0.0..1.0).contains(&rng.f64()));
+        }
+
+        assert_eq!(rng.choose::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn shuffle_keeps_every_item() {
+        let mut rng = Rng::seed_from_u64(1);
+        let mut items: Vec<u32> = (0..10).collect();
+
+        rng.shuffle(&mut items);
+        items.sort();
+
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+
+use crate::checkpoint::WorldCheckpoint;
+use crate::World;
+
+/**
+Advances the world by one tick using the inputs of that tick. It must be deterministic: given
+the same world and inputs it has to produce the same result, so that resimulating after a
+rollback reaches the same state the other peers see.
+*/
+pub type Simulation<I> = fn(&mut World, &I);
+
+#[derive(Debug)]
+pub struct RollbackFrame<I> {
+    pub tick: u64,
+    pub checkpoint: WorldCheckpoint,
+    pub input: I,
+}
+
+/**
+Keeps a checkpoint of the world and the inputs for each of the last `capacity` ticks, so the
+world can go back to one of them and resimulate when late or corrected inputs arrive, like in
+GGPO-style netcode. Stored as a resource and driven by `World::advance_rollback` and
+`World::rollback_to`.
+
+Checkpoints only contain entities and components, so state that must be rolled back has to
+live in components rather than resources.
+*/
+#[derive(Debug)]
+pub struct Rollback<I> {
+    capacity: usize,
+    simulate: Simulation<I>,
+    frames: VecDeque<RollbackFrame<I>>,
+    current_tick: u64,
+}
+
+impl<I> Rollback<I> {
+    pub fn new(capacity: usize, simulate: Simulation<I>) -> Self {
+        Self {
+            capacity,
+            simulate,
+            frames: VecDeque::with_capacity(capacity),
+            current_tick: 0,
+        }
+    }
+
+    /**
+    The tick that the next call to `World::advance_rollback` will simulate.
+    */
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick
+    }
+
+    pub fn oldest_tick(&self) -> Option<u64> {
+        self.frames.front().map(|frame| frame.tick)
+    }
+
+    pub fn input(&self, tick: u64) -> Option<&I> {
+        self.frame_index(tick)
+            .map(|index| &self.frames[index].input)
+    }
+
+    /**
+    Replaces the recorded inputs of a buffered tick, usually followed by `World::rollback_to`
+    to resimulate from it. Returns `false` if the tick is not buffered.
+    */
+    pub fn set_input(&mut self, tick: u64, input: I) -> bool {
+        match self.frame_index(tick) {
+            Some(index) => {
+                self.frames[index].input = input;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn simulation(&self) -> Simulation<I> {
+        self.simulate
+    }
+
+    pub(crate) fn push(&mut self, checkpoint: WorldCheckpoint, input: I) {
+        if self.capacity == 0 {
+            self.current_tick += 1;
+            return;
+        }
+
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(RollbackFrame {
+            tick: self.current_tick,
+            checkpoint,
+            input,
+        });
+        self.current_tick += 1;
+    }
+
+    pub(crate) fn checkpoint(&self, tick: u64) -> Option<&WorldCheckpoint> {
+        self.frame_index(tick)
+            .map(|index| &self.frames[index].checkpoint)
+    }
+
+    // Removes the frames from `tick` onwards and rewinds the current tick to it.
+    pub(crate) fn rewind(&mut self, tick: u64) -> Option<Vec<RollbackFrame<I>>> {
+        let index = self.frame_index(tick)?;
+
+        self.current_tick = tick;
+        Some(self.frames.drain(index..).collect())
+    }
+
+    fn frame_index(&self, tick: u64) -> Option<usize> {
+        let oldest_tick = self.oldest_tick()?;
+
+        if tick < oldest_tick || tick >= self.current_tick {
+            return None;
+        }
+
+        Some((tick - oldest_tick) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rollback(capacity: usize) -> Rollback<u32> {
+        let mut rollback = Rollback::new(capacity, |_world, _input| {});
+
+        for input in 0..5 {
+            rollback.push(WorldCheckpoint::default(), input);
+        }
+
+        rollback
+    }
+
+    #[test]
+    fn only_the_last_ticks_are_kept() {
+        let rollback = rollback(3);
+
+        assert_eq!(rollback.current_tick(), 5);
+        assert_eq!(rollback.oldest_tick(), Some(2));
+        assert_eq!(rollback.input(1), None);
+        assert_eq!(rollback.input(4), Some(&4));
+    }
+
+    #[test]
+    fn inputs_can_be_corrected() {
+        let mut rollback = rollback(3);
+
+        assert!(rollback.set_input(3, 30));
+        assert!(!rollback.set_input(5, 50));
+        assert_eq!(rollback.input(3), Some(&30));
+    }
+
+    #[test]
+    fn rewind_drops_the_following_frames() {
+        let mut rollback = rollback(3);
+
+        let frames = rollback.rewind(3).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].tick, 3);
+        assert_eq!(rollback.current_tick(), 3);
+        assert!(rollback.rewind(1).is_none());
+    }
+}
@@ -0,0 +1,414 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+
+use crate::async_systems::{AsyncSystem, AsyncWorld};
+use crate::events::EventReader;
+use crate::World;
+
+pub type System = Box<dyn FnMut(&mut World)>;
+pub type RunCondition = Box<dyn FnMut(&World) -> bool>;
+
+struct ScheduledSystem {
+    system: System,
+    condition: Option<RunCondition>,
+}
+
+/**
+An ordered list of systems that are run one after another against a world.
+Systems can be given a run condition, in which case they are skipped whenever the
+condition returns `false`. Async systems are polled after the others, see `add_async_system`.
+A tick can also be run one system at a time with `step_system`, to pause between systems.
+*/
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<ScheduledSystem>,
+    async_systems: Vec<AsyncSystem>,
+    // Index of the next system `step_system` runs, the async systems once past the last one.
+    next_system: usize,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Schedule::default()
+    }
+
+    pub fn add_system(&mut self, system: impl FnMut(&mut World) + 'static) -> &mut Self {
+        self.systems.push(ScheduledSystem {
+            system: Box::new(system),
+            condition: None,
+        });
+        self
+    }
+
+    pub fn add_system_with_condition(
+        &mut self,
+        system: impl FnMut(&mut World) + 'static,
+        condition: impl FnMut(&World) -> bool + 'static,
+    ) -> &mut Self {
+        self.systems.push(ScheduledSystem {
+            system: Box::new(system),
+            condition: Some(Box::new(condition)),
+        });
+        self
+    }
+
+    /**
+    Adds a system written as an async function, for logic spanning several frames like dialogue
+    scripts or network handshakes. Each run of the schedule polls it until it awaits
+    `AsyncWorld::next_frame`, and it's removed once it returns. The world is only accessed
+    through the `AsyncWorld` it's given.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::async_systems::AsyncWorld;
+    use sara_ecs::schedule::Schedule;
+
+    struct Line(pub &'static str);
+
+    async fn dialogue(world: AsyncWorld) {
+        for line in ["Hello!", "Goodbye!"] {
+            world.commands(|commands| {
+                commands.add(move |world| {
+                    world.remove_resource::<Line>();
+                    world.add_resource(Line(line))
+                })
+            });
+            world.next_frame().await;
+        }
+    }
+
+    let mut world = World::new();
+    let mut schedule = Schedule::new();
+
+    schedule.add_async_system(dialogue);
+
+    schedule.run(&mut world);
+    assert_eq!(world.get_resource::<Line>().unwrap().0, "Hello!");
+
+    schedule.run(&mut world);
+    assert_eq!(world.get_resource::<Line>().unwrap().0, "Goodbye!");
+    ```
+    */
+    pub fn add_async_system<F: Future<Output = ()> + 'static>(
+        &mut self,
+        system: impl FnOnce(AsyncWorld) -> F,
+    ) -> &mut Self {
+        self.async_systems.push(AsyncSystem::new(system));
+        self
+    }
+
+    /**
+    Runs a whole tick. If the current tick was partially run with `step_system`, only the rest
+    of it is run.
+    */
+    pub fn run(&mut self, world: &mut World) {
+        while !self.step_system(world) {}
+    }
+
+    /**
+    Runs the next system of the current tick, skipping it if its run condition fails, and
+    returns `true` once the tick is over. After the last system, a final step polls the async
+    systems and ends the tick.
+
+    Example:
+    ```
+    use sara_ecs::World;
+    use sara_ecs::schedule::Schedule;
+
+    struct Log(pub Vec<&'static str>);
+
+    let mut world = World::new();
+    let mut schedule = Schedule::new();
+
+    world.add_resource(Log(vec![])).unwrap();
+    schedule
+        .add_system(|world| world.get_resource_mut::<Log>().unwrap().0.push("input"))
+        .add_system(|world| world.get_resource_mut::<Log>().unwrap().0.push("physics"));
+
+    assert!(!schedule.step_system(&mut world));
+    assert_eq!(world.get_resource::<Log>().unwrap().0, vec!["input"]);
+    assert_eq!(schedule.next_system_index(), 1);
+
+    schedule.run(&mut world);
+    assert_eq!(world.get_resource::<Log>().unwrap().0, vec!["input", "physics"]);
+    assert_eq!(schedule.next_system_index(), 0);
+    ```
+    */
+    pub fn step_system(&mut self, world: &mut World) -> bool {
+        let Some(scheduled) = self.systems.get_mut(self.next_system) else {
+            self.async_systems
+                .retain_mut(|async_system| !async_system.run(world));
+            self.next_system = 0;
+            return true;
+        };
+
+        self.next_system += 1;
+
+        if let Some(condition) = &mut scheduled.condition {
+            if !condition(world) {
+                return false;
+            }
+        }
+
+        (scheduled.system)(world);
+        false
+    }
+
+    /**
+    Index of the system the next call to `step_system` runs, `0` between ticks. Equal to
+    `len` when only the async systems are left.
+    */
+    pub fn next_system_index(&self) -> usize {
+        self.next_system
+    }
+
+    /**
+    Number of systems, not counting async systems.
+    */
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Schedule")
+            .field("systems", &self.systems.len())
+            .field("async_systems", &self.async_systems.len())
+            .field("next_system", &self.next_system)
+            .finish()
+    }
+}
+
+/**
+Systems registered under a name, to be run on demand with `World::run_named_system`.
+*/
+#[derive(Default)]
+pub(crate) struct NamedSystems {
+    systems: HashMap<String, System>,
+}
+
+impl NamedSystems {
+    pub fn insert(&mut self, name: String, system: System) {
+        self.systems.insert(name, system);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<System> {
+        self.systems.remove(name)
+    }
+
+    // Puts back a system taken out to be run, unless another one was registered under the
+    // same name while it was running.
+    pub fn restore(&mut self, name: &str, system: System) {
+        self.systems.entry(name.to_owned()).or_insert(system);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.systems.keys().map(String::as_str).collect();
+
+        names.sort_unstable();
+        names
+    }
+}
+
+impl std::fmt::Debug for NamedSystems {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamedSystems")
+            .field("systems", &self.names())
+            .finish()
+    }
+}
+
+/**
+Run condition that returns `true` when events of type `T` were sent since the last time
+the condition was checked. Systems using it are skipped on frames where no such events exist.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::schedule::{on_event, Schedule};
+
+struct Explosion;
+struct ExplosionCount(pub u32);
+
+let mut world = World::new();
+world.add_event::<Explosion>();
+world.add_resource(ExplosionCount(0)).unwrap();
+
+let mut schedule = Schedule::new();
+schedule.add_system_with_condition(
+    |world| world.get_resource_mut::<ExplosionCount>().unwrap().0 += 1,
+    on_event::<Explosion>(),
+);
+
+schedule.run(&mut world);
+assert_eq!(world.get_resource::<ExplosionCount>().unwrap().0, 0);
+
+world.send_event(Explosion).unwrap();
+schedule.run(&mut world);
+assert_eq!(world.get_resource::<ExplosionCount>().unwrap().0, 1);
+```
+*/
+pub fn on_event<T: Any>() -> impl FnMut(&World) -> bool {
+    let mut reader = EventReader::<T>::default();
+
+    move |world| match world.get_events::<T>() {
+        Some(events) => reader.read(&events).next().is_some(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ecs_errors::ECSError;
+
+    struct Counter(pub u32);
+    struct Ping;
+
+    #[test]
+    fn systems_run_in_order() {
+        let mut world = World::new();
+        world.add_resource(Counter(1)).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(|world| world.get_resource_mut::<Counter>().unwrap().0 += 1)
+            .add_system(|world| world.get_resource_mut::<Counter>().unwrap().0 *= 10);
+
+        schedule.run(&mut world);
+
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 20);
+    }
+
+    #[test]
+    fn system_is_skipped_when_condition_fails() {
+        let mut world = World::new();
+        world.add_resource(Counter(0)).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system_with_condition(
+            |world| world.get_resource_mut::<Counter>().unwrap().0 += 1,
+            |_world| false,
+        );
+
+        schedule.run(&mut world);
+
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 0);
+    }
+
+    #[test]
+    fn stepping_runs_one_system_at_a_time() {
+        let mut world = World::new();
+        world.add_resource(Counter(1)).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(|world| world.get_resource_mut::<Counter>().unwrap().0 += 1)
+            .add_system_with_condition(
+                |world| world.get_resource_mut::<Counter>().unwrap().0 *= 10,
+                |_world| false,
+            );
+
+        let steps = std::iter::repeat_with(|| schedule.step_system(&mut world))
+            .take_while(|tick_over| !tick_over)
+            .count();
+
+        assert_eq!(steps, 2);
+        assert_eq!(schedule.next_system_index(), 0);
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 2);
+    }
+
+    #[test]
+    fn async_systems_read_the_world_across_frames() {
+        let mut world = World::new();
+        world.add_resource(Counter(1)).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(|world| world.get_resource_mut::<Counter>().unwrap().0 += 1)
+            .add_async_system(|world: AsyncWorld| async move {
+                loop {
+                    let counter = world
+                        .read(|world| world.get_resource::<Counter>().unwrap().0)
+                        .await;
+
+                    if counter >= 3 {
+                        world.commands(|commands| {
+                            commands.add(|world| {
+                                world.get_resource_mut::<Counter>().unwrap().0 = 100;
+                                Ok(())
+                            })
+                        });
+                        return;
+                    }
+
+                    world.next_frame().await;
+                }
+            });
+
+        schedule.run(&mut world);
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 2);
+
+        schedule.run(&mut world);
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 100);
+
+        schedule.run(&mut world);
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 101);
+    }
+
+    #[test]
+    fn named_systems_run_by_name() {
+        let mut world = World::new();
+        world.add_resource(Counter(0)).unwrap();
+
+        world.register_named_system("increment", |world| {
+            world.get_resource_mut::<Counter>().unwrap().0 += 1;
+            assert!(matches!(
+                world.run_named_system("increment"),
+                Err(ECSError::SystemNotFound)
+            ));
+        });
+
+        world.run_named_system("increment").unwrap();
+        world.run_named_system("increment").unwrap();
+
+        assert_eq!(world.get_resource::<Counter>().unwrap().0, 2);
+        assert_eq!(world.named_systems(), vec!["increment"]);
+
+        assert!(world.remove_named_system("increment"));
+        assert!(matches!(
+            world.run_named_system("increment"),
+            Err(ECSError::SystemNotFound)
+        ));
+    }
+
+    #[test]
+    fn on_event_only_triggers_on_new_events() {
+        let mut world = World::new();
+        world.add_event::<Ping>();
+
+        let mut condition = on_event::<Ping>();
+        assert!(!condition(&world));
+
+        world.send_event(Ping).unwrap();
+        assert!(condition(&world));
+
+        world.update_events();
+        assert!(!condition(&world));
+    }
+
+    #[test]
+    fn on_event_is_false_for_unregistered_events() {
+        let world = World::new();
+        let mut condition = on_event::<Ping>();
+
+        assert!(!condition(&world));
+    }
+}
@@ -0,0 +1,542 @@
+use std::any::TypeId;
+use std::collections::HashSet;
+
+use crate::commands::Commands;
+use crate::dynamic_component::{DynamicComponent, DynamicValue};
+use crate::ecs_errors::ECSError;
+use crate::entity_ref::ComponentInfo;
+use crate::reflect::Reflect;
+use crate::type_registry::TypeRegistry;
+use crate::World;
+
+/**
+Components a script is allowed to touch. Scripts can only reach dynamic components and
+components registered with `World::register_reflectable_component`, and a sandbox can narrow
+that down further to a list of component names.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptSandbox {
+    // `None` allows every dynamic and reflectable component.
+    components: Option<HashSet<String>>,
+}
+
+impl ScriptSandbox {
+    pub fn unrestricted() -> Self {
+        ScriptSandbox::default()
+    }
+
+    /**
+    Only allows the components with the given names, as scripts refer to them.
+    */
+    pub fn allowing<S: Into<String>>(components: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            components: Some(components.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    pub fn allows(&self, component: &str) -> bool {
+        self.components
+            .as_ref()
+            .is_none_or(|components| components.contains(component))
+    }
+}
+
+/**
+What the bindings of a scripting language, like Lua or a WASM guest, call into. Scripts look
+components up by name, read and write their fields as `DynamicValue`s, run queries and record
+structural changes, which are applied once the script is done. Dynamic components are reached
+through their fields, and Rust components through reflection, where field paths like
+`"position.x"` work and primitive fields are converted to and from the closest value type.
+
+Field writes happen right away, while spawning, inserting, removing and despawning are
+recorded as commands, so a script never invalidates the queries it iterates over.
+
+Example:
+```
+use sara_ecs::{impl_reflect, World};
+use sara_ecs::dynamic_component::{DynamicComponentDescriptor, DynamicValue, FieldType};
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::scripting::ScriptSandbox;
+
+struct Health {
+    current: u32,
+}
+impl_reflect!(Health { current });
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_reflectable_component::<Health>();
+    world.register_dynamic_component(
+        DynamicComponentDescriptor::new("Poison").with_field("damage", FieldType::Int),
+    )?;
+
+    let player = world.create_entity().with_component(Health { current: 100 })?.id();
+    let poison = world
+        .dynamic_component_descriptor("Poison")
+        .unwrap()
+        .instantiate()
+        .with_field("damage", DynamicValue::Int(15));
+    world.add_dynamic_component(player, poison)?;
+
+    // What a script binding would do for `for entity in query("Health", "Poison") ...`.
+    world.run_script(ScriptSandbox::allowing(["Health", "Poison"]), |script| {
+        for entity in script.query(&["Health", "Poison"])? {
+            let DynamicValue::Int(damage) = script.get_field(entity, "Poison", "damage")? else {
+                unreachable!();
+            };
+            let DynamicValue::Int(health) = script.get_field(entity, "Health", "current")? else {
+                unreachable!();
+            };
+
+            script.set_field(entity, "Health", "current", DynamicValue::Int(health - damage))?;
+            script.remove(entity, "Poison")?;
+        }
+        Ok(())
+    })?;
+
+    assert_eq!(world.reflect_component(player, "Health")?.get::<u32>("current"), Some(&85));
+    assert!(world.get_dynamic_component(player, "Poison").is_err());
+    Ok(())
+}
+```
+*/
+pub struct ScriptContext<'w> {
+    world: &'w World,
+    sandbox: ScriptSandbox,
+    commands: Commands,
+}
+
+impl<'w> ScriptContext<'w> {
+    pub fn new(world: &'w World, sandbox: ScriptSandbox) -> Self {
+        Self {
+            world,
+            sandbox,
+            commands: Commands::new(),
+        }
+    }
+
+    pub fn has_component(&self, entity_id: usize, component: &str) -> bool {
+        match self.check(component) {
+            Ok(Target::Dynamic) => self
+                .world
+                .get_dynamic_component(entity_id, component)
+                .is_ok(),
+            Ok(Target::Reflected) => self.world.reflect_component(entity_id, component).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /**
+    Reads the field at `path` of a component. Fails with `FieldNotAccessible` if the field
+    doesn't exist or isn't of a primitive type a script can hold.
+    */
+    pub fn get_field(
+        &self,
+        entity_id: usize,
+        component: &str,
+        path: &str,
+    ) -> Result<DynamicValue, ECSError> {
+        let value = match self.check(component)? {
+            Target::Dynamic => self
+                .world
+                .get_dynamic_component(entity_id, component)?
+                .get(path)
+                .cloned(),
+            Target::Reflected => self
+                .world
+                .reflect_component(entity_id, component)?
+                .path(path)
+                .and_then(read_value),
+        };
+
+        value.ok_or_else(|| field_not_accessible(component, path))
+    }
+
+    /**
+    Writes the field at `path` of a component. Fails with `FieldNotAccessible` if the field
+    doesn't exist, or can't hold the value, like a negative number in an unsigned field.
+    */
+    pub fn set_field(
+        &self,
+        entity_id: usize,
+        component: &str,
+        path: &str,
+        value: DynamicValue,
+    ) -> Result<(), ECSError> {
+        let written = match self.check(component)? {
+            Target::Dynamic => self
+                .world
+                .get_dynamic_component_mut(entity_id, component)?
+                .set(path, value),
+            Target::Reflected => self
+                .world
+                .reflect_component_mut(entity_id, component)?
+                .path_mut(path)
+                .is_some_and(|field| write_value(field, value)),
+        };
+
+        if !written {
+            return Err(field_not_accessible(component, path));
+        }
+        Ok(())
+    }
+
+    /**
+    IDs of the enabled entities having every component of `components`, sorted.
+    */
+    pub fn query(&self, components: &[&str]) -> Result<Vec<usize>, ECSError> {
+        let mut query = self.world.query();
+
+        for component in components {
+            match self.check(component)? {
+                Target::Dynamic => query.with_dynamic_component_filter(component)?,
+                Target::Reflected => {
+                    let (type_id, _, _) = self.world.reflect_functions(component)?;
+                    query.with_component_id_filter(type_id)?
+                }
+            };
+        }
+
+        Ok(query.run().entity_ids)
+    }
+
+    /**
+    Reserves an entity, which comes alive once a component is inserted into it.
+    */
+    pub fn spawn(&mut self) -> usize {
+        self.world.entities().reserve()
+    }
+
+    /**
+    Records the insertion of a dynamic component, usually created from
+    `World::dynamic_component_descriptor`.
+    */
+    pub fn insert(
+        &mut self,
+        entity_id: usize,
+        component: DynamicComponent,
+    ) -> Result<(), ECSError> {
+        if !self.sandbox.allows(&component.name) {
+            return Err(ECSError::ScriptAccessDenied {
+                component: component.name,
+            });
+        }
+
+        self.commands
+            .add(move |world| world.add_dynamic_component(entity_id, component));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, entity_id: usize, component: &str) -> Result<(), ECSError> {
+        match self.check(component)? {
+            Target::Dynamic => {
+                let component = component.to_owned();
+
+                self.commands
+                    .add(move |world| world.remove_dynamic_component(entity_id, &component));
+            }
+            Target::Reflected => {
+                let (type_id, _, _) = self.world.reflect_functions(component)?;
+
+                self.commands
+                    .add(move |world| world.remove_component_dynamic(entity_id, type_id));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+    Records despawning an entity. As that removes all of its components, the script must be
+    able to use every one of them, or this fails with `ScriptAccessDenied` for the first it
+    can't.
+    */
+    pub fn despawn(&mut self, entity_id: usize) -> Result<(), ECSError> {
+        // Entities spawned by the script have no components yet.
+        if let Ok(entity) = self.world.entity(entity_id) {
+            for component in entity.component_types() {
+                if !self.can_remove(&component) {
+                    return Err(ECSError::ScriptAccessDenied {
+                        component: component.name,
+                    });
+                }
+            }
+        }
+
+        self.commands.entity(entity_id).despawn();
+        Ok(())
+    }
+
+    /**
+    Ends the script, returning the changes it recorded, to apply with `World::apply_commands`.
+    */
+    pub fn into_commands(self) -> Commands {
+        self.commands
+    }
+
+    // Typed components are listed under their full type name, but the sandbox may allow them
+    // under the short one.
+    fn can_remove(&self, component: &ComponentInfo) -> bool {
+        if component.type_id == TypeId::of::<DynamicComponent>() {
+            return self.sandbox.allows(&component.name);
+        }
+
+        let registry = self.world.resource_storage.borrow::<TypeRegistry>();
+
+        registry
+            .as_ref()
+            .and_then(|registry| registry.get(&component.type_id))
+            .is_some_and(|registration| {
+                registration.reflect.is_some()
+                    && (self.sandbox.allows(registration.name)
+                        || self.sandbox.allows(&registration.short_name))
+            })
+    }
+
+    // Checks that the script may use `component`, and how it's reached.
+    fn check(&self, component: &str) -> Result<Target, ECSError> {
+        if !self.sandbox.allows(component) {
+            return Err(ECSError::ScriptAccessDenied {
+                component: component.to_owned(),
+            });
+        }
+
+        if self.world.dynamic_component_descriptor(component).is_some() {
+            return Ok(Target::Dynamic);
+        }
+
+        self.world
+            .reflect_functions(component)
+            .map(|_| Target::Reflected)
+    }
+}
+
+impl std::fmt::Debug for ScriptContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptContext")
+            .field("sandbox", &self.sandbox)
+            .field("commands", &self.commands.len())
+            .finish()
+    }
+}
+
+enum Target {
+    Dynamic,
+    Reflected,
+}
+
+fn field_not_accessible(component: &str, field: &str) -> ECSError {
+    ECSError::FieldNotAccessible {
+        component: component.to_owned(),
+        field: field.to_owned(),
+    }
+}
+
+// Converts a primitive field to the value type scripts hold, integers as `Int` when they fit.
+fn read_value(field: &dyn Reflect) -> Option<DynamicValue> {
+    let any = field.as_any();
+
+    macro_rules! read_int {
+        ($($type:ty),*) => {
+            $(if let Some(value) = any.downcast_ref::<$type>() {
+                return i64::try_from(*value).ok().map(DynamicValue::Int);
+            })*
+        };
+    }
+
+    read_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+    if let Some(value) = any.downcast_ref::<f32>() {
+        return Some(DynamicValue::Float(f64::from(*value)));
+    }
+    if let Some(value) = any.downcast_ref::<f64>() {
+        return Some(DynamicValue::Float(*value));
+    }
+    if let Some(value) = any.downcast_ref::<bool>() {
+        return Some(DynamicValue::Bool(*value));
+    }
+
+    any.downcast_ref::<String>()
+        .map(|value| DynamicValue::String(value.clone()))
+}
+
+// Writes a script value into a primitive field, returning `false` if it can't hold it. Integers
+// can be written to float fields, but not the other way around.
+fn write_value(field: &mut dyn Reflect, value: DynamicValue) -> bool {
+    let any = field.as_any_mut();
+
+    macro_rules! write_int {
+        ($($type:ty),*) => {
+            $(if let Some(field) = any.downcast_mut::<$type>() {
+                let DynamicValue::Int(value) = value else {
+                    return false;
+                };
+                return <$type>::try_from(value).map(|value| *field = value).is_ok();
+            })*
+        };
+    }
+
+    write_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+    let float = match value {
+        DynamicValue::Float(value) => Some(value),
+        DynamicValue::Int(value) => Some(value as f64),
+        _ => None,
+    };
+
+    if let Some(field) = any.downcast_mut::<f32>() {
+        return float.map(|value| *field = value as f32).is_some();
+    }
+    if let Some(field) = any.downcast_mut::<f64>() {
+        return float.map(|value| *field = value).is_some();
+    }
+
+    match (any.downcast_mut::<bool>(), value) {
+        (Some(field), DynamicValue::Bool(value)) => {
+            *field = value;
+            return true;
+        }
+        (Some(_), _) => return false,
+        (None, value) => {
+            if let (Some(field), DynamicValue::String(value)) =
+                (any.downcast_mut::<String>(), value)
+            {
+                *field = value;
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::impl_reflect;
+
+    struct Stats {
+        level: u8,
+        speed: f32,
+    }
+    impl_reflect!(Stats { level, speed });
+
+    #[test]
+    fn reflected_fields_convert_to_script_values() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_reflectable_component::<Stats>();
+        let entity_id = world
+            .create_entity()
+            .with_component(Stats {
+                level: 1,
+                speed: 1.5,
+            })?
+            .id();
+
+        let script = ScriptContext::new(&world, ScriptSandbox::unrestricted());
+
+        assert_eq!(
+            script.get_field(entity_id, "Stats", "speed")?,
+            DynamicValue::Float(1.5)
+        );
+
+        script.set_field(entity_id, "Stats", "speed", DynamicValue::Int(2))?;
+        script.set_field(entity_id, "Stats", "level", DynamicValue::Int(5))?;
+
+        assert_eq!(
+            script.get_field(entity_id, "Stats", "speed")?,
+            DynamicValue::Float(2.0)
+        );
+        assert!(matches!(
+            script.set_field(entity_id, "Stats", "level", DynamicValue::Int(-1)),
+            Err(ECSError::FieldNotAccessible { .. })
+        ));
+        assert_eq!(
+            script.get_field(entity_id, "Stats", "level")?,
+            DynamicValue::Int(5)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn the_sandbox_hides_components() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_reflectable_component::<Stats>();
+        world.register_component::<u32>();
+        let entity_id = world
+            .create_entity()
+            .with_component(Stats {
+                level: 1,
+                speed: 1.5,
+            })?
+            .with_component(7_u32)?
+            .id();
+
+        let mut script = ScriptContext::new(&world, ScriptSandbox::allowing(["Health"]));
+
+        assert!(!script.has_component(entity_id, "Stats"));
+        assert!(matches!(
+            script.query(&["Stats"]),
+            Err(ECSError::ScriptAccessDenied { .. })
+        ));
+        assert!(script.remove(entity_id, "Stats").is_err());
+        assert!(script.despawn(entity_id).is_err());
+
+        let mut script = ScriptContext::new(&world, ScriptSandbox::unrestricted());
+
+        // Components that aren't reflectable stay out of reach.
+        assert!(!script.has_component(entity_id, "u32"));
+        assert!(matches!(
+            script.despawn(entity_id),
+            Err(ECSError::ScriptAccessDenied { component }) if component == "u32"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn entities_with_only_allowed_components_can_be_despawned() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_reflectable_component::<Stats>();
+        let entity_id = world
+            .create_entity()
+            .with_component(Stats {
+                level: 1,
+                speed: 1.5,
+            })?
+            .id();
+
+        world.run_script(ScriptSandbox::allowing(["Stats"]), |script| {
+            let spawned = script.spawn();
+
+            script.despawn(entity_id)?;
+            script.despawn(spawned)
+        })?;
+
+        assert!(world.entity(entity_id).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn removing_from_missing_entities_fails() -> Result<(), ECSError> {
+        let mut world = World::new();
+
+        world.register_reflectable_component::<Stats>();
+        world.create_entity().with_component(Stats {
+            level: 1,
+            speed: 1.5,
+        })?;
+
+        let result = world.run_script(ScriptSandbox::unrestricted(), |script| {
+            script.remove(usize::MAX, "Stats")
+        });
+
+        assert!(matches!(
+            result,
+            Err(ECSError::EntityDoesNotExist { id: usize::MAX })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,548 @@
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ecs_errors::ECSError;
+use crate::entity_storage::EntityStorage;
+use crate::resource_storage::ResourceStorage;
+use crate::World;
+
+/**
+Serializable copy of a world: the components of every living entity, the number of entity
+slots, and the serializable resources. Created with `World::snapshot` and restored with
+`World::load_snapshot`. Only types registered as serializable are included.
+
+`component_versions` records the version every component was saved with, so older snapshots
+can be migrated when they are loaded. Components missing from it are assumed to be version 0.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WorldSnapshot {
+    pub entity_count: usize,
+    pub entities: Vec<EntitySnapshot>,
+    pub resources: HashMap<String, Value>,
+    #[serde(default)]
+    pub component_versions: HashMap<String, u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct EntitySnapshot {
+    pub id: usize,
+    pub components: HashMap<String, Value>,
+}
+
+pub type EntityMap = HashMap<usize, usize>;
+
+/**
+Upgrades the serialized form of a component from one version to the next.
+*/
+pub type ComponentMigration = fn(Value) -> Value;
+
+/**
+Implemented by components that hold entity IDs, so the IDs can be rewritten when a snapshot is
+spawned into a world with `World::spawn_snapshot` and its entities receive new IDs.
+*/
+pub trait MapEntities {
+    fn map_entities(&mut self, entity_map: &EntityMap);
+}
+
+type SerializeResource = fn(&ResourceStorage) -> Option<Result<Value, ECSError>>;
+type DeserializeResource = fn(&mut ResourceStorage, Value) -> Result<(), ECSError>;
+type SerializeComponent = fn(&EntityStorage, usize) -> Option<Result<Value, ECSError>>;
+type DeserializeComponent = fn(&mut World, usize, Value) -> Result<(), ECSError>;
+type MapComponentEntities = fn(&EntityStorage, usize, &EntityMap);
+
+// `name` is the key in serialized data. It defaults to `type_name`, which isn't stable across
+// compiler versions, so lookups also accept the type name of types given another name.
+#[derive(Debug, Clone)]
+struct ResourceSerializer {
+    name: &'static str,
+    type_name: &'static str,
+    serialize: SerializeResource,
+    deserialize: DeserializeResource,
+}
+
+#[derive(Debug, Clone)]
+struct ComponentSerializer {
+    name: &'static str,
+    type_name: &'static str,
+    serialize: SerializeComponent,
+    deserialize: DeserializeComponent,
+    version: u32,
+    migrations: HashMap<u32, ComponentMigration>,
+}
+
+#[derive(Default, Debug)]
+pub struct SerializationRegistry {
+    resources: HashMap<TypeId, ResourceSerializer>,
+    components: HashMap<TypeId, ComponentSerializer>,
+    entity_mappers: HashMap<TypeId, MapComponentEntities>,
+}
+
+impl SerializationRegistry {
+    pub fn register_entity_mapper<T: MapEntities + Any>(&mut self) {
+        self.entity_mappers
+            .insert(TypeId::of::<T>(), |entities, entity_id, entity_map| {
+                if let Ok(mut component) = entities.get_component_mut::<T>(entity_id) {
+                    component.map_entities(entity_map);
+                }
+            });
+    }
+
+    pub fn map_entities(&self, entities: &EntityStorage, entity_map: &EntityMap) {
+        for map_component_entities in self.entity_mappers.values() {
+            for entity_id in entity_map.values() {
+                map_component_entities(entities, *entity_id, entity_map);
+            }
+        }
+    }
+
+    // Only patches the component named `name` of one entity, for when `entity_map` also covers
+    // references that were already mapped.
+    pub fn map_component_entities(
+        &self,
+        name: &str,
+        entities: &EntityStorage,
+        entity_id: usize,
+        entity_map: &EntityMap,
+    ) {
+        let mapper = self
+            .component_serializer(name)
+            .and_then(|(type_id, _)| self.entity_mappers.get(type_id));
+
+        if let Some(map_component_entities) = mapper {
+            map_component_entities(entities, entity_id, entity_map);
+        }
+    }
+
+    pub fn register_component<T: Serialize + DeserializeOwned + Any>(&mut self) {
+        self.register_component_with_name::<T>(type_name::<T>());
+    }
+
+    pub fn register_component_with_name<T: Serialize + DeserializeOwned + Any>(
+        &mut self,
+        name: &'static str,
+    ) {
+        self.components.insert(
+            TypeId::of::<T>(),
+            ComponentSerializer {
+                name,
+                type_name: type_name::<T>(),
+                serialize: |entities, entity_id| {
+                    let component = entities.get_component::<T>(entity_id).ok()?;
+                    Some(serde_json::to_value(&*component).map_err(to_ecs_error))
+                },
+                deserialize: |world, entity_id, value| {
+                    let component: T = serde_json::from_value(value).map_err(to_ecs_error)?;
+                    world.add_component_to_entity(entity_id, component)
+                },
+                version: 0,
+                migrations: HashMap::new(),
+            },
+        );
+    }
+
+    pub fn set_component_version<T: Any>(&mut self, version: u32) -> Result<(), ECSError> {
+        self.component_serializer_mut::<T>()?.version = version;
+        Ok(())
+    }
+
+    pub fn add_component_migration<T: Any>(
+        &mut self,
+        from_version: u32,
+        migration: ComponentMigration,
+    ) -> Result<(), ECSError> {
+        self.component_serializer_mut::<T>()?
+            .migrations
+            .insert(from_version, migration);
+        Ok(())
+    }
+
+    pub fn component_versions(&self) -> HashMap<String, u32> {
+        self.components
+            .values()
+            .map(|serializer| (serializer.name.to_owned(), serializer.version))
+            .collect()
+    }
+
+    // Runs the migrations of the component named `name`, one version at a time, until `value`
+    // matches the current version.
+    pub fn migrate_component(
+        &self,
+        name: &str,
+        version: u32,
+        mut value: Value,
+    ) -> Result<Value, ECSError> {
+        let (_, serializer) = self
+            .component_serializer(name)
+            .ok_or(ECSError::TypeNotSerializable)?;
+
+        if version > serializer.version {
+            return Err(ECSError::Serialization(format!(
+                "{name} was saved with version {version}, newer than the current version {}",
+                serializer.version
+            )));
+        }
+
+        for from_version in version..serializer.version {
+            let migration = serializer.migrations.get(&from_version).ok_or_else(|| {
+                ECSError::Serialization(format!(
+                    "{name} has no migration from version {from_version}"
+                ))
+            })?;
+            value = migration(value);
+        }
+
+        Ok(value)
+    }
+
+    fn component_serializer(&self, name: &str) -> Option<(&TypeId, &ComponentSerializer)> {
+        self.components
+            .iter()
+            .find(|(_, serializer)| serializer.name == name)
+            .or_else(|| {
+                self.components
+                    .iter()
+                    .find(|(_, serializer)| serializer.type_name == name)
+            })
+    }
+
+    fn component_serializer_mut<T: Any>(&mut self) -> Result<&mut ComponentSerializer, ECSError> {
+        self.components
+            .get_mut(&TypeId::of::<T>())
+            .ok_or(ECSError::TypeNotSerializable)
+    }
+
+    pub fn serialize_entities(
+        &self,
+        entities: &EntityStorage,
+    ) -> Result<Vec<EntitySnapshot>, ECSError> {
+        self.serialize_entities_where(entities, |_| true)
+    }
+
+    pub fn serialize_entities_where(
+        &self,
+        entities: &EntityStorage,
+        keep: impl Fn(usize) -> bool,
+    ) -> Result<Vec<EntitySnapshot>, ECSError> {
+        let mut serialized = vec![];
+
+        for entity_id in 0..entities.entity_count() {
+            if !entities.is_alive(entity_id) || !keep(entity_id) {
+                continue;
+            }
+
+            let mut components = HashMap::new();
+
+            for serializer in self.components.values() {
+                if let Some(value) = (serializer.serialize)(entities, entity_id) {
+                    components.insert(serializer.name.to_owned(), value?);
+                }
+            }
+
+            serialized.push(EntitySnapshot {
+                id: entity_id,
+                components,
+            });
+        }
+
+        Ok(serialized)
+    }
+
+    pub fn serialize_component(
+        &self,
+        type_id: &TypeId,
+        entities: &EntityStorage,
+        entity_id: usize,
+    ) -> Option<Result<(&'static str, Value), ECSError>> {
+        let serializer = self.components.get(type_id)?;
+        let value = (serializer.serialize)(entities, entity_id)?;
+
+        Some(value.map(|value| (serializer.name, value)))
+    }
+
+    pub fn component_deserializer(&self, name: &str) -> Result<DeserializeComponent, ECSError> {
+        self.component_serializer(name)
+            .map(|(_, serializer)| serializer.deserialize)
+            .ok_or(ECSError::TypeNotSerializable)
+    }
+
+    pub fn register_resource<T: Serialize + DeserializeOwned + Any>(&mut self) {
+        self.register_resource_with_name::<T>(type_name::<T>());
+    }
+
+    pub fn register_resource_with_name<T: Serialize + DeserializeOwned + Any>(
+        &mut self,
+        name: &'static str,
+    ) {
+        self.resources.insert(
+            TypeId::of::<T>(),
+            ResourceSerializer {
+                name,
+                type_name: type_name::<T>(),
+                serialize: |resources| {
+                    let resource = resources.borrow::<T>()?;
+                    Some(serde_json::to_value(&*resource).map_err(to_ecs_error))
+                },
+                deserialize: |resources, value| {
+                    let resource: T = serde_json::from_value(value).map_err(to_ecs_error)?;
+                    resources.replace(resource);
+                    Ok(())
+                },
+            },
+        );
+    }
+
+    pub fn serialize_resource(
+        &self,
+        type_id: &TypeId,
+        resources: &ResourceStorage,
+    ) -> Result<Value, ECSError> {
+        let serializer = self
+            .resources
+            .get(type_id)
+            .ok_or(ECSError::TypeNotSerializable)?;
+
+        (serializer.serialize)(resources).ok_or_else(|| ECSError::ResourceDoesNotExist {
+            type_name: serializer.name.to_owned(),
+        })?
+    }
+
+    pub fn deserialize_resource(
+        &self,
+        type_id: &TypeId,
+        resources: &mut ResourceStorage,
+        value: Value,
+    ) -> Result<(), ECSError> {
+        let serializer = self
+            .resources
+            .get(type_id)
+            .ok_or(ECSError::TypeNotSerializable)?;
+
+        (serializer.deserialize)(resources, value)
+    }
+
+    pub fn resource_type_name(&self, type_id: &TypeId) -> Option<&'static str> {
+        self.resources
+            .get(type_id)
+            .map(|serializer| serializer.type_name)
+    }
+
+    pub fn serialize_resources(
+        &self,
+        resources: &ResourceStorage,
+    ) -> Result<HashMap<String, Value>, ECSError> {
+        self.serialize_resources_where(resources, |_| true)
+    }
+
+    pub fn serialize_resources_where(
+        &self,
+        resources: &ResourceStorage,
+        keep: impl Fn(&TypeId) -> bool,
+    ) -> Result<HashMap<String, Value>, ECSError> {
+        let mut serialized = HashMap::new();
+
+        for (type_id, serializer) in &self.resources {
+            if !keep(type_id) {
+                continue;
+            }
+
+            if let Some(value) = (serializer.serialize)(resources) {
+                serialized.insert(serializer.name.to_owned(), value?);
+            }
+        }
+
+        Ok(serialized)
+    }
+
+    /**
+    Type of the serializable resource saved under `name` by `serialize_resources`.
+    */
+    pub fn resource_type_id(&self, name: &str) -> Option<TypeId> {
+        self.resources
+            .iter()
+            .find(|(_, serializer)| serializer.name == name)
+            .or_else(|| {
+                self.resources
+                    .iter()
+                    .find(|(_, serializer)| serializer.type_name == name)
+            })
+            .map(|(type_id, _)| *type_id)
+    }
+}
+
+fn to_ecs_error(error: serde_json::Error) -> ECSError {
+    ECSError::Serialization(error.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Settings {
+        volume: f32,
+        fullscreen: bool,
+    }
+
+    #[test]
+    fn serialize_registered_resource() {
+        let mut registry = SerializationRegistry::default();
+        let mut resources = ResourceStorage::default();
+
+        registry.register_resource::<Settings>();
+        resources
+            .insert(Settings {
+                volume: 0.5,
+                fullscreen: true,
+            })
+            .unwrap();
+
+        let value = registry
+            .serialize_resource(&TypeId::of::<Settings>(), &resources)
+            .unwrap();
+
+        assert_eq!(value["volume"], 0.5);
+        assert_eq!(value["fullscreen"], true);
+    }
+
+    #[test]
+    fn serialize_unregistered_resource_fails() {
+        let registry = SerializationRegistry::default();
+        let resources = ResourceStorage::default();
+
+        let result = registry.serialize_resource(&TypeId::of::<Settings>(), &resources);
+
+        assert!(matches!(result, Err(ECSError::TypeNotSerializable)));
+    }
+
+    #[test]
+    fn serialize_entities_skips_dead_entities_and_unregistered_components() {
+        let mut registry = SerializationRegistry::default();
+        let mut entities = EntityStorage::default();
+
+        registry.register_component::<u32>();
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+        entities.create_entity().with_component(10_u32).unwrap();
+        entities.create_entity().with_component(20_u32).unwrap();
+        entities
+            .create_entity()
+            .with_component(30_u32)
+            .unwrap()
+            .with_component(1.5_f32)
+            .unwrap();
+        entities.remove_entity(1).unwrap();
+
+        let serialized = registry.serialize_entities(&entities).unwrap();
+
+        assert_eq!(serialized.len(), 2);
+        assert_eq!(serialized[0].id, 0);
+        assert_eq!(serialized[1].id, 2);
+        assert_eq!(serialized[1].components.len(), 1);
+        assert_eq!(serialized[1].components["u32"], 30);
+    }
+
+    struct Target(pub usize);
+
+    impl MapEntities for Target {
+        fn map_entities(&mut self, entity_map: &EntityMap) {
+            self.0 = entity_map[&self.0];
+        }
+    }
+
+    #[test]
+    fn map_entities_only_touches_mapped_entities() {
+        let mut registry = SerializationRegistry::default();
+        let mut entities = EntityStorage::default();
+
+        registry.register_entity_mapper::<Target>();
+        entities.register_component::<Target>();
+        entities.create_entity().with_component(Target(0)).unwrap();
+        entities.create_entity().with_component(Target(7)).unwrap();
+
+        registry.map_entities(&entities, &EntityMap::from([(7, 1)]));
+
+        assert_eq!(entities.get_component::<Target>(0).unwrap().0, 0);
+        assert_eq!(entities.get_component::<Target>(1).unwrap().0, 1);
+    }
+
+    #[test]
+    fn migrations_run_in_order_up_to_the_current_version() {
+        let mut registry = SerializationRegistry::default();
+
+        registry.register_component::<u32>();
+        registry.set_component_version::<u32>(2).unwrap();
+        registry
+            .add_component_migration::<u32>(0, |value| Value::from(value.as_u64().unwrap() + 1))
+            .unwrap();
+        registry
+            .add_component_migration::<u32>(1, |value| Value::from(value.as_u64().unwrap() * 10))
+            .unwrap();
+
+        assert_eq!(
+            registry
+                .migrate_component("u32", 0, Value::from(1))
+                .unwrap(),
+            Value::from(20)
+        );
+        assert_eq!(
+            registry
+                .migrate_component("u32", 2, Value::from(1))
+                .unwrap(),
+            Value::from(1)
+        );
+        assert!(matches!(
+            registry.migrate_component("u32", 3, Value::from(1)),
+            Err(ECSError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn missing_migrations_fail() {
+        let mut registry = SerializationRegistry::default();
+
+        registry.register_component::<u32>();
+        registry.set_component_version::<u32>(1).unwrap();
+
+        assert!(matches!(
+            registry.migrate_component("u32", 0, Value::from(1)),
+            Err(ECSError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn resources_roundtrip() {
+        let mut registry = SerializationRegistry::default();
+        let mut resources = ResourceStorage::default();
+
+        registry.register_resource::<Settings>();
+        registry.register_resource::<u32>();
+        resources
+            .insert(Settings {
+                volume: 0.5,
+                fullscreen: false,
+            })
+            .unwrap();
+
+        let serialized = registry.serialize_resources(&resources).unwrap();
+        assert_eq!(serialized.len(), 1);
+
+        let mut loaded = ResourceStorage::default();
+        for (name, value) in serialized {
+            let type_id = registry.resource_type_id(&name).unwrap();
+            registry
+                .deserialize_resource(&type_id, &mut loaded, value)
+                .unwrap();
+        }
+
+        assert_eq!(
+            *loaded.borrow::<Settings>().unwrap(),
+            Settings {
+                volume: 0.5,
+                fullscreen: false
+            }
+        );
+    }
+}
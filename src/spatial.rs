@@ -0,0 +1,241 @@
+use std::any::Any;
+use std::collections::HashMap;
+
+use crate::entity_storage::EntityStorage;
+
+/**
+Implemented by the component holding the position of entities, so they can be tracked by the
+spatial index enabled with `World::enable_spatial_index`.
+*/
+pub trait SpatialPosition: Any {
+    fn position(&self) -> [f32; 2];
+}
+
+type PositionFn = fn(&EntityStorage, usize) -> Option<[f32; 2]>;
+type Cell = (i32, i32);
+
+/**
+Uniform grid bucketing entities by position, used for broad-phase queries like
+`World::entities_within_radius`. Each cell is `cell_size` wide, so it works best when the cell
+size is close to the size of typical queries.
+*/
+#[derive(Debug)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    position_fn: PositionFn,
+    cells: HashMap<Cell, Vec<usize>>,
+    positions: HashMap<usize, [f32; 2]>,
+}
+
+impl SpatialIndex {
+    pub(crate) fn new<P: SpatialPosition>(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            position_fn: |entity_storage, entity_id| {
+                entity_storage
+                    .get_component::<P>(entity_id)
+                    .ok()
+                    .map(|position| position.position())
+            },
+            cells: HashMap::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /**
+    Position of an entity as of the last time it was indexed.
+    */
+    pub fn position(&self, entity_id: usize) -> Option<[f32; 2]> {
+        self.positions.get(&entity_id).copied()
+    }
+
+    pub(crate) fn current_position(
+        &self,
+        entity_storage: &EntityStorage,
+        entity_id: usize,
+    ) -> Option<[f32; 2]> {
+        (self.position_fn)(entity_storage, entity_id)
+    }
+
+    pub(crate) fn insert(&mut self, entity_id: usize, position: [f32; 2]) {
+        self.remove(entity_id);
+        self.cells
+            .entry(self.cell(position))
+            .or_default()
+            .push(entity_id);
+        self.positions.insert(entity_id, position);
+    }
+
+    pub(crate) fn remove(&mut self, entity_id: usize) {
+        let Some(position) = self.positions.remove(&entity_id) else {
+            return;
+        };
+        let cell = self.cell(position);
+
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            entities.retain(|indexed| *indexed != entity_id);
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /**
+    Moves the entities whose position changed since they were indexed, and returns how many
+    moved. Only entities that were already indexed are checked.
+    */
+    pub(crate) fn update(&mut self, entity_storage: &EntityStorage) -> usize {
+        let moved: Vec<(usize, Option<[f32; 2]>)> = self
+            .positions
+            .iter()
+            .filter_map(|(entity_id, indexed)| {
+                let current = self.current_position(entity_storage, *entity_id);
+                (current != Some(*indexed)).then_some((*entity_id, current))
+            })
+            .collect();
+
+        for (entity_id, current) in &moved {
+            match current {
+                Some(position) => self.insert(*entity_id, *position),
+                None => self.remove(*entity_id),
+            }
+        }
+
+        moved.len()
+    }
+
+    pub(crate) fn rebuild(&mut self, entity_storage: &EntityStorage) {
+        self.cells.clear();
+        self.positions.clear();
+
+        for entity_id in 0..entity_storage.entity_count() {
+            if let Some(position) = self.current_position(entity_storage, entity_id) {
+                self.insert(entity_id, position);
+            }
+        }
+    }
+
+    /**
+    Entities inside the axis-aligned box going from `min` to `max`, edges included, sorted by ID.
+    */
+    pub fn entities_in_aabb(&self, min: [f32; 2], max: [f32; 2]) -> Vec<usize> {
+        self.candidates(min, max, |[x, y]| {
+            x >= min[0] && x <= max[0] && y >= min[1] && y <= max[1]
+        })
+    }
+
+    /**
+    Entities at a distance of `radius` or less from `center`, sorted by ID.
+    */
+    pub fn entities_within_radius(&self, center: [f32; 2], radius: f32) -> Vec<usize> {
+        let min = [center[0] - radius, center[1] - radius];
+        let max = [center[0] + radius, center[1] + radius];
+
+        self.candidates(min, max, |[x, y]| {
+            let (dx, dy) = (x - center[0], y - center[1]);
+            dx * dx + dy * dy <= radius * radius
+        })
+    }
+
+    fn candidates(
+        &self,
+        min: [f32; 2],
+        max: [f32; 2],
+        contains: impl Fn([f32; 2]) -> bool,
+    ) -> Vec<usize> {
+        let (min_x, min_y) = self.cell(min);
+        let (max_x, max_y) = self.cell(max);
+        let mut entities = vec![];
+
+        for cell_x in min_x..=max_x {
+            for cell_y in min_y..=max_y {
+                let Some(cell) = self.cells.get(&(cell_x, cell_y)) else {
+                    continue;
+                };
+
+                entities.extend(
+                    cell.iter()
+                        .copied()
+                        .filter(|entity_id| contains(self.positions[entity_id])),
+                );
+            }
+        }
+
+        entities.sort_unstable();
+        entities
+    }
+
+    fn cell(&self, [x, y]: [f32; 2]) -> Cell {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Position(pub f32, pub f32);
+
+    impl SpatialPosition for Position {
+        fn position(&self) -> [f32; 2] {
+            [self.0, self.1]
+        }
+    }
+
+    fn index() -> SpatialIndex {
+        let mut index = SpatialIndex::new::<Position>(10.0);
+
+        index.insert(0, [0.0, 0.0]);
+        index.insert(1, [5.0, 5.0]);
+        index.insert(2, [-15.0, 3.0]);
+        index.insert(3, [40.0, 40.0]);
+        index
+    }
+
+    #[test]
+    fn aabb_queries_span_several_cells() {
+        let index = index();
+
+        assert_eq!(
+            index.entities_in_aabb([-20.0, 0.0], [5.0, 5.0]),
+            vec![0, 1, 2]
+        );
+        assert!(index
+            .entities_in_aabb([50.0, 50.0], [60.0, 60.0])
+            .is_empty());
+    }
+
+    #[test]
+    fn radius_queries_check_the_exact_distance() {
+        let index = index();
+
+        assert_eq!(index.entities_within_radius([0.0, 0.0], 7.1), vec![0, 1]);
+        assert_eq!(index.entities_within_radius([0.0, 0.0], 7.0), vec![0]);
+    }
+
+    #[test]
+    fn reinserting_moves_entities() {
+        let mut index = index();
+
+        index.insert(3, [1.0, 1.0]);
+        index.remove(0);
+
+        assert_eq!(index.entities_within_radius([0.0, 0.0], 2.0), vec![3]);
+        assert_eq!(index.len(), 3);
+    }
+}
@@ -0,0 +1,327 @@
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+use std::sync::Arc;
+use std::task::{Context, Waker};
+#[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+use std::{
+    pin::pin,
+    task::{Poll, Wake},
+    thread::{self, Thread},
+};
+
+use crate::commands::Commands;
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+// Callback recording the result of a task, or the message of its panic.
+type Completion = Result<Box<dyn FnOnce(&mut Commands) + Send>, String>;
+type PendingFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/**
+Resource running work in the background, like loading assets or pathfinding, and bringing the
+results back to the world. Each task runs on its own thread, and once it finishes its
+`on_complete` callback records the changes to make into a command buffer, which is applied
+the next time `poll_tasks` runs.
+
+A task that panics still counts as finished: its callback is skipped and the panic is
+reported by `apply_completed_tasks`.
+
+Without the `multithreaded` feature, and always on `wasm32`, tasks run on the calling thread
+instead: `spawn` runs the task right away, and futures given to `spawn_async` are polled each
+time the completed tasks are applied, so they shouldn't block.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::tasks::{apply_completed_tasks, Tasks};
+
+struct Path(pub Vec<(i32, i32)>);
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Path>();
+    world.init_resource::<Tasks>();
+
+    let reserver = world.entities().reserver();
+
+    world.get_resource_mut::<Tasks>().unwrap().spawn(
+        move || (reserver.reserve(), vec![(0, 0), (1, 0), (1, 1)]),
+        |(walker, path), commands| {
+            commands.entity(walker).insert(Path(path));
+        },
+    );
+
+    while world.get_resource::<Tasks>().unwrap().running() > 0 {
+        apply_completed_tasks(&mut world)?;
+    }
+
+    let walkers = world.query().with_component_filter::<Path>()?.run();
+    assert_eq!(walkers.entity_ids.len(), 1);
+    Ok(())
+}
+```
+*/
+pub struct Tasks {
+    sender: Sender<Completion>,
+    receiver: Receiver<Completion>,
+    // Futures of `spawn_async` waiting to be polled when running single-threaded.
+    pending: Vec<PendingFuture>,
+    running: usize,
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            sender,
+            receiver,
+            pending: vec![],
+            running: 0,
+        }
+    }
+}
+
+impl std::fmt::Debug for Tasks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tasks")
+            .field("running", &self.running)
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl Tasks {
+    pub fn new() -> Self {
+        Tasks::default()
+    }
+
+    /**
+    Runs `task` on a new thread. When it's done, `on_complete` is given its result and a
+    command buffer to record changes to the world.
+    */
+    pub fn spawn<R: Send + 'static>(
+        &mut self,
+        task: impl FnOnce() -> R + Send + 'static,
+        on_complete: impl FnOnce(R, &mut Commands) + Send + 'static,
+    ) {
+        let sender = self.sender.clone();
+        let run = move || {
+            let completion: Completion = match panic::catch_unwind(AssertUnwindSafe(task)) {
+                Ok(result) => Ok(Box::new(move |commands| on_complete(result, commands))),
+                Err(payload) => Err(panic_message(payload.as_ref())),
+            };
+
+            // The receiver is gone if the world was dropped, in which case the result is too.
+            let _ = sender.send(completion);
+        };
+
+        self.running += 1;
+
+        #[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+        thread::spawn(run);
+        #[cfg(not(all(feature = "multithreaded", not(target_arch = "wasm32"))))]
+        run();
+    }
+
+    /**
+    Like `spawn`, but drives a future to completion on the new thread.
+    */
+    pub fn spawn_async<F>(
+        &mut self,
+        future: F,
+        on_complete: impl FnOnce(F::Output, &mut Commands) + Send + 'static,
+    ) where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        #[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+        self.spawn(move || block_on(future), on_complete);
+
+        #[cfg(not(all(feature = "multithreaded", not(target_arch = "wasm32"))))]
+        {
+            let sender = self.sender.clone();
+
+            self.running += 1;
+            self.pending.push(Box::pin(async move {
+                let result = future.await;
+                let completion: Completion =
+                    Ok(Box::new(move |commands| on_complete(result, commands)));
+
+                let _ = sender.send(completion);
+            }));
+        }
+    }
+
+    /**
+    Number of tasks whose results haven't been applied yet.
+    */
+    pub fn running(&self) -> usize {
+        self.running
+    }
+
+    // Records the results of the tasks that finished since the last call, returning the
+    // messages of the ones that panicked.
+    fn completed(&mut self) -> (Commands, Vec<String>) {
+        let mut commands = Commands::new();
+        let mut panics = vec![];
+        let mut context = Context::from_waker(Waker::noop());
+
+        self.pending.retain_mut(|future| {
+            match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(&mut context))) {
+                Ok(poll) => poll.is_pending(),
+                Err(payload) => {
+                    panics.push(panic_message(payload.as_ref()));
+                    false
+                }
+            }
+        });
+        // Futures that panicked never send their completion.
+        self.running -= panics.len();
+
+        for completion in self.receiver.try_iter() {
+            match completion {
+                Ok(on_complete) => on_complete(&mut commands),
+                Err(message) => panics.push(message),
+            }
+            self.running -= 1;
+        }
+
+        (commands, panics)
+    }
+}
+
+/**
+Applies the results of the background tasks that finished, see `Tasks`. Does nothing if the
+world has no `Tasks` resource. Fails with `TasksPanicked` if some of them panicked, after
+applying the results of the others.
+*/
+pub fn apply_completed_tasks(world: &mut World) -> Result<(), ECSError> {
+    let Some((commands, panics)) = world
+        .get_resource_mut::<Tasks>()
+        .map(|mut tasks| tasks.completed())
+    else {
+        return Ok(());
+    };
+
+    let applied = world.apply_commands(commands);
+
+    if !panics.is_empty() {
+        return Err(ECSError::TasksPanicked(panics));
+    }
+
+    applied
+}
+
+/**
+System applying the results of the background tasks that finished, to add to a schedule.
+Commands that fail, for example because their entity was removed in the meantime, and tasks
+that panicked are dropped; use `apply_completed_tasks` to handle the errors.
+*/
+pub fn poll_tasks(world: &mut World) {
+    let _ = apply_completed_tasks(world);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_owned())
+}
+
+#[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+struct ThreadWaker(Thread);
+
+#[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Polls a future on the current thread, parking it until the future is woken.
+#[cfg(all(feature = "multithreaded", not(target_arch = "wasm32")))]
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Loaded(pub u32);
+
+    #[test]
+    fn async_task_results_are_applied() {
+        let mut world = World::new();
+        let mut tasks = Tasks::new();
+
+        world.register_component::<Loaded>();
+
+        let entity_id = world.entities().reserve();
+        tasks.spawn_async(async { 21 * 2 }, move |value, commands| {
+            commands.entity(entity_id).insert(Loaded(value));
+        });
+        world.add_resource(tasks).unwrap();
+
+        while world.get_resource::<Tasks>().unwrap().running() > 0 {
+            poll_tasks(&mut world);
+        }
+
+        let entities = world
+            .query()
+            .with_component_filter::<Loaded>()
+            .unwrap()
+            .get_entities();
+        assert_eq!(entities[0].get_component::<Loaded>().unwrap().0, 42);
+    }
+
+    #[test]
+    fn panicking_tasks_are_reported() {
+        let mut world = World::new();
+        let mut tasks = Tasks::new();
+
+        world.register_component::<Loaded>();
+
+        let entity_id = world.entities().reserve();
+        tasks.spawn(|| -> u32 { panic!("asset missing") }, |_, _| {});
+        tasks.spawn(
+            || 7,
+            move |value, commands| {
+                commands.entity(entity_id).insert(Loaded(value));
+            },
+        );
+        world.add_resource(tasks).unwrap();
+
+        let mut panics = vec![];
+        while world.get_resource::<Tasks>().unwrap().running() > 0 {
+            if let Err(ECSError::TasksPanicked(messages)) = apply_completed_tasks(&mut world) {
+                panics.extend(messages);
+            }
+        }
+
+        assert_eq!(panics, vec!["asset missing".to_owned()]);
+        let loaded = world
+            .query()
+            .with_component_filter::<Loaded>()
+            .unwrap()
+            .run();
+        assert_eq!(loaded.entity_ids, vec![entity_id]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use crate::World;
+
+/**
+Clock of the world: how long the last tick lasted, the total time elapsed and the number of
+ticks, all affected by the time scale. Advanced with `World::advance_time`, or by the
+`update_time` system from the real time between runs.
+
+Example:
+```
+use std::time::Duration;
+use sara_ecs::World;
+use sara_ecs::time::Time;
+
+let mut world = World::new();
+
+world.advance_time(Duration::from_millis(16));
+world.get_resource_mut::<Time>().unwrap().set_time_scale(0.5);
+world.advance_time(Duration::from_millis(16));
+
+let time = world.get_resource::<Time>().unwrap();
+
+assert_eq!(time.tick(), 2);
+assert_eq!(time.delta(), Duration::from_millis(8));
+assert_eq!(time.elapsed(), Duration::from_millis(24));
+```
+*/
+#[derive(Debug, Clone)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    tick: u64,
+    time_scale: f32,
+    #[cfg(not(target_arch = "wasm32"))]
+    last_update: Option<Instant>,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            tick: 0,
+            time_scale: 1.0,
+            #[cfg(not(target_arch = "wasm32"))]
+            last_update: None,
+        }
+    }
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Time::default()
+    }
+
+    /**
+    Scaled duration of the last tick.
+    */
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /**
+    Scaled time elapsed since the first tick.
+    */
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    /**
+    Number of ticks so far.
+    */
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /**
+    Multiplies the duration of the following ticks, to slow the game down or speed it up.
+    `0.0` pauses it, while ticks are still counted. Negative scales are treated as `0.0`.
+    */
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /**
+    Starts a new tick that lasted `real_delta` before scaling.
+    */
+    pub fn advance(&mut self, real_delta: Duration) {
+        self.delta = real_delta.mul_f64(f64::from(self.time_scale));
+        self.elapsed += self.delta;
+        self.tick += 1;
+    }
+
+    // Starts a new tick that lasted the real time since the previous one. The first tick
+    // lasts nothing.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn advance_to(&mut self, now: Instant) {
+        let real_delta = self.last_update.map_or(Duration::ZERO, |last_update| {
+            now.duration_since(last_update)
+        });
+
+        self.last_update = Some(now);
+        self.advance(real_delta);
+    }
+}
+
+/**
+System advancing the `Time` resource by the real time elapsed since its previous run, adding
+the resource if the world doesn't have one. Meant to be the first system of each tick.
+Not available on `wasm32`, where the clock can't be read; pass the frame time given by the
+browser to `World::advance_time` instead.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+pub fn update_time(world: &mut World) {
+    world
+        .get_resource_or_insert_with(Time::new)
+        .advance_to(Instant::now());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pausing_still_counts_ticks() {
+        let mut time = Time::new();
+
+        time.set_time_scale(0.0);
+        time.advance(Duration::from_secs(1));
+
+        assert_eq!(
+            (time.tick(), time.delta(), time.elapsed()),
+            (1, Duration::ZERO, Duration::ZERO)
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn real_time_is_measured_between_updates() {
+        let mut time = Time::new();
+        let start = Instant::now();
+
+        time.advance_to(start);
+        time.advance_to(start + Duration::from_millis(20));
+
+        assert_eq!(time.delta(), Duration::from_millis(20));
+        assert_eq!(time.tick(), 2);
+    }
+}
@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use crate::time::Time;
+use crate::World;
+
+/**
+Component counting down a duration, ticked by the `tick_timers` system. Repeating timers start
+over when they finish, carrying over the extra time.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    repeating: bool,
+    // Times the timer finished during the last tick.
+    times_finished: u32,
+}
+
+impl Timer {
+    pub fn once(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            repeating: false,
+            times_finished: 0,
+        }
+    }
+
+    pub fn repeating(duration: Duration) -> Self {
+        Self {
+            repeating: true,
+            ..Timer::once(duration)
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    /**
+    Elapsed part of the duration, from `0.0` to `1.0`.
+    */
+    pub fn fraction(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    /**
+    Whether a non-repeating timer ran out.
+    */
+    pub fn finished(&self) -> bool {
+        !self.repeating && self.elapsed >= self.duration
+    }
+
+    /**
+    Whether the timer finished during the last tick.
+    */
+    pub fn just_finished(&self) -> bool {
+        self.times_finished > 0
+    }
+
+    /**
+    Times the timer finished during the last tick, which can be more than one for a repeating
+    timer shorter than the tick.
+    */
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.times_finished = 0;
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.times_finished = 0;
+
+        if self.finished() {
+            return;
+        }
+
+        self.elapsed += delta;
+
+        if self.elapsed < self.duration {
+            return;
+        }
+
+        if !self.repeating || self.duration.is_zero() {
+            self.elapsed = self.duration;
+            self.times_finished = 1;
+            return;
+        }
+
+        let duration = self.duration.as_nanos();
+        let elapsed = self.elapsed.as_nanos();
+
+        self.times_finished = (elapsed / duration) as u32;
+        self.elapsed = Duration::from_nanos((elapsed % duration) as u64);
+    }
+}
+
+/**
+Component removing its entity once the time runs out, ticked by the `despawn_expired`
+system. Useful for projectiles and particles.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lifetime(pub Duration);
+
+/**
+Sent by `tick_timers` for every timer that finished, if the event type was registered with
+`World::add_event`. Holds the ID of the entity with the timer.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerFinished(pub usize);
+
+/**
+System ticking every `Timer` by the delta of the `Time` resource, and sending a
+`TimerFinished` event for each one that finished.
+
+Example:
+```
+use std::time::Duration;
+use sara_ecs::World;
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::timer::{despawn_expired, tick_timers, Lifetime, Timer, TimerFinished};
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Timer>();
+    world.register_component::<Lifetime>();
+    world.add_event::<TimerFinished>();
+
+    let spawner = world
+        .create_entity()
+        .with_component(Timer::repeating(Duration::from_secs(1)))?
+        .id();
+    let bullet = world
+        .create_entity()
+        .with_component(Lifetime(Duration::from_millis(500)))?
+        .id();
+
+    world.advance_time(Duration::from_secs(1));
+    tick_timers(&mut world);
+    despawn_expired(&mut world);
+
+    let finished: Vec<TimerFinished> = world.get_events::<TimerFinished>().unwrap().iter().copied().collect();
+
+    assert_eq!(finished, vec![TimerFinished(spawner)]);
+    assert!(world.entity(bullet).is_err());
+    Ok(())
+}
+```
+*/
+pub fn tick_timers(world: &mut World) {
+    let delta = time_delta(world);
+    let Ok(mut query) = world
+        .query()
+        .with_component_filter::<Timer>()
+        .map(|query| query.get_entities())
+    else {
+        return;
+    };
+    let mut finished = vec![];
+
+    for entity in &mut query {
+        let entity_id = entity.id;
+        let Ok(mut timer) = entity.get_component_mut::<Timer>() else {
+            continue;
+        };
+
+        timer.tick(delta);
+        for _ in 0..timer.times_finished() {
+            finished.push(entity_id);
+        }
+    }
+
+    drop(query);
+
+    for entity_id in finished {
+        world.send_event_if_registered(TimerFinished(entity_id));
+    }
+}
+
+/**
+System counting down every `Lifetime` by the delta of the `Time` resource, and removing the
+entities whose lifetime ran out. See `tick_timers` for an example.
+*/
+pub fn despawn_expired(world: &mut World) {
+    let delta = time_delta(world);
+    let Ok(mut query) = world
+        .query()
+        .with_component_filter::<Lifetime>()
+        .map(|query| query.get_entities())
+    else {
+        return;
+    };
+    let mut expired = vec![];
+
+    for entity in &mut query {
+        let entity_id = entity.id;
+        let Ok(mut lifetime) = entity.get_component_mut::<Lifetime>() else {
+            continue;
+        };
+
+        lifetime.0 = lifetime.0.saturating_sub(delta);
+        if lifetime.0.is_zero() {
+            expired.push(entity_id);
+        }
+    }
+
+    drop(query);
+
+    for entity_id in expired {
+        let _ = world.remove_entity(entity_id);
+    }
+}
+
+fn time_delta(world: &World) -> Duration {
+    world
+        .get_resource::<Time>()
+        .map_or(Duration::ZERO, |time| time.delta())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeating_timers_carry_over_extra_time() {
+        let mut timer = Timer::repeating(Duration::from_millis(100));
+
+        timer.tick(Duration::from_millis(250));
+
+        assert_eq!(timer.times_finished(), 2);
+        assert_eq!(timer.elapsed(), Duration::from_millis(50));
+        assert!(!timer.finished());
+    }
+
+    #[test]
+    fn one_shot_timers_finish_once() {
+        let mut timer = Timer::once(Duration::from_millis(100));
+
+        timer.tick(Duration::from_millis(150));
+        assert!(timer.just_finished() && timer.finished());
+
+        timer.tick(Duration::from_millis(150));
+        assert!(!timer.just_finished() && timer.finished());
+        assert_eq!(timer.fraction(), 1.0);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::World;
+
+/**
+Position, rotation (in radians) and scale of an entity relative to its parent, or to the
+world if it has no parent. Requires the `transform` feature.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform {
+    pub translation: [f32; 2],
+    pub rotation: f32,
+    pub scale: [f32; 2],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+        }
+    }
+}
+
+impl Transform {
+    pub fn from_translation(x: f32, y: f32) -> Self {
+        Self {
+            translation: [x, y],
+            ..Default::default()
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, x: f32, y: f32) -> Self {
+        self.scale = [x, y];
+        self
+    }
+
+    fn matrix(&self) -> [[f32; 3]; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let [x, y] = self.translation;
+        let [scale_x, scale_y] = self.scale;
+
+        [
+            [cos * scale_x, -sin * scale_y, x],
+            [sin * scale_x, cos * scale_y, y],
+        ]
+    }
+}
+
+/**
+Transform of an entity relative to the world, computed by `propagate_transforms` by composing
+the `Transform`s of the entity and its ancestors. Stored as a 2x3 affine matrix, since scaled
+and rotated parents can skew their children.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlobalTransform {
+    matrix: [[f32; 3]; 2],
+    // What the matrix was computed from, used to skip entities that didn't change.
+    local: Transform,
+    parent: Option<usize>,
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Transform::default().matrix(),
+            local: Transform::default(),
+            parent: None,
+        }
+    }
+}
+
+impl GlobalTransform {
+    pub fn matrix(&self) -> [[f32; 3]; 2] {
+        self.matrix
+    }
+
+    pub fn translation(&self) -> [f32; 2] {
+        [self.matrix[0][2], self.matrix[1][2]]
+    }
+
+    pub fn transform_point(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [row_x, row_y] = self.matrix;
+
+        [
+            row_x[0] * x + row_x[1] * y + row_x[2],
+            row_y[0] * x + row_y[1] * y + row_y[2],
+        ]
+    }
+
+    fn compose(parent: &[[f32; 3]; 2], local: &[[f32; 3]; 2]) -> [[f32; 3]; 2] {
+        let row = |row: &[f32; 3]| {
+            [
+                row[0] * local[0][0] + row[1] * local[1][0],
+                row[0] * local[0][1] + row[1] * local[1][1],
+                row[0] * local[0][2] + row[1] * local[1][2] + row[2],
+            ]
+        };
+
+        [row(&parent[0]), row(&parent[1])]
+    }
+}
+
+/**
+System updating the `GlobalTransform` of every entity with a `Transform`, walking down the
+hierarchy from the roots. Entities whose `Transform` and parent didn't change since the last
+run, and whose ancestors didn't change either, keep their `GlobalTransform` untouched.
+Children without a `Transform` are skipped along with their descendants.
+Requires the `transform` feature and `World::register_transforms`.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::schedule::Schedule;
+use sara_ecs::transform::{propagate_transforms, GlobalTransform, Transform};
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+    let mut schedule = Schedule::new();
+
+    world.register_transforms();
+    schedule.add_system(propagate_transforms);
+
+    let ship = world.create_entity().with_component(Transform::from_translation(10.0, 0.0))?.id();
+    let turret = world.create_entity().with_component(Transform::from_translation(0.0, 2.0))?.id();
+    world.set_parent(turret, ship)?;
+
+    schedule.run(&mut world);
+
+    let entities = world.query().with_component_filter::<GlobalTransform>()?.get_entities();
+    assert_eq!(entities[1].id, turret);
+    assert_eq!(entities[1].get_component::<GlobalTransform>()?.translation(), [10.0, 2.0]);
+
+    Ok(())
+}
+```
+*/
+pub fn propagate_transforms(world: &mut World) {
+    let Ok(query) = world
+        .query()
+        .with_component_filter::<Transform>()
+        .map(|query| query.run())
+    else {
+        return;
+    };
+
+    for entity_id in query.entity_ids {
+        if world.parent(entity_id).is_none() {
+            propagate(world, entity_id, None, false);
+        }
+    }
+}
+
+fn propagate(
+    world: &mut World,
+    entity_id: usize,
+    parent: Option<(usize, [[f32; 3]; 2])>,
+    parent_changed: bool,
+) {
+    let Ok(local) = world
+        .entitiy_storage
+        .get_component::<Transform>(entity_id)
+        .map(|transform| *transform)
+    else {
+        return;
+    };
+
+    let parent_id = parent.map(|(parent_id, _matrix)| parent_id);
+    let current = world
+        .entitiy_storage
+        .get_component::<GlobalTransform>(entity_id)
+        .ok()
+        .map(|global| *global);
+    let changed = parent_changed
+        || current.is_none_or(|global| global.local != local || global.parent != parent_id);
+
+    let global = match current {
+        Some(global) if !changed => global,
+        _ => {
+            let matrix = match parent {
+                Some((_parent_id, parent_matrix)) => {
+                    GlobalTransform::compose(&parent_matrix, &local.matrix())
+                }
+                None => local.matrix(),
+            };
+            let global = GlobalTransform {
+                matrix,
+                local,
+                parent: parent_id,
+            };
+
+            if current.is_some() {
+                if let Ok(mut current) = world
+                    .entitiy_storage
+                    .get_component_mut::<GlobalTransform>(entity_id)
+                {
+                    *current = global;
+                }
+            } else {
+                let _ = world.add_component_to_entity(entity_id, global);
+            }
+            global
+        }
+    };
+
+    for child in world.children(entity_id).collect::<Vec<usize>>() {
+        propagate(world, child, Some((entity_id, global.matrix)), changed);
+    }
+}
@@ -0,0 +1,256 @@
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::mem::size_of;
+
+#[cfg(feature = "serde")]
+use crate::ecs_errors::ECSError;
+use crate::reflect::Reflect;
+
+pub type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
+pub type DefaultFn = fn() -> Box<dyn Any>;
+pub type DebugFn = fn(&dyn Any) -> String;
+pub type ReflectFn = fn(&dyn Any) -> Option<&dyn Reflect>;
+pub type ReflectMutFn = fn(&mut dyn Any) -> Option<&mut dyn Reflect>;
+#[cfg(feature = "serde")]
+pub type SerializeFn = fn(&dyn Any) -> Result<serde_json::Value, ECSError>;
+#[cfg(feature = "serde")]
+pub type DeserializeFn = fn(serde_json::Value) -> Result<Box<dyn Any>, ECSError>;
+
+/**
+Everything the registry knows about one type. The function pointers work on type-erased values,
+so tools can clone, create, save and load values without knowing the concrete type.
+*/
+#[derive(Debug, Clone)]
+pub struct TypeRegistration {
+    pub type_id: TypeId,
+    pub name: &'static str,
+    pub short_name: String,
+    // Size of a value of the type, in bytes.
+    pub size: usize,
+    pub clone: Option<CloneFn>,
+    pub default: Option<DefaultFn>,
+    pub debug: Option<DebugFn>,
+    pub reflect: Option<ReflectFn>,
+    pub reflect_mut: Option<ReflectMutFn>,
+    #[cfg(feature = "serde")]
+    pub serialize: Option<SerializeFn>,
+    #[cfg(feature = "serde")]
+    pub deserialize: Option<DeserializeFn>,
+}
+
+impl TypeRegistration {
+    fn of<T: Any>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            name: type_name::<T>(),
+            short_name: short_name(type_name::<T>()),
+            size: size_of::<T>(),
+            clone: None,
+            default: None,
+            debug: None,
+            reflect: None,
+            reflect_mut: None,
+            #[cfg(feature = "serde")]
+            serialize: None,
+            #[cfg(feature = "serde")]
+            deserialize: None,
+        }
+    }
+}
+
+/**
+Maps type names to `TypeId`s and stores type-erased functions for every registered type.
+The world keeps one as a resource, and registers every component type in it.
+*/
+#[derive(Default, Debug)]
+pub struct TypeRegistry {
+    registrations: HashMap<TypeId, TypeRegistration>,
+    names: HashMap<String, TypeId>,
+}
+
+impl TypeRegistry {
+    pub fn register<T: Any>(&mut self) -> &mut TypeRegistration {
+        let type_id = TypeId::of::<T>();
+
+        if !self.registrations.contains_key(&type_id) {
+            let registration = TypeRegistration::of::<T>();
+
+            self.names.insert(registration.name.to_owned(), type_id);
+            self.names
+                .entry(registration.short_name.clone())
+                .or_insert(type_id);
+            self.registrations.insert(type_id, registration);
+        }
+
+        self.registrations.get_mut(&type_id).unwrap()
+    }
+
+    pub fn register_clone<T: Clone + Any>(&mut self) {
+        self.register::<T>().clone = Some(|value| {
+            let value = value.downcast_ref::<T>().unwrap();
+            Box::new(value.clone())
+        });
+    }
+
+    pub fn register_default<T: Default + Any>(&mut self) {
+        self.register::<T>().default = Some(|| Box::new(T::default()));
+    }
+
+    pub fn register_debug<T: Debug + Any>(&mut self) {
+        self.register::<T>().debug = Some(|value| {
+            let value = value.downcast_ref::<T>().unwrap();
+            format!("{value:?}")
+        });
+    }
+
+    pub fn register_reflect<T: Reflect>(&mut self) {
+        let registration = self.register::<T>();
+
+        registration.reflect = Some(|value| {
+            let value: &dyn Reflect = value.downcast_ref::<T>()?;
+            Some(value)
+        });
+        registration.reflect_mut = Some(|value| {
+            let value: &mut dyn Reflect = value.downcast_mut::<T>()?;
+            Some(value)
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn register_serde<T>(&mut self)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Any,
+    {
+        let registration = self.register::<T>();
+
+        registration.serialize = Some(|value| {
+            let value = value.downcast_ref::<T>().unwrap();
+            serde_json::to_value(value).map_err(|error| ECSError::Serialization(error.to_string()))
+        });
+        registration.deserialize = Some(|value| {
+            let value: T = serde_json::from_value(value)
+                .map_err(|error| ECSError::Serialization(error.to_string()))?;
+            Ok(Box::new(value))
+        });
+    }
+
+    pub fn get(&self, type_id: &TypeId) -> Option<&TypeRegistration> {
+        self.registrations.get(type_id)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&TypeRegistration> {
+        self.registrations.get(self.names.get(name)?)
+    }
+
+    pub fn type_id_by_name(&self, name: &str) -> Option<TypeId> {
+        self.names.get(name).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TypeRegistration> {
+        self.registrations.values()
+    }
+}
+
+// Strips the module path from every segment of a type name, so
+// `alloc::vec::Vec<my_game::Health>` becomes `Vec<Health>`.
+pub(crate) fn short_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+
+    for character in name.chars() {
+        if character.is_alphanumeric() || character == '_' || character == ':' {
+            segment.push(character);
+        } else {
+            short.push_str(segment.rsplit("::").next().unwrap_or_default());
+            short.push(character);
+            segment.clear();
+        }
+    }
+
+    short.push_str(segment.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone, Default, Debug, PartialEq)]
+    struct Health(pub u32);
+
+    #[test]
+    fn short_names_strip_module_paths() {
+        assert_eq!(short_name("my_game::Health"), "Health");
+        assert_eq!(
+            short_name("alloc::vec::Vec<my_game::Health>"),
+            "Vec<Health>"
+        );
+        assert_eq!(
+            short_name("(u32, core::option::Option<f32>)"),
+            "(u32, Option<f32>)"
+        );
+    }
+
+    #[test]
+    fn lookup_by_full_and_short_name() {
+        let mut registry = TypeRegistry::default();
+
+        registry.register::<Health>();
+
+        let full_name = type_name::<Health>();
+        assert_eq!(
+            registry.type_id_by_name(full_name),
+            Some(TypeId::of::<Health>())
+        );
+        assert_eq!(
+            registry.type_id_by_name("Health"),
+            Some(TypeId::of::<Health>())
+        );
+        assert!(registry.get_by_name("Speed").is_none());
+    }
+
+    #[test]
+    fn clone_and_default_functions() {
+        let mut registry = TypeRegistry::default();
+
+        registry.register_clone::<Health>();
+        registry.register_default::<Health>();
+
+        let registration = registry.get(&TypeId::of::<Health>()).unwrap();
+
+        let cloned = (registration.clone.unwrap())(&Health(10));
+        assert_eq!(cloned.downcast_ref::<Health>(), Some(&Health(10)));
+
+        let default = (registration.default.unwrap())();
+        assert_eq!(default.downcast_ref::<Health>(), Some(&Health(0)));
+    }
+
+    #[test]
+    fn debug_function() {
+        let mut registry = TypeRegistry::default();
+
+        registry.register_debug::<Health>();
+
+        let debug = registry
+            .get(&TypeId::of::<Health>())
+            .unwrap()
+            .debug
+            .unwrap();
+        assert_eq!(debug(&Health(10)), "Health(10)");
+    }
+
+    #[test]
+    fn registering_twice_keeps_functions() {
+        let mut registry = TypeRegistry::default();
+
+        registry.register_clone::<Health>();
+        registry.register::<Health>();
+
+        assert!(registry
+            .get(&TypeId::of::<Health>())
+            .unwrap()
+            .clone
+            .is_some());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::checkpoint::WorldCheckpoint;
+use crate::ecs_errors::ECSError;
+use crate::World;
+
+/**
+An operation recorded by an `UndoStack`, with the state of the world before and after it.
+*/
+#[derive(Debug)]
+pub struct UndoEntry {
+    pub label: String,
+    before: WorldCheckpoint,
+    after: WorldCheckpoint,
+}
+
+/**
+Undo and redo history of labeled operations on a world, for editors. Each recorded operation
+keeps a checkpoint of the world taken before and after it, so undoing and redoing restore
+them instead of running anything again. Like `World::checkpoint`, only entities and components
+are saved, every component needs a clone function, and resources are left as they are. Once
+`limit` operations are recorded, the oldest ones are forgotten.
+
+Example:
+```
+use sara_ecs::World;
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::undo::UndoStack;
+
+#[derive(Clone)]
+struct Health(pub u32);
+
+fn example() -> Result<(), ECSError> {
+    let mut world = World::new();
+    let mut history = UndoStack::new(100);
+
+    world.register_cloneable_component::<Health>();
+
+    history.record(&mut world, "Spawn enemy", |world| {
+        world.create_entity().with_component(Health(50))?;
+        Ok(())
+    })?;
+
+    assert_eq!(history.undo(&mut world)?.as_deref(), Some("Spawn enemy"));
+    assert!(world.entities().is_empty());
+
+    history.redo(&mut world)?;
+    assert_eq!(world.entities().len(), 1);
+
+    Ok(())
+}
+```
+*/
+#[derive(Debug)]
+pub struct UndoStack {
+    limit: usize,
+    undo: Vec<UndoEntry>,
+    redo: Vec<UndoEntry>,
+}
+
+impl UndoStack {
+    pub fn new(limit: usize) -> Self {
+        UndoStack {
+            limit,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /**
+    Runs `operation` on the world and records it under `label`, dropping the operations that
+    were undone. If the operation fails, the world is put back as it was and nothing is
+    recorded.
+    */
+    pub fn record<R>(
+        &mut self,
+        world: &mut World,
+        label: impl Into<String>,
+        operation: impl FnOnce(&mut World) -> Result<R, ECSError>,
+    ) -> Result<R, ECSError> {
+        let before = world.checkpoint()?;
+        let result = match operation(world) {
+            Ok(result) => result,
+            Err(error) => {
+                world.restore(&before)?;
+                return Err(error);
+            }
+        };
+        let after = world.checkpoint()?;
+
+        self.redo.clear();
+
+        if self.limit == 0 {
+            return Ok(result);
+        }
+
+        if self.undo.len() == self.limit {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(UndoEntry {
+            label: label.into(),
+            before,
+            after,
+        });
+
+        Ok(result)
+    }
+
+    /**
+    Puts the world back as it was before the last recorded operation. Returns its label, or
+    `None` if there was nothing to undo.
+    */
+    pub fn undo(&mut self, world: &mut World) -> Result<Option<String>, ECSError> {
+        let Some(entry) = self.undo.last() else {
+            return Ok(None);
+        };
+
+        // The entry stays undoable if the checkpoint can't be restored.
+        world.restore(&entry.before)?;
+
+        let entry = self.undo.pop().expect("the entry was just restored");
+        let label = entry.label.clone();
+        self.redo.push(entry);
+
+        Ok(Some(label))
+    }
+
+    /**
+    Applies again the last undone operation. Returns its label, or `None` if there was nothing
+    to redo.
+    */
+    pub fn redo(&mut self, world: &mut World) -> Result<Option<String>, ECSError> {
+        let Some(entry) = self.redo.last() else {
+            return Ok(None);
+        };
+
+        // The entry stays redoable if the checkpoint can't be restored.
+        world.restore(&entry.after)?;
+
+        let entry = self.redo.pop().expect("the entry was just restored");
+        let label = entry.label.clone();
+        self.undo.push(entry);
+
+        Ok(Some(label))
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /**
+    Label of the operation `undo` would revert, to show in menus.
+    */
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.last().map(|entry| entry.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().map(|entry| entry.label.as_str())
+    }
+
+    /**
+    Recorded operations, oldest first.
+    */
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().map(|entry| entry.label.as_str())
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::type_registry::TypeRegistry;
+
+    #[derive(Clone)]
+    struct Health(pub u32);
+
+    fn health(world: &World) -> Vec<u32> {
+        world
+            .query()
+            .with_component_filter::<Health>()
+            .unwrap()
+            .get_entities()
+            .iter()
+            .map(|entity| entity.get_component::<Health>().unwrap().0)
+            .collect()
+    }
+
+    #[test]
+    fn operations_are_undone_and_redone_in_order() {
+        let mut world = World::new();
+        let mut history = UndoStack::new(10);
+        world.register_cloneable_component::<Health>();
+
+        history
+            .record(&mut world, "spawn", |world| {
+                world.create_entity().with_component(Health(10))?;
+                Ok(())
+            })
+            .unwrap();
+        history
+            .record(&mut world, "heal", |world| {
+                world.add_component_to_entity(0, Health(20))
+            })
+            .unwrap();
+
+        assert_eq!(health(&world), vec![20]);
+        assert_eq!(history.undo(&mut world).unwrap().as_deref(), Some("heal"));
+        assert_eq!(health(&world), vec![10]);
+        assert_eq!(history.undo(&mut world).unwrap().as_deref(), Some("spawn"));
+        assert!(health(&world).is_empty());
+        assert_eq!(history.undo(&mut world).unwrap(), None);
+        assert_eq!(history.redo(&mut world).unwrap().as_deref(), Some("spawn"));
+        assert_eq!(health(&world), vec![10]);
+        assert_eq!(history.redo_label(), Some("heal"));
+
+        history
+            .record(&mut world, "hurt", |world| {
+                world.add_component_to_entity(0, Health(5))
+            })
+            .unwrap();
+
+        assert!(!history.can_redo());
+        assert_eq!(history.history().collect::<Vec<_>>(), vec!["spawn", "hurt"]);
+    }
+
+    #[test]
+    fn failed_operations_are_rolled_back_and_not_recorded() {
+        let mut world = World::new();
+        let mut history = UndoStack::new(1);
+        world.register_cloneable_component::<Health>();
+
+        let result = history.record(&mut world, "broken", |world| {
+            world.create_entity().with_component(Health(10))?;
+            world.remove_entity(42)
+        });
+
+        assert!(result.is_err());
+        assert!(health(&world).is_empty());
+        assert!(!history.can_undo());
+
+        for label in ["first", "second"] {
+            history
+                .record(&mut world, label, |world| {
+                    world.create_entity().with_component(Health(1))?;
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        assert_eq!(history.undo_label(), Some("second"));
+        history.undo(&mut world).unwrap();
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn entries_are_kept_when_restoring_fails() {
+        let mut world = World::new();
+        let mut history = UndoStack::new(10);
+        world.register_cloneable_component::<Health>();
+
+        history
+            .record(&mut world, "spawn", |world| {
+                world.create_entity().with_component(Health(10))?;
+                Ok(())
+            })
+            .unwrap();
+        history
+            .record(&mut world, "heal", |world| {
+                world.add_component_to_entity(0, Health(20))
+            })
+            .unwrap();
+
+        let registry = world.take_resource::<TypeRegistry>().unwrap();
+
+        assert!(matches!(
+            history.undo(&mut world),
+            Err(ECSError::TypeNotCloneable)
+        ));
+        assert_eq!(history.undo_label(), Some("heal"));
+        assert!(!history.can_redo());
+
+        world.add_resource(registry).unwrap();
+        history.undo(&mut world).unwrap();
+        world.take_resource::<TypeRegistry>();
+
+        assert!(matches!(
+            history.redo(&mut world),
+            Err(ECSError::TypeNotCloneable)
+        ));
+        assert_eq!(history.redo_label(), Some("heal"));
+        assert_eq!(history.undo_label(), Some("spawn"));
+    }
+}
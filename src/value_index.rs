@@ -0,0 +1,207 @@
+use std::any::{Any, TypeId};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+
+use crate::entity_storage::EntityStorage;
+
+/**
+Index from the values of a component to the entities holding them, registered with
+`World::register_value_index`.
+*/
+#[derive(Debug)]
+pub struct ValueIndex<T> {
+    entities: HashMap<T, BTreeSet<usize>>,
+    values: HashMap<usize, T>,
+}
+
+impl<T> Default for ValueIndex<T> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::new(),
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone + Any> ValueIndex<T> {
+    pub fn insert(&mut self, entity_id: usize, value: T) {
+        self.remove(entity_id);
+        self.entities
+            .entry(value.clone())
+            .or_default()
+            .insert(entity_id);
+        self.values.insert(entity_id, value);
+    }
+
+    pub fn remove(&mut self, entity_id: usize) {
+        let Some(value) = self.values.remove(&entity_id) else {
+            return;
+        };
+
+        if let Some(entities) = self.entities.get_mut(&value) {
+            entities.remove(&entity_id);
+            if entities.is_empty() {
+                self.entities.remove(&value);
+            }
+        }
+    }
+
+    /**
+    Entities holding `value` as of the last time they were indexed, sorted by ID.
+    */
+    pub fn get(&self, value: &T) -> impl Iterator<Item = usize> + '_ {
+        self.entities.get(value).into_iter().flatten().copied()
+    }
+}
+
+pub(crate) trait AnyValueIndex: Any {
+    // Reindexes the entities whose value was modified in place, returns how many changed.
+    fn update(&mut self, entity_storage: &EntityStorage) -> usize;
+    fn rebuild(&mut self, entity_storage: &EntityStorage);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Hash + Eq + Clone + Any> AnyValueIndex for ValueIndex<T> {
+    fn update(&mut self, entity_storage: &EntityStorage) -> usize {
+        let changed: Vec<(usize, Option<T>)> = self
+            .values
+            .iter()
+            .filter_map(|(entity_id, indexed)| {
+                let current = entity_storage
+                    .get_component::<T>(*entity_id)
+                    .ok()
+                    .map(|value| value.clone());
+                (current.as_ref() != Some(indexed)).then_some((*entity_id, current))
+            })
+            .collect();
+
+        for (entity_id, current) in &changed {
+            match current {
+                Some(value) => self.insert(*entity_id, value.clone()),
+                None => self.remove(*entity_id),
+            }
+        }
+
+        changed.len()
+    }
+
+    fn rebuild(&mut self, entity_storage: &EntityStorage) {
+        self.entities.clear();
+        self.values.clear();
+
+        for entity_id in 0..entity_storage.entity_count() {
+            if let Ok(value) = entity_storage.get_component::<T>(entity_id) {
+                let value = value.clone();
+                self.insert(entity_id, value);
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct ValueIndexes {
+    indexes: HashMap<TypeId, Box<dyn AnyValueIndex>>,
+}
+
+impl ValueIndexes {
+    pub(crate) fn register<T: Hash + Eq + Clone + Any>(&mut self) -> &mut ValueIndex<T> {
+        self.indexes
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(ValueIndex::<T>::default()))
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    pub fn contains<T: Any>(&self) -> bool {
+        self.indexes.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: Any>(&self) -> Option<&ValueIndex<T>> {
+        self.indexes
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref()
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut ValueIndex<T>> {
+        self.indexes
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut()
+    }
+
+    pub(crate) fn update(&mut self, entity_storage: &EntityStorage) -> usize {
+        self.indexes
+            .values_mut()
+            .map(|index| index.update(entity_storage))
+            .sum()
+    }
+
+    pub(crate) fn rebuild(&mut self, entity_storage: &EntityStorage) {
+        for index in self.indexes.values_mut() {
+            index.rebuild(entity_storage);
+        }
+    }
+}
+
+impl std::fmt::Debug for ValueIndexes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValueIndexes")
+            .field("indexes", &self.indexes.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    enum Team {
+        Red,
+        Blue,
+    }
+
+    #[test]
+    fn reinserting_moves_entities_between_values() {
+        let mut index = ValueIndex::default();
+
+        index.insert(2, Team::Red);
+        index.insert(0, Team::Red);
+        index.insert(1, Team::Blue);
+        index.insert(2, Team::Blue);
+        index.remove(1);
+
+        assert_eq!(index.get(&Team::Red).collect::<Vec<usize>>(), vec![0]);
+        assert_eq!(index.get(&Team::Blue).collect::<Vec<usize>>(), vec![2]);
+    }
+
+    #[test]
+    fn update_picks_up_modified_values() -> Result<(), crate::ecs_errors::ECSError> {
+        let mut entities = EntityStorage::default();
+        let mut indexes = ValueIndexes::default();
+
+        entities.register_component::<Team>();
+        entities.create_entity().with_component(Team::Red)?;
+        entities.create_entity().with_component(Team::Red)?;
+        indexes.register::<Team>().rebuild(&entities);
+
+        *entities.get_component_mut::<Team>(1)? = Team::Blue;
+
+        assert_eq!(indexes.update(&entities), 1);
+        let index = indexes.get::<Team>().unwrap();
+        assert_eq!(index.get(&Team::Red).collect::<Vec<usize>>(), vec![0]);
+        assert_eq!(index.get(&Team::Blue).collect::<Vec<usize>>(), vec![1]);
+        Ok(())
+    }
+}
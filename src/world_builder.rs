@@ -0,0 +1,230 @@
+use std::any::type_name;
+
+use crate::ecs_errors::ECSError;
+use crate::entity_uuid::EntityUuid;
+use crate::journal::Journal;
+use crate::name::Name;
+use crate::World;
+
+/**
+Whether the slots of removed entities, and so their IDs, are given to new entities.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EntityIdRecycling {
+    /**
+    New entities take the first free slot, keeping the entity table small.
+    */
+    #[default]
+    Reuse,
+    /**
+    Every new entity gets a new ID, so an ID always refers to the same entity, for example in
+    logs kept outside the world. The slots of removed entities are never freed.
+    */
+    Never,
+}
+
+/**
+Configures a `World` before it's created, for simulations that know their size up front.
+Returned by `World::builder`.
+
+Example:
+```
+use sara_ecs::World;
+
+struct Health(pub u32);
+
+let mut world = World::builder()
+    .entity_capacity(10_000)
+    .component_limit(16)
+    .entity_names(true)
+    .journal(256)
+    .build()
+    .unwrap();
+
+world.register_component::<Health>();
+world.create_entity().with_component(Health(100)).unwrap();
+```
+*/
+#[derive(Debug, Default)]
+pub struct WorldBuilder {
+    entity_capacity: usize,
+    component_limit: Option<usize>,
+    id_recycling: EntityIdRecycling,
+    entity_uuids: bool,
+    entity_names: bool,
+    journal_capacity: Option<usize>,
+}
+
+impl WorldBuilder {
+    pub fn new() -> Self {
+        WorldBuilder::default()
+    }
+
+    /**
+    Number of entities the world makes room for up front, so spawning that many doesn't
+    reallocate the entity masks or the columns of the components registered afterwards.
+    */
+    pub fn entity_capacity(mut self, capacity: usize) -> Self {
+        self.entity_capacity = capacity;
+        self
+    }
+
+    /**
+    Maximum number of component types, dynamic ones included. Capped at 32, the number of
+    bits in the component masks, which is also the default.
+    */
+    pub fn component_limit(mut self, limit: usize) -> Self {
+        self.component_limit = Some(limit);
+        self
+    }
+
+    /**
+    Whether entity IDs are reused once their entity is removed. They are by default.
+    */
+    pub fn entity_id_recycling(mut self, id_recycling: EntityIdRecycling) -> Self {
+        self.id_recycling = id_recycling;
+        self
+    }
+
+    /**
+    Whether the world starts with entity UUIDs enabled, see `World::enable_entity_uuids`.
+    */
+    pub fn entity_uuids(mut self, enabled: bool) -> Self {
+        self.entity_uuids = enabled;
+        self
+    }
+
+    /**
+    Whether the world starts with entity names enabled, see `World::enable_entity_names`.
+    */
+    pub fn entity_names(mut self, enabled: bool) -> Self {
+        self.entity_names = enabled;
+        self
+    }
+
+    /**
+    Adds a `Journal` resource keeping the last `capacity` structural changes.
+    */
+    pub fn journal(mut self, capacity: usize) -> Self {
+        self.journal_capacity = Some(capacity);
+        self
+    }
+
+    /**
+    Creates the world. Fails with `TooManyComponents`, listing the components that didn't fit,
+    if the component limit leaves no room for the components of the enabled features.
+    */
+    pub fn build(self) -> Result<World, ECSError> {
+        let rejected: Vec<&'static str> = [
+            (self.entity_uuids, type_name::<EntityUuid>()),
+            (self.entity_names, type_name::<Name>()),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .skip(self.component_limit.unwrap_or(u32::BITS as usize))
+        .collect();
+
+        if !rejected.is_empty() {
+            return Err(ECSError::TooManyComponents(rejected));
+        }
+
+        let mut world = World::new();
+
+        world.entitiy_storage.reserve_entities(self.entity_capacity);
+        world.entitiy_storage.set_id_recycling(self.id_recycling);
+
+        if let Some(limit) = self.component_limit {
+            world.entitiy_storage.set_component_limit(limit);
+        }
+
+        if self.entity_uuids {
+            world.enable_entity_uuids();
+        }
+
+        if self.entity_names {
+            world.enable_entity_names();
+        }
+
+        if let Some(capacity) = self.journal_capacity {
+            world.add_resource(Journal::new(capacity))?;
+        }
+
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Health;
+    struct Speed;
+    struct Armor;
+
+    #[test]
+    fn component_limit_is_applied() {
+        let mut world = World::builder().component_limit(2).build().unwrap();
+
+        let result = world.register_components::<(Health, Speed, Armor)>();
+
+        assert!(
+            matches!(result, Err(ECSError::TooManyComponents(rejected)) if rejected.len() == 1)
+        );
+    }
+
+    #[test]
+    fn component_limit_must_fit_the_enabled_features() {
+        let result = World::builder()
+            .component_limit(1)
+            .entity_uuids(true)
+            .entity_names(true)
+            .build();
+
+        assert!(
+            matches!(result, Err(ECSError::TooManyComponents(rejected)) if rejected == vec![type_name::<Name>()])
+        );
+
+        let world = World::builder()
+            .component_limit(2)
+            .entity_uuids(true)
+            .entity_names(true)
+            .build()
+            .unwrap();
+
+        assert!(world.entitiy_storage.component_limit_reached());
+    }
+
+    #[test]
+    fn entity_ids_can_be_kept_unique() {
+        let mut world = World::builder()
+            .entity_id_recycling(EntityIdRecycling::Never)
+            .build()
+            .unwrap();
+
+        world.register_component::<Health>();
+
+        let first = world.create_entity().with_component(Health).unwrap().id();
+        world.remove_entity(first).unwrap();
+        world.compact();
+
+        let second = world.create_entity().with_component(Health).unwrap().id();
+        assert_eq!((first, second), (0, 1));
+    }
+
+    #[test]
+    fn columns_get_the_entity_capacity() {
+        let mut world = World::builder().entity_capacity(100).build().unwrap();
+
+        world.register_component::<u32>();
+
+        for value in 0..100_u32 {
+            world.create_entity().with_component(value).unwrap();
+        }
+
+        let report = world.memory_report();
+        let column = report.component("u32").unwrap();
+
+        assert_eq!((column.len, column.capacity), (100, 100));
+        assert!(world.get_resource::<Journal>().is_none());
+    }
+}
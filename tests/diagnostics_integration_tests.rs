@@ -0,0 +1,44 @@
+#![cfg(feature = "diagnostics")]
+
+use sara_ecs::diagnostics::WorldDiagnostics;
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::World;
+
+struct Bullet;
+
+#[test]
+fn diagnostics_count_each_tick() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Bullet>();
+    world.create_entity().with_component(Bullet)?;
+    world.update_diagnostics(); // Not enabled yet, the first tick is discarded.
+
+    world.add_resource(WorldDiagnostics::default())?;
+    world.create_entity().with_component(Bullet)?;
+    world.query().with_component_filter::<Bullet>()?.run();
+    world.query().with_component_filter::<Bullet>()?.run();
+    world.update_diagnostics();
+
+    {
+        let diagnostics = world.get_resource::<WorldDiagnostics>().unwrap();
+
+        assert_eq!(diagnostics.tick, 1);
+        assert_eq!(diagnostics.entity_count, 2);
+        assert_eq!((diagnostics.spawned, diagnostics.queries), (1, 2));
+        assert_eq!(
+            diagnostics.component_count(std::any::type_name::<Bullet>()),
+            2
+        );
+    }
+
+    world.remove_entity(0)?;
+    world.update_diagnostics();
+
+    let diagnostics = world.get_resource::<WorldDiagnostics>().unwrap();
+
+    assert_eq!((diagnostics.spawned, diagnostics.despawned), (0, 1));
+    assert_eq!(diagnostics.total_spawned, 1);
+    assert_eq!(diagnostics.entity_count, 1);
+    Ok(())
+}
@@ -1,5 +1,19 @@
+use std::any::TypeId;
+
+use sara_ecs::commands::Commands;
+use sara_ecs::component_registration::StorageKind;
+use sara_ecs::dynamic_component::{
+    DynamicComponent, DynamicComponentDescriptor, DynamicValue, FieldType,
+};
 use sara_ecs::ecs_errors::ECSError;
-use sara_ecs::World;
+use sara_ecs::journal::{Journal, StructuralChange};
+use sara_ecs::name::Name;
+use sara_ecs::previous::Prev;
+use sara_ecs::relation::{Relation, RelationCleanup, RelationTargetDespawned};
+use sara_ecs::resource_hooks::ResourceChange;
+use sara_ecs::spatial::SpatialPosition;
+use sara_ecs::type_registry::TypeRegistry;
+use sara_ecs::{query, World};
 
 struct Position(pub f32, pub f32);
 struct Scale(pub f32, pub f32);
@@ -155,3 +169,1826 @@ fn deleting_an_entity() -> Result<(), ECSError> {
     assert_eq!(position.1, 35.0);
     Ok(())
 }
+
+struct HookCalls(pub Vec<(&'static str, usize)>);
+
+#[test]
+fn component_hooks_run_on_add_and_remove() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Scale>();
+    world.add_resource(HookCalls(vec![]))?;
+
+    world.on_add::<Position>(|world, entity_id| {
+        let mut calls = world.get_resource_mut::<HookCalls>().unwrap();
+        calls.0.push(("add", entity_id));
+    });
+    world.on_remove::<Position>(|world, entity_id| {
+        let mut calls = world.get_resource_mut::<HookCalls>().unwrap();
+        calls.0.push(("remove", entity_id));
+    });
+
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+    world.create_entity().with_component(Scale(1.0, 1.0))?;
+    world.add_component_to_entity(1, Position(1.0, 1.0))?;
+    world.remove_entity_component::<Position>(0)?;
+    world.remove_entity_component::<Position>(0)?;
+    world.remove_entity(1)?;
+
+    let calls = &world.get_resource::<HookCalls>().unwrap().0;
+    assert_eq!(
+        calls,
+        &vec![("add", 0), ("add", 1), ("remove", 0), ("remove", 1)]
+    );
+    Ok(())
+}
+
+struct Velocity {
+    x: f32,
+    y: f32,
+}
+
+sara_ecs::impl_reflect!(Velocity { x, y });
+
+#[test]
+fn reflect_components_by_name() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_reflectable_component::<Velocity>();
+    world.register_component::<Scale>();
+
+    world
+        .create_entity()
+        .with_component(Velocity { x: 1.0, y: 2.0 })?
+        .with_component(Scale(1.0, 1.0))?;
+
+    world
+        .reflect_component_mut(0, "Velocity")?
+        .set("x", 3.0_f32);
+
+    let velocity = world.reflect_component(0, "Velocity")?;
+    assert_eq!(velocity.get::<f32>("x"), Some(&3.0));
+    assert_eq!(velocity.get::<f32>("y"), Some(&2.0));
+    drop(velocity);
+
+    assert!(matches!(
+        world.reflect_component(0, "Scale"),
+        Err(ECSError::TypeNotReflectable)
+    ));
+    assert!(matches!(
+        world.reflect_component(0, "Mass"),
+        Err(ECSError::ComponentNotRegistered { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn dynamic_components_can_be_queried() -> Result<(), ECSError> {
+    let mut world = World::new();
+    let mana = DynamicComponentDescriptor::new("Mana").with_field("current", FieldType::Int);
+
+    world.register_component::<Position>();
+    world.register_dynamic_component(mana.clone())?;
+
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+    world.create_entity().with_component(Position(1.0, 1.0))?;
+    world.add_dynamic_component(1, mana.instantiate())?;
+    world
+        .get_dynamic_component_mut(1, "Mana")?
+        .set("current", DynamicValue::Int(30));
+
+    let result = world
+        .query()
+        .with_component_filter::<Position>()?
+        .with_dynamic_component_filter("Mana")?
+        .run();
+
+    assert_eq!(result.entity_ids, vec![1]);
+    let mana = result.components[1][0].borrow();
+    let mana = mana.downcast_ref::<DynamicComponent>().unwrap();
+    assert_eq!(mana.get("current"), Some(&DynamicValue::Int(30)));
+
+    assert!(matches!(
+        world.add_dynamic_component(0, DynamicComponent::new("Mana")),
+        Err(ECSError::DynamicComponentMismatch)
+    ));
+    Ok(())
+}
+
+#[test]
+fn add_and_remove_components_by_type_id() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Scale>();
+    world.add_resource(HookCalls(vec![]))?;
+    world.on_add::<Scale>(|world, entity_id| {
+        let mut calls = world.get_resource_mut::<HookCalls>().unwrap();
+        calls.0.push(("add", entity_id));
+    });
+    world.on_remove::<Scale>(|world, entity_id| {
+        let mut calls = world.get_resource_mut::<HookCalls>().unwrap();
+        calls.0.push(("remove", entity_id));
+    });
+
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+    world.add_component_dynamic(0, TypeId::of::<Scale>(), Box::new(Scale(2.0, 2.0)))?;
+
+    let result = world.query().with_component_filter::<Scale>()?.run();
+    assert_eq!(result.entity_ids, vec![0]);
+
+    world.remove_component_dynamic(0, TypeId::of::<Scale>())?;
+
+    let result = world.query().with_component_filter::<Scale>()?.run();
+    assert!(result.entity_ids.is_empty());
+
+    let calls = &world.get_resource::<HookCalls>().unwrap().0;
+    assert_eq!(calls, &vec![("add", 0), ("remove", 0)]);
+    Ok(())
+}
+
+#[test]
+fn look_up_components_by_name() {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.type_registry_mut().register::<HookCalls>();
+
+    assert_eq!(
+        world.component_id_by_name("Position"),
+        Some(TypeId::of::<Position>())
+    );
+    assert_eq!(
+        world.component_id_by_name(std::any::type_name::<Position>()),
+        Some(TypeId::of::<Position>())
+    );
+    assert_eq!(world.component_id_by_name("HookCalls"), None);
+    assert_eq!(world.component_id_by_name("Scale"), None);
+}
+
+#[derive(Clone)]
+struct Health(pub u32);
+
+#[test]
+fn checkpoints_restore_entities_and_components() -> Result<(), ECSError> {
+    let mut world = World::new();
+    let mana = DynamicComponentDescriptor::new("Mana").with_field("current", FieldType::Int);
+
+    world.register_cloneable_component::<Health>();
+    world.register_dynamic_component(mana.clone())?;
+
+    world.create_entity().with_component(Health(10))?;
+    world.add_dynamic_component(0, mana.instantiate())?;
+    world.create_entity().with_component(Health(20))?;
+
+    let checkpoint = world.checkpoint()?;
+
+    world.remove_entity(1)?;
+    world.remove_dynamic_component(0, "Mana")?;
+    world.create_entity().with_component(Health(30))?;
+    world.create_entity().with_component(Health(40))?;
+
+    world.restore(&checkpoint)?;
+
+    let result = world.query().with_component_filter::<Health>()?.run();
+    let healths: Vec<u32> = result.components[0]
+        .iter()
+        .map(|health| health.borrow().downcast_ref::<Health>().unwrap().0)
+        .collect();
+
+    assert_eq!(result.entity_ids, vec![0, 1]);
+    assert_eq!(healths, vec![10, 20]);
+    assert!(world.get_dynamic_component(0, "Mana").is_ok());
+    Ok(())
+}
+
+#[test]
+fn checkpoint_requires_cloneable_components() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+
+    assert!(matches!(
+        world.checkpoint(),
+        Err(ECSError::TypeNotCloneable)
+    ));
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+struct Counter(pub i64);
+
+fn add_input(world: &mut World, input: &i64) {
+    let entities = world
+        .query()
+        .with_component_filter::<Counter>()
+        .unwrap()
+        .get_entities();
+
+    for mut entity in entities {
+        let mut counter = entity.get_component_mut::<Counter>().unwrap();
+        // Not commutative, so resimulating with the wrong order of inputs would show.
+        counter.0 = counter.0 * 2 + input;
+    }
+}
+
+fn counter(world: &World) -> Result<i64, ECSError> {
+    let entities = world
+        .query()
+        .with_component_filter::<Counter>()?
+        .get_entities();
+    let value = entities[0].get_component::<Counter>()?.0;
+    Ok(value)
+}
+
+#[test]
+fn rollback_resimulates_with_corrected_inputs() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Counter>();
+    world.create_entity().with_component(Counter(0))?;
+    world.add_resource(sara_ecs::rollback::Rollback::new(3, add_input))?;
+
+    for input in [1_i64, 2, 3, 4] {
+        world.advance_rollback(input)?;
+    }
+    assert_eq!(counter(&world)?, 26);
+
+    // Tick 2 actually had an input of 10.
+    world
+        .get_resource_mut::<sara_ecs::rollback::Rollback<i64>>()
+        .unwrap()
+        .set_input(2, 10);
+    world.rollback_to::<i64>(2)?;
+    assert_eq!(counter(&world)?, 40);
+
+    assert!(matches!(
+        world.rollback_to::<i64>(0),
+        Err(ECSError::TickNotBuffered)
+    ));
+    assert!(matches!(
+        world.rollback_to::<u8>(0),
+        Err(ECSError::ResourceDoesNotExist { .. })
+    ));
+
+    world.advance_rollback(0_i64)?;
+    world.rollback_to::<i64>(4)?;
+    assert_eq!(counter(&world)?, 80);
+    Ok(())
+}
+
+#[test]
+fn rollback_keeps_its_frames_when_restoring_fails() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Counter>();
+    world.create_entity().with_component(Counter(0))?;
+    world.add_resource(sara_ecs::rollback::Rollback::new(3, add_input))?;
+
+    for input in [1_i64, 2, 3] {
+        world.advance_rollback(input)?;
+    }
+
+    let registry = world.take_resource::<TypeRegistry>().unwrap();
+
+    assert!(matches!(
+        world.rollback_to::<i64>(1),
+        Err(ECSError::TypeNotCloneable)
+    ));
+
+    let rollback = world
+        .get_resource::<sara_ecs::rollback::Rollback<i64>>()
+        .unwrap();
+    assert_eq!(
+        (rollback.oldest_tick(), rollback.current_tick()),
+        (Some(0), 3)
+    );
+    drop(rollback);
+
+    world.add_resource(registry)?;
+    world.rollback_to::<i64>(1)?;
+    assert_eq!(counter(&world)?, 11);
+    Ok(())
+}
+
+#[test]
+fn journal_records_structural_changes() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Scale>();
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+
+    world.add_resource(Journal::new(16))?;
+    world.get_resource_mut::<Journal>().unwrap().advance_tick();
+
+    world.create_entity().with_component(Position(1.0, 1.0))?;
+    world.add_component_to_entity(0, Scale(1.0, 1.0))?;
+    world.remove_entity_component::<Scale>(0)?;
+    world.remove_entity_component::<Scale>(0)?;
+    world.replace_resource(5_u32);
+    world.remove_entity(1)?;
+
+    let journal = world.get_resource::<Journal>().unwrap();
+    let changes: Vec<(u64, StructuralChange)> = journal
+        .entries()
+        .map(|entry| (entry.tick, entry.change.clone()))
+        .collect();
+    let scale = std::any::type_name::<Scale>().to_owned();
+
+    assert_eq!(
+        changes,
+        vec![
+            (
+                0,
+                StructuralChange::ResourceChanged {
+                    resource: std::any::type_name::<Journal>().to_owned(),
+                    change: ResourceChange::Added
+                }
+            ),
+            (1, StructuralChange::Spawned { entity_id: 1 }),
+            (
+                1,
+                StructuralChange::ComponentInserted {
+                    entity_id: 1,
+                    component: std::any::type_name::<Position>().to_owned()
+                }
+            ),
+            (
+                1,
+                StructuralChange::ComponentInserted {
+                    entity_id: 0,
+                    component: scale.clone()
+                }
+            ),
+            (
+                1,
+                StructuralChange::ComponentRemoved {
+                    entity_id: 0,
+                    component: scale
+                }
+            ),
+            (
+                1,
+                StructuralChange::ResourceChanged {
+                    resource: "u32".to_owned(),
+                    change: ResourceChange::Added
+                }
+            ),
+            (1, StructuralChange::Despawned { entity_id: 1 }),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn entities_can_be_found_by_uuid() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+    world.enable_entity_uuids();
+
+    let first = world.create_entity().with_component(Health(2))?.id();
+    let second = world.create_entity().id();
+    let first_uuid = world.entity_uuid(first).unwrap();
+    let second_uuid = world.entity_uuid(second).unwrap();
+
+    assert_ne!(first_uuid, second_uuid);
+    assert_eq!(world.entity_by_uuid(second_uuid), Some(second));
+
+    let checkpoint = world.checkpoint()?;
+
+    world.remove_entity(first)?;
+    assert_eq!(world.entity_by_uuid(first_uuid), None);
+
+    // The slot is reused by a new entity with a different UUID.
+    let reused = world.create_entity().id();
+    assert_eq!(reused, first);
+    assert_eq!(world.entity_by_uuid(first_uuid), None);
+
+    world.restore(&checkpoint)?;
+    assert_eq!(world.entity_by_uuid(first_uuid), Some(first));
+    Ok(())
+}
+
+#[test]
+fn copied_uuids_keep_the_original_findable() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.enable_entity_uuids();
+
+    let original = world.create_entity().id();
+    let uuid = world.entity_uuid(original).unwrap();
+    let copy = world.create_entity().id();
+    world.add_component_to_entity(copy, uuid)?;
+
+    assert_eq!(world.entity_by_uuid(uuid), Some(original));
+
+    world.remove_entity(copy)?;
+    assert_eq!(world.entity_by_uuid(uuid), Some(original));
+    Ok(())
+}
+
+#[test]
+fn entities_can_be_found_by_name() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+    world.enable_entity_names();
+
+    let player = world
+        .create_entity()
+        .with_component(Name::new("Player"))?
+        .with_component(Health(10))?
+        .id();
+    let enemy = world
+        .create_entity()
+        .with_component(Name::new("Enemy"))?
+        .id();
+
+    assert_eq!(world.entity_by_name("Player"), Some(player));
+    assert_eq!(world.entity_by_name("Enemy"), Some(enemy));
+    assert_eq!(world.entity_name(enemy).as_deref(), Some("Enemy"));
+    assert_eq!(world.entity_by_name("Boss"), None);
+
+    let checkpoint = world.checkpoint()?;
+
+    // Renaming replaces the index entry.
+    world.add_component_to_entity(enemy, Name::new("Boss"))?;
+    assert_eq!(world.entity_by_name("Enemy"), None);
+    assert_eq!(world.entity_by_name("Boss"), Some(enemy));
+
+    world.remove_entity(player)?;
+    assert_eq!(world.entity_by_name("Player"), None);
+
+    world.restore(&checkpoint)?;
+    assert_eq!(world.entity_by_name("Player"), Some(player));
+    assert_eq!(world.entity_by_name("Enemy"), Some(enemy));
+    assert_eq!(world.entity_by_name("Boss"), None);
+    Ok(())
+}
+
+#[test]
+fn entities_can_be_queried_by_group() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+
+    let goblin = world.create_entity().with_component(Health(10))?.id();
+    let bat = world.create_entity().with_component(Health(5))?.id();
+    let player = world.create_entity().with_component(Health(100))?.id();
+
+    world.add_to_group(goblin, "enemies")?;
+    world.add_to_group(bat, "enemies")?;
+    world.add_to_group(bat, "flying")?;
+
+    assert!(world.is_in_group(bat, "flying"));
+    assert!(!world.is_in_group(player, "enemies"));
+    assert_eq!(world.entity_groups(goblin), vec!["enemies"]);
+
+    let enemies = world
+        .query()
+        .with_component_filter::<Health>()?
+        .in_group("enemies")
+        .run();
+    assert_eq!(enemies.entity_ids, vec![goblin, bat]);
+
+    let checkpoint = world.checkpoint()?;
+
+    world.remove_from_group(goblin, "enemies")?;
+    world.remove_entity(bat)?;
+    assert!(world
+        .query()
+        .in_group("enemies")
+        .run()
+        .entity_ids
+        .is_empty());
+
+    // A new entity in a reused slot doesn't inherit the groups of the previous one.
+    let reused = world.create_entity().with_component(Health(1))?.id();
+    assert_eq!(reused, bat);
+    assert!(world.entity_groups(reused).is_empty());
+
+    world.restore(&checkpoint)?;
+    assert_eq!(
+        world.query().in_group("enemies").run().entity_ids,
+        vec![goblin, bat]
+    );
+    assert!(matches!(
+        world.add_to_group(10, "enemies"),
+        Err(ECSError::EntityDoesNotExist { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn disabled_entities_are_skipped_by_queries() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+
+    let hidden = world.create_entity().with_component(Health(10))?.id();
+    let visible = world.create_entity().with_component(Health(20))?.id();
+
+    world.disable_entity(hidden)?;
+    assert!(world.is_entity_disabled(hidden));
+
+    let mut query = world.query();
+    query.with_component_filter::<Health>()?;
+    assert_eq!(query.run().entity_ids, vec![visible]);
+    assert_eq!(
+        query.including_disabled().run().entity_ids,
+        vec![hidden, visible]
+    );
+
+    let checkpoint = world.checkpoint()?;
+
+    // The slot of a removed disabled entity is reused by an enabled one.
+    world.remove_entity(hidden)?;
+    let reused = world.create_entity().with_component(Health(30))?.id();
+    assert_eq!(reused, hidden);
+    assert!(!world.is_entity_disabled(reused));
+
+    world.restore(&checkpoint)?;
+    assert!(world.is_entity_disabled(hidden));
+
+    world.enable_entity(hidden)?;
+    assert_eq!(world.query().run().entity_ids, vec![hidden, visible]);
+    Ok(())
+}
+
+#[test]
+fn hierarchy_keeps_both_sides_in_sync() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_hierarchy();
+
+    let root = world.create_entity().with_component(Health(1))?.id();
+    let other_root = world.create_entity().with_component(Health(2))?.id();
+    let child = world.create_entity().with_component(Health(3))?.id();
+    let grandchild = world.create_entity().with_component(Health(4))?.id();
+
+    world.set_parent(child, root)?;
+    world.set_parent(grandchild, child)?;
+
+    assert_eq!(world.children(root).collect::<Vec<usize>>(), vec![child]);
+    assert_eq!(world.parent(grandchild), Some(child));
+    assert!(matches!(
+        world.set_parent(root, grandchild),
+        Err(ECSError::HierarchyCycle)
+    ));
+
+    // Reparenting detaches the child from its previous parent.
+    world.set_parent(child, other_root)?;
+    assert_eq!(world.children(root).count(), 0);
+    assert_eq!(
+        world.children(other_root).collect::<Vec<usize>>(),
+        vec![child]
+    );
+
+    world.remove_parent(child)?;
+    assert_eq!(world.parent(child), None);
+    assert_eq!(world.children(other_root).count(), 0);
+
+    // Removing a parent turns its children into roots.
+    world.remove_entity(child)?;
+    assert_eq!(world.parent(grandchild), None);
+
+    world.set_parent(grandchild, root)?;
+    world.remove_entity(grandchild)?;
+    assert_eq!(world.children(root).count(), 0);
+    Ok(())
+}
+
+#[test]
+fn hierarchies_can_be_despawned_recursively() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_hierarchy();
+
+    let root = world.create_entity().with_component(Health(1))?.id();
+    let child = world.create_entity().with_component(Health(2))?.id();
+    let grandchild = world.create_entity().with_component(Health(3))?.id();
+    let sibling = world.create_entity().with_component(Health(4))?.id();
+    let unrelated = world.create_entity().with_component(Health(5))?.id();
+
+    world.set_parent(child, root)?;
+    world.set_parent(grandchild, child)?;
+    world.set_parent(sibling, root)?;
+
+    world.despawn_descendants(child)?;
+    assert_eq!(world.children(child).count(), 0);
+    assert_eq!(
+        world
+            .query()
+            .with_component_filter::<Health>()?
+            .run()
+            .entity_ids,
+        vec![root, child, sibling, unrelated]
+    );
+
+    world.set_parent(unrelated, sibling)?;
+    world.despawn_recursive(root)?;
+    assert!(world
+        .query()
+        .with_component_filter::<Health>()?
+        .run()
+        .entity_ids
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn hierarchies_can_be_traversed() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_hierarchy();
+
+    // root
+    // ├── a
+    // │   └── a1
+    // └── b
+    //     └── b1
+    let ids: Vec<usize> = (0..5)
+        .map(|health| Ok(world.create_entity().with_component(Health(health))?.id()))
+        .collect::<Result<_, ECSError>>()?;
+    let (root, a, a1, b, b1) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+
+    world.set_parent(a, root)?;
+    world.set_parent(b, root)?;
+    world.set_parent(a1, a)?;
+    world.set_parent(b1, b)?;
+
+    assert_eq!(world.ancestors(b1).collect::<Vec<usize>>(), vec![b, root]);
+    assert_eq!(world.ancestors(root).count(), 0);
+    assert_eq!(
+        world.descendants_depth_first(root).collect::<Vec<usize>>(),
+        vec![a, a1, b, b1]
+    );
+    assert_eq!(
+        world
+            .descendants_breadth_first(root)
+            .collect::<Vec<usize>>(),
+        vec![a, b, a1, b1]
+    );
+    assert_eq!(world.descendants_breadth_first(a1).count(), 0);
+    Ok(())
+}
+
+#[derive(Clone)]
+struct Targets(pub usize);
+
+impl Relation for Targets {
+    fn target(&self) -> usize {
+        self.0
+    }
+}
+
+#[test]
+fn relations_can_be_followed_backwards() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+    world.register_cloneable_component::<Targets>();
+    world.register_relation::<Targets>();
+
+    let player = world.create_entity().with_component(Health(100))?.id();
+    let ally = world.create_entity().with_component(Health(50))?.id();
+    let archer = world.create_entity().with_component(Targets(player))?.id();
+    let mage = world.create_entity().with_component(Targets(player))?.id();
+
+    assert_eq!(world.relations_to::<Targets>(player), vec![archer, mage]);
+    assert!(world.relations_to::<Targets>(ally).is_empty());
+
+    let checkpoint = world.checkpoint()?;
+
+    // Retargeting by adding the component again moves the entity to the new target.
+    world.add_component_to_entity(mage, Targets(ally))?;
+    assert_eq!(world.relations_to::<Targets>(player), vec![archer]);
+    assert_eq!(world.relations_to::<Targets>(ally), vec![mage]);
+
+    world.remove_entity(archer)?;
+    assert!(world.relations_to::<Targets>(player).is_empty());
+
+    world.restore(&checkpoint)?;
+    assert_eq!(world.relations_to::<Targets>(player), vec![archer, mage]);
+    assert!(world.relations_to::<Targets>(ally).is_empty());
+    Ok(())
+}
+
+struct Follows(pub usize);
+struct AttachedTo(pub usize);
+struct Hunts(pub usize);
+
+impl Relation for Follows {
+    const ON_TARGET_DESPAWN: RelationCleanup = RelationCleanup::RemoveRelation;
+
+    fn target(&self) -> usize {
+        self.0
+    }
+}
+
+impl Relation for AttachedTo {
+    const ON_TARGET_DESPAWN: RelationCleanup = RelationCleanup::DespawnSource;
+
+    fn target(&self) -> usize {
+        self.0
+    }
+}
+
+impl Relation for Hunts {
+    const ON_TARGET_DESPAWN: RelationCleanup = RelationCleanup::SendEvent;
+
+    fn target(&self) -> usize {
+        self.0
+    }
+}
+
+#[test]
+fn relations_are_cleaned_up_when_their_target_is_removed() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_event::<RelationTargetDespawned<Hunts>>();
+    world.register_component::<Health>();
+    world.register_component::<Follows>();
+    world.register_component::<AttachedTo>();
+    world.register_component::<Hunts>();
+    world.register_relation::<Follows>();
+    world.register_relation::<AttachedTo>();
+    world.register_relation::<Hunts>();
+
+    let player = world.create_entity().with_component(Health(100))?.id();
+    let pet = world
+        .create_entity()
+        .with_component(Health(10))?
+        .with_component(Follows(player))?
+        .id();
+    let shield = world
+        .create_entity()
+        .with_component(AttachedTo(player))?
+        .id();
+    let glow = world
+        .create_entity()
+        .with_component(AttachedTo(shield))?
+        .id();
+    let wolf = world
+        .create_entity()
+        .with_component(Health(30))?
+        .with_component(Hunts(player))?
+        .id();
+
+    world.remove_entity(player)?;
+
+    assert_eq!(world.relation_target::<Follows>(pet), None);
+    // Despawning cascades through chains of attachments.
+    assert!(world
+        .query()
+        .with_component_filter::<AttachedTo>()?
+        .run()
+        .entity_ids
+        .is_empty());
+    assert_eq!(world.relation_target::<AttachedTo>(glow), None);
+    assert!(world.relations_to::<AttachedTo>(shield).is_empty());
+
+    // Hunters keep their relation and get notified instead.
+    assert_eq!(world.relation_target::<Hunts>(wolf), Some(player));
+    let despawned = world
+        .get_events::<RelationTargetDespawned<Hunts>>()
+        .unwrap();
+    let despawned: Vec<(usize, usize)> = despawned
+        .iter()
+        .map(|event| (event.source, event.target))
+        .collect();
+    assert_eq!(despawned, vec![(wolf, player)]);
+    Ok(())
+}
+
+impl SpatialPosition for Position {
+    fn position(&self) -> [f32; 2] {
+        [self.0, self.1]
+    }
+}
+
+#[test]
+fn entities_can_be_found_by_position() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Health>();
+
+    assert!(matches!(
+        world.entities_in_aabb([0.0, 0.0], [1.0, 1.0]),
+        Err(ECSError::SpatialIndexNotEnabled)
+    ));
+
+    // Entities created before the index is enabled are indexed too.
+    let origin = world
+        .create_entity()
+        .with_component(Position(0.0, 0.0))?
+        .id();
+    world.enable_spatial_index::<Position>(10.0);
+
+    let near = world
+        .create_entity()
+        .with_component(Position(3.0, 4.0))?
+        .id();
+    let far = world
+        .create_entity()
+        .with_component(Position(-50.0, 20.0))?
+        .id();
+    world.create_entity().with_component(Health(10))?;
+
+    assert_eq!(
+        world.entities_within_radius([0.0, 0.0], 5.0)?,
+        vec![origin, near]
+    );
+    assert_eq!(
+        world.entities_in_aabb([-60.0, 0.0], [1.0, 30.0])?,
+        vec![origin, far]
+    );
+
+    // Positions modified in place are picked up on the next update.
+    for mut entity in world
+        .query()
+        .with_component_filter::<Position>()?
+        .get_entities()
+    {
+        if entity.id == far {
+            entity.get_component_mut::<Position>()?.0 = 1.0;
+        }
+    }
+    assert_eq!(
+        world.entities_within_radius([0.0, 0.0], 5.0)?,
+        vec![origin, near]
+    );
+    assert_eq!(world.update_spatial_index(), 1);
+    assert_eq!(
+        world.spatial_index().unwrap().position(far),
+        Some([1.0, 20.0])
+    );
+
+    world.remove_entity(origin)?;
+    world.remove_entity_component::<Position>(near)?;
+    assert!(world.entities_within_radius([0.0, 0.0], 5.0)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn queries_can_be_restricted_by_position() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Health>();
+
+    assert!(matches!(
+        world.query().within_radius([0.0, 0.0], 1.0),
+        Err(ECSError::SpatialIndexNotEnabled)
+    ));
+
+    world.enable_spatial_index::<Position>(8.0);
+
+    let wounded = world
+        .create_entity()
+        .with_component(Position(1.0, 1.0))?
+        .with_component(Health(5))?
+        .id();
+    world.create_entity().with_component(Position(2.0, 0.0))?;
+    let far = world
+        .create_entity()
+        .with_component(Position(30.0, 0.0))?
+        .with_component(Health(50))?
+        .id();
+
+    let nearby_health = world
+        .query()
+        .with_component_filter::<Health>()?
+        .within_radius([0.0, 0.0], 10.0)?
+        .run();
+    assert_eq!(nearby_health.entity_ids, vec![wounded]);
+    assert_eq!(nearby_health.components[0].len(), 1);
+
+    // Spatial constraints combine with each other.
+    let in_both = world
+        .query()
+        .within_radius([0.0, 0.0], 100.0)?
+        .in_aabb([20.0, -5.0], [40.0, 5.0])?
+        .get_entities();
+    assert_eq!(in_both.len(), 1);
+    assert_eq!(in_both[0].id, far);
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Team {
+    Red,
+    Blue,
+}
+
+#[test]
+fn entities_can_be_found_by_component_value() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Team>();
+
+    let first = world.create_entity().with_component(Team::Red)?.id();
+    world.register_value_index::<Team>();
+
+    let second = world.create_entity().with_component(Team::Blue)?.id();
+    let third = world.create_entity().with_component(Team::Red)?.id();
+
+    assert_eq!(world.entities_with_value(Team::Red), vec![first, third]);
+    assert_eq!(world.entities_with_value(Team::Blue), vec![second]);
+
+    let checkpoint = world.checkpoint()?;
+
+    // Modified in place: gone from the old value right away, found under the new one after
+    // the update.
+    for mut entity in world
+        .query()
+        .with_component_filter::<Team>()?
+        .get_entities()
+    {
+        if entity.id == first {
+            *entity.get_component_mut::<Team>()? = Team::Blue;
+        }
+    }
+    assert_eq!(world.entities_with_value(Team::Red), vec![third]);
+    assert_eq!(world.update_value_indexes(), 1);
+    assert_eq!(world.entities_with_value(Team::Blue), vec![first, second]);
+
+    world.remove_entity(second)?;
+    world.add_component_to_entity(third, Team::Blue)?;
+    assert!(world.entities_with_value(Team::Red).is_empty());
+    assert_eq!(world.entities_with_value(Team::Blue), vec![first, third]);
+
+    world.restore(&checkpoint)?;
+    assert_eq!(world.entities_with_value(Team::Red), vec![first, third]);
+    Ok(())
+}
+
+#[derive(Clone)]
+struct MainCamera;
+
+#[test]
+fn unique_components_are_held_by_one_entity() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<MainCamera>();
+    world.register_unique_component::<MainCamera>();
+    world.register_component::<Health>();
+
+    assert_eq!(world.single::<MainCamera>(), None);
+
+    let camera = world.create_entity().with_component(MainCamera)?.id();
+    let other = world.create_entity().with_component(Health(1))?.id();
+
+    assert!(matches!(
+        world.add_component_to_entity(other, MainCamera),
+        Err(ECSError::UniqueComponentTaken)
+    ));
+    assert!(matches!(
+        world.add_component_dynamic(other, TypeId::of::<MainCamera>(), Box::new(MainCamera)),
+        Err(ECSError::UniqueComponentTaken)
+    ));
+
+    // Replacing it on the holder is fine.
+    world.add_component_to_entity(camera, MainCamera)?;
+    assert_eq!(world.single::<MainCamera>(), Some(camera));
+
+    // Moving it means removing it first.
+    world.remove_entity_component::<MainCamera>(camera)?;
+    world.add_component_to_entity(other, MainCamera)?;
+    assert_eq!(world.single::<MainCamera>(), Some(other));
+    Ok(())
+}
+
+struct Sprite;
+
+#[derive(Default)]
+struct Collider;
+
+#[test]
+fn required_components_are_inserted_when_missing() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Sprite>();
+    world.register_component::<Position>();
+    world.register_component::<Collider>();
+    world.register_required_component_with::<Sprite, Position>(|| Position(1.0, 2.0));
+    world.register_required_component::<Position, Collider>();
+    world.on_add::<Sprite>(|world, entity_id| {
+        // Required components are already there when the hooks of `Sprite` run.
+        let has_collider = world
+            .query()
+            .with_component_filter::<Collider>()
+            .is_ok_and(|query| query.run().entity_ids.contains(&entity_id));
+        assert!(has_collider);
+    });
+
+    let spawned = world.create_entity().with_component(Sprite)?.id();
+    let positioned = world
+        .create_entity()
+        .with_component(Position(9.0, 9.0))?
+        .with_component(Sprite)?
+        .id();
+
+    let result = world
+        .query()
+        .with_component_filter::<Position>()?
+        .with_component_filter::<Collider>()?
+        .run();
+    assert_eq!(result.entity_ids, vec![spawned, positioned]);
+
+    // Components the entity already had are kept.
+    let positions: Vec<(f32, f32)> = result.components[0]
+        .iter()
+        .map(|position| {
+            let position = position.borrow();
+            let position = position.downcast_ref::<Position>().unwrap();
+            (position.0, position.1)
+        })
+        .collect();
+    assert_eq!(positions, vec![(1.0, 2.0), (9.0, 9.0)]);
+    Ok(())
+}
+
+#[derive(Default)]
+struct Armor(pub u32);
+
+#[test]
+fn components_can_be_created_from_their_default() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component_with_default(|| Health(100));
+    world.register_component::<Armor>();
+    world.register_component::<Position>();
+    world.type_registry_mut().register_default::<Armor>();
+
+    let entity_id = world
+        .create_entity()
+        .insert_default::<Health>()?
+        .insert_default::<Armor>()?
+        .id();
+
+    assert!(matches!(
+        world.insert_default::<Position>(entity_id),
+        Err(ECSError::NoDefaultValue)
+    ));
+
+    let query = world
+        .query()
+        .with_component_filter::<Health>()?
+        .with_component_filter::<Armor>()?
+        .get_entities();
+    assert_eq!(query.len(), 1);
+    assert_eq!(query[0].get_component::<Health>()?.0, 100);
+    assert_eq!(query[0].get_component::<Armor>()?.0, 0);
+    Ok(())
+}
+
+#[test]
+fn defaults_can_be_added_to_registered_components() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.create_entity().with_component(Health(5))?;
+    world.register_component_with_default(|| Health(100));
+    world.create_entity().insert_default::<Health>()?;
+
+    let query = world
+        .query()
+        .with_component_filter::<Health>()?
+        .get_entities();
+    assert_eq!(query.len(), 2);
+    assert_eq!(query[0].get_component::<Health>()?.0, 5);
+    assert_eq!(query[1].get_component::<Health>()?.0, 100);
+    Ok(())
+}
+
+struct Dead;
+
+#[test]
+fn zero_sized_components_can_be_queried() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dead>();
+
+    world
+        .create_entity()
+        .with_component(Health(0))?
+        .with_component(Dead)?;
+    world.create_entity().with_component(Health(10))?;
+    world
+        .create_entity()
+        .with_component(Health(0))?
+        .with_component(Dead)?;
+
+    let query = world
+        .query()
+        .with_component_filter::<Health>()?
+        .with_component_filter::<Dead>()?
+        .run();
+
+    assert_eq!(query.entity_ids, vec![0, 2]);
+    assert_eq!(query.components[1].len(), 2);
+
+    let dead = world
+        .query()
+        .with_component_filter::<Dead>()?
+        .get_entities();
+    assert!(dead[1].get_component::<Dead>().is_ok());
+    Ok(())
+}
+
+#[test]
+fn entities_can_be_queried_by_tag() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+
+    let dragon = world.create_entity().with_component(Health(500))?.id();
+    let goblin = world.create_entity().with_component(Health(10))?.id();
+
+    world.add_tag(dragon, "boss")?;
+    world.add_tag(dragon, "flying")?;
+    world.add_to_group(goblin, "boss")?;
+
+    assert!(world.has_tag(dragon, "flying"));
+    assert!(!world.has_tag(goblin, "boss"));
+
+    let checkpoint = world.checkpoint()?;
+
+    world.remove_tag(dragon, "boss")?;
+    assert!(world.query().with_tag("boss").run().entity_ids.is_empty());
+    assert_eq!(world.entity_tags(dragon), vec!["flying"]);
+
+    world.restore(&checkpoint)?;
+    assert_eq!(
+        world
+            .query()
+            .with_tag("boss")
+            .with_tag("flying")
+            .run()
+            .entity_ids,
+        vec![dragon]
+    );
+    assert!(matches!(
+        world.add_tag(7, "boss"),
+        Err(ECSError::EntityDoesNotExist { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn previous_values_are_kept_until_the_next_update() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Health>();
+    world.track_previous_values::<Health>();
+
+    let knight = world.create_entity().with_component(Health(100))?.id();
+    let previous_health = |world: &World| -> Result<u32, ECSError> {
+        let entities = world
+            .query()
+            .with_component_filter::<Prev<Health>>()?
+            .get_entities();
+        let health = entities[0].get_component::<Prev<Health>>()?.0 .0;
+        Ok(health)
+    };
+
+    assert_eq!(previous_health(&world)?, 100);
+
+    for mut entity in world
+        .query()
+        .with_component_filter::<Health>()?
+        .get_entities()
+    {
+        entity.get_component_mut::<Health>()?.0 -= 30;
+    }
+    assert_eq!(previous_health(&world)?, 100);
+
+    world.update_previous_values();
+    assert_eq!(previous_health(&world)?, 70);
+
+    world.remove_entity_component::<Health>(knight)?;
+    assert!(world
+        .query()
+        .with_component_filter::<Prev<Health>>()?
+        .run()
+        .entity_ids
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn compacting_keeps_living_entities() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Position>();
+
+    for health in 0..10 {
+        world.create_entity().with_component(Health(health))?;
+    }
+    for entity_id in (0..10).filter(|entity_id| *entity_id != 4) {
+        world.remove_entity(entity_id)?;
+    }
+
+    assert_eq!(world.compact(), 5);
+    assert_eq!(world.compact(), 0);
+
+    let survivors = world
+        .query()
+        .with_component_filter::<Health>()?
+        .get_entities();
+    assert_eq!(survivors.len(), 1);
+    assert_eq!(survivors[0].id, 4);
+    assert_eq!(survivors[0].get_component::<Health>()?.0, 4);
+    drop(survivors);
+
+    // Freed slots below the last living entity are still reused, and new ones appended.
+    let mut entity_ids = vec![];
+    for _ in 0..6 {
+        entity_ids.push(
+            world
+                .create_entity()
+                .with_component(Position(0.0, 0.0))?
+                .id(),
+        );
+    }
+    assert_eq!(entity_ids, vec![0, 1, 2, 3, 5, 6]);
+    Ok(())
+}
+
+#[test]
+fn memory_report_lists_columns_and_resources() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dead>();
+    world.add_resource(Counter(0))?;
+
+    for health in 0..4 {
+        world.create_entity().with_component(Health(health))?;
+    }
+    world.add_component_to_entity(0, Dead)?;
+    world.remove_entity(3)?;
+
+    let report = world.memory_report();
+    let health = report.component(std::any::type_name::<Health>()).unwrap();
+    let dead = report.component(std::any::type_name::<Dead>()).unwrap();
+
+    assert_eq!(report.entity_count, 4);
+    assert_eq!(health.len, 3);
+    assert!(health.heap_bytes >= 4 * std::mem::size_of::<Health>());
+    assert_eq!((dead.len, dead.capacity), (1, 0));
+    assert!(report
+        .resources
+        .iter()
+        .any(|resource| resource.name == std::any::type_name::<Counter>()));
+
+    world.compact();
+    assert!(world.memory_report().total_bytes() < report.total_bytes());
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Mana(pub u32);
+
+#[test]
+fn debug_dump_lists_entities_and_resources() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_debuggable_component::<Mana>();
+    world.register_component::<Dead>();
+    world.add_resource(Counter(3))?;
+    world.type_registry_mut().register_debug::<Counter>();
+
+    world.create_entity().with_component(Mana(5))?;
+    let ghost = world
+        .create_entity()
+        .with_component(Mana(0))?
+        .with_component(Dead)?
+        .id();
+    world.create_entity().with_component(Dead)?;
+    world.remove_entity(2)?;
+    world.disable_entity(ghost)?;
+
+    let dump = world.debug_dump();
+    let first_mana = world
+        .query()
+        .with_component_filter::<Mana>()?
+        .get_entities()[0]
+        .get_component::<Mana>()?
+        .0;
+
+    assert_eq!(dump.entities.len(), 2);
+    assert_eq!(
+        dump.entity(0).unwrap().component("Mana").unwrap().value,
+        Some(format!("Mana({first_mana})"))
+    );
+    let ghost = dump.entity(ghost).unwrap();
+    assert!(ghost.disabled);
+    assert_eq!(
+        ghost
+            .components
+            .iter()
+            .map(|component| component.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Dead", "Mana"]
+    );
+    assert_eq!(
+        ghost.component("Mana").unwrap().value.as_deref(),
+        Some("Mana(0)")
+    );
+
+    let counter = dump
+        .resources
+        .iter()
+        .find(|resource| resource.name == "Counter")
+        .unwrap();
+    assert_eq!(counter.value.as_deref(), Some("Counter(3)"));
+    assert!(dump
+        .to_string()
+        .contains("  1 (disabled):\n    Dead\n    Mana: Mana(0)\n"));
+    Ok(())
+}
+
+#[test]
+fn registration_helpers_keep_existing_components() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_cloneable_component::<Counter>();
+    world.register_component::<Scale>();
+    world.create_entity().with_component(Counter(7))?;
+    world.register_debuggable_component::<Counter>();
+    world.create_entity().with_component(Scale(1.0, 1.0))?;
+
+    assert_eq!(counter(&world)?, 7);
+    assert!(world
+        .query()
+        .with_component_filter::<Counter>()?
+        .with_component_filter::<Scale>()?
+        .run()
+        .entity_ids
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn entities_list_their_component_types() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dead>();
+    world.register_dynamic_component(
+        DynamicComponentDescriptor::new("Mana").with_field("current", FieldType::Int),
+    )?;
+
+    let entity_id = world
+        .create_entity()
+        .with_component(Health(10))?
+        .with_component(Dead)?
+        .id();
+    world.add_dynamic_component(
+        entity_id,
+        DynamicComponent::new("Mana").with_field("current", DynamicValue::Int(3)),
+    )?;
+
+    let components = world.entity(entity_id)?.component_types();
+    let names: Vec<&str> = components
+        .iter()
+        .map(|component| component.name.as_str())
+        .collect();
+
+    assert_eq!(
+        names,
+        vec![
+            "Mana",
+            std::any::type_name::<Dead>(),
+            std::any::type_name::<Health>()
+        ]
+    );
+    assert_eq!(components[1].size, 0);
+    assert_eq!(components[2].type_id, TypeId::of::<Health>());
+    assert_eq!(components[2].size, std::mem::size_of::<Health>());
+    assert!(matches!(
+        world.entity(5),
+        Err(ECSError::EntityDoesNotExist { id: 5 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn registered_components_can_be_enumerated() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dead>();
+    world.register_dynamic_component(DynamicComponentDescriptor::new("Mana"))?;
+
+    world.create_entity().with_component(Health(10))?;
+    world
+        .create_entity()
+        .with_component(Health(0))?
+        .with_component(Dead)?;
+    world.remove_entity(0)?;
+
+    let registrations = world.component_registrations();
+    let summary: Vec<(&str, StorageKind, usize)> = registrations
+        .iter()
+        .map(|registration| {
+            (
+                registration.name.as_str(),
+                registration.storage,
+                registration.count,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            ("Mana", StorageKind::Dynamic, 0),
+            (std::any::type_name::<Dead>(), StorageKind::ZeroSized, 1),
+            (std::any::type_name::<Health>(), StorageKind::Column, 1),
+        ]
+    );
+    assert!(registrations
+        .iter()
+        .all(|registration| registration.bitmask.count_ones() == 1));
+    assert_ne!(registrations[1].bitmask, registrations[2].bitmask);
+    Ok(())
+}
+
+#[test]
+fn query_macro_borrows_components() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dead>();
+    world.register_component::<Armor>();
+
+    world.create_entity().with_component(Health(10))?;
+    world
+        .create_entity()
+        .with_component(Health(0))?
+        .with_component(Dead)?;
+    world.create_entity().with_component(Health(5))?;
+
+    query!(world, |health: &mut Health, _dead: &Dead, entity| {
+        health.0 = 1;
+        world.remove_entity(entity)?;
+    })?;
+
+    let mut remaining = vec![];
+    query!(world, |health: &Health, entity| {
+        remaining.push((entity, health.0));
+    })?;
+    assert_eq!(remaining, vec![(0, 10), (2, 5)]);
+
+    let result = query!(world, |_armor: &Armor, _position: &Position| {});
+    assert!(matches!(
+        result,
+        Err(ECSError::ComponentNotRegistered { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn components_can_be_registered_after_entities_exist() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.create_entity().with_component(Health(10))?;
+    world.create_entity().with_component(Health(20))?;
+
+    world.register_component::<Armor>();
+    world.add_component_to_entity(1, Armor(5))?;
+    world.create_entity().with_component(Armor(1))?;
+
+    let query = world.query().with_component_filter::<Armor>()?.run();
+    assert_eq!(query.entity_ids, vec![1, 2]);
+    Ok(())
+}
+
+#[test]
+fn query_entities_record_deferred_commands() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Dead>();
+    world.register_component::<Armor>();
+
+    for health in [0, 10, 0] {
+        world.create_entity().with_component(Health(health))?;
+    }
+
+    let mut commands = Commands::new();
+
+    for entity in world
+        .query()
+        .with_component_filter::<Health>()?
+        .get_entities()
+    {
+        match entity.get_component::<Health>()?.0 {
+            0 if entity.id == 0 => entity.commands(&mut commands).despawn(),
+            0 => {
+                entity
+                    .commands(&mut commands)
+                    .insert(Dead)
+                    .remove::<Health>();
+            }
+            _ => {
+                entity.commands(&mut commands).insert(Armor(3));
+            }
+        }
+    }
+
+    assert_eq!(commands.len(), 4);
+    world.apply_commands(commands)?;
+
+    assert_eq!(
+        world
+            .query()
+            .with_component_filter::<Health>()?
+            .run()
+            .entity_ids,
+        vec![1]
+    );
+    assert_eq!(
+        world
+            .query()
+            .with_component_filter::<Dead>()?
+            .run()
+            .entity_ids,
+        vec![2]
+    );
+    assert_eq!(
+        world
+            .query()
+            .with_component_filter::<Armor>()?
+            .run()
+            .entity_ids,
+        vec![1]
+    );
+
+    let mut commands = Commands::new();
+    commands.entity(0).insert(Armor(1));
+    assert!(matches!(
+        world.apply_commands(commands),
+        Err(ECSError::EntityDoesNotExist { id: 0 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn reserved_entities_are_spawned_by_commands() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.create_entity().with_component(Health(1))?;
+    world.remove_entity(0)?;
+
+    let mut commands = Commands::new();
+    let reserved: Vec<usize> = (0..2).map(|_| world.entities().reserve()).collect();
+
+    for entity_id in &reserved {
+        commands.entity(*entity_id).insert(Health(10));
+    }
+
+    assert_eq!(reserved, vec![1, 2]);
+    assert_eq!(world.entities().len(), 1);
+
+    let created = world.create_entity().with_component(Health(5))?.id();
+    assert_eq!(created, 0);
+
+    world.apply_commands(commands)?;
+
+    let query = world.query().with_component_filter::<Health>()?.run();
+    assert_eq!(query.entity_ids, vec![0, 1, 2]);
+    Ok(())
+}
+
+#[test]
+fn entities_can_be_reserved_from_other_threads() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+
+    let reserver = world.entities().reserver();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let reserver = reserver.clone();
+
+            std::thread::spawn(move || (0..10).map(|_| reserver.reserve()).collect::<Vec<_>>())
+        })
+        .collect();
+
+    world.create_entity().with_component(Health(5))?;
+
+    let mut commands = Commands::new();
+
+    for thread in threads {
+        for entity_id in thread.join().unwrap() {
+            commands.entity(entity_id).insert(Health(10));
+        }
+    }
+
+    world.apply_commands(commands)?;
+
+    let query = world.query().with_component_filter::<Health>()?.run();
+    assert_eq!(query.entity_ids, (0..41).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn pooled_entities_are_reset_and_reused() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Scale>();
+    world.enable_entity_pool::<(Position, Health)>(1)?;
+
+    let first = world.spawn_from_pool((Position(1.0, 1.0), Health(10)))?;
+    let second = world.spawn_from_pool((Position(2.0, 2.0), Health(20)))?;
+    world.add_component_to_entity(first, Scale(2.0, 2.0))?;
+
+    world.despawn_to_pool::<(Position, Health)>(first)?;
+    world.despawn_to_pool::<(Position, Health)>(second)?;
+
+    // The pool only had room for the first one.
+    assert_eq!(world.pooled_entities::<(Position, Health)>(), &[first]);
+    assert!(!world.entities().is_alive(second));
+    assert!(world.is_entity_disabled(first));
+    assert!(world
+        .query()
+        .with_component_filter::<Scale>()?
+        .run()
+        .entity_ids
+        .is_empty());
+
+    let reused = world.spawn_from_pool((Position(3.0, 3.0), Health(30)))?;
+    let query = world.query().with_component_filter::<Health>()?.run();
+
+    assert_eq!(reused, first);
+    assert_eq!(query.entity_ids, vec![first]);
+    assert_eq!(
+        query.components[0][0]
+            .borrow()
+            .downcast_ref::<Health>()
+            .unwrap()
+            .0,
+        30
+    );
+    assert!(matches!(
+        world.despawn_to_pool::<(Scale,)>(reused),
+        Err(ECSError::EntityPoolNotEnabled)
+    ));
+    Ok(())
+}
+
+#[test]
+fn pooled_entities_cannot_be_despawned_twice() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.enable_entity_pool::<(Health,)>(4)?;
+
+    let entity_id = world.spawn_from_pool((Health(10),))?;
+    world.despawn_to_pool::<(Health,)>(entity_id)?;
+
+    assert!(matches!(
+        world.despawn_to_pool::<(Health,)>(entity_id),
+        Err(ECSError::EntityAlreadyPooled { .. })
+    ));
+    assert_eq!(world.pooled_entities::<(Health,)>(), &[entity_id]);
+
+    let first = world.spawn_from_pool((Health(20),))?;
+    let second = world.spawn_from_pool((Health(30),))?;
+    assert_ne!(first, second);
+    Ok(())
+}
+
+#[test]
+fn defragmenting_packs_entities_and_patches_references() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_hierarchy();
+    world.enable_entity_names();
+
+    let ids: Vec<usize> = (0..6)
+        .map(|health| {
+            world
+                .create_entity()
+                .with_component(Health(health))
+                .unwrap()
+                .id()
+        })
+        .collect();
+
+    world.set_parent(ids[5], ids[3])?;
+    world.add_component_to_entity(ids[5], Name::new("leaf"))?;
+    world.disable_entity(ids[3])?;
+
+    for removed in [ids[0], ids[1], ids[4]] {
+        world.remove_entity(removed)?;
+    }
+
+    let remap = world.defragment();
+
+    assert_eq!(remap.len(), 3);
+    assert_eq!((remap[&2], remap[&3], remap[&5]), (0, 1, 2));
+    assert_eq!(world.parent(2), Some(1));
+    assert_eq!(world.children(1).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(world.entity_by_name("leaf"), Some(2));
+    assert!(world.is_entity_disabled(1));
+    assert_eq!(world.entities().len(), 3);
+
+    let query = world.query().with_component_filter::<Health>()?.run();
+    let healths: Vec<u32> = query.components[0]
+        .iter()
+        .map(|health| health.borrow().downcast_ref::<Health>().unwrap().0)
+        .collect();
+
+    assert_eq!(query.entity_ids, vec![0, 2]);
+    assert_eq!(healths, vec![2, 5]);
+    Ok(())
+}
+
+#[test]
+fn query_one_reports_why_a_component_is_unavailable() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+    world.register_component::<Scale>();
+
+    let player = world.create_entity().with_component(Health(10))?.id();
+    let removed = world.create_entity().with_component(Health(5))?.id();
+    world.remove_entity(removed)?;
+
+    {
+        let health = world.query_one::<Health>(player)?;
+
+        assert_eq!(health.0, 10);
+        assert!(matches!(
+            world.query_one_mut::<Health>(player),
+            Err(ECSError::ComponentAlreadyBorrowed { id, .. }) if id == player
+        ));
+    }
+
+    assert!(matches!(
+        world.query_one::<Position>(player),
+        Err(ECSError::ComponentNotRegistered { .. })
+    ));
+    assert!(matches!(
+        world.query_one::<Health>(removed),
+        Err(ECSError::EntityDoesNotExist { id }) if id == removed
+    ));
+    assert!(matches!(
+        world.query_one::<Scale>(player),
+        Err(ECSError::ComponentDoesNotExist { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn each_visits_entities_with_every_component() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Scale>();
+    world.register_component::<Health>();
+
+    world
+        .create_entity()
+        .with_component(Position(1.0, 1.0))?
+        .with_component(Scale(2.0, 3.0))?;
+    world.create_entity().with_component(Position(5.0, 5.0))?;
+
+    world.each_mut::<(Position, Scale), _>(|_, position, scale| {
+        position.0 *= scale.0;
+        position.1 *= scale.1;
+    })?;
+
+    let mut positions = vec![];
+    world.each::<(Position,), _>(|entity, position| {
+        positions.push((entity, position.0, position.1));
+    })?;
+
+    assert_eq!(positions, vec![(0, 2.0, 3.0), (1, 5.0, 5.0)]);
+    assert!(matches!(
+        world.each::<(Position, Velocity), _>(|_, _, _| {}),
+        Err(ECSError::ComponentNotRegistered { .. })
+    ));
+
+    let mut visited = 0;
+    world.each::<(Health,), _>(|_, _| visited += 1)?;
+    assert_eq!(visited, 0);
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use sara_ecs::{
+    ecs_errors::ECSError,
+    events::{EntityDespawned, EntitySpawned, EventReader},
+    World,
+};
+
+struct Collision(pub usize, pub usize);
+
+#[test]
+fn send_and_read_events() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_event::<Collision>();
+    world.send_event(Collision(0, 1))?;
+    world.send_event(Collision(2, 3))?;
+
+    let collisions = world.get_events::<Collision>().unwrap();
+    let pairs: Vec<(usize, usize)> = collisions.iter().map(|c| (c.0, c.1)).collect();
+
+    assert_eq!(pairs, vec![(0, 1), (2, 3)]);
+    Ok(())
+}
+
+#[test]
+fn sending_unregistered_event_fails() {
+    let mut world = World::new();
+
+    let result = world.send_event(Collision(0, 1));
+
+    assert!(matches!(result, Err(ECSError::EventNotRegistered)));
+}
+
+#[test]
+fn events_are_dropped_after_two_updates() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_event::<Collision>();
+    world.send_event(Collision(0, 1))?;
+
+    world.update_events();
+    assert_eq!(world.get_events::<Collision>().unwrap().len(), 1);
+
+    world.update_events();
+    assert!(world.get_events::<Collision>().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn registering_event_twice_keeps_pending_events() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_event::<Collision>();
+    world.send_event(Collision(0, 1))?;
+    world.add_event::<Collision>();
+
+    assert_eq!(world.get_events::<Collision>().unwrap().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn late_reader_sees_last_frame_events() -> Result<(), ECSError> {
+    let mut world = World::new();
+    let mut reader = EventReader::<Collision>::default();
+
+    world.add_event::<Collision>();
+    world.send_event(Collision(0, 1))?;
+    world.update_events();
+
+    let collisions = world.get_events::<Collision>().unwrap();
+    assert_eq!(reader.read(&collisions).count(), 1);
+    assert_eq!(reader.read(&collisions).count(), 0);
+    Ok(())
+}
+
+#[test]
+fn lifecycle_events_are_sent_when_registered() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_event::<EntitySpawned>();
+    world.add_event::<EntityDespawned>();
+    world.register_component::<Collision>();
+
+    world.create_entity().with_component(Collision(0, 0))?;
+    world.create_entity().with_component(Collision(1, 1))?;
+    world.remove_entity(0)?;
+
+    let spawned = world.get_events::<EntitySpawned>().unwrap();
+    let despawned = world.get_events::<EntityDespawned>().unwrap();
+    let spawned: Vec<&EntitySpawned> = spawned.iter().collect();
+    let despawned: Vec<&EntityDespawned> = despawned.iter().collect();
+
+    assert_eq!(spawned, vec![&EntitySpawned(0), &EntitySpawned(1)]);
+    assert_eq!(despawned, vec![&EntityDespawned(0)]);
+    Ok(())
+}
+
+#[test]
+fn lifecycle_events_are_not_stored_when_unregistered() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Collision>();
+    world.create_entity().with_component(Collision(0, 0))?;
+
+    assert!(world.get_events::<EntitySpawned>().is_none());
+    Ok(())
+}
+
+#[test]
+fn removed_entities_are_only_despawned_once() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_event::<EntityDespawned>();
+    world.register_component::<Collision>();
+
+    world.create_entity().with_component(Collision(0, 0))?;
+    world.remove_entity(0)?;
+
+    assert!(matches!(
+        world.remove_entity(0),
+        Err(ECSError::EntityDoesNotExist { id: 0 })
+    ));
+
+    let empty = world.create_entity().id();
+    world.remove_entity(empty)?;
+
+    let despawned = world.get_events::<EntityDespawned>().unwrap();
+    let despawned: Vec<&EntityDespawned> = despawned.iter().collect();
+
+    assert_eq!(
+        despawned,
+        vec![&EntityDespawned(0), &EntityDespawned(empty)]
+    );
+    Ok(())
+}
+
+struct OnDamage(pub u32);
+struct DamageLog(pub Vec<(usize, u32)>);
+
+#[test]
+fn observers_run_immediately_in_registration_order() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.add_resource(DamageLog(vec![]))?;
+    world.observe::<OnDamage>(|trigger, world| {
+        let mut log = world.get_resource_mut::<DamageLog>().unwrap();
+        log.0.push((trigger.entity_id, trigger.event.0));
+    });
+    world.observe::<OnDamage>(|trigger, world| {
+        let mut log = world.get_resource_mut::<DamageLog>().unwrap();
+        log.0.push((trigger.entity_id, trigger.event.0 * 2));
+    });
+
+    world.trigger(4, OnDamage(5));
+
+    let log = &world.get_resource::<DamageLog>().unwrap().0;
+    assert_eq!(log, &vec![(4, 5), (4, 10)]);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "fuzzing")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use sara_ecs::fuzz::{self, Operation};
+use sara_ecs::rng::Rng;
+
+#[test]
+fn random_operation_sequences_keep_the_world_consistent() {
+    let mut rng = Rng::seed_from_u64(0x5eed);
+
+    for _ in 0..200 {
+        let operations = Operation::random_sequence(&mut rng, 100);
+
+        if let Err(failure) = fuzz::run(&operations) {
+            panic!("{failure}, shrunk to {:?}", fuzz::shrink(operations));
+        }
+    }
+}
+
+#[test]
+fn arbitrary_byte_inputs_keep_the_world_consistent() {
+    let mut rng = Rng::seed_from_u64(0xb17e5);
+
+    for _ in 0..50 {
+        let data: Vec<u8> = (0..300).map(|_| rng.next_u32() as u8).collect();
+        let operations = Vec::<Operation>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+
+        if let Err(failure) = fuzz::run(&operations) {
+            panic!("{failure}, shrunk to {:?}", fuzz::shrink(operations));
+        }
+    }
+}
+
+#[test]
+fn operations_on_missing_entities_are_refused() {
+    use sara_ecs::fuzz::FuzzComponent;
+
+    let operations = [
+        Operation::Spawn(FuzzComponent::Small(1)),
+        Operation::Spawn(FuzzComponent::Marker),
+        Operation::Despawn { entity: 0 },
+        Operation::DespawnId { id: 0 },
+        Operation::InsertId {
+            id: 0,
+            component: FuzzComponent::Large(2),
+        },
+        Operation::RemoveId { id: 0, kind: 0 },
+        Operation::DespawnId { id: 200 },
+        Operation::InsertId {
+            id: 200,
+            component: FuzzComponent::Marker,
+        },
+        Operation::RemoveId { id: 200, kind: 2 },
+        Operation::RemoveId { id: 1, kind: 2 },
+    ];
+
+    assert_eq!(fuzz::run(&operations), Ok(()));
+}
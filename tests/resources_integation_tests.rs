@@ -1,4 +1,4 @@
-use sara_ecs::{ecs_errors::ECSError, World};
+use sara_ecs::{ecs_errors::ECSError, resource_hooks::ResourceChange, World};
 
 struct FpsResource(pub u32);
 
@@ -22,10 +22,10 @@ fn create_and_get_resource_immutably() {
 
 #[test]
 fn get_resources_mutably() {
-    let mut world = get_test_world().unwrap();
+    let world = get_test_world().unwrap();
 
     {
-        let fps = world.get_resource_mut::<FpsResource>().unwrap();
+        let mut fps = world.get_resource_mut::<FpsResource>().unwrap();
         fps.0 += 1;
     }
 
@@ -34,6 +34,31 @@ fn get_resources_mutably() {
     assert_eq!(fps.0, 61);
 }
 
+#[test]
+fn get_different_resources_mutably_at_once() {
+    let mut world = get_test_world().unwrap();
+    world.add_resource(Gravity(9.8)).unwrap();
+
+    let world = &world;
+    let mut fps = world.get_resource_mut::<FpsResource>().unwrap();
+    let mut gravity = world.get_resource_mut::<Gravity>().unwrap();
+
+    fps.0 += 1;
+    gravity.0 = 0.0;
+
+    assert_eq!(fps.0, 61);
+    assert_eq!(gravity.0, 0.0);
+}
+
+#[test]
+#[should_panic]
+fn get_same_resource_mutably_twice_panics() {
+    let world = get_test_world().unwrap();
+
+    let _fps = world.get_resource_mut::<FpsResource>().unwrap();
+    let _same_fps = world.get_resource_mut::<FpsResource>().unwrap();
+}
+
 #[test]
 fn remove_resources() {
     let mut world = get_test_world().unwrap();
@@ -61,3 +86,210 @@ fn replace_resource() {
     let fps = world.get_resource::<FpsResource>().unwrap();
     assert_eq!(fps.0, 120);
 }
+
+struct ResourceChanges(pub Vec<ResourceChange>);
+
+#[test]
+fn resource_hooks_run_on_every_change() {
+    let mut world = World::new();
+
+    world.add_resource(ResourceChanges(vec![])).unwrap();
+    world.on_resource_change::<FpsResource>(|world, change| {
+        world
+            .get_resource_mut::<ResourceChanges>()
+            .unwrap()
+            .0
+            .push(change);
+    });
+
+    world.add_resource(FpsResource(60)).unwrap();
+    world.replace_resource(FpsResource(30));
+    world.remove_resource::<FpsResource>();
+    world.remove_resource::<FpsResource>();
+    world.replace_resource(FpsResource(144));
+
+    let changes = &world.get_resource::<ResourceChanges>().unwrap().0;
+    assert_eq!(
+        changes,
+        &vec![
+            ResourceChange::Added,
+            ResourceChange::Replaced,
+            ResourceChange::Removed,
+            ResourceChange::Added,
+        ]
+    );
+}
+
+#[derive(Default)]
+struct Gravity(pub f32);
+
+#[test]
+fn init_resource_uses_default() {
+    let mut world = World::new();
+
+    world.init_resource::<Gravity>();
+
+    assert_eq!(world.get_resource::<Gravity>().unwrap().0, 0.0);
+}
+
+#[test]
+fn init_resource_keeps_existing_resource() {
+    let mut world = World::new();
+
+    world.add_resource(Gravity(9.8)).unwrap();
+    world.init_resource::<Gravity>();
+
+    assert_eq!(world.get_resource::<Gravity>().unwrap().0, 9.8);
+}
+
+#[test]
+fn get_resource_or_insert_with_creates_missing_resource() {
+    let mut world = World::new();
+
+    world.get_resource_or_insert_with(|| Gravity(9.8)).0 *= 2.0;
+
+    assert_eq!(world.get_resource::<Gravity>().unwrap().0, 19.6);
+}
+
+#[test]
+fn get_resource_or_insert_with_returns_existing_resource() {
+    let mut world = get_test_world().unwrap();
+
+    let fps = world.get_resource_or_insert_with(|| FpsResource(30));
+
+    assert_eq!(fps.0, 60);
+}
+
+#[test]
+fn take_resource_returns_ownership() {
+    let mut world = get_test_world().unwrap();
+
+    let fps = world.take_resource::<FpsResource>().unwrap();
+
+    assert_eq!(fps.0, 60);
+    assert!(!world.contains_resource::<FpsResource>());
+}
+
+#[test]
+fn resource_scope_lends_resource_and_world() {
+    let mut world = get_test_world().unwrap();
+    world.add_resource(Gravity(1.0)).unwrap();
+
+    let result = world.resource_scope::<FpsResource, _>(|world, fps| {
+        world.get_resource_mut::<Gravity>().unwrap().0 = fps.0 as f32;
+        assert!(!world.contains_resource::<FpsResource>());
+        fps.0 += 1;
+        fps.0
+    });
+
+    assert_eq!(result.unwrap(), 61);
+    assert_eq!(world.get_resource::<FpsResource>().unwrap().0, 61);
+    assert_eq!(world.get_resource::<Gravity>().unwrap().0, 60.0);
+}
+
+#[test]
+fn resource_scope_fails_for_missing_resource() {
+    let mut world = World::new();
+
+    let result = world.resource_scope::<FpsResource, _>(|_world, _fps| ());
+
+    assert!(matches!(result, Err(ECSError::ResourceDoesNotExist { .. })));
+}
+
+#[test]
+fn keyed_resources_of_same_type() {
+    let mut world = World::new();
+
+    world
+        .add_keyed_resource("player1", FpsResource(30))
+        .unwrap();
+    world
+        .add_keyed_resource("player2", FpsResource(60))
+        .unwrap();
+
+    assert_eq!(
+        world
+            .get_keyed_resource::<FpsResource>("player1")
+            .unwrap()
+            .0,
+        30
+    );
+    assert_eq!(
+        world
+            .get_keyed_resource::<FpsResource>("player2")
+            .unwrap()
+            .0,
+        60
+    );
+    assert!(!world.contains_resource::<FpsResource>());
+
+    let result = world.add_keyed_resource("player1", FpsResource(120));
+    assert!(matches!(result, Err(ECSError::ResourceAlreadyRegistered)));
+}
+
+trait Renderer {
+    fn draw_calls(&self) -> u32;
+    fn draw(&mut self);
+}
+
+struct NullRenderer(pub u32);
+
+impl Renderer for NullRenderer {
+    fn draw_calls(&self) -> u32 {
+        self.0
+    }
+
+    fn draw(&mut self) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn trait_resources_are_retrieved_by_trait() {
+    let mut world = World::new();
+
+    world
+        .add_trait_resource::<dyn Renderer>(Box::new(NullRenderer(0)))
+        .unwrap();
+
+    world
+        .get_trait_resource_mut::<dyn Renderer>()
+        .unwrap()
+        .draw();
+
+    let renderer = world.get_trait_resource::<dyn Renderer>().unwrap();
+    assert_eq!(renderer.draw_calls(), 1);
+    assert!(!world.contains_resource::<NullRenderer>());
+}
+
+#[test]
+fn resources_can_be_iterated_without_their_types() -> Result<(), ECSError> {
+    let mut world = get_test_world()?;
+
+    world.add_resource(10_u32)?;
+    world.add_keyed_resource("gold", 5_u64)?;
+
+    let names: Vec<&str> = world
+        .resources_iter()
+        .map(|(_type_id, name, _resource)| name)
+        .collect();
+    assert!(names.contains(&std::any::type_name::<FpsResource>()));
+    assert!(names.contains(&"u32"));
+    assert!(!names.contains(&"u64"));
+
+    let total: u32 = world
+        .resources_iter()
+        .filter_map(|(_type_id, _name, resource)| {
+            resource
+                .downcast_ref::<FpsResource>()
+                .map(|fps| fps.0)
+                .or_else(|| resource.downcast_ref::<u32>().copied())
+        })
+        .sum();
+    assert_eq!(total, 70);
+
+    // Mutably borrowed resources are skipped instead of panicking.
+    let _fps = world.get_resource_mut::<FpsResource>().unwrap();
+    assert_eq!(world.resources_iter().count(), 1);
+    Ok(())
+}
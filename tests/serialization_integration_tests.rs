@@ -0,0 +1,642 @@
+#![cfg(feature = "serde")]
+
+use sara_ecs::{
+    ecs_errors::ECSError,
+    prefab::Prefab,
+    replication::{InterestedPeers, Owner, PeerId},
+    type_registry::TypeRegistry,
+    EntityMap, MapEntities, World,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct GameProgress {
+    level: u32,
+    coins: u32,
+}
+
+#[test]
+fn resource_roundtrip_through_json() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_serializable_resource::<GameProgress>();
+    world.add_resource(GameProgress {
+        level: 3,
+        coins: 120,
+    })?;
+
+    let json = world.serialize_resource::<GameProgress>()?.to_string();
+
+    let mut loaded_world = World::new();
+    loaded_world.register_serializable_resource::<GameProgress>();
+    loaded_world.deserialize_resource::<GameProgress>(serde_json::from_str(&json).unwrap())?;
+
+    let progress = loaded_world.get_resource::<GameProgress>().unwrap();
+    assert_eq!(
+        *progress,
+        GameProgress {
+            level: 3,
+            coins: 120
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn serializing_unregistered_resource_fails() {
+    let mut world = World::new();
+
+    world.add_resource(10_u32).unwrap();
+
+    let result = world.serialize_resource::<u32>();
+    assert!(matches!(result, Err(ECSError::TypeNotSerializable)));
+}
+
+#[test]
+fn deserializing_invalid_data_fails() {
+    let mut world = World::new();
+
+    world.register_serializable_resource::<GameProgress>();
+
+    let result = world.deserialize_resource::<GameProgress>(serde_json::json!("not progress"));
+    assert!(matches!(result, Err(ECSError::Serialization(_))));
+}
+
+#[derive(Default)]
+struct ProgressChanges(pub Vec<sara_ecs::resource_hooks::ResourceChange>);
+
+#[test]
+fn loading_resources_runs_their_hooks() -> Result<(), ECSError> {
+    use sara_ecs::resource_hooks::ResourceChange;
+
+    let mut world = World::new();
+
+    world.register_serializable_resource::<GameProgress>();
+    world.add_resource(GameProgress { level: 1, coins: 5 })?;
+    let saved = world.serialize_resources()?;
+
+    let mut loaded = World::new();
+    loaded.register_serializable_resource::<GameProgress>();
+    loaded.add_resource(ProgressChanges::default())?;
+    loaded.on_resource_change::<GameProgress>(|world, change| {
+        world
+            .get_resource_mut::<ProgressChanges>()
+            .unwrap()
+            .0
+            .push(change);
+    });
+
+    loaded.deserialize_resources(saved.clone())?;
+    loaded.deserialize_resources(saved)?;
+
+    assert_eq!(
+        loaded.get_resource::<ProgressChanges>().unwrap().0,
+        vec![ResourceChange::Added, ResourceChange::Replaced]
+    );
+    assert_eq!(loaded.get_resource::<GameProgress>().unwrap().coins, 5);
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Position(pub f32, pub f32);
+
+struct Particle;
+
+#[test]
+fn world_snapshot_roundtrip() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Particle>();
+    world.register_serializable_component::<Position>();
+    world.register_serializable_resource::<GameProgress>();
+
+    world.create_entity().with_component(Position(1.0, 2.0))?;
+    world.create_entity().with_component(Position(3.0, 4.0))?;
+    world
+        .create_entity()
+        .with_component(Position(5.0, 6.0))?
+        .with_component(Particle)?;
+    world.remove_entity(0)?;
+    world.add_resource(GameProgress { level: 1, coins: 5 })?;
+
+    let json = serde_json::to_string(&world.snapshot()?).unwrap();
+
+    let mut loaded_world = World::new();
+    loaded_world.register_component::<Position>();
+    loaded_world.register_component::<Particle>();
+    loaded_world.register_serializable_component::<Position>();
+    loaded_world.register_serializable_resource::<GameProgress>();
+    loaded_world
+        .create_entity()
+        .with_component(Position(9.0, 9.0))?;
+    loaded_world.load_snapshot(serde_json::from_str(&json).unwrap())?;
+
+    let query = loaded_world
+        .query()
+        .with_component_filter::<Position>()?
+        .run();
+    assert_eq!(query.entity_ids, vec![1, 2]);
+
+    let borrowed_position = query.components[0][1].borrow();
+    let position = borrowed_position.downcast_ref::<Position>().unwrap();
+    assert_eq!(*position, Position(5.0, 6.0));
+
+    let progress = loaded_world.get_resource::<GameProgress>().unwrap();
+    assert_eq!(progress.level, 1);
+    Ok(())
+}
+
+#[test]
+fn stable_names_key_saved_data() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_serializable_component_with_name::<Position>("Position");
+    world.register_serializable_resource_with_name::<GameProgress>("GameProgress");
+    world.create_entity().with_component(Position(1.0, 2.0))?;
+    world.add_resource(GameProgress {
+        level: 2,
+        coins: 40,
+    })?;
+
+    let snapshot = world.snapshot()?;
+    assert!(snapshot.entities[0].components.contains_key("Position"));
+    assert!(snapshot.resources.contains_key("GameProgress"));
+
+    // Data saved under the type name, before the stable name was given, still loads.
+    let mut legacy = snapshot.clone();
+    let position = legacy.entities[0].components.remove("Position").unwrap();
+    legacy.entities[0]
+        .components
+        .insert(std::any::type_name::<Position>().to_owned(), position);
+
+    let mut loaded = World::new();
+    loaded.register_component::<Position>();
+    loaded.register_serializable_component_with_name::<Position>("Position");
+    loaded.register_serializable_resource_with_name::<GameProgress>("GameProgress");
+
+    loaded.load_snapshot(legacy)?;
+    assert_eq!(loaded.snapshot()?, snapshot);
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Follows(pub usize);
+
+impl MapEntities for Follows {
+    fn map_entities(&mut self, entity_map: &EntityMap) {
+        if let Some(new_id) = entity_map.get(&self.0) {
+            self.0 = *new_id;
+        }
+    }
+}
+
+#[test]
+fn spawn_snapshot_remaps_entity_references() -> Result<(), ECSError> {
+    let mut scene_world = World::new();
+
+    scene_world.register_component::<Position>();
+    scene_world.register_component::<Follows>();
+    scene_world.register_serializable_component::<Position>();
+    scene_world.register_serializable_component::<Follows>();
+
+    scene_world
+        .create_entity()
+        .with_component(Position(0.0, 0.0))?;
+    scene_world
+        .create_entity()
+        .with_component(Position(1.0, 0.0))?
+        .with_component(Follows(0))?;
+
+    let scene = scene_world.snapshot()?;
+
+    let mut world = World::new();
+    world.register_component::<Position>();
+    world.register_component::<Follows>();
+    world.register_serializable_component::<Position>();
+    world.register_serializable_component::<Follows>();
+    world.register_map_entities::<Follows>();
+
+    world.create_entity().with_component(Position(9.0, 9.0))?;
+    let entity_map = world.spawn_snapshot(scene)?;
+
+    assert_eq!(entity_map[&0], 1);
+    assert_eq!(entity_map[&1], 2);
+
+    let query = world.query().with_component_filter::<Follows>()?.run();
+    assert_eq!(query.entity_ids, vec![2]);
+
+    let borrowed_follows = query.components[0][0].borrow();
+    let follows = borrowed_follows.downcast_ref::<Follows>().unwrap();
+    assert_eq!(*follows, Follows(1));
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Stats {
+    health: u32,
+    speed: u32,
+}
+
+#[test]
+fn prefab_instances_keep_overrides_when_prefab_changes() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Stats>();
+    world.register_component::<Position>();
+    world.register_serializable_component::<Stats>();
+    world.register_serializable_component::<Position>();
+
+    let goblin = world.add_prefab(
+        Prefab::new()
+            .with_component(&Stats {
+                health: 30,
+                speed: 2,
+            })
+            .with_child(Prefab::new().with_component(&Position(0.0, 1.0))),
+    );
+
+    let regular = world.instantiate_prefab(goblin)?;
+    let boss = world.instantiate_prefab(goblin)?;
+    world.set_prefab_override::<Stats>(boss, serde_json::json!({ "health": 300 }))?;
+
+    world.update_prefab(
+        goblin,
+        Prefab::new()
+            .with_component(&Stats {
+                health: 40,
+                speed: 3,
+            })
+            .with_child(Prefab::new().with_component(&Position(0.0, 2.0))),
+    )?;
+
+    let snapshot = world.snapshot()?;
+    let stats_of = |entity_id: usize| {
+        let entity = snapshot
+            .entities
+            .iter()
+            .find(|e| e.id == entity_id)
+            .unwrap();
+        serde_json::from_value::<Stats>(entity.components[std::any::type_name::<Stats>()].clone())
+            .unwrap()
+    };
+
+    assert_eq!(
+        stats_of(regular),
+        Stats {
+            health: 40,
+            speed: 3
+        }
+    );
+    assert_eq!(
+        stats_of(boss),
+        Stats {
+            health: 300,
+            speed: 3
+        }
+    );
+
+    let query = world.query().with_component_filter::<Position>()?.run();
+    assert_eq!(query.entity_ids.len(), 2);
+
+    let borrowed_position = query.components[0][0].borrow();
+    let position = borrowed_position.downcast_ref::<Position>().unwrap();
+    assert_eq!(*position, Position(0.0, 2.0));
+    Ok(())
+}
+
+#[test]
+fn overriding_non_instance_fails() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+
+    let result = world.set_prefab_override::<Position>(0, serde_json::json!([1.0, 1.0]));
+    assert!(matches!(result, Err(ECSError::NotAPrefabInstance)));
+    Ok(())
+}
+
+#[test]
+fn serializable_components_are_added_to_type_registry() {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_serializable_component::<Position>();
+
+    let registry = world.get_resource::<TypeRegistry>().unwrap();
+    let registration = registry.get_by_name("Position").unwrap();
+
+    let value = (registration.serialize.unwrap())(&Position(1.0, 2.0)).unwrap();
+    let position = (registration.deserialize.unwrap())(value).unwrap();
+
+    assert_eq!(
+        position.downcast_ref::<Position>(),
+        Some(&Position(1.0, 2.0))
+    );
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Armor {
+    value: u32,
+    broken: bool,
+}
+
+#[test]
+fn old_snapshots_are_migrated_on_load() -> Result<(), ECSError> {
+    // Saved before Armor was versioned, when it was a bare number.
+    let old_save = serde_json::json!({
+        "entity_count": 1,
+        "entities": [{ "id": 0, "components": { std::any::type_name::<Armor>(): 12 } }],
+        "resources": {}
+    });
+
+    let mut world = World::new();
+
+    world.register_component::<Armor>();
+    world.register_serializable_component::<Armor>();
+    world.set_component_version::<Armor>(2)?;
+    world.add_component_migration::<Armor>(0, |value| serde_json::json!({ "value": value }))?;
+    world.add_component_migration::<Armor>(1, |mut value| {
+        value["broken"] = serde_json::json!(false);
+        value
+    })?;
+
+    world.load_snapshot(serde_json::from_value(old_save).unwrap())?;
+
+    let query = world.query().with_component_filter::<Armor>()?.run();
+    let armor = query.components[0][0].borrow();
+    assert_eq!(
+        armor.downcast_ref::<Armor>(),
+        Some(&Armor {
+            value: 12,
+            broken: false
+        })
+    );
+    drop(armor);
+
+    let snapshot = world.snapshot()?;
+    assert_eq!(
+        snapshot.component_versions[std::any::type_name::<Armor>()],
+        2
+    );
+    Ok(())
+}
+
+struct SaveMe;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Settings(pub u32);
+
+#[test]
+fn filtered_snapshots_only_keep_marked_entities_and_allowed_resources() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<SaveMe>();
+    world.register_serializable_component::<Position>();
+    world.register_serializable_resource::<GameProgress>();
+    world.register_serializable_resource::<Settings>();
+    world.add_resource(GameProgress { level: 1, coins: 2 })?;
+    world.add_resource(Settings(3))?;
+
+    world.create_entity().with_component(Position(0.0, 0.0))?;
+    world
+        .create_entity()
+        .with_component(Position(1.0, 1.0))?
+        .with_component(SaveMe)?;
+
+    let snapshot = world.snapshot_filtered::<SaveMe>(&[std::any::TypeId::of::<GameProgress>()])?;
+
+    assert_eq!(snapshot.entity_count, 2);
+    assert_eq!(snapshot.entities.len(), 1);
+    assert_eq!(snapshot.entities[0].id, 1);
+    assert!(snapshot
+        .resources
+        .contains_key(std::any::type_name::<GameProgress>()));
+    assert!(!snapshot
+        .resources
+        .contains_key(std::any::type_name::<Settings>()));
+
+    assert!(matches!(
+        world.snapshot_filtered::<Particle>(&[]),
+        Err(ECSError::ComponentNotRegistered { .. })
+    ));
+    Ok(())
+}
+
+fn replicated_world() -> World {
+    let mut world = World::new();
+
+    world.register_component::<Position>();
+    world.register_component::<Follows>();
+    world.register_component::<Particle>();
+    world.register_replicated_component::<Position>();
+    world.register_replicated_component::<Follows>();
+    world.register_map_entities::<Follows>();
+
+    world
+}
+
+fn positions(world: &World) -> Result<Vec<(usize, Position)>, ECSError> {
+    let query = world.query().with_component_filter::<Position>()?.run();
+
+    Ok(query
+        .entity_ids
+        .iter()
+        .zip(&query.components[0])
+        .map(|(entity_id, position)| {
+            let position = position.borrow();
+            let position = position.downcast_ref::<Position>().unwrap();
+            (*entity_id, Position(position.0, position.1))
+        })
+        .collect())
+}
+
+#[test]
+fn clients_mirror_replicated_changes() -> Result<(), ECSError> {
+    let mut server = replicated_world();
+    let mut client = replicated_world();
+
+    // The client already has a local entity, so replicated IDs differ from the server's.
+    client.create_entity().with_component(Particle)?;
+
+    server.create_entity().with_component(Position(0.0, 0.0))?;
+    server
+        .create_entity()
+        .with_component(Position(1.0, 1.0))?
+        .with_component(Follows(0))?;
+    server.create_entity().with_component(Particle)?;
+
+    let changes = server.collect_changes()?;
+    assert_eq!(changes.spawned, vec![0, 1]);
+    client.apply_changes(changes)?;
+
+    assert_eq!(
+        positions(&client)?,
+        vec![(1, Position(0.0, 0.0)), (2, Position(1.0, 1.0))]
+    );
+    let query = client.query().with_component_filter::<Follows>()?.run();
+    let follows = query.components[0][0].borrow();
+    assert_eq!(follows.downcast_ref::<Follows>(), Some(&Follows(1)));
+    drop(follows);
+
+    // Follows didn't change, so it must not be mapped a second time.
+    server.add_component_to_entity(1, Position(2.0, 2.0))?;
+    client.apply_changes(server.collect_changes()?)?;
+
+    let query = client.query().with_component_filter::<Follows>()?.run();
+    let follows = query.components[0][0].borrow();
+    assert_eq!(follows.downcast_ref::<Follows>(), Some(&Follows(1)));
+    drop(follows);
+
+    server.add_component_to_entity(0, Position(5.0, 0.0))?;
+    server.remove_entity_component::<Follows>(1)?;
+    let changes = server.collect_changes()?;
+    assert_eq!(changes.changed.len(), 1);
+    assert_eq!(changes.removed.len(), 1);
+    client.apply_changes(changes)?;
+
+    assert_eq!(
+        positions(&client)?,
+        vec![(1, Position(5.0, 0.0)), (2, Position(2.0, 2.0))]
+    );
+    assert!(client
+        .query()
+        .with_component_filter::<Follows>()?
+        .run()
+        .entity_ids
+        .is_empty());
+
+    server.remove_entity(0)?;
+    let changes = server.collect_changes()?;
+    assert_eq!(changes.despawned, vec![0]);
+    client.apply_changes(changes)?;
+
+    assert_eq!(positions(&client)?, vec![(2, Position(2.0, 2.0))]);
+    assert_eq!(client.replicated_entity(0), None);
+    assert_eq!(client.replicated_entity(1), Some(2));
+    assert!(server.collect_changes()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn peers_only_receive_owned_and_interesting_entities() -> Result<(), ECSError> {
+    let mut server = replicated_world();
+    let (alice, bob) = (PeerId(1), PeerId(2));
+
+    server.register_component::<Owner>();
+    server.register_component::<InterestedPeers>();
+
+    server
+        .create_entity()
+        .with_component(Position(0.0, 0.0))?
+        .with_component(Owner(alice))?;
+    server
+        .create_entity()
+        .with_component(Position(1.0, 0.0))?
+        .with_component(Owner(bob))?
+        .with_component(InterestedPeers(vec![alice]))?;
+    server.create_entity().with_component(Position(2.0, 0.0))?;
+
+    assert_eq!(server.entities_owned_by(alice), vec![0]);
+    assert_eq!(server.collect_changes_for(alice)?.spawned, vec![0, 1]);
+    assert_eq!(server.collect_changes_for(bob)?.spawned, vec![1]);
+
+    // Bob's entity moves out of Alice's interest.
+    server.add_component_to_entity(1, InterestedPeers(vec![]))?;
+    server.add_component_to_entity(1, Position(1.0, 1.0))?;
+
+    let alice_changes = server.collect_changes_for(alice)?;
+    assert_eq!(alice_changes.despawned, vec![1]);
+    assert!(alice_changes.changed.is_empty());
+
+    let bob_changes = server.collect_changes_for(bob)?;
+    assert_eq!(bob_changes.changed.len(), 1);
+
+    server.forget_peer(bob);
+    assert_eq!(server.collect_changes_for(bob)?.spawned, vec![1]);
+    Ok(())
+}
+
+#[test]
+fn entity_uuids_survive_snapshots_and_transfers() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.enable_entity_uuids();
+    world.register_component::<Position>();
+    world.register_serializable_component::<Position>();
+
+    let entity_id = world
+        .create_entity()
+        .with_component(Position(1.0, 2.0))?
+        .id();
+    let uuid = world.entity_uuid(entity_id).unwrap();
+
+    let json = serde_json::to_string(&world.snapshot()?).unwrap();
+    assert!(json.contains(&uuid.to_string()));
+
+    let mut loaded = World::new();
+    loaded.enable_entity_uuids();
+    loaded.register_component::<Position>();
+    loaded.register_serializable_component::<Position>();
+
+    loaded.load_snapshot(serde_json::from_str(&json).unwrap())?;
+    assert_eq!(loaded.entity_by_uuid(uuid), Some(entity_id));
+
+    let mut other = World::new();
+    other.enable_entity_uuids();
+    other.register_component::<Position>();
+    other.register_serializable_component::<Position>();
+
+    let entity_map = other.spawn_snapshot(serde_json::from_str(&json).unwrap())?;
+    let transferred = entity_map[&entity_id];
+    assert_eq!(other.entity_uuid(transferred), Some(uuid));
+    assert_eq!(other.entity_by_uuid(uuid), Some(transferred));
+
+    // Spawning it again where the UUID is taken gives the copy a new one.
+    let entity_map = loaded.spawn_snapshot(serde_json::from_str(&json).unwrap())?;
+    let copy = entity_map[&entity_id];
+    let copy_uuid = loaded.entity_uuid(copy).unwrap();
+    assert_ne!(copy_uuid, uuid);
+    assert_eq!(loaded.entity_by_uuid(copy_uuid), Some(copy));
+
+    loaded.remove_entity(copy)?;
+    assert_eq!(loaded.entity_by_uuid(uuid), Some(entity_id));
+    Ok(())
+}
+
+#[test]
+fn spawned_hierarchies_keep_their_relations() -> Result<(), ECSError> {
+    let mut scene_world = World::new();
+
+    scene_world.register_component::<Position>();
+    scene_world.register_serializable_component::<Position>();
+    scene_world.register_hierarchy();
+
+    let parent = scene_world
+        .create_entity()
+        .with_component(Position(0.0, 0.0))?
+        .id();
+    let child = scene_world
+        .create_entity()
+        .with_component(Position(1.0, 0.0))?
+        .id();
+    scene_world.set_parent(child, parent)?;
+
+    let mut world = World::new();
+    world.register_component::<Position>();
+    world.register_serializable_component::<Position>();
+    world.register_hierarchy();
+
+    world.create_entity().with_component(Position(9.0, 9.0))?;
+    let entity_map = world.spawn_snapshot(scene_world.snapshot()?)?;
+
+    assert_eq!(world.parent(entity_map[&child]), Some(entity_map[&parent]));
+    assert_eq!(
+        world.children(entity_map[&parent]).collect::<Vec<usize>>(),
+        vec![entity_map[&child]]
+    );
+    Ok(())
+}
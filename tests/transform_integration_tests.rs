@@ -0,0 +1,83 @@
+#![cfg(feature = "transform")]
+
+use std::f32::consts::FRAC_PI_2;
+
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::transform::{propagate_transforms, GlobalTransform, Transform};
+use sara_ecs::World;
+
+fn global_translation(world: &World, entity_id: usize) -> Result<[f32; 2], ECSError> {
+    let entities = world
+        .query()
+        .with_component_filter::<GlobalTransform>()?
+        .get_entities();
+    let entity = entities
+        .iter()
+        .find(|entity| entity.id == entity_id)
+        .ok_or(ECSError::EntityDoesNotExist { id: entity_id })?;
+    let translation = entity.get_component::<GlobalTransform>()?.translation();
+
+    Ok(translation.map(|value| (value * 1000.0).round() / 1000.0))
+}
+
+fn set_translation(world: &World, entity_id: usize, x: f32, y: f32) -> Result<(), ECSError> {
+    for mut entity in world
+        .query()
+        .with_component_filter::<Transform>()?
+        .get_entities()
+    {
+        if entity.id == entity_id {
+            entity.get_component_mut::<Transform>()?.translation = [x, y];
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn transforms_are_composed_down_the_hierarchy() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_transforms();
+
+    let ship = world
+        .create_entity()
+        .with_component(Transform::from_translation(10.0, 0.0).with_rotation(FRAC_PI_2))?
+        .id();
+    let turret = world
+        .create_entity()
+        .with_component(Transform::from_translation(2.0, 0.0).with_scale(2.0, 2.0))?
+        .id();
+    let barrel = world
+        .create_entity()
+        .with_component(Transform::from_translation(1.0, 0.0))?
+        .id();
+
+    world.set_parent(turret, ship)?;
+    world.set_parent(barrel, turret)?;
+    propagate_transforms(&mut world);
+
+    assert_eq!(global_translation(&world, ship)?, [10.0, 0.0]);
+    assert_eq!(global_translation(&world, turret)?, [10.0, 2.0]);
+    assert_eq!(global_translation(&world, barrel)?, [10.0, 4.0]);
+
+    // Moving the root moves the whole subtree.
+    set_translation(&world, ship, 0.0, 0.0)?;
+    propagate_transforms(&mut world);
+    assert_eq!(global_translation(&world, barrel)?, [0.0, 4.0]);
+
+    // Moving a leaf only moves the leaf.
+    set_translation(&world, barrel, 0.0, 0.0)?;
+    propagate_transforms(&mut world);
+    assert_eq!(global_translation(&world, turret)?, [0.0, 2.0]);
+    assert_eq!(global_translation(&world, barrel)?, [0.0, 2.0]);
+
+    // Reparenting without changing the local transform is picked up too.
+    world.set_parent(barrel, ship)?;
+    propagate_transforms(&mut world);
+    assert_eq!(global_translation(&world, barrel)?, [0.0, 0.0]);
+
+    world.remove_parent(barrel)?;
+    propagate_transforms(&mut world);
+    assert_eq!(global_translation(&world, barrel)?, [0.0, 0.0]);
+    Ok(())
+}
@@ -0,0 +1,64 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::time::Duration;
+
+use sara_ecs::ecs_errors::ECSError;
+use sara_ecs::schedule::Schedule;
+use sara_ecs::tasks::{poll_tasks, Tasks};
+use sara_ecs::time::Time;
+use sara_ecs::World;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+struct Health(pub u32);
+
+#[wasm_bindgen_test]
+fn entities_can_be_created_queried_and_removed() -> Result<(), ECSError> {
+    let mut world = World::new();
+
+    world.register_component::<Health>();
+
+    let player = world.create_entity().with_component(Health(100))?.id();
+    world.create_entity().with_component(Health(50))?;
+    world.remove_entity(player)?;
+
+    let result = world.query().with_component_filter::<Health>()?.run();
+    assert_eq!(result.entity_ids, vec![1]);
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn schedules_advance_time() {
+    let mut world = World::new();
+
+    world.set_schedule({
+        let mut schedule = Schedule::new();
+        schedule.add_system(|world| world.advance_time(Duration::from_millis(16)));
+        schedule
+    });
+    world.step_n(2);
+
+    assert_eq!(world.get_resource::<Time>().unwrap().tick(), 2);
+}
+
+#[wasm_bindgen_test]
+fn tasks_run_on_the_calling_thread() {
+    let mut world = World::new();
+    let mut tasks = Tasks::new();
+
+    world.register_component::<Health>();
+
+    let entity_id = world.entities().reserve();
+    tasks.spawn(
+        || 10,
+        move |health, commands| {
+            commands.entity(entity_id).insert(Health(health));
+        },
+    );
+    tasks.spawn_async(async { 20 }, |_health, _commands| {});
+    world.add_resource(tasks).unwrap();
+
+    poll_tasks(&mut world);
+
+    assert_eq!(world.get_resource::<Tasks>().unwrap().running(), 0);
+    assert!(world.entities().is_alive(entity_id));
+}